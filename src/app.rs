@@ -1,27 +1,36 @@
-use crate::config::{Config, DefaultCommitsMode, DefaultDiffMode, Theme};
+use crate::config::{
+    Config, DefaultCommitsMode, DefaultDiffMode, LayoutConfig, RepoList, Session, Theme,
+};
 use crate::error::Result;
-use crate::git::{IssueInfo, PullRequestInfo, ReleaseInfo, Repository, WorkflowRun};
+use crate::git::{
+    IssueInfo, MergeMode, MergeRequest, PatchTarget, PullOptions, PullRequestInfo, PullStrategy,
+    PushRequest, ReleaseInfo, RepoChanges, RepoSummary, Repository, TransferProgress, Watcher,
+    WorkflowRun,
+};
 use crate::input::{
-    Event, EventReader, KeyCode, KeyEvent, Modifiers, MouseButton, MouseEvent, MouseEventKind,
+    signals, Event, EventReader, KeyCode, KeyEvent, Modifiers, MouseButton, MouseEvent,
+    MouseEventKind,
 };
 use crate::tui::{Buffer, Color, Rect, Style, Terminal};
 use crate::views::{
     ActionsView, BlameView, BranchesView, CommitsView, CommitsViewMode, ConflictView, DiffMode,
-    DiffView, FileTreeView, FileViewMode, IssuesView, MenuView, PanelType, PullRequestsView,
-    ReleasesView, RemotesView, Section, StashView, StatusView, SubmodulesView, TagsView,
-    WorktreeView,
+    DiffView, FileTreeView, FileViewMode, HooksView, IssuesView, MenuView, PanelType, PreviewType,
+    PullRequestsView, ReflogView, ReleasesView, RemotesView, RepoEntry, RepoSwitcherView, Section,
+    StashView, StatusView, SubmodulesView, TagsView, TrashView, WorktreeView,
 };
 use crate::widgets::{Block, Borders, Widget};
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
 use std::sync::mpsc::{self, Receiver, Sender};
 use std::thread;
 use std::time::{Duration, Instant};
 
 use crate::git::{
-    BranchInfo, CommitInfo, ConflictEntry, FileTreeEntry, GraphLine, RemoteInfo, StatusEntry,
-    SubmoduleInfo, TagInfo, WorktreeInfo,
+    merge_status, BlameInfo, BranchInfo, CheckoutMode, CheckoutTarget, CommitInfo, ConflictEntry,
+    DiffInfo, FileText, FileTreeEntry, GraphLine, HookLog, ReflogEntry, RemoteInfo, StatusEntry,
+    StatusQuery, SubmoduleInfo, TagInfo, TrashEntry, UntrackedFiles, WorktreeInfo,
 };
+use crate::jobs::{CancelToken, JobInfo, JobView, Jobs, RefHold};
 
 /// Result from async fetch operations
 pub enum AsyncLoadResult {
@@ -37,25 +46,69 @@ pub enum AsyncLoadResult {
     /// Action run view content (run_id, content)
     ActionView(u64, std::result::Result<String, String>),
     // Git data results
-    GitStatus(std::result::Result<Vec<StatusEntry>, String>),
+    /// Status load (generation, reset the selection, scope read, entries)
+    GitStatus(
+        u64,
+        bool,
+        Vec<String>,
+        std::result::Result<Vec<StatusEntry>, String>,
+    ),
     GitBranches(std::result::Result<Vec<BranchInfo>, String>),
-    GitCommits(std::result::Result<Vec<CommitInfo>, String>),
+    /// Commits load (generation, commits)
+    GitCommits(u64, std::result::Result<Vec<CommitInfo>, String>),
+    /// Diff of the status selection (generation, selected path, diff)
+    GitDiff(u64, String, std::result::Result<DiffInfo, String>),
+    /// Blame load (generation, path, line to go to, blame)
+    GitBlame(
+        u64,
+        String,
+        Option<usize>,
+        std::result::Result<BlameInfo, String>,
+    ),
     GitGraphCommits(std::result::Result<Vec<GraphLine>, String>),
     GitTags(std::result::Result<Vec<TagInfo>, String>),
     GitRemotes(std::result::Result<Vec<RemoteInfo>, String>),
     GitWorktrees(std::result::Result<Vec<WorktreeInfo>, String>),
     GitSubmodules(std::result::Result<Vec<SubmoduleInfo>, String>),
+    /// Reflog refs and the entries of the ref being shown
+    GitReflog(std::result::Result<(Vec<String>, Vec<ReflogEntry>), String>),
+    GitTrash(std::result::Result<Vec<TrashEntry>, String>),
     GitConflicts(std::result::Result<Vec<ConflictEntry>, String>),
     GitFileTree(std::result::Result<Vec<FileTreeEntry>, String>),
     // Remote operation results (fetch/pull/push)
     RemoteOperationComplete(std::result::Result<String, String>),
+    /// Push refused as non-fast-forward (remote, branch, explanation)
+    PushRejected(String, String, String),
     // Background fetch from all remotes (periodic auto-fetch)
     BackgroundFetchComplete(std::result::Result<String, String>),
+    /// Dashboard row of one repository in the repository switcher
+    RepoSummary(RepoSummary),
+    /// Files or refs changed outside the app (file watcher)
+    RepoChanged(RepoChanges),
 }
 
 // Re-export PanelType as Panel for backwards compatibility within app
 pub use crate::views::PanelType as Panel;
 
+/// Where the TUI opens, from command-line arguments
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Startup {
+    /// Directory to find the repository from instead of the current one (`-C`)
+    pub repo_dir: Option<PathBuf>,
+    pub panel: Option<PanelType>,
+    pub target: Option<StartupTarget>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StartupTarget {
+    /// `g blame <file>[:line]`; paths are relative to the current directory
+    Blame { path: String, line: Option<usize> },
+    /// `g log <path>`: commits touching the path
+    Log { path: String },
+    /// `g show <rev>`
+    Show { rev: String },
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Mode {
     Normal,
@@ -65,6 +118,24 @@ pub enum Mode {
     Input(InputContext),
     Confirm(ConfirmAction),
     Select(SelectAction),
+    Push,   // Push options dialog
+    Merge,  // Merge options dialog
+    Repos,  // Repository switcher
+    Themes, // Theme picker with live preview
+    Jobs,   // Running background jobs
+}
+
+/// What the workspace should do when `App::run_tab` returns
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TabRequest {
+    Quit,
+    /// Open a repository, replacing the current tab or in a new one
+    Open {
+        path: PathBuf,
+        new_tab: bool,
+    },
+    Switch(usize),
+    Close,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -72,6 +143,10 @@ pub enum SelectAction {
     ResetOrRevert, // Choose between reset and revert
     ResetMode,     // Choose reset mode: --soft, --mixed, --hard
     PrMergeMethod, // Choose PR merge method: merge, rebase, squash
+    PullStrategy,  // Choose pull strategy: merge, rebase, ff-only
+    CheckoutDirty, // Checkout with local changes: carry, stash, abort
+    ExportPatch,   // Export commits as patch files or to the clipboard
+    ApplyPatch,    // Apply a patch file to the working tree, index or both
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -88,6 +163,9 @@ pub enum InputContext {
     TagName,
     StashMessage,
     IssueComment,
+    WorktreePath,
+    WorktreeLockReason,
+    SubmoduleAdd,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -95,11 +173,12 @@ pub enum ConfirmAction {
     BranchDelete,
     BranchForceDelete,
     RemoteBranchDelete,
-    BranchPush,
-    BranchMerge,
     DeleteMergedBranches,
     Discard,
-    Push,
+    DiscardAll,
+    CleanUntracked,
+    TrashRestore,
+    TrashDelete,
     StashDrop,
     CommitRevert,
     PrMerge,
@@ -108,6 +187,9 @@ pub enum ConfirmAction {
     IssueClose,
     IssueReopen,
     IssueDelete,
+    WorktreeRemove,
+    WorktreeForceRemove,
+    SubmoduleDeinit,
 }
 
 pub struct App {
@@ -129,6 +211,9 @@ pub struct App {
     pub remotes_view: RemotesView,
     pub worktree_view: WorktreeView,
     pub submodules_view: SubmodulesView,
+    pub reflog_view: ReflogView,
+    pub trash_view: TrashView,
+    pub hooks_view: HooksView,
     pub blame_view: BlameView,
     pub filetree_view: FileTreeView,
     pub conflict_view: ConflictView,
@@ -137,6 +222,7 @@ pub struct App {
     pub actions_view: ActionsView,
     pub releases_view: ReleasesView,
     pub menu_view: MenuView,
+    pub repos_view: RepoSwitcherView,
 
     pub input_buffer: String,
     pub input_cursor: usize,
@@ -153,6 +239,9 @@ pub struct App {
     // Issue number for comment input
     pub comment_issue_number: Option<u32>,
 
+    // Worktree creation source (branch or commit selected when pressing 'w')
+    pub worktree_create_from: Option<String>,
+
     // Confirmation context (stores target name for confirmation dialog)
     pub confirm_target: Option<String>,
 
@@ -165,6 +254,17 @@ pub struct App {
     // PR merge method (0: merge, 1: rebase, 2: squash)
     pr_merge_method: usize,
 
+    // Pull dialog: (remote, branch) to pull and the autostash toggle
+    pull_target: Option<(String, String)>,
+    pull_autostash: bool,
+
+    // Checkout waiting for the carry/stash/abort choice
+    pending_checkout: Option<CheckoutTarget>,
+
+    // Commits to export, and a previewed patch file (name, text) waiting to be applied
+    export_commits: Vec<String>,
+    pending_patch: Option<(String, String)>,
+
     // Async loading channel for GitHub API calls
     async_sender: Sender<AsyncLoadResult>,
     async_receiver: Receiver<AsyncLoadResult>,
@@ -172,8 +272,13 @@ pub struct App {
     // Repository path for spawning async tasks
     repo_path: PathBuf,
 
+    // Parent repositories when drilled into a submodule (outermost first)
+    repo_stack: Vec<PathBuf>,
+
     // Last spinner tick time
     last_spinner_tick: Instant,
+    /// Something on screen changed since the last frame
+    needs_redraw: bool,
 
     // Last auto-refresh time (for periodic background refresh of all data)
     last_auto_refresh: Option<Instant>,
@@ -184,14 +289,27 @@ pub struct App {
     fetching_remotes: bool,
 
     // Flags to track if data is being refreshed in background
-    refreshing_status: bool,
     refreshing_branches: bool,
-    refreshing_commits: bool,
+
+    /// Status, commits, diff and blame loads, and fetch/pull/push/commit
+    jobs: Jobs,
+    /// A diff refresh waits for the status load in flight, whose selection it follows
+    diff_pending: bool,
+    /// Status entry whose diff was loaded past the size limit; refreshes keep it loaded
+    large_diff_path: Option<String>,
+
+    // Commits panel limited to one path (`g log <path>`, `:log <path>`)
+    commits_path: Option<String>,
+
+    // Applied once the first refresh has loaded the data
+    startup: Startup,
     refreshing_graph_commits: bool,
     refreshing_tags: bool,
     refreshing_remotes: bool,
     refreshing_worktrees: bool,
     refreshing_submodules: bool,
+    refreshing_reflog: bool,
+    refreshing_trash: bool,
     refreshing_conflicts: bool,
     refreshing_filetree: bool,
     /// PR number currently being loaded for commits (None if not loading)
@@ -204,23 +322,240 @@ pub struct App {
     remote_operation: Option<RemoteOperation>,
     /// Spinner frame for remote operations (0-7)
     remote_spinner_frame: usize,
+    /// Progress/cancel handle of the running fetch/pull/push
+    transfer_progress: Option<TransferProgress>,
+    /// State of the push dialog while Mode::Push is active
+    push_dialog: Option<PushDialog>,
+    /// State of the merge dialog while Mode::Merge is active
+    merge_dialog: Option<MergeDialog>,
+    /// Output of git hooks, shared with the threads that run them
+    hook_log: HookLog,
+    /// Commit without the pre-commit and commit-msg hooks (toggled in the commit prompt)
+    commit_no_verify: bool,
+    /// Themes listed in the theme picker, and the theme to go back to on Esc
+    theme_choices: Vec<String>,
+    theme_before: Option<Theme>,
+    /// Set to hand control back to the workspace (open/switch/close a tab)
+    tab_request: Option<TabRequest>,
+    /// Titles of all open tabs and the index of this one, kept up to date by the workspace
+    pub tab_titles: Vec<String>,
+    pub active_tab: usize,
+    /// Refreshes views when the repository changes on disk; None falls back to polling
+    watcher: Option<Watcher>,
 }
 
 /// Remote operation type for spinner display
 #[derive(Debug, Clone)]
 pub enum RemoteOperation {
-    Fetch(String),        // branch/remote name
-    Pull(String),         // branch name
-    Push(String),         // branch name
-    PrMerge(u32),         // PR number
-    PrClose(u32),         // PR number
-    PrCreate(String),     // base branch
-    BranchDelete(String), // branch name
-    DeleteMergedBranches, // deleting multiple merged branches
-    IssueComment(u32),    // issue number
-    IssueClose(u32),      // issue number
-    IssueReopen(u32),     // issue number
-    IssueDelete(u32),     // issue number
+    Fetch(String),           // branch/remote name
+    Pull(String),            // branch name
+    Push(String),            // branch name
+    PrMerge(u32),            // PR number
+    PrClose(u32),            // PR number
+    PrCreate(String),        // base branch
+    BranchDelete(String),    // branch name
+    DeleteMergedBranches,    // deleting multiple merged branches
+    IssueComment(u32),       // issue number
+    IssueClose(u32),         // issue number
+    IssueReopen(u32),        // issue number
+    IssueDelete(u32),        // issue number
+    SubmoduleUpdate(String), // submodule name
+    SubmoduleAdd(String),    // submodule path
+    Commit,                  // commit running its hooks
+}
+
+/// Field of the push dialog that has focus
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PushField {
+    Remote,
+    Source,
+    Destination,
+    Force,
+    Tags,
+    DryRun,
+    SetUpstream,
+    NoVerify,
+}
+
+impl PushField {
+    const ALL: [PushField; 8] = [
+        PushField::Remote,
+        PushField::Source,
+        PushField::Destination,
+        PushField::Force,
+        PushField::Tags,
+        PushField::DryRun,
+        PushField::SetUpstream,
+        PushField::NoVerify,
+    ];
+}
+
+/// Push dialog state: remote, refspec and flags, plus where a force push expects the remote
+#[derive(Debug, Clone)]
+pub struct PushDialog {
+    pub remotes: Vec<String>,
+    pub remote_index: usize,
+    pub source: String,
+    pub destination: String,
+    pub force: bool,
+    /// Remote-tracking commit of the destination; a force push only replaces it
+    pub expected: Option<String>,
+    pub tags: bool,
+    pub dry_run: bool,
+    pub set_upstream: bool,
+    pub no_verify: bool,
+    pub field: PushField,
+}
+
+impl PushDialog {
+    pub fn remote(&self) -> &str {
+        self.remotes
+            .get(self.remote_index)
+            .map(|s| s.as_str())
+            .unwrap_or("origin")
+    }
+
+    fn move_field(&mut self, forward: bool) {
+        let pos = PushField::ALL
+            .iter()
+            .position(|f| *f == self.field)
+            .unwrap_or(0);
+        let len = PushField::ALL.len();
+        let next = if forward {
+            (pos + 1) % len
+        } else {
+            (pos + len - 1) % len
+        };
+        self.field = PushField::ALL[next];
+    }
+
+    pub fn request(&self) -> PushRequest {
+        PushRequest {
+            remote: self.remote().to_string(),
+            source: self.source.clone(),
+            destination: self.destination.clone(),
+            force_if_at: if self.force {
+                Some(self.expected.clone().unwrap_or_default())
+            } else {
+                None
+            },
+            tags: self.tags,
+            dry_run: self.dry_run,
+            set_upstream: self.set_upstream,
+            no_verify: self.no_verify,
+        }
+    }
+
+    /// Footer segments, one per field
+    fn segments(&self) -> Vec<(PushField, String)> {
+        let check = |on: bool| if on { "[x]" } else { "[ ]" };
+        let expected = match &self.expected {
+            Some(id) => id[..7.min(id.len())].to_string(),
+            None => "absent".to_string(),
+        };
+        vec![
+            (PushField::Remote, format!("remote: {}", self.remote())),
+            (PushField::Source, format!("src: {}", self.source)),
+            (PushField::Destination, format!("dst: {}", self.destination)),
+            (
+                PushField::Force,
+                format!("{} force if at {}", check(self.force), expected),
+            ),
+            (PushField::Tags, format!("{} tags", check(self.tags))),
+            (
+                PushField::DryRun,
+                format!("{} dry-run", check(self.dry_run)),
+            ),
+            (
+                PushField::SetUpstream,
+                format!("{} set upstream", check(self.set_upstream)),
+            ),
+            (
+                PushField::NoVerify,
+                format!("{} no-verify", check(self.no_verify)),
+            ),
+        ]
+    }
+}
+
+/// Field of the merge dialog that has focus
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MergeField {
+    Mode,
+    Subject,
+    Shortlog,
+    NoVerify,
+}
+
+/// Merge dialog state: what to merge, how, and the editable message
+#[derive(Debug, Clone)]
+pub struct MergeDialog {
+    /// Branch, remote-tracking branch or commit id
+    pub target: String,
+    /// Short form of the target for display
+    pub label: String,
+    pub mode: MergeMode,
+    pub subject: String,
+    /// Shortlog of the merged commits (message body)
+    pub shortlog: String,
+    pub include_shortlog: bool,
+    pub no_verify: bool,
+    pub field: MergeField,
+}
+
+impl MergeDialog {
+    fn cycle_mode(&mut self, forward: bool) {
+        let len = MergeMode::ALL.len();
+        let pos = MergeMode::ALL
+            .iter()
+            .position(|m| *m == self.mode)
+            .unwrap_or(0);
+        let next = if forward {
+            (pos + 1) % len
+        } else {
+            (pos + len - 1) % len
+        };
+        self.mode = MergeMode::ALL[next];
+    }
+
+    pub fn request(&self) -> MergeRequest {
+        let mut message = self.subject.clone();
+        if self.include_shortlog && !self.shortlog.is_empty() {
+            message.push_str("\n\n");
+            message.push_str(&self.shortlog);
+        }
+        MergeRequest {
+            target: self.target.clone(),
+            mode: self.mode,
+            message: Some(message),
+            no_verify: self.no_verify,
+        }
+    }
+
+    /// Footer segments, one per field
+    fn segments(&self) -> Vec<(MergeField, String)> {
+        let commits = self
+            .shortlog
+            .lines()
+            .filter(|l| l.starts_with("  "))
+            .count();
+        vec![
+            (MergeField::Mode, format!("mode: {}", self.mode.name())),
+            (MergeField::Subject, format!("message: {}", self.subject)),
+            (
+                MergeField::Shortlog,
+                format!(
+                    "[{}] shortlog ({} commits)",
+                    if self.include_shortlog { "x" } else { " " },
+                    commits
+                ),
+            ),
+            (
+                MergeField::NoVerify,
+                format!("[{}] no-verify", if self.no_verify { "x" } else { " " }),
+            ),
+        ]
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -243,10 +578,29 @@ pub enum DragType {
 }
 
 impl App {
-    pub fn new() -> Result<Self> {
-        let repo = Repository::discover()?;
+    pub fn new(startup: Startup) -> Result<Self> {
         let config = Config::load().unwrap_or_default();
-        let terminal = Terminal::new()?;
+        Self::with_terminal(startup, config, Terminal::new()?)
+    }
+
+    /// An app drawing to `terminal`, e.g. `Terminal::headless` in rendering tests
+    pub fn with_terminal(startup: Startup, config: Config, mut terminal: Terminal) -> Result<Self> {
+        let hook_log = HookLog::new();
+        let repo = match &startup.repo_dir {
+            Some(dir) => Repository::discover_from(dir)?,
+            None => Repository::discover()?,
+        }
+        .with_hook_log(hook_log.clone())
+        .with_size_limit(config.size_limit());
+        match repo.expire_trash(config.git.trash_days) {
+            Ok(0) => {}
+            Ok(n) => debug!("Expired {} trash entries", n),
+            Err(e) => debug!("Trash expiry failed: {}", e),
+        }
+        terminal.set_input_modes(config.input);
+        if let Some(depth) = config.color_depth {
+            terminal.set_color_depth(depth);
+        }
         let event_reader = EventReader::new();
 
         // Create views with default modes from config
@@ -289,6 +643,9 @@ impl App {
             remotes_view: RemotesView::new(),
             worktree_view: WorktreeView::new(),
             submodules_view: SubmodulesView::new(),
+            reflog_view: ReflogView::new(),
+            trash_view: TrashView::new(),
+            hooks_view: HooksView::new(),
             blame_view: BlameView::new(),
             filetree_view,
             conflict_view: ConflictView::new(),
@@ -297,6 +654,7 @@ impl App {
             actions_view: ActionsView::new(),
             releases_view: ReleasesView::new(),
             menu_view: MenuView::new(),
+            repos_view: RepoSwitcherView::new(),
             input_buffer: String::new(),
             input_cursor: 0,
             message: None,
@@ -304,25 +662,38 @@ impl App {
             drag_state: None,
             branch_create_from: None,
             comment_issue_number: None,
+            worktree_create_from: None,
             confirm_target: None,
             merged_branches_to_delete: None,
             select_index: 0,
             pr_merge_method: 0,
+            pull_target: None,
+            pull_autostash: false,
+            pending_checkout: None,
+            export_commits: Vec::new(),
+            pending_patch: None,
             async_sender,
             async_receiver,
             repo_path,
+            repo_stack: Vec::new(),
             last_spinner_tick: Instant::now(),
+            needs_redraw: true,
             last_auto_refresh: None,
             last_auto_fetch: None,
             fetching_remotes: false,
-            refreshing_status: false,
             refreshing_branches: false,
-            refreshing_commits: false,
+            jobs: Jobs::new(),
+            diff_pending: false,
+            large_diff_path: None,
+            commits_path: None,
+            startup,
             refreshing_graph_commits: false,
             refreshing_tags: false,
             refreshing_remotes: false,
             refreshing_worktrees: false,
             refreshing_submodules: false,
+            refreshing_reflog: false,
+            refreshing_trash: false,
             refreshing_conflicts: false,
             refreshing_filetree: false,
             refreshing_pr_commits: None,
@@ -330,13 +701,80 @@ impl App {
             refreshing_action_view: None,
             remote_operation: None,
             remote_spinner_frame: 0,
+            transfer_progress: None,
+            push_dialog: None,
+            merge_dialog: None,
+            hook_log,
+            commit_no_verify: false,
+            theme_choices: Vec::new(),
+            theme_before: None,
+            tab_request: None,
+            tab_titles: Vec::new(),
+            active_tab: 0,
+            watcher: None,
         })
     }
 
-    pub fn run(&mut self) -> Result<()> {
-        self.terminal.init()?;
+    /// Load the data, restore the saved session and open the startup target
+    pub fn start(&mut self) -> Result<()> {
         self.refresh_all()?;
+        // The session and startup targets select items in what was loaded
+        self.wait_for_loads();
+        if let Some(session) = Session::load(&self.session_key()) {
+            self.restore_session(session)?;
+        }
+        // Command-line targets win over the restored session
+        let startup = std::mem::take(&mut self.startup);
+        self.open_startup(startup)?;
+
+        let mut repos = RepoList::load();
+        repos.touch(&self.session_key());
+        if let Err(e) = repos.save() {
+            debug!("Recent repositories save failed: {}", e);
+        }
 
+        self.start_watcher();
+        Ok(())
+    }
+
+    /// Watch the current repository when `watch_files` is set, replacing
+    /// (and so stopping) the watcher of a previous one
+    fn start_watcher(&mut self) {
+        self.watcher = None;
+        if self.config.watch_files {
+            let sender = self.async_sender.clone();
+            self.watcher = self
+                .repo
+                .watch(move |changes| sender.send(AsyncLoadResult::RepoChanged(changes)).is_ok());
+        }
+    }
+
+    /// Like `start`, without the saved session and the recent repositories,
+    /// which live outside the repository
+    pub fn start_isolated(&mut self) -> Result<()> {
+        self.refresh_all()?;
+        self.wait_for_loads();
+        let startup = std::mem::take(&mut self.startup);
+        self.open_startup(startup)
+    }
+
+    /// Save the session of this tab
+    pub fn finish(&self) {
+        if let Err(e) = self.capture_session().save() {
+            debug!("Session save failed: {}", e);
+        }
+    }
+
+    /// Take over the screen and input of `other`, which keeps this tab's (inactive) ones
+    pub fn take_screen(&mut self, other: &mut App) {
+        std::mem::swap(&mut self.terminal, &mut other.terminal);
+        std::mem::swap(&mut self.event_reader, &mut other.event_reader);
+        self.terminal.force_full_redraw();
+        self.needs_redraw = true;
+    }
+
+    /// Run the event loop until the user quits or asks for another tab
+    pub fn run_tab(&mut self) -> Result<TabRequest> {
         let mut frame_count = 0u64;
         loop {
             debug!(
@@ -359,201 +797,343 @@ impl App {
             // Periodic background fetch from all remotes
             self.check_auto_fetch();
 
-            self.draw()?;
+            // Only draw when input, a result or a spinner changed something
+            if std::mem::take(&mut self.needs_redraw) {
+                self.draw()?;
+            }
 
             let event = self.event_reader.read_event(Duration::from_millis(100))?;
-
-            match event {
-                Event::Key(key) => {
-                    debug!("KEY EVENT: {:?}", key);
-                    self.handle_key(key)?;
-                }
-                Event::Mouse(mouse) => self.handle_mouse(mouse)?,
-                Event::None => {}
-                _ => {
-                    debug!("OTHER EVENT");
-                }
+            if event != Event::None {
+                self.needs_redraw = true;
             }
+            self.handle_event(event)?;
 
             if self.should_quit {
-                break;
+                return Ok(TabRequest::Quit);
+            }
+            if let Some(request) = self.tab_request.take() {
+                return Ok(request);
             }
         }
+    }
 
-        self.terminal.restore()?;
+    /// Act on one input event
+    pub fn handle_event(&mut self, event: Event) -> Result<()> {
+        match event {
+            Event::Key(key) => {
+                debug!("KEY EVENT: {:?}", key);
+                self.handle_key(key)?;
+            }
+            Event::Mouse(mouse) => self.handle_mouse(mouse)?,
+            Event::Paste(text) => self.handle_paste(&text),
+            Event::Resize(width, height) => {
+                self.terminal.resize(width, height);
+                self.terminal.force_full_redraw();
+            }
+            Event::Suspend => self.suspend()?,
+            Event::Resume => self.terminal.resume()?,
+            Event::Terminate => self.should_quit = true,
+            Event::SynchronizedOutput(supported) => {
+                self.terminal.set_synchronized_output(supported)
+            }
+            Event::FocusGained => {
+                // Whatever changed while we were in the background
+                self.last_auto_refresh = Some(Instant::now());
+                self.start_background_refresh_all();
+            }
+            Event::None => {}
+            _ => {
+                debug!("OTHER EVENT");
+            }
+        }
         Ok(())
     }
 
     /// Process any completed async load results from background threads
-    fn process_async_results(&mut self) {
+    pub fn process_async_results(&mut self) {
+        // Copy hook output streamed by a running hook
+        let version = self.hook_log.version();
+        if version != self.hooks_view.version {
+            self.hooks_view.update(self.hook_log.lines(), version);
+            self.needs_redraw = true;
+        }
+
         // Non-blocking receive of all pending results
         while let Ok(result) = self.async_receiver.try_recv() {
-            match result {
-                AsyncLoadResult::PullRequests(Ok(prs)) => {
-                    self.pull_requests_view.set_loaded(prs);
-                }
-                AsyncLoadResult::PullRequests(Err(e)) => {
-                    self.pull_requests_view.set_error(e);
-                }
-                AsyncLoadResult::Issues(Ok(issues)) => {
-                    self.issues_view.set_loaded(issues);
-                }
-                AsyncLoadResult::Issues(Err(e)) => {
-                    self.issues_view.set_error(e);
-                }
-                AsyncLoadResult::Actions(Ok(runs)) => {
-                    self.actions_view.set_loaded(runs);
-                }
-                AsyncLoadResult::Actions(Err(e)) => {
-                    self.actions_view.set_error(e);
-                }
-                AsyncLoadResult::Releases(Ok(releases)) => {
-                    self.releases_view.set_loaded(releases);
+            self.handle_async_result(result);
+            self.needs_redraw = true;
+        }
+    }
+
+    /// Block until the status, commits and diff loads in flight have come back
+    pub fn wait_for_loads(&mut self) {
+        while self.jobs.any_loading() {
+            match self.async_receiver.recv_timeout(Duration::from_millis(20)) {
+                Ok(result) => self.handle_async_result(result),
+                // A job that died without a result leaves nothing to wait for
+                Err(_) if !self.jobs.is_busy() => match self.async_receiver.try_recv() {
+                    Ok(result) => self.handle_async_result(result),
+                    Err(_) => break,
+                },
+                Err(_) => {}
+            }
+        }
+    }
+
+    fn handle_async_result(&mut self, result: AsyncLoadResult) {
+        match result {
+            AsyncLoadResult::PullRequests(Ok(prs)) => {
+                self.pull_requests_view.set_loaded(prs);
+            }
+            AsyncLoadResult::PullRequests(Err(e)) => {
+                self.pull_requests_view.set_error(e);
+            }
+            AsyncLoadResult::Issues(Ok(issues)) => {
+                self.issues_view.set_loaded(issues);
+            }
+            AsyncLoadResult::Issues(Err(e)) => {
+                self.issues_view.set_error(e);
+            }
+            AsyncLoadResult::Actions(Ok(runs)) => {
+                self.actions_view.set_loaded(runs);
+            }
+            AsyncLoadResult::Actions(Err(e)) => {
+                self.actions_view.set_error(e);
+            }
+            AsyncLoadResult::Releases(Ok(releases)) => {
+                self.releases_view.set_loaded(releases);
+            }
+            AsyncLoadResult::Releases(Err(e)) => {
+                self.releases_view.set_error(e);
+            }
+            AsyncLoadResult::PrCommits(pr_number, Ok(commit_ids)) => {
+                // Only apply if we're still waiting for this PR's commits
+                if self.refreshing_pr_commits == Some(pr_number) {
+                    debug!(
+                        "Async fetch_pr_commits: got {} commits for PR #{}",
+                        commit_ids.len(),
+                        pr_number
+                    );
+                    self.commits_view.set_highlight_commits(commit_ids);
+                    self.refreshing_pr_commits = None;
                 }
-                AsyncLoadResult::Releases(Err(e)) => {
-                    self.releases_view.set_error(e);
+            }
+            AsyncLoadResult::PrCommits(pr_number, Err(e)) => {
+                if self.refreshing_pr_commits == Some(pr_number) {
+                    debug!("Async fetch_pr_commits error for PR #{}: {}", pr_number, e);
+                    self.refreshing_pr_commits = None;
                 }
-                AsyncLoadResult::PrCommits(pr_number, Ok(commit_ids)) => {
-                    // Only apply if we're still waiting for this PR's commits
-                    if self.refreshing_pr_commits == Some(pr_number) {
-                        debug!(
-                            "Async fetch_pr_commits: got {} commits for PR #{}",
-                            commit_ids.len(),
-                            pr_number
-                        );
-                        self.commits_view.set_highlight_commits(commit_ids);
-                        self.refreshing_pr_commits = None;
-                    }
+            }
+            AsyncLoadResult::GitStatus(generation, reset, scope, result) => {
+                if !self.jobs.finish(JobView::Status, generation) {
+                    return;
                 }
-                AsyncLoadResult::PrCommits(pr_number, Err(e)) => {
-                    if self.refreshing_pr_commits == Some(pr_number) {
-                        debug!("Async fetch_pr_commits error for PR #{}: {}", pr_number, e);
-                        self.refreshing_pr_commits = None;
+                let result = match result {
+                    Ok(fresh) if !scope.is_empty() => {
+                        Ok(merge_status(self.status_view.entries(), &scope, fresh))
                     }
+                    result => result,
+                };
+                match result {
+                    Ok(status) if reset => self.status_view.update(status),
+                    Ok(status) => self.status_view.update_preserve_scroll(status),
+                    Err(e) => debug!("Status load failed: {}", e),
                 }
-                AsyncLoadResult::GitStatus(Ok(status)) => {
-                    self.status_view.update_preserve_scroll(status);
-                    self.refreshing_status = false;
-                }
-                AsyncLoadResult::GitStatus(Err(_)) => {
-                    self.refreshing_status = false;
-                }
-                AsyncLoadResult::GitBranches(Ok(branches)) => {
-                    self.branches_view.update_preserve_scroll(branches);
-                    self.refreshing_branches = false;
-                }
-                AsyncLoadResult::GitBranches(Err(_)) => {
-                    self.refreshing_branches = false;
-                }
-                AsyncLoadResult::GitCommits(Ok(commits)) => {
-                    self.commits_view.update_preserve_scroll(commits);
-                    self.refreshing_commits = false;
-                }
-                AsyncLoadResult::GitCommits(Err(_)) => {
-                    self.refreshing_commits = false;
-                }
-                AsyncLoadResult::GitGraphCommits(Ok(commits)) => {
-                    self.commits_view.update_graph(commits);
-                    self.refreshing_graph_commits = false;
-                }
-                AsyncLoadResult::GitGraphCommits(Err(_)) => {
-                    self.refreshing_graph_commits = false;
-                }
-                AsyncLoadResult::GitTags(Ok(tags)) => {
-                    self.tags_view.update(tags);
-                    self.refreshing_tags = false;
-                }
-                AsyncLoadResult::GitTags(Err(_)) => {
-                    self.refreshing_tags = false;
-                }
-                AsyncLoadResult::GitRemotes(Ok(remotes)) => {
-                    self.remotes_view.update(remotes);
-                    self.refreshing_remotes = false;
-                }
-                AsyncLoadResult::GitRemotes(Err(_)) => {
-                    self.refreshing_remotes = false;
-                }
-                AsyncLoadResult::GitWorktrees(Ok(worktrees)) => {
-                    self.worktree_view.update(worktrees);
-                    self.refreshing_worktrees = false;
-                }
-                AsyncLoadResult::GitWorktrees(Err(_)) => {
-                    self.refreshing_worktrees = false;
-                }
-                AsyncLoadResult::GitSubmodules(Ok(submodules)) => {
-                    self.submodules_view.update(submodules);
-                    self.refreshing_submodules = false;
-                }
-                AsyncLoadResult::GitSubmodules(Err(_)) => {
-                    self.refreshing_submodules = false;
-                }
-                AsyncLoadResult::GitConflicts(Ok(conflicts)) => {
-                    self.conflict_view.update(conflicts);
-                    self.refreshing_conflicts = false;
-                }
-                AsyncLoadResult::GitConflicts(Err(_)) => {
-                    self.refreshing_conflicts = false;
-                }
-                AsyncLoadResult::GitFileTree(Ok(tree)) => {
-                    self.filetree_view.update(tree);
-                    self.refreshing_filetree = false;
+                if std::mem::take(&mut self.diff_pending) {
+                    let _ = self.refresh_diff();
                 }
-                AsyncLoadResult::GitFileTree(Err(_)) => {
-                    self.refreshing_filetree = false;
+            }
+            AsyncLoadResult::GitBranches(Ok(branches)) => {
+                self.branches_view.update_preserve_scroll(branches);
+                self.refreshing_branches = false;
+            }
+            AsyncLoadResult::GitBranches(Err(_)) => {
+                self.refreshing_branches = false;
+            }
+            AsyncLoadResult::GitCommits(generation, result) => {
+                if !self.jobs.finish(JobView::Commits, generation) {
+                    return;
                 }
-                AsyncLoadResult::RemoteOperationComplete(Ok(msg)) => {
-                    self.remote_operation = None;
-                    self.message = Some(msg);
-                    // Refresh all data after remote operation
-                    let _ = self.refresh_all();
+                match result {
+                    Ok(commits) => {
+                        self.commits_view.update_preserve_scroll(commits);
+                        self.commits_view.path_filter = self.commits_path.clone();
+                        // Set current branch for proper coloring
+                        self.commits_view
+                            .set_current_branch(self.repo.head_name().ok().flatten());
+                    }
+                    Err(e) => debug!("Commits load failed: {}", e),
                 }
-                AsyncLoadResult::RemoteOperationComplete(Err(e)) => {
-                    self.remote_operation = None;
-                    self.message = Some(format!("Error: {}", e));
+            }
+            AsyncLoadResult::GitDiff(generation, path, result) => {
+                if !self.jobs.finish(JobView::Diff, generation) {
+                    return;
                 }
-                AsyncLoadResult::IssueView(issue_number, Ok(content)) => {
-                    // Only update if this is still the issue we're waiting for
-                    if self.refreshing_issue_view == Some(issue_number) {
-                        self.diff_view.set_issue_preview(issue_number, content);
-                        self.refreshing_issue_view = None;
+                match result {
+                    Ok(diff) => {
+                        let file_idx = diff.files.iter().position(|f| f.path == path);
+                        self.diff_view.update(diff);
+                        if let Some(file_idx) = file_idx {
+                            self.diff_view.current_file = file_idx;
+                        }
                     }
+                    Err(e) => self.message = Some(format!("Diff failed: {}", e)),
                 }
-                AsyncLoadResult::IssueView(issue_number, Err(_)) => {
-                    if self.refreshing_issue_view == Some(issue_number) {
-                        self.refreshing_issue_view = None;
-                    }
+            }
+            AsyncLoadResult::GitBlame(generation, path, line, result) => {
+                if !self.jobs.finish(JobView::Blame, generation) {
+                    return;
                 }
-                AsyncLoadResult::ActionView(run_id, Ok(content)) => {
-                    // Only update if this is still the action run we're waiting for
-                    if self.refreshing_action_view == Some(run_id) {
-                        self.diff_view.set_action_preview(run_id, content);
-                        self.refreshing_action_view = None;
+                match result {
+                    Ok(blame) => {
+                        self.blame_view.clear();
+                        self.blame_view.update(blame);
+                        if let Some(line) = line {
+                            for _ in 1..line {
+                                self.blame_view.move_down();
+                            }
+                        }
+                        self.focus_panel(PanelType::Blame);
                     }
+                    Err(e) => self.message = Some(format!("Cannot blame {}: {}", path, e)),
                 }
-                AsyncLoadResult::ActionView(run_id, Err(_)) => {
-                    if self.refreshing_action_view == Some(run_id) {
-                        self.refreshing_action_view = None;
-                    }
+            }
+            AsyncLoadResult::GitGraphCommits(Ok(commits)) => {
+                self.commits_view.update_graph(commits);
+                self.refreshing_graph_commits = false;
+            }
+            AsyncLoadResult::GitGraphCommits(Err(_)) => {
+                self.refreshing_graph_commits = false;
+            }
+            AsyncLoadResult::GitTags(Ok(tags)) => {
+                self.tags_view.update(tags);
+                self.refreshing_tags = false;
+            }
+            AsyncLoadResult::GitTags(Err(_)) => {
+                self.refreshing_tags = false;
+            }
+            AsyncLoadResult::GitRemotes(Ok(remotes)) => {
+                self.remotes_view.update(remotes);
+                self.refreshing_remotes = false;
+            }
+            AsyncLoadResult::GitRemotes(Err(_)) => {
+                self.refreshing_remotes = false;
+            }
+            AsyncLoadResult::GitWorktrees(Ok(worktrees)) => {
+                self.worktree_view.update(worktrees);
+                self.refreshing_worktrees = false;
+            }
+            AsyncLoadResult::GitWorktrees(Err(_)) => {
+                self.refreshing_worktrees = false;
+            }
+            AsyncLoadResult::GitSubmodules(Ok(submodules)) => {
+                self.submodules_view.update(submodules);
+                self.refreshing_submodules = false;
+            }
+            AsyncLoadResult::GitSubmodules(Err(_)) => {
+                self.refreshing_submodules = false;
+            }
+            AsyncLoadResult::GitReflog(Ok((refs, entries))) => {
+                self.reflog_view.update(refs, entries);
+                self.refreshing_reflog = false;
+            }
+            AsyncLoadResult::GitReflog(Err(_)) => {
+                self.refreshing_reflog = false;
+            }
+            AsyncLoadResult::GitTrash(Ok(entries)) => {
+                self.trash_view.update(entries);
+                self.refreshing_trash = false;
+            }
+            AsyncLoadResult::GitTrash(Err(_)) => {
+                self.refreshing_trash = false;
+            }
+            AsyncLoadResult::GitConflicts(Ok(conflicts)) => {
+                self.conflict_view.update(conflicts);
+                self.refreshing_conflicts = false;
+            }
+            AsyncLoadResult::GitConflicts(Err(_)) => {
+                self.refreshing_conflicts = false;
+            }
+            AsyncLoadResult::GitFileTree(Ok(tree)) => {
+                self.filetree_view.update(tree);
+                self.refreshing_filetree = false;
+            }
+            AsyncLoadResult::GitFileTree(Err(_)) => {
+                self.refreshing_filetree = false;
+            }
+            AsyncLoadResult::RepoChanged(changes) => {
+                debug!("Repository changed: {:?}", changes);
+                self.start_background_refresh(changes);
+            }
+            AsyncLoadResult::RemoteOperationComplete(Ok(msg)) => {
+                self.remote_operation = None;
+                self.transfer_progress = None;
+                self.message = Some(msg);
+                // Refresh all data after remote operation
+                let _ = self.refresh_all();
+            }
+            AsyncLoadResult::RemoteOperationComplete(Err(e)) => {
+                self.remote_operation = None;
+                self.transfer_progress = None;
+                self.message = Some(format!("Error: {}", e));
+            }
+            AsyncLoadResult::PushRejected(remote, branch, explanation) => {
+                self.remote_operation = None;
+                self.transfer_progress = None;
+                self.open_pull_dialog(remote, branch);
+                self.message = Some(explanation);
+            }
+            AsyncLoadResult::IssueView(issue_number, Ok(content)) => {
+                // Only update if this is still the issue we're waiting for
+                if self.refreshing_issue_view == Some(issue_number) {
+                    self.diff_view.set_issue_preview(issue_number, content);
+                    self.refreshing_issue_view = None;
                 }
-                AsyncLoadResult::BackgroundFetchComplete(Ok(_msg)) => {
-                    self.fetching_remotes = false;
-                    // Refresh git data after fetch
-                    self.start_background_refresh_all();
+            }
+            AsyncLoadResult::IssueView(issue_number, Err(_)) => {
+                if self.refreshing_issue_view == Some(issue_number) {
+                    self.refreshing_issue_view = None;
+                }
+            }
+            AsyncLoadResult::ActionView(run_id, Ok(content)) => {
+                // Only update if this is still the action run we're waiting for
+                if self.refreshing_action_view == Some(run_id) {
+                    self.diff_view.set_action_preview(run_id, content);
+                    self.refreshing_action_view = None;
                 }
-                AsyncLoadResult::BackgroundFetchComplete(Err(_)) => {
-                    self.fetching_remotes = false;
+            }
+            AsyncLoadResult::ActionView(run_id, Err(_)) => {
+                if self.refreshing_action_view == Some(run_id) {
+                    self.refreshing_action_view = None;
                 }
             }
+            AsyncLoadResult::BackgroundFetchComplete(Ok(_msg)) => {
+                self.fetching_remotes = false;
+                // Refresh git data after fetch
+                self.start_background_refresh_all();
+            }
+            AsyncLoadResult::BackgroundFetchComplete(Err(_)) => {
+                self.fetching_remotes = false;
+            }
+            AsyncLoadResult::RepoSummary(summary) => {
+                self.repos_view
+                    .summaries
+                    .insert(summary.path.clone(), summary);
+            }
         }
     }
 
     /// Update loading states: check timeouts and tick spinners
     fn update_loading_states(&mut self) {
         // Check timeouts
+        let loading = self.github_views_loading();
         self.pull_requests_view.check_timeout();
         self.issues_view.check_timeout();
         self.actions_view.check_timeout();
         self.releases_view.check_timeout();
+        if loading && !self.github_views_loading() {
+            self.needs_redraw = true;
+        }
 
         // Tick spinners every 100ms
         if self.last_spinner_tick.elapsed() >= Duration::from_millis(100) {
@@ -578,9 +1158,24 @@ impl App {
             if self.remote_operation.is_some() {
                 self.remote_spinner_frame = (self.remote_spinner_frame + 1) % 8;
             }
+            // Spinners moved, or the jobs list shows running times
+            if self.github_views_loading()
+                || self.diff_view.is_action_loading()
+                || self.remote_operation.is_some()
+                || self.mode == Mode::Jobs
+            {
+                self.needs_redraw = true;
+            }
         }
     }
 
+    fn github_views_loading(&self) -> bool {
+        self.pull_requests_view.is_loading()
+            || self.issues_view.is_loading()
+            || self.actions_view.is_loading()
+            || self.releases_view.is_loading()
+    }
+
     /// Check if it's time to do a periodic background refresh for all data
     fn check_auto_refresh(&mut self) {
         let interval = self.config.auto_refresh;
@@ -599,7 +1194,20 @@ impl App {
 
         if should_refresh {
             self.last_auto_refresh = Some(Instant::now());
-            self.start_background_refresh_all();
+            match &self.watcher {
+                // Local data is refreshed as it changes; poll for the rest
+                Some(watcher) => {
+                    let worktree = watcher.watches_worktree();
+                    self.start_background_refresh_github();
+                    if !worktree {
+                        self.start_background_refresh(RepoChanges {
+                            status: true,
+                            ..Default::default()
+                        });
+                    }
+                }
+                None => self.start_background_refresh_all(),
+            }
         }
     }
 
@@ -637,39 +1245,46 @@ impl App {
         }
         self.fetching_remotes = true;
 
-        let sender = self.async_sender.clone();
         let repo_path = self.repo_path.clone();
-
-        thread::spawn(move || {
-            let result: std::result::Result<String, String> = (|| {
-                let repo = crate::git::Repository::open(&repo_path).map_err(|e| e.to_string())?;
-                let remotes = repo.remotes().map_err(|e| e.to_string())?;
-
-                let mut fetched = Vec::new();
-                let mut errors = Vec::new();
-
-                for remote in remotes {
-                    match repo.fetch(&remote) {
-                        Ok(()) => fetched.push(remote),
-                        Err(e) => errors.push(format!("{}: {}", remote, e)),
+        let remotes = self.repo.remotes().unwrap_or_default();
+        let refs = remotes.iter().map(|r| remote_refs(r)).collect();
+
+        self.jobs.spawn(
+            "Fetch all remotes",
+            refs,
+            CancelToken::new(),
+            self.async_sender.clone(),
+            move |token| {
+                let result: std::result::Result<String, String> = (|| {
+                    let repo =
+                        crate::git::Repository::open(&repo_path).map_err(|e| e.to_string())?;
+
+                    let mut fetched = Vec::new();
+                    let mut errors = Vec::new();
+
+                    for remote in remotes {
+                        match repo.fetch(&remote, &TransferProgress::with_cancel(token.clone())) {
+                            Ok(()) => fetched.push(remote),
+                            Err(e) => errors.push(format!("{}: {}", remote, e)),
+                        }
                     }
-                }
 
-                if errors.is_empty() {
-                    Ok(format!("Fetched from: {}", fetched.join(", ")))
-                } else if fetched.is_empty() {
-                    Err(errors.join("; "))
-                } else {
-                    Ok(format!(
-                        "Fetched from: {}; errors: {}",
-                        fetched.join(", "),
-                        errors.join("; ")
-                    ))
-                }
-            })();
+                    if errors.is_empty() {
+                        Ok(format!("Fetched from: {}", fetched.join(", ")))
+                    } else if fetched.is_empty() {
+                        Err(errors.join("; "))
+                    } else {
+                        Ok(format!(
+                            "Fetched from: {}; errors: {}",
+                            fetched.join(", "),
+                            errors.join("; ")
+                        ))
+                    }
+                })();
 
-            let _ = sender.send(AsyncLoadResult::BackgroundFetchComplete(result));
-        });
+                AsyncLoadResult::BackgroundFetchComplete(result)
+            },
+        );
     }
 
     /// Start async loading of pull requests
@@ -762,154 +1377,181 @@ impl App {
 
     /// Start background refresh for all data (no spinner, keeps showing old data)
     fn start_background_refresh_all(&mut self) {
-        // Refresh GitHub views
+        self.start_background_refresh_github();
+        self.start_background_refresh(RepoChanges::ALL);
+    }
+
+    fn start_background_refresh_github(&mut self) {
         self.start_loading_pull_requests_impl(true);
         self.start_loading_issues_impl(true);
         self.start_loading_actions_impl(true);
         self.start_loading_releases_impl(true);
+    }
 
-        // Refresh git status
-        if !self.refreshing_status {
-            self.refreshing_status = true;
-            let sender = self.async_sender.clone();
-            let repo_path = self.repo_path.clone();
-            thread::spawn(move || {
-                let result = match Repository::open(&repo_path) {
-                    Ok(repo) => repo.status().map_err(|e| e.to_string()),
-                    Err(e) => Err(e.to_string()),
-                };
-                let _ = sender.send(AsyncLoadResult::GitStatus(result));
-            });
-        }
+    /// Refresh the views affected by `changes` in the background
+    fn start_background_refresh(&mut self, changes: RepoChanges) {
+        if changes.status {
+            // Refresh git status
+            self.load_status(false, changes.status_scope.clone());
+
+            // Refresh conflicts
+            if !self.refreshing_conflicts {
+                self.refreshing_conflicts = true;
+                let sender = self.async_sender.clone();
+                let repo_path = self.repo_path.clone();
+                thread::spawn(move || {
+                    let result = match Repository::open(&repo_path) {
+                        Ok(repo) => repo.conflicts().map_err(|e| e.to_string()),
+                        Err(e) => Err(e.to_string()),
+                    };
+                    let _ = sender.send(AsyncLoadResult::GitConflicts(result));
+                });
+            }
 
-        // Refresh branches
-        if !self.refreshing_branches {
-            self.refreshing_branches = true;
-            let sender = self.async_sender.clone();
-            let repo_path = self.repo_path.clone();
-            let show_remote = self.branches_view.show_remote;
-            thread::spawn(move || {
-                let result = match Repository::open(&repo_path) {
-                    Ok(repo) => repo.branches(show_remote).map_err(|e| e.to_string()),
-                    Err(e) => Err(e.to_string()),
-                };
-                let _ = sender.send(AsyncLoadResult::GitBranches(result));
-            });
+            // Refresh file tree
+            if !self.refreshing_filetree {
+                self.refreshing_filetree = true;
+                let sender = self.async_sender.clone();
+                let repo_path = self.repo_path.clone();
+                let show_ignored = self.filetree_view.show_ignored();
+                thread::spawn(move || {
+                    let result = match Repository::open(&repo_path) {
+                        Ok(repo) => repo.file_tree(show_ignored).map_err(|e| e.to_string()),
+                        Err(e) => Err(e.to_string()),
+                    };
+                    let _ = sender.send(AsyncLoadResult::GitFileTree(result));
+                });
+            }
         }
 
-        // Refresh commits
-        if !self.refreshing_commits {
-            self.refreshing_commits = true;
-            let sender = self.async_sender.clone();
-            let repo_path = self.repo_path.clone();
-            let max_commits = self.config.max_commits;
-            thread::spawn(move || {
-                let result = match Repository::open(&repo_path) {
-                    Ok(repo) => repo.commits(max_commits).map_err(|e| e.to_string()),
-                    Err(e) => Err(e.to_string()),
-                };
-                let _ = sender.send(AsyncLoadResult::GitCommits(result));
-            });
-        }
+        if changes.branches {
+            // Refresh branches
+            if !self.refreshing_branches {
+                self.refreshing_branches = true;
+                let sender = self.async_sender.clone();
+                let repo_path = self.repo_path.clone();
+                let show_remote = self.branches_view.show_remote;
+                thread::spawn(move || {
+                    let result = match Repository::open(&repo_path) {
+                        Ok(repo) => repo.branches(show_remote).map_err(|e| e.to_string()),
+                        Err(e) => Err(e.to_string()),
+                    };
+                    let _ = sender.send(AsyncLoadResult::GitBranches(result));
+                });
+            }
 
-        // Refresh graph commits
-        if !self.refreshing_graph_commits {
-            self.refreshing_graph_commits = true;
-            let sender = self.async_sender.clone();
-            let repo_path = self.repo_path.clone();
-            let max_commits = self.config.max_commits;
-            thread::spawn(move || {
-                let result = match Repository::open(&repo_path) {
-                    Ok(repo) => repo.log_graph(max_commits).map_err(|e| e.to_string()),
-                    Err(e) => Err(e.to_string()),
-                };
-                let _ = sender.send(AsyncLoadResult::GitGraphCommits(result));
-            });
-        }
+            // Refresh commits
+            self.load_commits();
 
-        // Refresh tags
-        if !self.refreshing_tags {
-            self.refreshing_tags = true;
-            let sender = self.async_sender.clone();
-            let repo_path = self.repo_path.clone();
-            thread::spawn(move || {
-                let result = match Repository::open(&repo_path) {
-                    Ok(repo) => repo.tags().map_err(|e| e.to_string()),
-                    Err(e) => Err(e.to_string()),
-                };
-                let _ = sender.send(AsyncLoadResult::GitTags(result));
-            });
-        }
+            // Refresh graph commits
+            if !self.refreshing_graph_commits {
+                self.refreshing_graph_commits = true;
+                let sender = self.async_sender.clone();
+                let repo_path = self.repo_path.clone();
+                let max_commits = self.config.max_commits;
+                thread::spawn(move || {
+                    let result = match Repository::open(&repo_path) {
+                        Ok(repo) => repo.log_graph(max_commits).map_err(|e| e.to_string()),
+                        Err(e) => Err(e.to_string()),
+                    };
+                    let _ = sender.send(AsyncLoadResult::GitGraphCommits(result));
+                });
+            }
 
-        // Refresh remotes
-        if !self.refreshing_remotes {
-            self.refreshing_remotes = true;
-            let sender = self.async_sender.clone();
-            let repo_path = self.repo_path.clone();
-            thread::spawn(move || {
-                let result = match Repository::open(&repo_path) {
-                    Ok(repo) => repo.remote_info().map_err(|e| e.to_string()),
-                    Err(e) => Err(e.to_string()),
-                };
-                let _ = sender.send(AsyncLoadResult::GitRemotes(result));
-            });
+            // Refresh reflog
+            if !self.refreshing_reflog {
+                self.refreshing_reflog = true;
+                let sender = self.async_sender.clone();
+                let repo_path = self.repo_path.clone();
+                let refname = self.reflog_view.current_ref().to_string();
+                thread::spawn(move || {
+                    let result = match Repository::open(&repo_path) {
+                        Ok(repo) => load_reflog(&repo, &refname).map_err(|e| e.to_string()),
+                        Err(e) => Err(e.to_string()),
+                    };
+                    let _ = sender.send(AsyncLoadResult::GitReflog(result));
+                });
+            }
         }
 
-        // Refresh worktrees
-        if !self.refreshing_worktrees {
-            self.refreshing_worktrees = true;
-            let sender = self.async_sender.clone();
-            let repo_path = self.repo_path.clone();
-            thread::spawn(move || {
-                let result = match Repository::open(&repo_path) {
-                    Ok(repo) => repo.worktrees().map_err(|e| e.to_string()),
-                    Err(e) => Err(e.to_string()),
-                };
-                let _ = sender.send(AsyncLoadResult::GitWorktrees(result));
-            });
+        if changes.tags {
+            // Refresh tags
+            if !self.refreshing_tags {
+                self.refreshing_tags = true;
+                let sender = self.async_sender.clone();
+                let repo_path = self.repo_path.clone();
+                thread::spawn(move || {
+                    let result = match Repository::open(&repo_path) {
+                        Ok(repo) => repo.tags().map_err(|e| e.to_string()),
+                        Err(e) => Err(e.to_string()),
+                    };
+                    let _ = sender.send(AsyncLoadResult::GitTags(result));
+                });
+            }
         }
 
-        // Refresh submodules
-        if !self.refreshing_submodules {
-            self.refreshing_submodules = true;
-            let sender = self.async_sender.clone();
-            let repo_path = self.repo_path.clone();
-            thread::spawn(move || {
-                let result = match Repository::open(&repo_path) {
-                    Ok(repo) => repo.submodules().map_err(|e| e.to_string()),
-                    Err(e) => Err(e.to_string()),
-                };
-                let _ = sender.send(AsyncLoadResult::GitSubmodules(result));
-            });
-        }
+        if changes.stash {
+            if let Err(e) = self.refresh_stash() {
+                debug!("Stash refresh failed: {}", e);
+            }
 
-        // Refresh conflicts
-        if !self.refreshing_conflicts {
-            self.refreshing_conflicts = true;
-            let sender = self.async_sender.clone();
-            let repo_path = self.repo_path.clone();
-            thread::spawn(move || {
-                let result = match Repository::open(&repo_path) {
-                    Ok(repo) => repo.conflicts().map_err(|e| e.to_string()),
-                    Err(e) => Err(e.to_string()),
-                };
-                let _ = sender.send(AsyncLoadResult::GitConflicts(result));
-            });
+            // Refresh trash
+            if !self.refreshing_trash {
+                self.refreshing_trash = true;
+                let sender = self.async_sender.clone();
+                let repo_path = self.repo_path.clone();
+                thread::spawn(move || {
+                    let result = match Repository::open(&repo_path) {
+                        Ok(repo) => repo.trash_entries().map_err(|e| e.to_string()),
+                        Err(e) => Err(e.to_string()),
+                    };
+                    let _ = sender.send(AsyncLoadResult::GitTrash(result));
+                });
+            }
         }
 
-        // Refresh file tree
-        if !self.refreshing_filetree {
-            self.refreshing_filetree = true;
-            let sender = self.async_sender.clone();
-            let repo_path = self.repo_path.clone();
-            let show_ignored = self.filetree_view.show_ignored();
-            thread::spawn(move || {
-                let result = match Repository::open(&repo_path) {
-                    Ok(repo) => repo.file_tree(show_ignored).map_err(|e| e.to_string()),
-                    Err(e) => Err(e.to_string()),
-                };
-                let _ = sender.send(AsyncLoadResult::GitFileTree(result));
-            });
+        if changes.config {
+            // Refresh remotes
+            if !self.refreshing_remotes {
+                self.refreshing_remotes = true;
+                let sender = self.async_sender.clone();
+                let repo_path = self.repo_path.clone();
+                thread::spawn(move || {
+                    let result = match Repository::open(&repo_path) {
+                        Ok(repo) => repo.remote_info().map_err(|e| e.to_string()),
+                        Err(e) => Err(e.to_string()),
+                    };
+                    let _ = sender.send(AsyncLoadResult::GitRemotes(result));
+                });
+            }
+
+            // Refresh worktrees
+            if !self.refreshing_worktrees {
+                self.refreshing_worktrees = true;
+                let sender = self.async_sender.clone();
+                let repo_path = self.repo_path.clone();
+                thread::spawn(move || {
+                    let result = match Repository::open(&repo_path) {
+                        Ok(repo) => repo.worktrees().map_err(|e| e.to_string()),
+                        Err(e) => Err(e.to_string()),
+                    };
+                    let _ = sender.send(AsyncLoadResult::GitWorktrees(result));
+                });
+            }
+
+            // Refresh submodules
+            if !self.refreshing_submodules {
+                self.refreshing_submodules = true;
+                let sender = self.async_sender.clone();
+                let repo_path = self.repo_path.clone();
+                thread::spawn(move || {
+                    let result = match Repository::open(&repo_path) {
+                        Ok(repo) => repo.submodules().map_err(|e| e.to_string()),
+                        Err(e) => Err(e.to_string()),
+                    };
+                    let _ = sender.send(AsyncLoadResult::GitSubmodules(result));
+                });
+            }
         }
     }
 
@@ -928,6 +1570,8 @@ impl App {
         self.start_loading_releases();
         self.refresh_worktrees()?;
         self.refresh_submodules()?;
+        self.refresh_reflog()?;
+        self.refresh_trash()?;
         self.refresh_conflicts()?;
         self.refresh_filetree()?;
         self.refresh_graph_commits()?;
@@ -964,6 +1608,18 @@ impl App {
         Ok(())
     }
 
+    fn refresh_reflog(&mut self) -> Result<()> {
+        let (refs, entries) = load_reflog(&self.repo, self.reflog_view.current_ref())?;
+        self.reflog_view.update(refs, entries);
+        Ok(())
+    }
+
+    fn refresh_trash(&mut self) -> Result<()> {
+        let entries = self.repo.trash_entries()?;
+        self.trash_view.update(entries);
+        Ok(())
+    }
+
     fn refresh_conflicts(&mut self) -> Result<()> {
         let conflicts = self.repo.conflicts()?;
         self.conflict_view.update(conflicts);
@@ -987,11 +1643,45 @@ impl App {
     }
 
     fn refresh_status(&mut self) -> Result<()> {
-        let status = self.repo.status()?;
-        self.status_view.update(status);
+        self.load_status(true, Vec::new());
         Ok(())
     }
 
+    /// Load the status in the background; `reset` selects the first entry
+    /// again. With a `scope`, only those places are read again and merged in.
+    fn load_status(&mut self, reset: bool, mut scope: Vec<String>) {
+        let show_untracked = self.config.git.show_untracked;
+        // The load this one supersedes may have covered more than the scope
+        if reset || self.jobs.is_loading(JobView::Status) {
+            scope.clear();
+        }
+        let (generation, token) = self.jobs.begin(JobView::Status);
+        let repo_path = self.repo_path.clone();
+        self.jobs.spawn(
+            "Status",
+            Vec::new(),
+            token,
+            self.async_sender.clone(),
+            move |token| {
+                let result = Repository::open(&repo_path)
+                    .map(|repo| repo.with_cancel(token.clone()))
+                    .and_then(|repo| {
+                        let untracked = show_untracked.unwrap_or_else(|| repo.untracked_default());
+                        // Untracked directories listed as one entry span scopes
+                        if untracked == UntrackedFiles::Normal {
+                            scope.clear();
+                        }
+                        repo.status_with(&StatusQuery {
+                            untracked,
+                            scope: scope.clone(),
+                        })
+                    })
+                    .map_err(|e| e.to_string());
+                AsyncLoadResult::GitStatus(generation, reset, scope, result)
+            },
+        );
+    }
+
     fn refresh_branches(&mut self) -> Result<()> {
         let branches = self.repo.branches(self.branches_view.show_remote)?;
         self.branches_view.update(branches);
@@ -999,31 +1689,100 @@ impl App {
     }
 
     fn refresh_commits(&mut self) -> Result<()> {
-        let commits = self.repo.commits(self.config.max_commits)?;
-        self.commits_view.update(commits);
-        // Set current branch for proper coloring
-        self.commits_view
-            .set_current_branch(self.repo.head_name().ok().flatten());
+        self.load_commits();
         Ok(())
     }
 
+    fn load_commits(&mut self) {
+        let (generation, token) = self.jobs.begin(JobView::Commits);
+        let repo_path = self.repo_path.clone();
+        let max_commits = self.config.max_commits;
+        let path = self.commits_path.clone();
+        let label = match &path {
+            Some(path) => format!("Commits of {}", path),
+            None => "Commits".to_string(),
+        };
+        self.jobs.spawn(
+            label,
+            Vec::new(),
+            token,
+            self.async_sender.clone(),
+            move |token| {
+                let result = Repository::open(&repo_path)
+                    .map(|repo| repo.with_cancel(token.clone()))
+                    .and_then(|repo| match path {
+                        Some(path) => repo.file_commits(&path, max_commits),
+                        None => repo.commits(max_commits),
+                    })
+                    .map_err(|e| e.to_string());
+                AsyncLoadResult::GitCommits(generation, result)
+            },
+        );
+    }
+
     fn refresh_diff(&mut self) -> Result<()> {
+        if self.jobs.is_loading(JobView::Status) {
+            // The selection may still change; the status result refreshes the diff
+            self.diff_pending = true;
+            return Ok(());
+        }
         // Show diff based on status selection
-        if let Some(entry) = self.status_view.selected_entry() {
-            let diff = match self.status_view.section {
-                Section::Staged => self.repo.diff_staged()?,
-                _ => self.repo.diff_unstaged()?,
-            };
+        let Some(entry) = self.status_view.selected_entry() else {
+            self.jobs.begin(JobView::Diff);
+            self.diff_view.clear();
+            return Ok(());
+        };
+        let path = entry.path.clone();
+        let staged = self.status_view.section == Section::Staged;
+        let size_limit = match &self.large_diff_path {
+            Some(large) if *large == path => None,
+            _ => self.config.size_limit(),
+        };
+        let (generation, token) = self.jobs.begin(JobView::Diff);
+        let repo_path = self.repo_path.clone();
+        self.jobs.spawn(
+            format!("Diff of {}", path),
+            Vec::new(),
+            token,
+            self.async_sender.clone(),
+            move |_| {
+                let result = Repository::open(&repo_path)
+                    .and_then(|repo| repo.with_size_limit(size_limit).diff_file(&path, staged))
+                    .map_err(|e| e.to_string());
+                AsyncLoadResult::GitDiff(generation, path, result)
+            },
+        );
+        Ok(())
+    }
 
-            // Find the file in diff
-            if let Some(file_idx) = diff.files.iter().position(|f| f.path == entry.path) {
-                self.diff_view.update(diff);
-                self.diff_view.current_file = file_idx;
-            } else {
-                self.diff_view.update(diff);
+    /// Show the large file collapsed in the preview, reading it without the size limit
+    fn load_preview_anyway(&mut self) -> Result<()> {
+        let repo = Repository::open(&self.repo_path)?;
+        match self.diff_view.preview_type {
+            PreviewType::Diff => {
+                self.large_diff_path = self.status_view.selected_entry().map(|e| e.path.clone());
+                self.refresh_diff()?;
             }
-        } else {
-            self.diff_view.clear();
+            PreviewType::FileContent => {
+                if let Some(path) = self
+                    .diff_view
+                    .file_content
+                    .as_ref()
+                    .map(|fc| fc.path.clone())
+                {
+                    if let FileText::Text(content) = repo.read_file_text(&path)? {
+                        self.diff_view.set_file_content(path, content);
+                    }
+                }
+            }
+            PreviewType::Commit => {
+                if let Some(id) = self.diff_view.commit_preview.as_ref().map(|c| c.id.clone()) {
+                    let scroll = self.diff_view.scroll;
+                    self.diff_view.set_commit_diff(repo.diff_commit(&id)?);
+                    self.diff_view.scroll = scroll;
+                }
+            }
+            _ => {}
         }
         Ok(())
     }
@@ -1032,10 +1791,13 @@ impl App {
         if let Some(entry) = self.filetree_view.selected_entry() {
             if !entry.is_dir {
                 let path = entry.path.clone();
-                match self.repo.read_file_content(&path) {
-                    Ok(content) => {
+                match self.repo.read_file_text(&path) {
+                    Ok(FileText::Text(content)) => {
                         self.diff_view.set_file_content(path, content);
                     }
+                    Ok(FileText::Omitted(omitted)) => {
+                        self.diff_view.set_file_omitted(path, omitted);
+                    }
                     Err(_) => {
                         self.diff_view.clear();
                     }
@@ -1098,6 +1860,38 @@ impl App {
         }
     }
 
+    fn refresh_reflog_preview(&mut self) {
+        let commit = self
+            .reflog_view
+            .selected_entry()
+            .and_then(|entry| self.repo.commit_info(&entry.id).ok());
+        match commit {
+            Some(commit) => {
+                self.diff_view.set_commit_preview(&commit);
+                if let Ok(diff) = self.repo.diff_commit(&commit.id) {
+                    self.diff_view.set_commit_diff(diff);
+                }
+            }
+            None => self.diff_view.clear_commit_preview(),
+        }
+    }
+
+    fn refresh_trash_preview(&mut self) {
+        let commit = self
+            .trash_view
+            .selected_entry()
+            .and_then(|entry| self.repo.commit_info(&entry.id).ok());
+        match commit {
+            Some(commit) => {
+                self.diff_view.set_commit_preview(&commit);
+                if let Ok(diff) = self.repo.diff_commit(&commit.id) {
+                    self.diff_view.set_commit_diff(diff);
+                }
+            }
+            None => self.diff_view.clear_commit_preview(),
+        }
+    }
+
     fn refresh_issue_preview(&mut self) {
         if let Some(issue) = self.issues_view.selected_issue() {
             let issue_number = issue.number;
@@ -1241,47 +2035,490 @@ impl App {
         }
         self.remote_operation = Some(RemoteOperation::Fetch(branch.clone()));
         self.remote_spinner_frame = 0;
+        let token = CancelToken::new();
+        let progress = TransferProgress::with_cancel(token.clone());
+        self.transfer_progress = Some(progress.clone());
 
-        let sender = self.async_sender.clone();
         let repo_path = self.repo_path.clone();
 
-        thread::spawn(move || {
-            let result = crate::git::Repository::open(&repo_path)
-                .and_then(|repo| repo.fetch_branch(&remote, &branch))
-                .map(|()| format!("Fetched {}/{}", remote, branch))
-                .map_err(|e| e.to_string());
-            let _ = sender.send(AsyncLoadResult::RemoteOperationComplete(result));
-        });
+        self.jobs.spawn(
+            format!("Fetch {}/{}", remote, branch),
+            vec![remote_refs(&remote)],
+            token,
+            self.async_sender.clone(),
+            move |_| {
+                let result = crate::git::Repository::open(&repo_path)
+                    .and_then(|repo| repo.fetch_branch(&remote, &branch, &progress))
+                    .map(|()| format!("Fetched {}/{}", remote, branch))
+                    .map_err(|e| e.to_string());
+                AsyncLoadResult::RemoteOperationComplete(result)
+            },
+        );
     }
 
     /// Start async fetch operation for entire remote with spinner
     fn start_async_fetch_remote(&mut self, remote: String) {
         if self.remote_operation.is_some() {
-            self.message = Some("Remote operation already in progress".to_string());
+            self.message = Some("Remote operation already in progress".to_string());
+            return;
+        }
+        self.remote_operation = Some(RemoteOperation::Fetch(remote.clone()));
+        self.remote_spinner_frame = 0;
+        let token = CancelToken::new();
+        let progress = TransferProgress::with_cancel(token.clone());
+        self.transfer_progress = Some(progress.clone());
+
+        let repo_path = self.repo_path.clone();
+
+        self.jobs.spawn(
+            format!("Fetch {}", remote),
+            vec![remote_refs(&remote)],
+            token,
+            self.async_sender.clone(),
+            move |_| {
+                let result = crate::git::Repository::open(&repo_path)
+                    .and_then(|repo| repo.fetch(&remote, &progress))
+                    .map(|()| format!("Fetched from {}", remote))
+                    .map_err(|e| e.to_string());
+                AsyncLoadResult::RemoteOperationComplete(result)
+            },
+        );
+    }
+
+    /// Pull strategy and autostash: g's [git] config first, then git's own pull config
+    fn pull_options(&self) -> PullOptions {
+        let defaults = self.repo.pull_defaults();
+        PullOptions {
+            strategy: self.config.git.pull_strategy.unwrap_or(defaults.strategy),
+            autostash: self.config.git.pull_autostash.unwrap_or(defaults.autostash),
+        }
+    }
+
+    /// Ask how to pull `remote/branch`, preselecting the configured strategy
+    fn open_pull_dialog(&mut self, remote: String, branch: String) {
+        let options = self.pull_options();
+        self.select_index = PullStrategy::ALL
+            .iter()
+            .position(|s| *s == options.strategy)
+            .unwrap_or(0);
+        self.pull_autostash = options.autostash;
+        self.confirm_target = Some(format!("{}/{}", remote, branch));
+        self.pull_target = Some((remote, branch));
+        self.mode = Mode::Select(SelectAction::PullStrategy);
+    }
+
+    /// Start async pull operation with spinner
+    fn start_async_pull(&mut self, remote: String, branch: String, options: PullOptions) {
+        if self.remote_operation.is_some() {
+            self.message = Some("Remote operation already in progress".to_string());
+            return;
+        }
+        self.remote_operation = Some(RemoteOperation::Pull(branch.clone()));
+        self.remote_spinner_frame = 0;
+        let token = CancelToken::new();
+        let progress = TransferProgress::with_cancel(token.clone());
+        self.transfer_progress = Some(progress.clone());
+
+        let repo_path = self.repo_path.clone();
+        let hook_log = self.hook_log.clone();
+
+        // Moves the remote-tracking branch and then HEAD
+        self.jobs.spawn(
+            format!("Pull {}/{}", remote, branch),
+            vec![remote_refs(&remote), "HEAD".to_string()],
+            token,
+            self.async_sender.clone(),
+            move |_| {
+                let result = crate::git::Repository::open(&repo_path)
+                    .map(|repo| repo.with_hook_log(hook_log))
+                    .and_then(|mut repo| repo.pull_branch(&remote, &branch, options, &progress))
+                    .map(|outcome| outcome.summary(&format!("{}/{}", remote, branch)))
+                    .map_err(|e| e.to_string());
+                AsyncLoadResult::RemoteOperationComplete(result)
+            },
+        );
+    }
+
+    /// Check out `target`, first asking what to do with uncommitted changes
+    fn request_checkout(&mut self, target: CheckoutTarget) -> Result<()> {
+        let preview = match self.repo.checkout_preview(&target) {
+            Ok(preview) => preview,
+            Err(e) => {
+                self.message = Some(format!("Checkout failed: {}", e));
+                return Ok(());
+            }
+        };
+        if preview.is_clean() {
+            return self.run_checkout(target, CheckoutMode::Carry);
+        }
+
+        self.message = Some(if preview.blocking.is_empty() {
+            format!(
+                "{} changed file(s), none touched by the switch",
+                preview.changed.len()
+            )
+        } else {
+            format!("Would be overwritten: {}", preview.blocking.join(", "))
+        });
+        // Carrying cannot work when files block; default to stashing then
+        self.select_index = if preview.blocking.is_empty() { 0 } else { 1 };
+        self.confirm_target = Some(target.display_name().to_string());
+        self.pending_checkout = Some(target);
+        self.mode = Mode::Select(SelectAction::CheckoutDirty);
+        Ok(())
+    }
+
+    fn run_checkout(&mut self, target: CheckoutTarget, mode: CheckoutMode) -> Result<()> {
+        let verb = match target {
+            CheckoutTarget::Branch(..) => "Switched to",
+            CheckoutTarget::Commit(_) => "Checked out",
+        };
+        let Some(_hold) = self.hold_local_refs() else {
+            return Ok(());
+        };
+        match self.repo.checkout(&target, mode) {
+            Ok(outcome) => {
+                let name = target.display_name();
+                self.message = Some(if outcome.stash_kept {
+                    format!(
+                        "{}: {} (stashed changes conflict; kept in stash@{{0}})",
+                        verb, name
+                    )
+                } else {
+                    format!("{}: {}", verb, name)
+                });
+                self.refresh_all()?;
+            }
+            Err(e) => {
+                self.message = Some(format!("Checkout failed: {}", e));
+            }
+        }
+        Ok(())
+    }
+
+    /// Open the merge dialog for a branch, remote-tracking branch or commit
+    fn open_merge_dialog(&mut self, target: String, label: String) -> Result<()> {
+        let message = self.repo.merge_message(&target)?;
+        let (subject, shortlog) = match message.split_once("\n\n") {
+            Some((subject, body)) => (subject.to_string(), body.to_string()),
+            None => (message, String::new()),
+        };
+        self.merge_dialog = Some(MergeDialog {
+            target,
+            label,
+            mode: self.repo.merge_defaults(),
+            subject,
+            include_shortlog: !shortlog.is_empty(),
+            shortlog,
+            no_verify: false,
+            field: MergeField::Mode,
+        });
+        self.mode = Mode::Merge;
+        Ok(())
+    }
+
+    fn handle_merge_key(&mut self, key: KeyEvent) -> Result<()> {
+        let dialog = match self.merge_dialog.as_mut() {
+            Some(d) => d,
+            None => {
+                self.mode = Mode::Normal;
+                return Ok(());
+            }
+        };
+        match key.code {
+            KeyCode::Escape => {
+                self.message = Some("Cancelled".to_string());
+                self.merge_dialog = None;
+                self.mode = Mode::Normal;
+            }
+            KeyCode::Enter => {
+                let request = dialog.request();
+                let label = dialog.label.clone();
+                self.merge_dialog = None;
+                self.mode = Mode::Normal;
+                self.run_merge(&request, &label)?;
+            }
+            KeyCode::Tab | KeyCode::Down => {
+                dialog.field = match dialog.field {
+                    MergeField::Mode => MergeField::Subject,
+                    MergeField::Subject => MergeField::Shortlog,
+                    MergeField::Shortlog => MergeField::NoVerify,
+                    MergeField::NoVerify => MergeField::Mode,
+                };
+            }
+            KeyCode::BackTab | KeyCode::Up => {
+                dialog.field = match dialog.field {
+                    MergeField::Mode => MergeField::NoVerify,
+                    MergeField::Subject => MergeField::Mode,
+                    MergeField::Shortlog => MergeField::Subject,
+                    MergeField::NoVerify => MergeField::Shortlog,
+                };
+            }
+            KeyCode::Left if dialog.field == MergeField::Mode => dialog.cycle_mode(false),
+            KeyCode::Right | KeyCode::Char(' ') if dialog.field == MergeField::Mode => {
+                dialog.cycle_mode(true)
+            }
+            KeyCode::Char(' ') if dialog.field == MergeField::Shortlog => {
+                dialog.include_shortlog = !dialog.include_shortlog;
+            }
+            KeyCode::Char(' ') if dialog.field == MergeField::NoVerify => {
+                dialog.no_verify = !dialog.no_verify;
+            }
+            KeyCode::Char(c) if dialog.field == MergeField::Subject => dialog.subject.push(c),
+            KeyCode::Backspace if dialog.field == MergeField::Subject => {
+                dialog.subject.pop();
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn run_merge(&mut self, request: &MergeRequest, label: &str) -> Result<()> {
+        self.hook_log.clear();
+        let Some(_hold) = self.hold_local_refs() else {
+            return Ok(());
+        };
+        match self.repo.merge_with(request) {
+            Ok(result) => {
+                let msg = match result {
+                    crate::git::MergeResult::UpToDate => {
+                        format!("Already up to date with '{}'", label)
+                    }
+                    crate::git::MergeResult::FastForward => {
+                        format!("Fast-forwarded to '{}'", label)
+                    }
+                    crate::git::MergeResult::Merged | crate::git::MergeResult::Rebased => {
+                        format!("Merged '{}' into current branch", label)
+                    }
+                    crate::git::MergeResult::Squashed => format!(
+                        "Squashed '{}' into the index - commit to finish (c in Status)",
+                        label
+                    ),
+                    crate::git::MergeResult::Conflict
+                    | crate::git::MergeResult::StoppedForConflict => format!(
+                        "Merge conflicts with '{}' - resolve in the Conflicts panel, then commit",
+                        label
+                    ),
+                    crate::git::MergeResult::Aborted => {
+                        format!("'{}' cannot be fast-forwarded; nothing changed", label)
+                    }
+                };
+                self.message = Some(msg);
+                self.refresh_all()?;
+            }
+            Err(e) => {
+                self.message = Some(format!("Merge failed: {}", e));
+            }
+        }
+        Ok(())
+    }
+
+    /// Start async push operation with spinner
+    fn start_async_push(&mut self, request: PushRequest) {
+        if self.remote_operation.is_some() {
+            self.message = Some("Remote operation already in progress".to_string());
+            return;
+        }
+        self.remote_operation = Some(RemoteOperation::Push(request.source.clone()));
+        self.remote_spinner_frame = 0;
+        let token = CancelToken::new();
+        let progress = TransferProgress::with_cancel(token.clone());
+        self.transfer_progress = Some(progress.clone());
+
+        let repo_path = self.repo_path.clone();
+        self.hook_log.clear();
+        let hook_log = self.hook_log.clone();
+
+        self.jobs.spawn(
+            format!("Push {} to {}", request.source, request.remote),
+            vec![remote_refs(&request.remote)],
+            token,
+            self.async_sender.clone(),
+            move |_| {
+                let result = crate::git::Repository::open(&repo_path)
+                    .map(|repo| repo.with_hook_log(hook_log))
+                    .and_then(|repo| repo.push_with(&request, &progress));
+                let message = match result {
+                    Ok(summary) => AsyncLoadResult::RemoteOperationComplete(Ok(summary)),
+                    Err(crate::error::Error::Git(e))
+                        if e.code() == git2::ErrorCode::NotFastForward =>
+                    {
+                        AsyncLoadResult::PushRejected(
+                            request.remote.clone(),
+                            request.destination.clone(),
+                            e.message().to_string(),
+                        )
+                    }
+                    Err(e) => AsyncLoadResult::RemoteOperationComplete(Err(e.to_string())),
+                };
+                message
+            },
+        );
+    }
+
+    /// Commit in the background so hook output streams into the Hooks panel
+    fn start_async_commit(&mut self, message: String) {
+        if self.remote_operation.is_some() {
+            self.message = Some("Another operation is in progress".to_string());
             return;
         }
-        self.remote_operation = Some(RemoteOperation::Fetch(remote.clone()));
+        self.remote_operation = Some(RemoteOperation::Commit);
         self.remote_spinner_frame = 0;
+        self.hook_log.clear();
 
-        let sender = self.async_sender.clone();
         let repo_path = self.repo_path.clone();
+        let hook_log = self.hook_log.clone();
+        let no_verify = self.commit_no_verify;
+
+        self.jobs.spawn(
+            "Commit",
+            vec!["HEAD".to_string()],
+            CancelToken::new(),
+            self.async_sender.clone(),
+            move |_| {
+                let result = crate::git::Repository::open(&repo_path)
+                    .map(|repo| repo.with_hook_log(hook_log))
+                    .and_then(|repo| repo.commit(&message, no_verify))
+                    .map(|oid| format!("Created commit: {}", &oid[..7]))
+                    .map_err(|e| match e {
+                        crate::error::Error::Hook(msg) => format!("{} (see Hooks panel)", msg),
+                        e => e.to_string(),
+                    });
+                AsyncLoadResult::RemoteOperationComplete(result)
+            },
+        );
+    }
 
-        thread::spawn(move || {
-            let result = crate::git::Repository::open(&repo_path)
-                .and_then(|repo| repo.fetch(&remote))
-                .map(|()| format!("Fetched from {}", remote))
-                .map_err(|e| e.to_string());
-            let _ = sender.send(AsyncLoadResult::RemoteOperationComplete(result));
-        });
+    /// Open the push dialog for `branch` (the current branch when None)
+    fn open_push_dialog(&mut self, branch: Option<String>) -> Result<()> {
+        let branch = match branch.or(self.repo.head_name()?) {
+            Some(b) => b,
+            None => {
+                self.message = Some("HEAD is detached; select a branch to push".to_string());
+                return Ok(());
+            }
+        };
+        let remotes = self.repo.remotes()?;
+        if remotes.is_empty() {
+            self.message = Some("No remote configured".to_string());
+            return Ok(());
+        }
+        let upstream = self.repo.branch_upstream(&branch);
+        let remote_index = upstream
+            .as_ref()
+            .and_then(|(remote, _)| remotes.iter().position(|r| r == remote))
+            .or_else(|| remotes.iter().position(|r| r == "origin"))
+            .unwrap_or(0);
+        let destination = upstream
+            .as_ref()
+            .map(|(_, merge)| merge.clone())
+            .unwrap_or_else(|| branch.clone());
+        let mut dialog = PushDialog {
+            remotes,
+            remote_index,
+            source: branch,
+            destination,
+            force: false,
+            expected: None,
+            tags: false,
+            dry_run: false,
+            set_upstream: upstream.is_none(),
+            no_verify: false,
+            field: PushField::Remote,
+        };
+        dialog.expected = self
+            .repo
+            .remote_tracking_id(dialog.remote(), &dialog.destination);
+        self.push_dialog = Some(dialog);
+        self.mode = Mode::Push;
+        Ok(())
     }
 
-    /// Start async pull operation with spinner
-    fn start_async_pull(&mut self, remote: String, branch: String) {
+    fn handle_push_key(&mut self, key: KeyEvent) -> Result<()> {
+        let dialog = match self.push_dialog.as_mut() {
+            Some(d) => d,
+            None => {
+                self.mode = Mode::Normal;
+                return Ok(());
+            }
+        };
+        let mut expected_stale = false;
+        match key.code {
+            KeyCode::Escape => {
+                self.message = Some("Cancelled".to_string());
+                self.push_dialog = None;
+                self.mode = Mode::Normal;
+                return Ok(());
+            }
+            KeyCode::Enter => {
+                let request = dialog.request();
+                self.push_dialog = None;
+                self.mode = Mode::Normal;
+                if request.source.is_empty() || request.destination.is_empty() {
+                    self.message = Some("Source and destination are required".to_string());
+                } else {
+                    self.start_async_push(request);
+                }
+                return Ok(());
+            }
+            KeyCode::Tab | KeyCode::Down => dialog.move_field(true),
+            KeyCode::BackTab | KeyCode::Up => dialog.move_field(false),
+            KeyCode::Left | KeyCode::Right | KeyCode::Char(' ')
+                if dialog.field == PushField::Remote =>
+            {
+                let len = dialog.remotes.len().max(1);
+                dialog.remote_index = if key.code == KeyCode::Left {
+                    (dialog.remote_index + len - 1) % len
+                } else {
+                    (dialog.remote_index + 1) % len
+                };
+                expected_stale = true;
+            }
+            KeyCode::Char(' ') => match dialog.field {
+                PushField::Force => dialog.force = !dialog.force,
+                PushField::Tags => dialog.tags = !dialog.tags,
+                PushField::DryRun => dialog.dry_run = !dialog.dry_run,
+                PushField::SetUpstream => dialog.set_upstream = !dialog.set_upstream,
+                PushField::NoVerify => dialog.no_verify = !dialog.no_verify,
+                PushField::Source => dialog.source.push(' '),
+                PushField::Destination => dialog.destination.push(' '),
+                PushField::Remote => {}
+            },
+            KeyCode::Char(c) => match dialog.field {
+                PushField::Source => dialog.source.push(c),
+                PushField::Destination => {
+                    dialog.destination.push(c);
+                    expected_stale = true;
+                }
+                _ => {}
+            },
+            KeyCode::Backspace => match dialog.field {
+                PushField::Source => {
+                    dialog.source.pop();
+                }
+                PushField::Destination => {
+                    dialog.destination.pop();
+                    expected_stale = true;
+                }
+                _ => {}
+            },
+            _ => {}
+        }
+        if expected_stale {
+            dialog.expected = self
+                .repo
+                .remote_tracking_id(dialog.remote(), &dialog.destination);
+        }
+        Ok(())
+    }
+
+    /// Start async (recursive) submodule update with spinner
+    fn start_async_submodule_update(&mut self, name: String) {
         if self.remote_operation.is_some() {
             self.message = Some("Remote operation already in progress".to_string());
             return;
         }
-        self.remote_operation = Some(RemoteOperation::Pull(branch.clone()));
+        self.remote_operation = Some(RemoteOperation::SubmoduleUpdate(name.clone()));
         self.remote_spinner_frame = 0;
 
         let sender = self.async_sender.clone();
@@ -1289,30 +2526,20 @@ impl App {
 
         thread::spawn(move || {
             let result = crate::git::Repository::open(&repo_path)
-                .and_then(|repo| repo.pull_branch(&remote, &branch))
-                .map(|merge_result| match merge_result {
-                    crate::git::MergeResult::UpToDate => format!("Branch {} is up to date", branch),
-                    crate::git::MergeResult::FastForward => {
-                        format!("Fast-forwarded branch: {}", branch)
-                    }
-                    crate::git::MergeResult::Merged => format!("Merged branch: {}", branch),
-                    crate::git::MergeResult::Conflict => {
-                        format!("Merge conflicts in branch: {}", branch)
-                    }
-                })
-                .map_err(|e| e.to_string());
+                .and_then(|repo| repo.submodule_update(&name, true))
+                .map(|()| format!("Updated submodule: {}", name))
+                .map_err(|e| format!("Submodule update failed: {}", e));
             let _ = sender.send(AsyncLoadResult::RemoteOperationComplete(result));
         });
     }
 
-    /// Start async push operation with spinner
-    fn start_async_push(&mut self, remote: String, branch: Option<String>) {
+    /// Start async submodule clone + registration with spinner
+    fn start_async_submodule_add(&mut self, url: String, path: String) {
         if self.remote_operation.is_some() {
             self.message = Some("Remote operation already in progress".to_string());
             return;
         }
-        let display_name = branch.clone().unwrap_or_else(|| "HEAD".to_string());
-        self.remote_operation = Some(RemoteOperation::Push(display_name.clone()));
+        self.remote_operation = Some(RemoteOperation::SubmoduleAdd(path.clone()));
         self.remote_spinner_frame = 0;
 
         let sender = self.async_sender.clone();
@@ -1320,26 +2547,14 @@ impl App {
 
         thread::spawn(move || {
             let result = crate::git::Repository::open(&repo_path)
-                .and_then(|repo| {
-                    if let Some(ref b) = branch {
-                        repo.push_branch(&remote, b)
-                    } else {
-                        repo.push(&remote)
-                    }
-                })
-                .map(|()| {
-                    if let Some(b) = branch {
-                        format!("Pushed {} to {}", b, remote)
-                    } else {
-                        format!("Pushed to {}", remote)
-                    }
-                })
-                .map_err(|e| e.to_string());
+                .and_then(|repo| repo.submodule_add(&url, &path))
+                .map(|()| format!("Added submodule: {}", path))
+                .map_err(|e| format!("Submodule add failed: {}", e));
             let _ = sender.send(AsyncLoadResult::RemoteOperationComplete(result));
         });
     }
 
-    fn draw(&mut self) -> Result<()> {
+    pub fn draw(&mut self) -> Result<()> {
         let theme = self.config.current_theme().clone();
         let focused_panel = self.focused_panel;
         let mode = self.mode;
@@ -1348,6 +2563,7 @@ impl App {
         let input_buffer = self.input_buffer.clone();
         let branch_create_from = self.branch_create_from.clone();
         let comment_issue_number = self.comment_issue_number;
+        let worktree_create_from = self.worktree_create_from.clone();
 
         self.terminal.draw(|buf| {
             let area = buf.area;
@@ -1431,6 +2647,15 @@ impl App {
                                 PanelType::Submodules => self
                                     .submodules_view
                                     .render(panel_area, buf, &theme, is_focused),
+                                PanelType::Reflog => {
+                                    self.reflog_view.render(panel_area, buf, &theme, is_focused)
+                                }
+                                PanelType::Trash => {
+                                    self.trash_view.render(panel_area, buf, &theme, is_focused)
+                                }
+                                PanelType::Hooks => {
+                                    self.hooks_view.render(panel_area, buf, &theme, is_focused)
+                                }
                                 PanelType::Blame => {
                                     self.blame_view.render(panel_area, buf, &theme, is_focused)
                                 }
@@ -1496,6 +2721,9 @@ impl App {
                         PanelType::Submodules => {
                             self.submodules_view.render(main, buf, &theme, true)
                         }
+                        PanelType::Reflog => self.reflog_view.render(main, buf, &theme, true),
+                        PanelType::Trash => self.trash_view.render(main, buf, &theme, true),
+                        PanelType::Hooks => self.hooks_view.render(main, buf, &theme, true),
                         PanelType::Blame => self.blame_view.render(main, buf, &theme, true),
                         PanelType::Files => self.filetree_view.render(main, buf, &theme, true),
                         PanelType::Conflicts => self.conflict_view.render(main, buf, &theme, true),
@@ -1509,6 +2737,17 @@ impl App {
                 }
             }
 
+            // Repository switcher over the panels
+            if mode == Mode::Repos {
+                let popup = Rect::new(
+                    main.x + main.width / 10,
+                    main.y + main.height / 8,
+                    main.width - main.width / 5,
+                    main.height - main.height / 4,
+                );
+                self.repos_view.render(popup, buf, &theme);
+            }
+
             // Footer - pass scroll state for focused panel
             let (can_scroll_left, can_scroll_right) = match focused_panel {
                 PanelType::Files => (
@@ -1543,6 +2782,18 @@ impl App {
                     self.submodules_view.can_scroll_left(),
                     self.submodules_view.can_scroll_right(),
                 ),
+                PanelType::Reflog => (
+                    self.reflog_view.can_scroll_left(),
+                    self.reflog_view.can_scroll_right(),
+                ),
+                PanelType::Trash => (
+                    self.trash_view.can_scroll_left(),
+                    self.trash_view.can_scroll_right(),
+                ),
+                PanelType::Hooks => (
+                    self.hooks_view.can_scroll_left(),
+                    self.hooks_view.can_scroll_right(),
+                ),
                 PanelType::Blame => (
                     self.blame_view.can_scroll_left(),
                     self.blame_view.can_scroll_right(),
@@ -1587,11 +2838,18 @@ impl App {
                 focused_panel,
                 branch_create_from.as_deref(),
                 comment_issue_number,
+                worktree_create_from.as_deref(),
                 self.confirm_target.as_deref(),
                 can_scroll_left,
                 can_scroll_right,
                 self.select_index,
                 self.pr_merge_method,
+                self.push_dialog.as_ref(),
+                self.merge_dialog.as_ref(),
+                self.pull_autostash,
+                self.commit_no_verify,
+                &self.theme_choices,
+                &self.jobs.running(),
             );
 
             // Remote operation spinner in footer (right-aligned, above logo)
@@ -1629,6 +2887,21 @@ impl App {
                     RemoteOperation::IssueDelete(issue_number) => {
                         format!("{} Deleting issue #{}...", spinner, issue_number)
                     }
+                    RemoteOperation::SubmoduleUpdate(name) => {
+                        format!("{} Updating submodule {}...", spinner, name)
+                    }
+                    RemoteOperation::SubmoduleAdd(path) => {
+                        format!("{} Adding submodule {}...", spinner, path)
+                    }
+                    RemoteOperation::Commit => format!("{} Committing...", spinner),
+                };
+                let op_text = match &self.transfer_progress {
+                    Some(progress) => format!(
+                        "{} {} (Esc: cancel)",
+                        op_text,
+                        progress.snapshot().summary()
+                    ),
+                    None => op_text,
                 };
                 let spinner_x = footer.x + 1;
                 let spinner_y = footer.y; // Top line of footer (message line)
@@ -1640,6 +2913,47 @@ impl App {
                 );
             }
 
+            // Tabs (right-aligned on the message line)
+            let mut tabs_width = 0;
+            if self.tab_titles.len() > 1 {
+                let mut x = footer.x + footer.width;
+                for (i, title) in self.tab_titles.iter().enumerate().rev() {
+                    let label = format!(" {}:{} ", i + 1, title);
+                    let width = label.chars().count() as u16;
+                    x = x.saturating_sub(width + 1);
+                    let style = if i == self.active_tab {
+                        Style::new().fg(theme.selection_text).bg(theme.selection)
+                    } else {
+                        Style::new().fg(theme.untracked)
+                    };
+                    buf.set_string(x, footer.y, &label, style);
+                    tabs_width += width + 1;
+                }
+            }
+
+            // Submodule breadcrumb (right-aligned on the message line, left of the tabs)
+            if !self.repo_stack.is_empty() {
+                let mut crumbs: Vec<String> = self
+                    .repo_stack
+                    .iter()
+                    .map(|p| {
+                        p.file_name()
+                            .map(|s| s.to_string_lossy().to_string())
+                            .unwrap_or_default()
+                    })
+                    .collect();
+                crumbs.push(self.repo.name());
+                let breadcrumb = format!("{} (Backspace: parent)", crumbs.join(" > "));
+                let width = breadcrumb.chars().count() as u16;
+                let x = (footer.x + footer.width).saturating_sub(tabs_width + width + 1);
+                buf.set_string(
+                    x,
+                    footer.y,
+                    &breadcrumb,
+                    Style::new().fg(theme.branch_current),
+                );
+            }
+
             // Logo at bottom right
             let logo = "g v0.1.0";
             let logo_x = buf.area.width.saturating_sub(logo.len() as u16 + 1);
@@ -1713,11 +3027,18 @@ impl App {
         focused_panel: Panel,
         branch_create_from: Option<&str>,
         comment_issue_number: Option<u32>,
+        worktree_create_from: Option<&str>,
         confirm_target: Option<&str>,
         can_scroll_left: bool,
         can_scroll_right: bool,
         select_index: usize,
         pr_merge_method: usize,
+        push_dialog: Option<&PushDialog>,
+        merge_dialog: Option<&MergeDialog>,
+        pull_autostash: bool,
+        commit_no_verify: bool,
+        theme_choices: &[String],
+        jobs: &[JobInfo],
     ) {
         // Message line (top of footer)
         if let Some(msg) = message {
@@ -1791,6 +3112,8 @@ impl App {
                         ("a", "toggle stage"),
                         ("c", "commit"),
                         ("d", "discard"),
+                        ("D", "discard all"),
+                        ("X", "clean untracked"),
                         ("s", "stash"),
                         ("P", "push"),
                     ],
@@ -1805,24 +3128,57 @@ impl App {
                         ("D", "force delete"),
                         ("M", "delete merged"),
                         ("C", "create PR"),
+                        ("w", "worktree"),
                     ],
                     PanelType::Commits => &[
                         ("Enter", "view diff"),
                         ("c", "checkout"),
+                        ("M", "merge"),
                         ("R", "reset/revert"),
+                        ("F", "format-patch"),
                         ("v", "view mode"),
+                        ("w", "worktree"),
                     ],
                     PanelType::Stash => &[("Enter", "pop"), ("a", "apply"), ("d", "drop")],
                     PanelType::Diff => &[("j/k", "scroll"), ("v", "toggle inline/split")],
                     PanelType::Tags => &[("n", "new tag"), ("d", "delete"), ("R", "reset/revert")],
                     PanelType::Remotes => &[("f", "fetch")],
-                    PanelType::Worktrees => &[],
-                    PanelType::Submodules => &[("u", "update")],
+                    PanelType::Worktrees => &[
+                        ("Enter", "switch"),
+                        ("a", "add"),
+                        ("d", "remove"),
+                        ("D", "force remove"),
+                        ("x", "lock/unlock"),
+                        ("p", "prune"),
+                    ],
+                    PanelType::Submodules => &[
+                        ("Enter", "open"),
+                        ("u", "update"),
+                        ("i", "init"),
+                        ("I", "deinit"),
+                        ("s", "sync url"),
+                        ("a", "add"),
+                    ],
+                    PanelType::Reflog => &[
+                        ("v", "next ref"),
+                        ("c", "checkout"),
+                        ("b", "branch"),
+                        ("R", "reset/revert"),
+                    ],
+                    PanelType::Trash => {
+                        &[("Enter", "view diff"), ("R", "restore"), ("d", "delete")]
+                    }
+                    PanelType::Hooks => &[("j/k", "scroll")],
                     PanelType::Blame => &[("j/k", "scroll")],
                     PanelType::Files => {
                         &[("Space/Enter", "open"), ("v", "view mode"), ("b", "blame")]
                     }
-                    PanelType::Conflicts => &[("o", "use ours"), ("t", "use theirs")],
+                    PanelType::Conflicts => &[
+                        ("o", "use ours"),
+                        ("t", "use theirs"),
+                        ("C", "continue rebase"),
+                        ("A", "abort"),
+                    ],
                     PanelType::PullRequests => &[("M", "merge"), ("d", "close"), ("R", "reload")],
                     PanelType::Issues => &[
                         ("c", "comment"),
@@ -1848,6 +3204,9 @@ impl App {
             Mode::Search | Mode::Input(_) => {
                 let prompt: String = match mode {
                     Mode::Search => "/".to_string(),
+                    Mode::Input(InputContext::CommitMessage) if commit_no_verify => {
+                        "Commit (no-verify): ".to_string()
+                    }
                     Mode::Input(InputContext::CommitMessage) => "Commit: ".to_string(),
                     Mode::Input(InputContext::BranchName) => match branch_create_from {
                         Some(from) => format!("New branch from '{}': ", from),
@@ -1860,9 +3219,20 @@ impl App {
                         Some(n) => format!("Comment on #{}: ", n),
                         None => "Comment: ".to_string(),
                     },
+                    Mode::Input(InputContext::WorktreePath) => match worktree_create_from {
+                        Some(from) => format!("New worktree from '{}' at: ", from),
+                        None => "New worktree <path> [branch|commit]: ".to_string(),
+                    },
+                    Mode::Input(InputContext::WorktreeLockReason) => {
+                        "Lock reason (optional): ".to_string()
+                    }
+                    Mode::Input(InputContext::SubmoduleAdd) => {
+                        "Add submodule <url> <path>: ".to_string()
+                    }
                     _ => "> ".to_string(),
                 };
-                let line = format!("{}{}", prompt, input);
+                // A pasted commit message can span lines; show it on one
+                let line = format!("{}{}", prompt, input.replace('\n', "⏎"));
                 buf.set_string(
                     area.x + 1,
                     area.y + 1,
@@ -1871,12 +3241,19 @@ impl App {
                 );
 
                 // Show input help
-                let input_help = [("Enter", "confirm"), ("Esc", "cancel")];
+                let input_help: &[(&str, &str)] = match mode {
+                    Mode::Input(InputContext::CommitMessage) => &[
+                        ("Enter", "confirm"),
+                        ("Tab", "toggle hooks"),
+                        ("Esc", "cancel"),
+                    ],
+                    _ => &[("Enter", "confirm"), ("Esc", "cancel")],
+                };
                 Self::render_command_line(
                     buf,
                     area.x + 1,
                     area.y + 2,
-                    &input_help,
+                    input_help,
                     key_style,
                     desc_style,
                     sep_style,
@@ -1903,16 +3280,23 @@ impl App {
                     ConfirmAction::RemoteBranchDelete => {
                         format!("Delete remote branch '{}'?", confirm_target.unwrap_or("?"))
                     }
-                    ConfirmAction::Discard => {
-                        format!("Discard changes in '{}'?", confirm_target.unwrap_or("?"))
+                    ConfirmAction::Discard => format!(
+                        "Discard changes in '{}'? (recoverable from Trash)",
+                        confirm_target.unwrap_or("?")
+                    ),
+                    ConfirmAction::DiscardAll => {
+                        "Discard all changes to tracked files? (recoverable from Trash)".to_string()
                     }
-                    ConfirmAction::Push => "Push to remote?".to_string(),
-                    ConfirmAction::BranchPush => {
-                        format!("Push branch '{}' to remote?", confirm_target.unwrap_or("?"))
+                    ConfirmAction::CleanUntracked => {
+                        "Delete all untracked files? (recoverable from Trash)".to_string()
                     }
-                    ConfirmAction::BranchMerge => format!(
-                        "Merge branch '{}' into current branch?",
-                        confirm_target.unwrap_or("?")
+                    ConfirmAction::TrashRestore => format!(
+                        "Restore trash entry {} into the working tree?",
+                        confirm_target.map(trash_label).unwrap_or("?")
+                    ),
+                    ConfirmAction::TrashDelete => format!(
+                        "Permanently delete trash entry {}?",
+                        confirm_target.map(trash_label).unwrap_or("?")
                     ),
                     ConfirmAction::StashDrop => {
                         format!("Drop stash@{{{}}}?", confirm_target.unwrap_or("?"))
@@ -1954,6 +3338,18 @@ impl App {
                         "Delete issue #{}? (This cannot be undone!)",
                         confirm_target.unwrap_or("?")
                     ),
+                    ConfirmAction::WorktreeRemove => format!(
+                        "Remove worktree '{}' and its directory?",
+                        confirm_target.unwrap_or("?")
+                    ),
+                    ConfirmAction::WorktreeForceRemove => format!(
+                        "Force remove worktree '{}'? (uncommitted changes are lost)",
+                        confirm_target.unwrap_or("?")
+                    ),
+                    ConfirmAction::SubmoduleDeinit => format!(
+                        "Deinit submodule '{}' and empty its directory?",
+                        confirm_target.unwrap_or("?")
+                    ),
                 };
                 let warn_style = Style::new().fg(theme.diff_remove).bold();
                 buf.set_string(area.x + 1, area.y + 1, &action_desc, warn_style);
@@ -2023,6 +3419,47 @@ impl App {
                             ("3", "squash", "Squash and merge"),
                         ],
                     ),
+                    SelectAction::CheckoutDirty => (
+                        &format!(
+                            "Switch to {} with uncommitted changes",
+                            confirm_target.unwrap_or("?")
+                        ),
+                        vec![
+                            ("1", "carry", "Keep the changes in the working tree"),
+                            ("2", "stash", "Stash, switch, then re-apply the changes"),
+                            ("3", "abort", "Stay where you are"),
+                        ],
+                    ),
+                    SelectAction::PullStrategy => (
+                        &format!(
+                            "Pull {} [a: autostash {}]",
+                            confirm_target.unwrap_or("?"),
+                            if pull_autostash { "on" } else { "off" }
+                        ),
+                        vec![
+                            ("1", "merge", "Merge commit if the branches diverged"),
+                            ("2", "rebase", "Replay local commits on top of upstream"),
+                            ("3", "ff-only", "Fast-forward only; never create commits"),
+                        ],
+                    ),
+                    SelectAction::ExportPatch => (
+                        &format!(
+                            "Export {} commit(s) as patches",
+                            confirm_target.unwrap_or("?")
+                        ),
+                        vec![
+                            ("1", "files", "Write 0001-*.patch files to patches/"),
+                            ("2", "clipboard", "Copy the series as one mbox"),
+                        ],
+                    ),
+                    SelectAction::ApplyPatch => (
+                        &format!("Apply {}", confirm_target.unwrap_or("?")),
+                        vec![
+                            ("1", "worktree", "Change files only, like git apply"),
+                            ("2", "index", "Stage the changes only, like --cached"),
+                            ("3", "both", "Working tree and index, like --index"),
+                        ],
+                    ),
                 };
 
                 // Render title
@@ -2056,6 +3493,189 @@ impl App {
                     desc_style,
                 );
             }
+            Mode::Push => {
+                let Some(dialog) = push_dialog else {
+                    return;
+                };
+                let title_style = Style::new().fg(theme.branch_current).bold();
+                buf.set_string(area.x + 1, area.y + 1, "Push", title_style);
+
+                let mut x_pos = area.x + 6;
+                for (field, text) in dialog.segments() {
+                    let style = if field == dialog.field {
+                        Style::new().fg(theme.selection_text).bg(theme.selection)
+                    } else {
+                        Style::new().fg(theme.foreground)
+                    };
+                    buf.set_string(x_pos, area.y + 1, &text, style);
+                    x_pos += text.chars().count() as u16 + 2;
+                }
+
+                let cmds = [
+                    ("Tab", "next field"),
+                    ("Space", "toggle"),
+                    ("←/→", "remote"),
+                    ("Enter", "push"),
+                    ("Esc", "cancel"),
+                ];
+                Self::render_command_line(
+                    buf,
+                    area.x + 1,
+                    area.y + 2,
+                    &cmds,
+                    key_style,
+                    desc_style,
+                    sep_style,
+                    area.width.saturating_sub(2),
+                );
+            }
+            Mode::Merge => {
+                let Some(dialog) = merge_dialog else {
+                    return;
+                };
+                let title = format!("Merge {}", dialog.label);
+                let title_style = Style::new().fg(theme.branch_current).bold();
+                buf.set_string(area.x + 1, area.y + 1, &title, title_style);
+
+                let mut x_pos = area.x + 3 + title.chars().count() as u16;
+                for (field, text) in dialog.segments() {
+                    let style = if field == dialog.field {
+                        Style::new().fg(theme.selection_text).bg(theme.selection)
+                    } else {
+                        Style::new().fg(theme.foreground)
+                    };
+                    buf.set_string(x_pos, area.y + 1, &text, style);
+                    x_pos += text.chars().count() as u16 + 2;
+                }
+
+                let cmds = [
+                    ("Tab", "next field"),
+                    ("←/→", "mode"),
+                    ("Space", "toggle"),
+                    ("Enter", "merge"),
+                    ("Esc", "cancel"),
+                ];
+                Self::render_command_line(
+                    buf,
+                    area.x + 1,
+                    area.y + 2,
+                    &cmds,
+                    key_style,
+                    desc_style,
+                    sep_style,
+                    area.width.saturating_sub(2),
+                );
+            }
+            Mode::Themes => {
+                let title = "Theme";
+                let title_style = Style::new().fg(theme.branch_current).bold();
+                buf.set_string(area.x + 1, area.y + 1, title, title_style);
+
+                // Scroll the list so the selected theme stays visible
+                let start_x = area.x + 3 + title.len() as u16;
+                let avail = area.width.saturating_sub(start_x - area.x + 1) as usize;
+                let widths: Vec<usize> = theme_choices.iter().map(|n| n.len() + 3).collect();
+                let mut first = 0;
+                while first < select_index
+                    && widths[first..=select_index].iter().sum::<usize>() > avail
+                {
+                    first += 1;
+                }
+                let mut x_pos = start_x;
+                for (i, name) in theme_choices.iter().enumerate().skip(first) {
+                    let label = format!(" {} ", name);
+                    if (x_pos - start_x) as usize + label.len() > avail {
+                        break;
+                    }
+                    let style = if i == select_index {
+                        Style::new().fg(theme.selection_text).bg(theme.selection)
+                    } else {
+                        Style::new().fg(theme.foreground)
+                    };
+                    buf.set_string(x_pos, area.y + 1, &label, style);
+                    x_pos += label.len() as u16 + 1;
+                }
+
+                let cmds = [("h/l", "preview"), ("Enter", "keep"), ("Esc", "cancel")];
+                Self::render_command_line(
+                    buf,
+                    area.x + 1,
+                    area.y + 2,
+                    &cmds,
+                    key_style,
+                    desc_style,
+                    sep_style,
+                    area.width.saturating_sub(2),
+                );
+            }
+            Mode::Jobs => {
+                let title = "Jobs";
+                let title_style = Style::new().fg(theme.branch_current).bold();
+                buf.set_string(area.x + 1, area.y + 1, title, title_style);
+
+                let start_x = area.x + 3 + title.len() as u16;
+                if jobs.is_empty() {
+                    buf.set_string(
+                        start_x,
+                        area.y + 1,
+                        "No jobs running",
+                        Style::new().fg(theme.untracked),
+                    );
+                }
+                let mut x_pos = start_x;
+                let right = area.x + area.width.saturating_sub(1);
+                for (i, job) in jobs.iter().enumerate() {
+                    let state = if job.queued {
+                        "queued".to_string()
+                    } else {
+                        format!("{}s", job.started.elapsed().as_secs())
+                    };
+                    let label = format!(" {} ({}) ", job.label, state);
+                    let width = label.chars().count() as u16;
+                    if x_pos + width > right {
+                        break;
+                    }
+                    let style = if i == select_index {
+                        Style::new().fg(theme.selection_text).bg(theme.selection)
+                    } else {
+                        Style::new().fg(theme.foreground)
+                    };
+                    buf.set_string(x_pos, area.y + 1, &label, style);
+                    x_pos += width + 1;
+                }
+
+                let cmds = [("h/l", "select"), ("x", "cancel job"), ("Esc", "close")];
+                Self::render_command_line(
+                    buf,
+                    area.x + 1,
+                    area.y + 2,
+                    &cmds,
+                    key_style,
+                    desc_style,
+                    sep_style,
+                    area.width.saturating_sub(2),
+                );
+            }
+            Mode::Repos => {
+                let cmds = [
+                    ("type", "filter"),
+                    ("↑/↓", "select"),
+                    ("Enter", "open"),
+                    ("Tab", "open in new tab"),
+                    ("C-f", "favorite"),
+                    ("Esc", "close"),
+                ];
+                Self::render_command_line(
+                    buf,
+                    area.x + 1,
+                    area.y + 1,
+                    &cmds,
+                    key_style,
+                    desc_style,
+                    sep_style,
+                    area.width.saturating_sub(2),
+                );
+            }
         }
     }
 
@@ -2117,6 +3737,9 @@ impl App {
             (PanelType::Remotes, "Remote"),
             (PanelType::Worktrees, "Wktree"),
             (PanelType::Submodules, "Submod"),
+            (PanelType::Reflog, "Reflog"),
+            (PanelType::Trash, "Trash"),
+            (PanelType::Hooks, "Hooks"),
             (PanelType::Blame, "Blame"),
             (PanelType::Files, "Files"),
             (PanelType::Conflicts, "Conflct"),
@@ -2162,6 +3785,11 @@ impl App {
             return Ok(());
         }
 
+        // Ctrl+Z: raw mode turns off ISIG, so suspend by hand
+        if key == KeyEvent::ctrl('z') {
+            return self.suspend();
+        }
+
         match self.mode {
             Mode::Normal => self.handle_normal_key(key),
             Mode::Visual => self.handle_visual_key(key),
@@ -2170,6 +3798,11 @@ impl App {
             Mode::Input(ctx) => self.handle_input_key(key, ctx),
             Mode::Confirm(action) => self.handle_confirm_key(key, action),
             Mode::Select(action) => self.handle_select_key(key, action),
+            Mode::Push => self.handle_push_key(key),
+            Mode::Merge => self.handle_merge_key(key),
+            Mode::Repos => self.handle_repos_key(key),
+            Mode::Themes => self.handle_themes_key(key),
+            Mode::Jobs => self.handle_jobs_key(key),
         }
     }
 
@@ -2198,36 +3831,39 @@ impl App {
             SelectAction::ResetOrRevert => 2,
             SelectAction::ResetMode => 3,
             SelectAction::PrMergeMethod => 3,
+            SelectAction::PullStrategy => 3,
+            SelectAction::CheckoutDirty => 3,
+            SelectAction::ExportPatch => 2,
+            SelectAction::ApplyPatch => 3,
         };
 
         match key.code {
             // Navigation (j/l = next, k/h = prev)
-            KeyCode::Char('j') | KeyCode::Char('l') | KeyCode::Down | KeyCode::Right => {
-                if self.select_index + 1 < option_count {
-                    self.select_index += 1;
-                }
+            KeyCode::Char('j') | KeyCode::Char('l') | KeyCode::Down | KeyCode::Right
+                if self.select_index + 1 < option_count =>
+            {
+                self.select_index += 1;
             }
-            KeyCode::Char('k') | KeyCode::Char('h') | KeyCode::Up | KeyCode::Left => {
-                if self.select_index > 0 {
-                    self.select_index -= 1;
-                }
+            KeyCode::Char('k') | KeyCode::Char('h') | KeyCode::Up | KeyCode::Left
+                if self.select_index > 0 =>
+            {
+                self.select_index -= 1;
             }
             // Direct selection by number
             KeyCode::Char('1') => {
                 self.select_index = 0;
                 self.execute_select_action(action)?;
             }
-            KeyCode::Char('2') => {
-                if option_count >= 2 {
-                    self.select_index = 1;
-                    self.execute_select_action(action)?;
-                }
+            KeyCode::Char('2') if option_count >= 2 => {
+                self.select_index = 1;
+                self.execute_select_action(action)?;
             }
-            KeyCode::Char('3') => {
-                if option_count >= 3 {
-                    self.select_index = 2;
-                    self.execute_select_action(action)?;
-                }
+            KeyCode::Char('3') if option_count >= 3 => {
+                self.select_index = 2;
+                self.execute_select_action(action)?;
+            }
+            KeyCode::Char('a') if action == SelectAction::PullStrategy => {
+                self.pull_autostash = !self.pull_autostash;
             }
             // Enter confirms current selection
             KeyCode::Enter => {
@@ -2239,6 +3875,8 @@ impl App {
                 self.mode = Mode::Normal;
                 self.confirm_target = None;
                 self.select_index = 0;
+                self.export_commits.clear();
+                self.pending_patch = None;
             }
             _ => {}
         }
@@ -2246,6 +3884,11 @@ impl App {
     }
 
     fn execute_select_action(&mut self, action: SelectAction) -> Result<()> {
+        let local = matches!(action, SelectAction::ResetMode | SelectAction::ApplyPatch);
+        let _hold = match local.then(|| self.hold_local_refs()) {
+            Some(None) => return Ok(()),
+            hold => hold.flatten(),
+        };
         match action {
             SelectAction::ResetOrRevert => {
                 match self.select_index {
@@ -2292,11 +3935,91 @@ impl App {
                 self.pr_merge_method = self.select_index;
                 self.mode = Mode::Confirm(ConfirmAction::PrMerge);
             }
+            SelectAction::CheckoutDirty => {
+                self.mode = Mode::Normal;
+                self.confirm_target = None;
+                if let Some(target) = self.pending_checkout.take() {
+                    match self.select_index {
+                        0 => self.run_checkout(target, CheckoutMode::Carry)?,
+                        1 => self.run_checkout(target, CheckoutMode::Stash)?,
+                        _ => self.message = Some("Checkout aborted".to_string()),
+                    }
+                }
+                self.select_index = 0;
+            }
+            SelectAction::PullStrategy => {
+                self.mode = Mode::Normal;
+                self.confirm_target = None;
+                if let Some((remote, branch)) = self.pull_target.take() {
+                    let options = PullOptions {
+                        strategy: PullStrategy::ALL[self.select_index.min(2)],
+                        autostash: self.pull_autostash,
+                    };
+                    self.start_async_pull(remote, branch, options);
+                }
+                self.select_index = 0;
+            }
+            SelectAction::ExportPatch => {
+                self.mode = Mode::Normal;
+                self.confirm_target = None;
+                let ids = std::mem::take(&mut self.export_commits);
+                if self.select_index == 0 {
+                    let dir = self.repo_path.join("patches");
+                    match self.repo.export_patches(&ids, &dir) {
+                        Ok(count) => {
+                            self.message = Some(format!("Wrote {} patch(es) to patches/", count))
+                        }
+                        Err(e) => self.message = Some(format!("Export failed: {}", e)),
+                    }
+                } else {
+                    match self.repo.format_patches(&ids) {
+                        Ok(patches) => {
+                            let mbox: String = patches.into_iter().map(|(_, mail)| mail).collect();
+                            self.message = Some(if self.copy_to_clipboard(&mbox) {
+                                format!("Copied {} patch(es)", ids.len())
+                            } else {
+                                "Failed to copy to clipboard".to_string()
+                            });
+                        }
+                        Err(e) => self.message = Some(format!("Export failed: {}", e)),
+                    }
+                }
+                self.select_index = 0;
+            }
+            SelectAction::ApplyPatch => {
+                self.mode = Mode::Normal;
+                self.confirm_target = None;
+                if let Some((name, text)) = self.pending_patch.take() {
+                    let target = PatchTarget::ALL[self.select_index.min(2)];
+                    match self.repo.apply_patch(&text, target) {
+                        Ok(()) => {
+                            self.message =
+                                Some(format!("Applied {} to the {}", name, target.name()));
+                            self.refresh_status()?;
+                        }
+                        Err(e) => self.message = Some(format!("Apply failed: {}", e)),
+                    }
+                }
+                self.select_index = 0;
+            }
         }
         Ok(())
     }
 
     fn execute_confirmed_action(&mut self, action: ConfirmAction) -> Result<()> {
+        let local = matches!(
+            action,
+            ConfirmAction::Discard
+                | ConfirmAction::DiscardAll
+                | ConfirmAction::CleanUntracked
+                | ConfirmAction::TrashRestore
+                | ConfirmAction::StashDrop
+                | ConfirmAction::CommitRevert
+        );
+        let _hold = match local.then(|| self.hold_local_refs()) {
+            Some(None) => return Ok(()),
+            hold => hold.flatten(),
+        };
         match action {
             ConfirmAction::BranchDelete => {
                 if let Some(ref name) = self.confirm_target {
@@ -2305,13 +4028,19 @@ impl App {
                         let sender = self.async_sender.clone();
                         let repo_path = self.repo_path.clone();
                         let branch_name = name.clone();
-                        thread::spawn(move || {
-                            let result = crate::git::Repository::open(&repo_path)
-                                .and_then(|repo| repo.delete_branch(&branch_name, false))
-                                .map(|()| format!("Deleted branch '{}'", branch_name))
-                                .map_err(|e| format!("Delete failed: {}", e));
-                            let _ = sender.send(AsyncLoadResult::RemoteOperationComplete(result));
-                        });
+                        self.jobs.spawn(
+                            format!("Delete branch {}", branch_name),
+                            vec!["HEAD".to_string()],
+                            CancelToken::new(),
+                            sender,
+                            move |_| {
+                                let result = crate::git::Repository::open(&repo_path)
+                                    .and_then(|repo| repo.delete_branch(&branch_name, false))
+                                    .map(|()| format!("Deleted branch '{}'", branch_name))
+                                    .map_err(|e| format!("Delete failed: {}", e));
+                                AsyncLoadResult::RemoteOperationComplete(result)
+                            },
+                        );
                     }
                 }
             }
@@ -2322,13 +4051,19 @@ impl App {
                         let sender = self.async_sender.clone();
                         let repo_path = self.repo_path.clone();
                         let branch_name = name.clone();
-                        thread::spawn(move || {
-                            let result = crate::git::Repository::open(&repo_path)
-                                .and_then(|repo| repo.delete_branch(&branch_name, true))
-                                .map(|()| format!("Force deleted branch '{}'", branch_name))
-                                .map_err(|e| format!("Force delete failed: {}", e));
-                            let _ = sender.send(AsyncLoadResult::RemoteOperationComplete(result));
-                        });
+                        self.jobs.spawn(
+                            format!("Delete branch {}", branch_name),
+                            vec!["HEAD".to_string()],
+                            CancelToken::new(),
+                            sender,
+                            move |_| {
+                                let result = crate::git::Repository::open(&repo_path)
+                                    .and_then(|repo| repo.delete_branch(&branch_name, true))
+                                    .map(|()| format!("Force deleted branch '{}'", branch_name))
+                                    .map_err(|e| format!("Force delete failed: {}", e));
+                                AsyncLoadResult::RemoteOperationComplete(result)
+                            },
+                        );
                     }
                 }
             }
@@ -2344,16 +4079,22 @@ impl App {
                             let full_name = name.clone();
                             let remote_name = name[..slash_pos].to_string();
                             let branch_name = name[slash_pos + 1..].to_string();
-                            thread::spawn(move || {
-                                let result = crate::git::Repository::open(&repo_path)
-                                    .and_then(|repo| {
-                                        repo.delete_remote_branch(&remote_name, &branch_name)
-                                    })
-                                    .map(|()| format!("Deleted remote branch '{}'", full_name))
-                                    .map_err(|e| format!("Delete failed: {}", e));
-                                let _ =
-                                    sender.send(AsyncLoadResult::RemoteOperationComplete(result));
-                            });
+                            let refs = vec![remote_refs(&remote_name)];
+                            self.jobs.spawn(
+                                format!("Delete {}", full_name),
+                                refs,
+                                CancelToken::new(),
+                                sender,
+                                move |_| {
+                                    let result = crate::git::Repository::open(&repo_path)
+                                        .and_then(|repo| {
+                                            repo.delete_remote_branch(&remote_name, &branch_name)
+                                        })
+                                        .map(|()| format!("Deleted remote branch '{}'", full_name))
+                                        .map_err(|e| format!("Delete failed: {}", e));
+                                    AsyncLoadResult::RemoteOperationComplete(result)
+                                },
+                            );
                         }
                     } else {
                         self.message = Some("Invalid remote branch name".to_string());
@@ -2374,50 +4115,52 @@ impl App {
                     }
                 }
             }
-            ConfirmAction::Push => {
-                let remotes = self.repo.remotes()?;
-                if let Some(remote) = remotes.first() {
-                    self.start_async_push(remote.clone(), None);
-                } else {
-                    self.message = Some("No remote configured".to_string());
+            ConfirmAction::DiscardAll => match self.repo.discard_all() {
+                Ok(()) => {
+                    self.message = Some("Discarded all changes".to_string());
+                    self.refresh_status()?;
+                    self.refresh_diff()?;
+                    self.refresh_trash()?;
                 }
-            }
-            ConfirmAction::BranchPush => {
-                if let Some(ref branch_name) = self.confirm_target {
-                    let remotes = self.repo.remotes()?;
-                    if let Some(remote) = remotes.first() {
-                        self.start_async_push(remote.clone(), Some(branch_name.clone()));
-                    } else {
-                        self.message = Some("No remote configured".to_string());
+                Err(e) => {
+                    self.message = Some(format!("Discard failed: {}", e));
+                }
+            },
+            ConfirmAction::CleanUntracked => match self.repo.clean_untracked() {
+                Ok(n) => {
+                    self.message = Some(format!("Removed {} untracked file(s)", n));
+                    self.refresh_status()?;
+                    self.refresh_diff()?;
+                    self.refresh_trash()?;
+                }
+                Err(e) => {
+                    self.message = Some(format!("Clean failed: {}", e));
+                }
+            },
+            ConfirmAction::TrashRestore => {
+                if let Some(ref refname) = self.confirm_target {
+                    match self.repo.restore_trash(refname) {
+                        Ok(n) => {
+                            self.message = Some(format!("Restored {} file(s)", n));
+                            self.refresh_status()?;
+                            self.refresh_diff()?;
+                            self.refresh_trash()?;
+                        }
+                        Err(e) => {
+                            self.message = Some(format!("Restore failed: {}", e));
+                        }
                     }
                 }
             }
-            ConfirmAction::BranchMerge => {
-                if let Some(ref branch_name) = self.confirm_target {
-                    match self.repo.merge(branch_name) {
-                        Ok(result) => {
-                            let msg = match result {
-                                crate::git::MergeResult::UpToDate => {
-                                    format!("Already up to date with '{}'", branch_name)
-                                }
-                                crate::git::MergeResult::FastForward => {
-                                    format!("Fast-forwarded to '{}'", branch_name)
-                                }
-                                crate::git::MergeResult::Merged => {
-                                    format!("Merged '{}' into current branch", branch_name)
-                                }
-                                crate::git::MergeResult::Conflict => {
-                                    format!(
-                                        "Merge conflicts with '{}' - resolve manually",
-                                        branch_name
-                                    )
-                                }
-                            };
-                            self.message = Some(msg);
-                            self.refresh_all()?;
+            ConfirmAction::TrashDelete => {
+                if let Some(ref refname) = self.confirm_target {
+                    match self.repo.delete_trash(refname) {
+                        Ok(()) => {
+                            self.message = Some("Deleted trash entry".to_string());
+                            self.refresh_trash()?;
                         }
                         Err(e) => {
-                            self.message = Some(format!("Merge failed: {}", e));
+                            self.message = Some(format!("Delete failed: {}", e));
                         }
                     }
                 }
@@ -2458,58 +4201,74 @@ impl App {
                         self.remote_operation = Some(RemoteOperation::DeleteMergedBranches);
                         let sender = self.async_sender.clone();
                         let repo_path = self.repo_path.clone();
-                        thread::spawn(move || {
-                            let result = crate::git::Repository::open(&repo_path)
-                                .map_err(|e| e.to_string())
-                                .map(|repo| {
-                                    let mut deleted_local = 0;
-                                    let mut deleted_remote = 0;
-                                    let mut errors = Vec::new();
-
-                                    // Delete local branches
-                                    for branch in &local_branches {
-                                        match repo.delete_branch(branch, false) {
-                                            Ok(()) => deleted_local += 1,
-                                            Err(e) => errors.push(format!("{}: {}", branch, e)),
+                        let mut refs = vec!["HEAD".to_string()];
+                        for branch in &remote_branches {
+                            if let Some((remote, _)) = branch.split_once('/') {
+                                if !refs.contains(&remote_refs(remote)) {
+                                    refs.push(remote_refs(remote));
+                                }
+                            }
+                        }
+                        self.jobs.spawn(
+                            "Delete merged branches",
+                            refs,
+                            CancelToken::new(),
+                            sender,
+                            move |_| {
+                                let result = crate::git::Repository::open(&repo_path)
+                                    .map_err(|e| e.to_string())
+                                    .map(|repo| {
+                                        let mut deleted_local = 0;
+                                        let mut deleted_remote = 0;
+                                        let mut errors = Vec::new();
+
+                                        // Delete local branches
+                                        for branch in &local_branches {
+                                            match repo.delete_branch(branch, false) {
+                                                Ok(()) => deleted_local += 1,
+                                                Err(e) => errors.push(format!("{}: {}", branch, e)),
+                                            }
                                         }
-                                    }
 
-                                    // Delete remote branches
-                                    for branch in &remote_branches {
-                                        if let Some(slash_pos) = branch.find('/') {
-                                            let remote_name = &branch[..slash_pos];
-                                            let branch_name = &branch[slash_pos + 1..];
-                                            match repo
-                                                .delete_remote_branch(remote_name, branch_name)
-                                            {
-                                                Ok(()) => deleted_remote += 1,
-                                                Err(e) => errors.push(format!("{}: {}", branch, e)),
+                                        // Delete remote branches
+                                        for branch in &remote_branches {
+                                            if let Some(slash_pos) = branch.find('/') {
+                                                let remote_name = &branch[..slash_pos];
+                                                let branch_name = &branch[slash_pos + 1..];
+                                                match repo
+                                                    .delete_remote_branch(remote_name, branch_name)
+                                                {
+                                                    Ok(()) => deleted_remote += 1,
+                                                    Err(e) => {
+                                                        errors.push(format!("{}: {}", branch, e))
+                                                    }
+                                                }
                                             }
                                         }
-                                    }
 
-                                    if errors.is_empty() {
-                                        format!(
-                                            "Deleted {} local and {} remote merged branches",
-                                            deleted_local, deleted_remote
-                                        )
-                                    } else if errors.len() == 1 {
-                                        format!(
-                                            "Deleted {} local, {} remote. Error: {}",
-                                            deleted_local, deleted_remote, errors[0]
-                                        )
-                                    } else {
-                                        format!(
-                                            "Deleted {} local, {} remote. {} errors: {}",
-                                            deleted_local,
-                                            deleted_remote,
-                                            errors.len(),
-                                            errors.join("; ")
-                                        )
-                                    }
-                                });
-                            let _ = sender.send(AsyncLoadResult::RemoteOperationComplete(result));
-                        });
+                                        if errors.is_empty() {
+                                            format!(
+                                                "Deleted {} local and {} remote merged branches",
+                                                deleted_local, deleted_remote
+                                            )
+                                        } else if errors.len() == 1 {
+                                            format!(
+                                                "Deleted {} local, {} remote. Error: {}",
+                                                deleted_local, deleted_remote, errors[0]
+                                            )
+                                        } else {
+                                            format!(
+                                                "Deleted {} local, {} remote. {} errors: {}",
+                                                deleted_local,
+                                                deleted_remote,
+                                                errors.len(),
+                                                errors.join("; ")
+                                            )
+                                        }
+                                    });
+                                AsyncLoadResult::RemoteOperationComplete(result)
+                            },
+                        );
                     }
                 }
             }
@@ -2728,11 +4487,43 @@ impl App {
                     }
                 }
             }
+            ConfirmAction::WorktreeRemove | ConfirmAction::WorktreeForceRemove => {
+                if let Some(ref name) = self.confirm_target {
+                    let force = action == ConfirmAction::WorktreeForceRemove;
+                    match self.repo.worktree_remove(name, force) {
+                        Ok(()) => {
+                            self.message = Some(format!("Removed worktree '{}'", name));
+                            self.refresh_worktrees()?;
+                        }
+                        Err(e) => {
+                            self.message = Some(format!("Remove failed: {}", e));
+                        }
+                    }
+                }
+            }
+            ConfirmAction::SubmoduleDeinit => {
+                if let Some(ref name) = self.confirm_target {
+                    match self.repo.submodule_deinit(name, false) {
+                        Ok(()) => {
+                            self.message = Some(format!("Deinitialized submodule '{}'", name));
+                            self.refresh_submodules()?;
+                        }
+                        Err(e) => {
+                            self.message = Some(format!("Deinit failed: {}", e));
+                        }
+                    }
+                }
+            }
         }
         Ok(())
     }
 
     fn handle_mouse(&mut self, mouse: MouseEvent) -> Result<()> {
+        // The repository switcher is keyboard only
+        if self.mode == Mode::Repos {
+            return Ok(());
+        }
+
         let (width, height) = self.terminal.size()?;
         let main_top = 0u16;
         let main_height = height.saturating_sub(3); // footer only
@@ -3182,69 +4973,479 @@ impl App {
                     panel.height
                 ));
             }
-            content.push_str("]\n\n");
+            content.push_str("]\n\n");
+        }
+
+        let _ = std::fs::write(&config_path, content);
+    }
+
+    fn next_panel(&self) -> PanelType {
+        let all_panels = self.available_panels();
+        let current_idx = all_panels
+            .iter()
+            .position(|p| *p == self.focused_panel)
+            .unwrap_or(0);
+        let next_idx = (current_idx + 1) % all_panels.len();
+        all_panels[next_idx]
+    }
+
+    fn prev_panel(&self) -> PanelType {
+        let all_panels = self.available_panels();
+        let current_idx = all_panels
+            .iter()
+            .position(|p| *p == self.focused_panel)
+            .unwrap_or(0);
+        let prev_idx = if current_idx == 0 {
+            all_panels.len() - 1
+        } else {
+            current_idx - 1
+        };
+        all_panels[prev_idx]
+    }
+
+    /// Called when focused panel changes - updates preview pane content
+    fn on_panel_focus_changed(&mut self) {
+        self.terminal.force_full_redraw();
+        match self.focused_panel {
+            PanelType::PullRequests => {
+                self.refresh_pr_preview();
+            }
+            PanelType::Status => {
+                // Restore diff view for status
+                self.clear_pr_highlights();
+                let _ = self.refresh_diff();
+            }
+            PanelType::Files => {
+                self.clear_pr_highlights();
+                let _ = self.refresh_file_preview();
+            }
+            PanelType::Commits => {
+                self.clear_pr_highlights();
+                self.refresh_commit_preview();
+            }
+            PanelType::Reflog => {
+                self.clear_pr_highlights();
+                self.refresh_reflog_preview();
+            }
+            PanelType::Trash => {
+                self.clear_pr_highlights();
+                self.refresh_trash_preview();
+            }
+            PanelType::Hooks => {
+                self.clear_pr_highlights();
+            }
+            PanelType::Issues => {
+                self.clear_pr_highlights();
+                self.refresh_issue_preview();
+            }
+            PanelType::Conflicts => {
+                self.clear_pr_highlights();
+                self.refresh_conflict_preview();
+            }
+            _ => {
+                // Clear PR preview and highlights for other panels
+                self.clear_pr_highlights();
+            }
+        }
+    }
+
+    fn open_theme_picker(&mut self) {
+        self.theme_choices = Theme::available();
+        self.select_index = self
+            .theme_choices
+            .iter()
+            .position(|name| *name == self.config.theme.name)
+            .unwrap_or(0);
+        self.theme_before = Some(self.config.theme.clone());
+        self.mode = Mode::Themes;
+    }
+
+    fn handle_themes_key(&mut self, key: KeyEvent) -> Result<()> {
+        let count = self.theme_choices.len();
+        let previous = self.select_index;
+        match key.code {
+            KeyCode::Char('l') | KeyCode::Char('j') | KeyCode::Right | KeyCode::Down
+                if self.select_index + 1 < count =>
+            {
+                self.select_index += 1;
+            }
+            KeyCode::Char('h') | KeyCode::Char('k') | KeyCode::Left | KeyCode::Up
+                if self.select_index > 0 =>
+            {
+                self.select_index -= 1;
+            }
+            KeyCode::Enter => {
+                self.mode = Mode::Normal;
+                self.theme_before = None;
+                self.config.save_theme_name();
+                self.message = Some(format!("Theme: {}", self.config.theme.name));
+            }
+            KeyCode::Escape => {
+                self.mode = Mode::Normal;
+                if let Some(theme) = self.theme_before.take() {
+                    self.config.theme = theme;
+                }
+            }
+            _ => {}
+        }
+
+        // Live preview
+        if self.mode == Mode::Themes && self.select_index != previous {
+            let name = &self.theme_choices[self.select_index];
+            match self.config.theme_named(name) {
+                Some(theme) => self.config.theme = theme,
+                None => self.message = Some(format!("Cannot load theme {}", name)),
+            }
+        }
+        Ok(())
+    }
+
+    fn open_jobs(&mut self) {
+        self.select_index = 0;
+        self.mode = Mode::Jobs;
+    }
+
+    fn handle_jobs_key(&mut self, key: KeyEvent) -> Result<()> {
+        let jobs = self.jobs.running();
+        // Jobs finish while the list is open
+        self.select_index = self.select_index.min(jobs.len().saturating_sub(1));
+        match key.code {
+            KeyCode::Char('l') | KeyCode::Char('j') | KeyCode::Right | KeyCode::Down
+                if self.select_index + 1 < jobs.len() =>
+            {
+                self.select_index += 1;
+            }
+            KeyCode::Char('h') | KeyCode::Char('k') | KeyCode::Left | KeyCode::Up
+                if self.select_index > 0 =>
+            {
+                self.select_index -= 1;
+            }
+            KeyCode::Char('x') | KeyCode::Char('d') => {
+                if let Some(job) = jobs.get(self.select_index) {
+                    if self.jobs.cancel(job.id) {
+                        self.message = Some(format!("Cancelling {}...", job.label));
+                    }
+                }
+            }
+            KeyCode::Escape | KeyCode::Char('q') | KeyCode::Char('&') => {
+                self.mode = Mode::Normal;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Show the switcher with favorites, configured and recent repositories,
+    /// loading the dashboard summaries in the background
+    fn open_repo_switcher(&mut self) {
+        let list = RepoList::load();
+        let mut entries: Vec<RepoEntry> = Vec::new();
+        let candidates = list
+            .favorites
+            .iter()
+            .chain(&self.config.workspace_repos)
+            .chain(&list.recent);
+        for path in candidates {
+            let path = path.canonicalize().unwrap_or_else(|_| path.clone());
+            if entries.iter().any(|e| e.path == path) {
+                continue;
+            }
+            let favorite = list
+                .favorites
+                .iter()
+                .any(|f| f.canonicalize().unwrap_or_else(|_| f.clone()) == path);
+            entries.push(RepoEntry { path, favorite });
+        }
+
+        self.repos_view.summaries.clear();
+        for entry in &entries {
+            let sender = self.async_sender.clone();
+            let path = entry.path.clone();
+            thread::spawn(move || {
+                let _ = sender.send(AsyncLoadResult::RepoSummary(RepoSummary::load(&path)));
+            });
+        }
+        self.repos_view.open(entries);
+        self.mode = Mode::Repos;
+    }
+
+    fn handle_repos_key(&mut self, key: KeyEvent) -> Result<()> {
+        match key.code {
+            KeyCode::Escape => self.mode = Mode::Normal,
+            KeyCode::Enter | KeyCode::Tab => {
+                let Some(entry) = self.repos_view.selected_entry() else {
+                    return Ok(());
+                };
+                let path = entry.path.clone();
+                let new_tab = key.code == KeyCode::Tab;
+                self.mode = Mode::Normal;
+                if !new_tab && path == self.session_key() {
+                    self.message = Some("Already open".to_string());
+                } else {
+                    self.tab_request = Some(TabRequest::Open { path, new_tab });
+                }
+            }
+            KeyCode::Char('f') if key.modifiers.contains(Modifiers::CTRL) => {
+                let Some(entry) = self.repos_view.selected_entry() else {
+                    return Ok(());
+                };
+                let path = entry.path.clone();
+                let mut list = RepoList::load();
+                let favorite = list.toggle_favorite(&path);
+                if let Err(e) = list.save() {
+                    self.message = Some(format!("Failed to save favorites: {}", e));
+                }
+                self.repos_view.set_favorite(&path, favorite);
+            }
+            KeyCode::Char('p') if key.modifiers.contains(Modifiers::CTRL) => {
+                self.repos_view.move_up()
+            }
+            KeyCode::Char('n') if key.modifiers.contains(Modifiers::CTRL) => {
+                self.repos_view.move_down()
+            }
+            KeyCode::Up => self.repos_view.move_up(),
+            KeyCode::Down => self.repos_view.move_down(),
+            KeyCode::Backspace => self.repos_view.pop_char(),
+            KeyCode::Char(c) if !key.modifiers.contains(Modifiers::CTRL) => {
+                self.repos_view.push_char(c)
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn session_key(&self) -> PathBuf {
+        self.repo_path
+            .canonicalize()
+            .unwrap_or_else(|_| self.repo_path.clone())
+    }
+
+    fn capture_session(&self) -> Session {
+        let mut positions = HashMap::new();
+        positions.insert("stash".to_string(), self.stash_view.selected);
+        positions.insert("tags".to_string(), self.tags_view.selected);
+        positions.insert("reflog".to_string(), self.reflog_view.selected);
+        positions.insert("files".to_string(), self.filetree_view.selected);
+
+        let searches = [
+            ("commits", &self.commits_view.search_query),
+            ("branches", &self.branches_view.search_query),
+            ("status", &self.status_view.search_query),
+            ("files", &self.filetree_view.search_query),
+        ]
+        .into_iter()
+        .filter_map(|(panel, query)| Some((panel.to_string(), query.clone()?)))
+        .collect();
+
+        Session {
+            repo: self.session_key(),
+            focused_panel: Some(panel_type_to_string(self.focused_panel).to_string()),
+            zoomed: self.view_mode == ViewMode::SinglePane,
+            commits_mode: Some(
+                match self.commits_view.view_mode {
+                    CommitsViewMode::Compact => "compact",
+                    CommitsViewMode::Detailed => "detailed",
+                    CommitsViewMode::Graph => "graph",
+                }
+                .to_string(),
+            ),
+            diff_mode: Some(
+                match self.diff_view.mode {
+                    DiffMode::Inline => "inline",
+                    DiffMode::SideBySide => "split",
+                }
+                .to_string(),
+            ),
+            files_mode: Some(
+                match self.filetree_view.view_mode {
+                    FileViewMode::Tree => "tree",
+                    FileViewMode::Flat => "flat",
+                    FileViewMode::TreeWithIgnored => "tree_ignored",
+                }
+                .to_string(),
+            ),
+            commits_path: self.commits_path.clone(),
+            selected_commit: self.commits_view.selected_commit().map(|c| c.id.clone()),
+            selected_branch: self.branches_view.selected_branch().map(|b| b.name.clone()),
+            marked_commits: self
+                .commits_view
+                .get_marked_commits()
+                .iter()
+                .cloned()
+                .collect(),
+            positions,
+            searches,
+        }
+    }
+
+    /// Bring back the state saved by the last run; things that no longer exist are skipped
+    fn restore_session(&mut self, session: Session) -> Result<()> {
+        match session.diff_mode.as_deref() {
+            Some("inline") => self.diff_view.set_mode(DiffMode::Inline),
+            Some("split") => self.diff_view.set_mode(DiffMode::SideBySide),
+            _ => {}
+        }
+        let files_mode = match session.files_mode.as_deref() {
+            Some("tree") => Some(FileViewMode::Tree),
+            Some("flat") => Some(FileViewMode::Flat),
+            Some("tree_ignored") => Some(FileViewMode::TreeWithIgnored),
+            _ => None,
+        };
+        if let Some(mode) = files_mode.filter(|m| *m != self.filetree_view.view_mode) {
+            self.filetree_view.view_mode = mode;
+            self.refresh_filetree()?;
+        }
+        match session.commits_mode.as_deref() {
+            Some("compact") => self.commits_view.set_view_mode(CommitsViewMode::Compact),
+            Some("detailed") => self.commits_view.set_view_mode(CommitsViewMode::Detailed),
+            Some("graph") => self.commits_view.set_view_mode(CommitsViewMode::Graph),
+            _ => {}
+        }
+        if session.commits_path.is_some() {
+            // A path that no longer exists simply matches fewer commits
+            self.set_commits_path(session.commits_path)?;
+            self.wait_for_loads();
+        }
+
+        for (panel, query) in &session.searches {
+            match panel.as_str() {
+                "commits" => self.commits_view.search(query),
+                "branches" => self.branches_view.search(query),
+                "status" => self.status_view.search(query),
+                "files" => self.filetree_view.search(query),
+                _ => {}
+            }
+        }
+
+        let loaded: HashSet<&str> = self
+            .commits_view
+            .commits
+            .iter()
+            .map(|c| c.id.as_str())
+            .collect();
+        let marked: Vec<String> = session
+            .marked_commits
+            .into_iter()
+            .filter(|id| loaded.contains(id.as_str()))
+            .collect();
+        if !marked.is_empty() {
+            self.commits_view.marked_commits = marked.into_iter().collect();
+            self.update_files_filter_from_marked_commits()?;
+        }
+        if let Some(id) = session.selected_commit {
+            self.commits_view.select_commit(&id);
+        }
+        if let Some(name) = session.selected_branch {
+            if let Some(index) = self
+                .branches_view
+                .visible_branches()
+                .iter()
+                .position(|b| b.name == name)
+            {
+                self.branches_view.selected = index;
+            }
+        }
+        for (panel, &row) in &session.positions {
+            // move_down stops at the last row, so a shorter list is fine
+            for _ in 0..row {
+                match panel.as_str() {
+                    "stash" => self.stash_view.move_down(),
+                    "tags" => self.tags_view.move_down(),
+                    "reflog" => self.reflog_view.move_down(),
+                    "files" => self.filetree_view.move_down(),
+                    _ => break,
+                }
+            }
         }
 
-        let _ = std::fs::write(&config_path, content);
+        if session.zoomed {
+            self.view_mode = ViewMode::SinglePane;
+        }
+        let panel = session
+            .focused_panel
+            .as_deref()
+            .and_then(LayoutConfig::parse_panel_type);
+        if let Some(panel) = panel {
+            self.focus_panel(panel);
+        }
+        self.message = None;
+        Ok(())
     }
 
-    fn next_panel(&self) -> PanelType {
-        let all_panels = self.available_panels();
-        let current_idx = all_panels
-            .iter()
-            .position(|p| *p == self.focused_panel)
-            .unwrap_or(0);
-        let next_idx = (current_idx + 1) % all_panels.len();
-        all_panels[next_idx]
+    /// Focus `panel`, zooming in when the layout does not show it
+    fn focus_panel(&mut self, panel: PanelType) {
+        if !self.config.layout.all_panels().contains(&panel) {
+            self.view_mode = ViewMode::SinglePane;
+        }
+        self.focused_panel = panel;
+        self.on_panel_focus_changed();
     }
 
-    fn prev_panel(&self) -> PanelType {
-        let all_panels = self.available_panels();
-        let current_idx = all_panels
-            .iter()
-            .position(|p| *p == self.focused_panel)
-            .unwrap_or(0);
-        let prev_idx = if current_idx == 0 {
-            all_panels.len() - 1
-        } else {
-            current_idx - 1
-        };
-        all_panels[prev_idx]
+    /// Blame `path` in the background, then show it at `line`
+    fn load_blame(&mut self, path: String, line: Option<usize>) {
+        let (generation, token) = self.jobs.begin(JobView::Blame);
+        let repo_path = self.repo_path.clone();
+        self.jobs.spawn(
+            format!("Blame {}", path),
+            Vec::new(),
+            token,
+            self.async_sender.clone(),
+            move |_| {
+                let result = Repository::open(&repo_path)
+                    .and_then(|repo| repo.blame_file(&path))
+                    .map_err(|e| e.to_string());
+                AsyncLoadResult::GitBlame(generation, path, line, result)
+            },
+        );
     }
 
-    /// Called when focused panel changes - updates preview pane content
-    fn on_panel_focus_changed(&mut self) {
-        self.terminal.force_full_redraw();
-        match self.focused_panel {
-            PanelType::PullRequests => {
-                self.refresh_pr_preview();
-            }
-            PanelType::Status => {
-                // Restore diff view for status
-                self.clear_pr_highlights();
-                let _ = self.refresh_diff();
-            }
-            PanelType::Files => {
-                self.clear_pr_highlights();
-                let _ = self.refresh_file_preview();
-            }
-            PanelType::Commits => {
-                self.clear_pr_highlights();
-                self.refresh_commit_preview();
-            }
-            PanelType::Issues => {
-                self.clear_pr_highlights();
-                self.refresh_issue_preview();
-            }
-            PanelType::Conflicts => {
-                self.clear_pr_highlights();
-                self.refresh_conflict_preview();
-            }
-            _ => {
-                // Clear PR preview and highlights for other panels
-                self.clear_pr_highlights();
-            }
+    /// Limit the Commits panel to commits touching `path`, or show all again
+    fn set_commits_path(&mut self, path: Option<String>) -> Result<()> {
+        self.commits_path = path.filter(|p| !p.is_empty());
+        if self.commits_path.is_some() && self.commits_view.view_mode == CommitsViewMode::Graph {
+            // The graph is always of the whole history
+            self.commits_view.set_view_mode(CommitsViewMode::Compact);
+        }
+        self.refresh_commits()
+    }
+
+    fn open_startup(&mut self, startup: Startup) -> Result<()> {
+        // Paths on the command line are relative to where g was started (or -C)
+        let base = match startup.repo_dir {
+            Some(dir) => dir,
+            None => std::env::current_dir()?,
+        };
+        match startup.target {
+            Some(StartupTarget::Blame { path, line }) => {
+                let path = self.repo.relative_path(&base.join(&path)).unwrap_or(path);
+                self.load_blame(path, line);
+            }
+            Some(StartupTarget::Log { path }) => {
+                let path = self.repo.relative_path(&base.join(&path)).unwrap_or(path);
+                self.set_commits_path(Some(path))?;
+                self.focus_panel(PanelType::Commits);
+            }
+            Some(StartupTarget::Show { rev }) => match self.repo.commit_info(&rev) {
+                Ok(commit) => {
+                    if self.commits_view.select_commit(&commit.id) {
+                        self.focus_panel(PanelType::Commits);
+                    } else {
+                        // Not among the loaded commits: show it in the diff panel
+                        self.focus_panel(PanelType::Diff);
+                        self.diff_view.set_commit_preview(&commit);
+                        if let Ok(diff) = self.repo.diff_commit(&commit.id) {
+                            self.diff_view.set_commit_diff(diff);
+                        }
+                    }
+                }
+                Err(e) => self.message = Some(format!("Unknown revision {}: {}", rev, e)),
+            },
+            None => {}
         }
+        if let Some(panel) = startup.panel {
+            self.focus_panel(panel);
+        }
+        Ok(())
     }
 
     fn available_panels(&self) -> Vec<PanelType> {
@@ -3261,6 +5462,9 @@ impl App {
                     PanelType::Remotes,
                     PanelType::Worktrees,
                     PanelType::Submodules,
+                    PanelType::Reflog,
+                    PanelType::Trash,
+                    PanelType::Hooks,
                     PanelType::Blame,
                     PanelType::Files,
                     PanelType::Conflicts,
@@ -3386,6 +5590,15 @@ impl App {
             PanelType::Remotes => self.remotes_view.select_at_row(row),
             PanelType::Worktrees => self.worktree_view.select_at_row(row),
             PanelType::Submodules => self.submodules_view.select_at_row(row),
+            PanelType::Reflog => {
+                self.reflog_view.select_at_row(row);
+                self.refresh_reflog_preview();
+            }
+            PanelType::Trash => {
+                self.trash_view.select_at_row(row);
+                self.refresh_trash_preview();
+            }
+            PanelType::Hooks => self.hooks_view.select_at_row(row),
             PanelType::Blame => self.blame_view.select_at_row(row),
             PanelType::Files => {
                 self.filetree_view.select_at_row(row);
@@ -3426,6 +5639,15 @@ impl App {
             PanelType::Remotes => self.remotes_view.move_up(),
             PanelType::Worktrees => self.worktree_view.move_up(),
             PanelType::Submodules => self.submodules_view.move_up(),
+            PanelType::Reflog => {
+                self.reflog_view.move_up();
+                self.refresh_reflog_preview();
+            }
+            PanelType::Trash => {
+                self.trash_view.move_up();
+                self.refresh_trash_preview();
+            }
+            PanelType::Hooks => self.hooks_view.move_up(),
             PanelType::Blame => self.blame_view.move_up(),
             PanelType::Files => {
                 self.filetree_view.move_up();
@@ -3466,6 +5688,15 @@ impl App {
             PanelType::Remotes => self.remotes_view.move_down(),
             PanelType::Worktrees => self.worktree_view.move_down(),
             PanelType::Submodules => self.submodules_view.move_down(),
+            PanelType::Reflog => {
+                self.reflog_view.move_down();
+                self.refresh_reflog_preview();
+            }
+            PanelType::Trash => {
+                self.trash_view.move_down();
+                self.refresh_trash_preview();
+            }
+            PanelType::Hooks => self.hooks_view.move_down(),
             PanelType::Blame => self.blame_view.move_down(),
             PanelType::Files => {
                 self.filetree_view.move_down();
@@ -3496,6 +5727,19 @@ impl App {
                 self.should_quit = true;
             }
 
+            // Cancel the running fetch/pull/push
+            KeyCode::Escape if self.transfer_progress.is_some() => {
+                if let Some(progress) = &self.transfer_progress {
+                    progress.cancel();
+                }
+                self.message = Some("Cancelling...".to_string());
+            }
+
+            // Redo (Ctrl+r) - must be before the 'r' handlers
+            KeyCode::Char('r') if key == KeyEvent::ctrl('r') => {
+                self.redo_operation()?;
+            }
+
             KeyCode::Tab => {
                 self.focused_panel = self.next_panel();
                 self.on_panel_focus_changed();
@@ -3512,8 +5756,8 @@ impl App {
                     if branch.is_head {
                         self.message = Some("Cannot merge current branch into itself".to_string());
                     } else {
-                        self.confirm_target = Some(branch.name.clone());
-                        self.mode = Mode::Confirm(ConfirmAction::BranchMerge);
+                        let name = branch.name.clone();
+                        self.open_merge_dialog(name.clone(), name)?;
                     }
                 }
             }
@@ -3536,6 +5780,31 @@ impl App {
                 self.menu_view.toggle();
             }
 
+            // Theme picker
+            KeyCode::Char('T') => self.open_theme_picker(),
+
+            // Running background jobs
+            KeyCode::Char('&') => self.open_jobs(),
+
+            // Repository switcher and tabs
+            KeyCode::Char('O') => self.open_repo_switcher(),
+            KeyCode::Char(']') | KeyCode::Char('[') if self.tab_titles.len() > 1 => {
+                let count = self.tab_titles.len();
+                let next = if key.code == KeyCode::Char(']') {
+                    (self.active_tab + 1) % count
+                } else {
+                    (self.active_tab + count - 1) % count
+                };
+                self.tab_request = Some(TabRequest::Switch(next));
+            }
+            KeyCode::Char('W') => {
+                if self.tab_titles.len() > 1 {
+                    self.tab_request = Some(TabRequest::Close);
+                } else {
+                    self.message = Some("Only one tab is open".to_string());
+                }
+            }
+
             // Vim navigation (j/k for item movement within panel, g/G for top/bottom)
             KeyCode::Char('j') => self.item_down()?,
             KeyCode::Char('k') => self.item_up()?,
@@ -3720,6 +5989,10 @@ impl App {
                 if self.status_view.staged_count() > 0 {
                     self.mode = Mode::Input(InputContext::CommitMessage);
                     self.input_buffer.clear();
+                    // Start from the subject an unfinished merge or squash prepared
+                    if let Some(prepared) = self.repo.prepared_message() {
+                        self.input_buffer = prepared.lines().next().unwrap_or("").to_string();
+                    }
                     self.input_cursor = 0;
                 } else {
                     self.message = Some("No changes staged".to_string());
@@ -3745,17 +6018,29 @@ impl App {
                 }
             }
 
+            // Discard all changes / delete untracked files (Status panel)
+            KeyCode::Char('D') if self.focused_panel == PanelType::Status => {
+                self.mode = Mode::Confirm(ConfirmAction::DiscardAll);
+            }
+
+            KeyCode::Char('X') if self.focused_panel == PanelType::Status => {
+                self.mode = Mode::Confirm(ConfirmAction::CleanUntracked);
+            }
+
             // Push to remote (Status panel)
             KeyCode::Char('P') if self.focused_panel == PanelType::Status => {
-                self.confirm_target = None;
-                self.mode = Mode::Confirm(ConfirmAction::Push);
+                self.open_push_dialog(None)?;
             }
 
             // Push to remote (Branches panel)
             KeyCode::Char('P') if self.focused_panel == PanelType::Branches => {
                 if let Some(branch) = self.branches_view.selected_branch() {
-                    self.confirm_target = Some(branch.name.clone());
-                    self.mode = Mode::Confirm(ConfirmAction::BranchPush);
+                    if branch.branch_type == crate::git::BranchType::Local {
+                        let name = branch.name.clone();
+                        self.open_push_dialog(Some(name))?;
+                    } else {
+                        self.message = Some("Select a local branch to push".to_string());
+                    }
                 }
             }
 
@@ -3862,7 +6147,7 @@ impl App {
                         // For local branches, pull
                         let remotes = self.repo.remotes()?;
                         if let Some(remote) = remotes.first() {
-                            self.start_async_pull(remote.clone(), branch_name);
+                            self.open_pull_dialog(remote.clone(), branch_name);
                         } else {
                             self.message = Some("No remote configured".to_string());
                         }
@@ -3870,20 +6155,20 @@ impl App {
                 }
             }
 
+            // Merge selected commit into current branch
+            KeyCode::Char('M') if self.focused_panel == PanelType::Commits => {
+                if let Some(commit) = self.commits_view.selected_commit() {
+                    let (id, short_id) = (commit.id.clone(), commit.short_id.clone());
+                    self.open_merge_dialog(id, short_id)?;
+                }
+            }
+
             // Commits panel actions
             KeyCode::Char('c') if self.focused_panel == PanelType::Commits => {
                 // Checkout (detached HEAD)
                 if let Some(commit) = self.commits_view.selected_commit() {
-                    let short_id = commit.short_id.clone();
-                    match self.repo.checkout_commit(&commit.id) {
-                        Ok(()) => {
-                            self.message = Some(format!("Checked out: {}", short_id));
-                            self.refresh_all()?;
-                        }
-                        Err(e) => {
-                            self.message = Some(format!("Checkout failed: {}", e));
-                        }
-                    }
+                    let target = CheckoutTarget::Commit(commit.id.clone());
+                    self.request_checkout(target)?;
                 }
             }
 
@@ -3896,6 +6181,26 @@ impl App {
                 }
             }
 
+            KeyCode::Char('F') if self.focused_panel == PanelType::Commits => {
+                // Format-patch the marked commits, or the selected one
+                let marked = self.commits_view.get_marked_commits();
+                let ids: Vec<String> = if marked.is_empty() {
+                    self.commits_view
+                        .selected_commit()
+                        .map(|c| c.id.clone())
+                        .into_iter()
+                        .collect()
+                } else {
+                    marked.iter().cloned().collect()
+                };
+                if !ids.is_empty() {
+                    self.confirm_target = Some(ids.len().to_string());
+                    self.export_commits = ids;
+                    self.select_index = 0;
+                    self.mode = Mode::Select(SelectAction::ExportPatch);
+                }
+            }
+
             KeyCode::Char('v') if self.focused_panel == PanelType::Commits => {
                 self.commits_view.toggle_view_mode();
                 // Load graph commits if switching to graph mode
@@ -3910,89 +6215,277 @@ impl App {
                 self.message = Some(format!("Commits view: {}", mode_name));
             }
 
-            // Switch to branch from commits pane
-            KeyCode::Char('s') if self.focused_panel == PanelType::Commits => {
-                if let Some(commit) = self.commits_view.selected_commit() {
-                    // Find first branch ref (skip tags)
-                    let branch_ref = commit
-                        .refs
-                        .iter()
-                        .find(|r| !r.starts_with("tag: ") && *r != "HEAD");
-
-                    if let Some(ref_name) = branch_ref {
-                        // Determine if it's a remote or local branch
-                        let remotes = self.repo.remotes().unwrap_or_default();
-                        let is_remote = remotes
-                            .iter()
-                            .any(|remote| ref_name.starts_with(&format!("{}/", remote)));
+            // Switch to branch from commits pane
+            KeyCode::Char('s') if self.focused_panel == PanelType::Commits => {
+                if let Some(commit) = self.commits_view.selected_commit() {
+                    // Find first branch ref (skip tags)
+                    let branch_ref = commit
+                        .refs
+                        .iter()
+                        .find(|r| !r.starts_with("tag: ") && *r != "HEAD");
+
+                    if let Some(ref_name) = branch_ref {
+                        // Determine if it's a remote or local branch
+                        let remotes = self.repo.remotes().unwrap_or_default();
+                        let is_remote = remotes
+                            .iter()
+                            .any(|remote| ref_name.starts_with(&format!("{}/", remote)));
+
+                        let (name, branch_type) = if is_remote {
+                            (ref_name.clone(), crate::git::BranchType::Remote)
+                        } else {
+                            (ref_name.clone(), crate::git::BranchType::Local)
+                        };
+
+                        self.request_checkout(CheckoutTarget::Branch(name, branch_type))?;
+                    } else {
+                        self.message = Some("No branch ref on this commit".to_string());
+                    }
+                }
+            }
+
+            KeyCode::Char('v') if self.focused_panel == PanelType::Files => {
+                self.filetree_view.cycle_view_mode();
+                match self.filetree_view.view_mode {
+                    FileViewMode::Flat => {
+                        if let Ok(flat) = self.repo.file_tree_flat() {
+                            self.filetree_view.update_flat(flat);
+                        }
+                    }
+                    FileViewMode::TreeWithIgnored => {
+                        self.refresh_filetree()?;
+                    }
+                    FileViewMode::Tree => {
+                        self.refresh_filetree()?;
+                    }
+                }
+                let mode_name = match self.filetree_view.view_mode {
+                    FileViewMode::Tree => "tree",
+                    FileViewMode::Flat => "flat",
+                    FileViewMode::TreeWithIgnored => "tree +ignored",
+                };
+                self.message = Some(format!("Files view: {}", mode_name));
+            }
+
+            // Stash panel actions
+            KeyCode::Char('a') if self.focused_panel == PanelType::Stash => {
+                if let Some(stash) = self.stash_view.selected_stash() {
+                    let index = stash.index;
+                    let Some(_hold) = self.hold_local_refs() else {
+                        return Ok(());
+                    };
+                    match self.repo.stash_apply(index) {
+                        Ok(()) => {
+                            self.message = Some(format!("Applied stash@{{{}}}", index));
+                            self.refresh_all()?;
+                        }
+                        Err(e) => {
+                            self.message = Some(format!("Stash apply failed: {}", e));
+                        }
+                    }
+                }
+            }
+
+            KeyCode::Char('d') if self.focused_panel == PanelType::Stash => {
+                if let Some(stash) = self.stash_view.selected_stash() {
+                    self.confirm_target = Some(stash.index.to_string());
+                    self.mode = Mode::Confirm(ConfirmAction::StashDrop);
+                }
+            }
+
+            // Submodules panel actions
+            KeyCode::Char('u') if self.focused_panel == PanelType::Submodules => {
+                if let Some(sm) = self.submodules_view.selected_submodule() {
+                    let name = sm.name.clone();
+                    self.start_async_submodule_update(name);
+                }
+            }
+
+            KeyCode::Char('i') if self.focused_panel == PanelType::Submodules => {
+                if let Some(sm) = self.submodules_view.selected_submodule() {
+                    let name = sm.name.clone();
+                    match self.repo.submodule_init(&name) {
+                        Ok(()) => {
+                            self.message = Some(format!("Initialized submodule '{}'", name));
+                            self.refresh_submodules()?;
+                        }
+                        Err(e) => {
+                            self.message = Some(format!("Init failed: {}", e));
+                        }
+                    }
+                }
+            }
+
+            KeyCode::Char('I') if self.focused_panel == PanelType::Submodules => {
+                if let Some(sm) = self.submodules_view.selected_submodule() {
+                    self.confirm_target = Some(sm.name.clone());
+                    self.mode = Mode::Confirm(ConfirmAction::SubmoduleDeinit);
+                }
+            }
+
+            KeyCode::Char('s') if self.focused_panel == PanelType::Submodules => {
+                if let Some(sm) = self.submodules_view.selected_submodule() {
+                    let name = sm.name.clone();
+                    match self.repo.submodule_sync(&name) {
+                        Ok(()) => {
+                            self.message = Some(format!("Synced URL of submodule '{}'", name));
+                            self.refresh_submodules()?;
+                        }
+                        Err(e) => {
+                            self.message = Some(format!("Sync failed: {}", e));
+                        }
+                    }
+                }
+            }
+
+            KeyCode::Char('a') if self.focused_panel == PanelType::Submodules => {
+                self.mode = Mode::Input(InputContext::SubmoduleAdd);
+                self.input_buffer.clear();
+                self.input_cursor = 0;
+            }
+
+            // Reflog panel actions
+            KeyCode::Char('v') if self.focused_panel == PanelType::Reflog => {
+                self.reflog_view.cycle_ref();
+                self.refresh_reflog()?;
+            }
+
+            KeyCode::Char('c') if self.focused_panel == PanelType::Reflog => {
+                if let Some(entry) = self.reflog_view.selected_entry() {
+                    let target = CheckoutTarget::Commit(entry.id.clone());
+                    self.request_checkout(target)?;
+                }
+            }
+
+            KeyCode::Char('b') if self.focused_panel == PanelType::Reflog => {
+                if let Some(entry) = self.reflog_view.selected_entry() {
+                    self.branch_create_from = Some(entry.short_id.clone());
+                    self.mode = Mode::Input(InputContext::BranchName);
+                    self.input_buffer.clear();
+                    self.input_cursor = 0;
+                }
+            }
+
+            KeyCode::Char('R') if self.focused_panel == PanelType::Reflog => {
+                if let Some(entry) = self.reflog_view.selected_entry() {
+                    self.confirm_target = Some(entry.id.clone());
+                    self.select_index = 0;
+                    self.mode = Mode::Select(SelectAction::ResetOrRevert);
+                }
+            }
+
+            // Trash panel actions
+            KeyCode::Char('R') if self.focused_panel == PanelType::Trash => {
+                if let Some(entry) = self.trash_view.selected_entry() {
+                    self.confirm_target = Some(entry.refname.clone());
+                    self.mode = Mode::Confirm(ConfirmAction::TrashRestore);
+                }
+            }
+
+            KeyCode::Char('d') if self.focused_panel == PanelType::Trash => {
+                if let Some(entry) = self.trash_view.selected_entry() {
+                    self.confirm_target = Some(entry.refname.clone());
+                    self.mode = Mode::Confirm(ConfirmAction::TrashDelete);
+                }
+            }
+
+            // Leave a submodule and return to its superproject
+            KeyCode::Backspace if !self.repo_stack.is_empty() => {
+                if let Some(parent) = self.repo_stack.pop() {
+                    match self.load_repo(&parent) {
+                        Ok(()) => {
+                            self.message = Some(format!("Back in {}", self.repo.name()));
+                        }
+                        Err(e) => {
+                            self.message = Some(format!("Cannot reopen parent: {}", e));
+                        }
+                    }
+                }
+            }
+
+            // Worktrees panel actions
+            KeyCode::Char('a') if self.focused_panel == PanelType::Worktrees => {
+                self.worktree_create_from = None;
+                self.mode = Mode::Input(InputContext::WorktreePath);
+                self.input_buffer.clear();
+                self.input_cursor = 0;
+            }
 
-                        let (name, branch_type) = if is_remote {
-                            (ref_name.clone(), crate::git::BranchType::Remote)
+            KeyCode::Char('d') | KeyCode::Char('D')
+                if self.focused_panel == PanelType::Worktrees =>
+            {
+                if let Some(wt) = self.worktree_view.selected_worktree() {
+                    if wt.is_main {
+                        self.message = Some("Cannot remove the main worktree".to_string());
+                    } else if wt.is_current {
+                        self.message = Some("Cannot remove the current worktree".to_string());
+                    } else {
+                        self.confirm_target = Some(wt.name.clone());
+                        self.mode = Mode::Confirm(if key.code == KeyCode::Char('D') {
+                            ConfirmAction::WorktreeForceRemove
                         } else {
-                            (ref_name.clone(), crate::git::BranchType::Local)
-                        };
+                            ConfirmAction::WorktreeRemove
+                        });
+                    }
+                }
+            }
 
-                        self.repo.switch_branch(&name, branch_type)?;
-                        let display_name = if is_remote {
-                            if let Some(pos) = name.find('/') {
-                                &name[pos + 1..]
-                            } else {
-                                &name
+            // Lock (asks for a reason) or unlock the selected worktree
+            KeyCode::Char('x') if self.focused_panel == PanelType::Worktrees => {
+                if let Some(wt) = self.worktree_view.selected_worktree() {
+                    if wt.is_main {
+                        self.message = Some("The main worktree cannot be locked".to_string());
+                    } else if wt.is_locked {
+                        let name = wt.name.clone();
+                        match self.repo.worktree_unlock(&name) {
+                            Ok(()) => {
+                                self.message = Some(format!("Unlocked worktree '{}'", name));
+                                self.refresh_worktrees()?;
                             }
-                        } else {
-                            &name
-                        };
-                        self.message = Some(format!("Switched to: {}", display_name));
-                        self.refresh_all()?;
+                            Err(e) => {
+                                self.message = Some(format!("Unlock failed: {}", e));
+                            }
+                        }
                     } else {
-                        self.message = Some("No branch ref on this commit".to_string());
+                        self.confirm_target = Some(wt.name.clone());
+                        self.mode = Mode::Input(InputContext::WorktreeLockReason);
+                        self.input_buffer.clear();
+                        self.input_cursor = 0;
                     }
                 }
             }
 
-            KeyCode::Char('v') if self.focused_panel == PanelType::Files => {
-                self.filetree_view.cycle_view_mode();
-                match self.filetree_view.view_mode {
-                    FileViewMode::Flat => {
-                        if let Ok(flat) = self.repo.file_tree_flat() {
-                            self.filetree_view.update_flat(flat);
-                        }
+            KeyCode::Char('p') if self.focused_panel == PanelType::Worktrees => {
+                match self.repo.worktree_prune() {
+                    Ok(pruned) if pruned.is_empty() => {
+                        self.message = Some("No stale worktrees to prune".to_string());
                     }
-                    FileViewMode::TreeWithIgnored => {
-                        self.refresh_filetree()?;
+                    Ok(pruned) => {
+                        self.message = Some(format!("Pruned worktrees: {}", pruned.join(", ")));
+                        self.refresh_worktrees()?;
                     }
-                    FileViewMode::Tree => {
-                        self.refresh_filetree()?;
+                    Err(e) => {
+                        self.message = Some(format!("Prune failed: {}", e));
                     }
                 }
-                let mode_name = match self.filetree_view.view_mode {
-                    FileViewMode::Tree => "tree",
-                    FileViewMode::Flat => "flat",
-                    FileViewMode::TreeWithIgnored => "tree +ignored",
-                };
-                self.message = Some(format!("Files view: {}", mode_name));
             }
 
-            // Stash panel actions
-            KeyCode::Char('a') if self.focused_panel == PanelType::Stash => {
-                if let Some(stash) = self.stash_view.selected_stash() {
-                    let index = stash.index;
-                    match self.repo.stash_apply(index) {
-                        Ok(()) => {
-                            self.message = Some(format!("Applied stash@{{{}}}", index));
-                            self.refresh_all()?;
-                        }
-                        Err(e) => {
-                            self.message = Some(format!("Stash apply failed: {}", e));
-                        }
-                    }
+            // Create a worktree from the selected branch / commit
+            KeyCode::Char('w') if self.focused_panel == PanelType::Branches => {
+                if let Some(branch) = self.branches_view.selected_branch() {
+                    self.worktree_create_from = Some(branch.name.clone());
+                    self.mode = Mode::Input(InputContext::WorktreePath);
+                    self.input_buffer.clear();
+                    self.input_cursor = 0;
                 }
             }
 
-            KeyCode::Char('d') if self.focused_panel == PanelType::Stash => {
-                if let Some(stash) = self.stash_view.selected_stash() {
-                    self.confirm_target = Some(stash.index.to_string());
-                    self.mode = Mode::Confirm(ConfirmAction::StashDrop);
+            KeyCode::Char('w') if self.focused_panel == PanelType::Commits => {
+                if let Some(commit) = self.commits_view.selected_commit() {
+                    self.worktree_create_from = Some(commit.short_id.clone());
+                    self.mode = Mode::Input(InputContext::WorktreePath);
+                    self.input_buffer.clear();
+                    self.input_cursor = 0;
                 }
             }
 
@@ -4038,12 +6531,18 @@ impl App {
                 self.message = Some("Refreshed".to_string());
             }
 
+            // Undo the last repository operation
+            KeyCode::Char('u') => {
+                self.undo_operation()?;
+            }
+
             // Retry/Load for GitHub panes (PRs, Issues, Actions, Releases)
-            KeyCode::Char('R') if self.focused_panel == PanelType::PullRequests => {
-                if self.pull_requests_view.can_retry() {
-                    self.start_loading_pull_requests();
-                    self.message = Some("Loading pull requests...".to_string());
-                }
+            KeyCode::Char('R')
+                if self.focused_panel == PanelType::PullRequests
+                    && self.pull_requests_view.can_retry() =>
+            {
+                self.start_loading_pull_requests();
+                self.message = Some("Loading pull requests...".to_string());
             }
 
             // Merge PR
@@ -4071,23 +6570,23 @@ impl App {
                     }
                 }
             }
-            KeyCode::Char('R') if self.focused_panel == PanelType::Issues => {
-                if self.issues_view.can_retry() {
-                    self.start_loading_issues();
-                    self.message = Some("Loading issues...".to_string());
-                }
+            KeyCode::Char('R')
+                if self.focused_panel == PanelType::Issues && self.issues_view.can_retry() =>
+            {
+                self.start_loading_issues();
+                self.message = Some("Loading issues...".to_string());
             }
-            KeyCode::Char('R') if self.focused_panel == PanelType::Actions => {
-                if self.actions_view.can_retry() {
-                    self.start_loading_actions();
-                    self.message = Some("Loading actions...".to_string());
-                }
+            KeyCode::Char('R')
+                if self.focused_panel == PanelType::Actions && self.actions_view.can_retry() =>
+            {
+                self.start_loading_actions();
+                self.message = Some("Loading actions...".to_string());
             }
-            KeyCode::Char('R') if self.focused_panel == PanelType::Releases => {
-                if self.releases_view.can_retry() {
-                    self.start_loading_releases();
-                    self.message = Some("Loading releases...".to_string());
-                }
+            KeyCode::Char('R')
+                if self.focused_panel == PanelType::Releases && self.releases_view.can_retry() =>
+            {
+                self.start_loading_releases();
+                self.message = Some("Loading releases...".to_string());
             }
 
             // Fetch remote (Remotes pane)
@@ -4098,6 +6597,63 @@ impl App {
                 }
             }
 
+            // Conflicts panel: resolve, continue a stopped rebase, abort merge/rebase
+            KeyCode::Char('o') | KeyCode::Char('t')
+                if self.focused_panel == PanelType::Conflicts =>
+            {
+                if let Some(conflict) = self.conflict_view.selected_conflict() {
+                    let path = conflict.path.clone();
+                    let Some(_hold) = self.hold_local_refs() else {
+                        return Ok(());
+                    };
+                    let result = if key.code == KeyCode::Char('o') {
+                        self.repo.resolve_conflict_ours(&path)
+                    } else {
+                        self.repo.resolve_conflict_theirs(&path)
+                    };
+                    match result {
+                        Ok(()) => {
+                            self.message = Some(format!("Resolved: {}", path));
+                            self.refresh_all()?;
+                        }
+                        Err(e) => self.message = Some(format!("Resolve failed: {}", e)),
+                    }
+                }
+            }
+            KeyCode::Char('C')
+                if self.focused_panel == PanelType::Conflicts && self.repo.am_in_progress() =>
+            {
+                self.continue_am(false)?;
+            }
+            KeyCode::Char('C') if self.focused_panel == PanelType::Conflicts => {
+                if !self.repo.rebase_in_progress() {
+                    self.message =
+                        Some("No rebase in progress; commit to finish a merge".to_string());
+                } else {
+                    let Some(_hold) = self.hold_local_refs() else {
+                        return Ok(());
+                    };
+                    match self.repo.continue_rebase() {
+                        Ok(crate::git::MergeResult::StoppedForConflict) => {
+                            self.message = Some("Rebase stopped at another conflict".to_string());
+                        }
+                        Ok(_) => self.message = Some("Rebase completed".to_string()),
+                        Err(e) => self.message = Some(format!("Continue failed: {}", e)),
+                    }
+                    self.refresh_all()?;
+                }
+            }
+            KeyCode::Char('A') if self.focused_panel == PanelType::Conflicts => {
+                let Some(_hold) = self.hold_local_refs() else {
+                    return Ok(());
+                };
+                match self.repo.abort_operation() {
+                    Ok(msg) => self.message = Some(msg),
+                    Err(e) => self.message = Some(format!("Abort failed: {}", e)),
+                }
+                self.refresh_all()?;
+            }
+
             // Open in browser (o)
             KeyCode::Char('o') if self.focused_panel == PanelType::PullRequests => {
                 if let Some(pr) = self.pull_requests_view.selected_pr() {
@@ -4161,7 +6717,8 @@ impl App {
                 if self.focused_panel != PanelType::Status
                     && self.focused_panel != PanelType::Branches
                     && self.focused_panel != PanelType::Commits
-                    && self.focused_panel != PanelType::Issues =>
+                    && self.focused_panel != PanelType::Issues
+                    && self.focused_panel != PanelType::Reflog =>
             {
                 let (_, name) = self.config.theme.cycle_highlight_color();
                 self.config.save_highlight_color();
@@ -4398,6 +6955,9 @@ impl App {
             KeyCode::Backspace => {
                 self.input_buffer.pop();
             }
+            KeyCode::Tab if ctx == InputContext::CommitMessage => {
+                self.commit_no_verify = !self.commit_no_verify;
+            }
             KeyCode::Char(c) => {
                 self.input_buffer.push(c);
             }
@@ -4406,6 +6966,43 @@ impl App {
         Ok(())
     }
 
+    /// Give the terminal back to the shell and stop; the SIGCONT that
+    /// continues the process arrives as `Event::Resume` and sets it up again
+    #[cfg(unix)]
+    fn suspend(&mut self) -> Result<()> {
+        self.terminal.suspend()?;
+        signals::suspend();
+        Ok(())
+    }
+
+    #[cfg(windows)]
+    fn suspend(&mut self) -> Result<()> {
+        self.message = Some("Suspend is not supported on Windows".to_string());
+        Ok(())
+    }
+
+    /// Bracketed paste: the text goes into the focused input as a whole, so
+    /// its characters are never taken as commands
+    fn handle_paste(&mut self, text: &str) {
+        match self.mode {
+            Mode::Input(InputContext::CommitMessage) => {
+                // Keep the body's line breaks, not a trailing newline
+                self.input_buffer.push_str(text.trim_end_matches('\n'));
+            }
+            Mode::Input(_) | Mode::Command | Mode::Search => {
+                // Single-line inputs
+                let line = text.trim_end_matches('\n').replace(['\n', '\t'], " ");
+                self.input_buffer.push_str(&line);
+            }
+            Mode::Repos => {
+                for c in text.trim().chars().filter(|c| !c.is_control()) {
+                    self.repos_view.push_char(c);
+                }
+            }
+            _ => self.message = Some("Paste ignored: no input is open".to_string()),
+        }
+    }
+
     fn item_up(&mut self) -> Result<()> {
         match self.focused_panel {
             PanelType::Status => {
@@ -4423,6 +7020,15 @@ impl App {
             PanelType::Remotes => self.remotes_view.move_up(),
             PanelType::Worktrees => self.worktree_view.move_up(),
             PanelType::Submodules => self.submodules_view.move_up(),
+            PanelType::Reflog => {
+                self.reflog_view.move_up();
+                self.refresh_reflog_preview();
+            }
+            PanelType::Trash => {
+                self.trash_view.move_up();
+                self.refresh_trash_preview();
+            }
+            PanelType::Hooks => self.hooks_view.move_up(),
             PanelType::Blame => self.blame_view.move_up(),
             PanelType::Files => {
                 self.filetree_view.move_up();
@@ -4467,6 +7073,15 @@ impl App {
             PanelType::Remotes => self.remotes_view.move_down(),
             PanelType::Worktrees => self.worktree_view.move_down(),
             PanelType::Submodules => self.submodules_view.move_down(),
+            PanelType::Reflog => {
+                self.reflog_view.move_down();
+                self.refresh_reflog_preview();
+            }
+            PanelType::Trash => {
+                self.trash_view.move_down();
+                self.refresh_trash_preview();
+            }
+            PanelType::Hooks => self.hooks_view.move_down(),
             PanelType::Blame => self.blame_view.move_down(),
             PanelType::Files => {
                 self.filetree_view.move_down();
@@ -4504,6 +7119,15 @@ impl App {
             PanelType::Remotes => self.remotes_view.move_to_top(),
             PanelType::Worktrees => self.worktree_view.move_to_top(),
             PanelType::Submodules => self.submodules_view.move_to_top(),
+            PanelType::Reflog => {
+                self.reflog_view.move_to_top();
+                self.refresh_reflog_preview();
+            }
+            PanelType::Trash => {
+                self.trash_view.move_to_top();
+                self.refresh_trash_preview();
+            }
+            PanelType::Hooks => self.hooks_view.move_to_top(),
             PanelType::Blame => self.blame_view.move_to_top(),
             PanelType::Files => self.filetree_view.move_to_top(),
             PanelType::Conflicts => {
@@ -4541,6 +7165,15 @@ impl App {
             PanelType::Remotes => self.remotes_view.move_to_bottom(),
             PanelType::Worktrees => self.worktree_view.move_to_bottom(),
             PanelType::Submodules => self.submodules_view.move_to_bottom(),
+            PanelType::Reflog => {
+                self.reflog_view.move_to_bottom();
+                self.refresh_reflog_preview();
+            }
+            PanelType::Trash => {
+                self.trash_view.move_to_bottom();
+                self.refresh_trash_preview();
+            }
+            PanelType::Hooks => self.hooks_view.move_to_bottom(),
             PanelType::Blame => self.blame_view.move_to_bottom(),
             PanelType::Files => self.filetree_view.move_to_bottom(),
             PanelType::Conflicts => {
@@ -4571,6 +7204,9 @@ impl App {
             PanelType::Remotes => self.remotes_view.scroll_left(),
             PanelType::Worktrees => self.worktree_view.scroll_left(),
             PanelType::Submodules => self.submodules_view.scroll_left(),
+            PanelType::Reflog => self.reflog_view.scroll_left(),
+            PanelType::Trash => self.trash_view.scroll_left(),
+            PanelType::Hooks => self.hooks_view.scroll_left(),
             PanelType::Blame => self.blame_view.scroll_left(),
             PanelType::Files => self.filetree_view.scroll_left(),
             PanelType::Conflicts => self.conflict_view.scroll_left(),
@@ -4592,6 +7228,9 @@ impl App {
             PanelType::Remotes => self.remotes_view.scroll_right(),
             PanelType::Worktrees => self.worktree_view.scroll_right(),
             PanelType::Submodules => self.submodules_view.scroll_right(),
+            PanelType::Reflog => self.reflog_view.scroll_right(),
+            PanelType::Trash => self.trash_view.scroll_right(),
+            PanelType::Hooks => self.hooks_view.scroll_right(),
             PanelType::Blame => self.blame_view.scroll_right(),
             PanelType::Files => self.filetree_view.scroll_right(),
             PanelType::Conflicts => self.conflict_view.scroll_right(),
@@ -4785,22 +7424,9 @@ impl App {
             PanelType::Branches => {
                 if let Some(branch) = self.branches_view.selected_branch() {
                     if !branch.is_head {
-                        let name = branch.name.clone();
-                        let branch_type = branch.branch_type;
-                        self.repo.switch_branch(&name, branch_type)?;
-                        // Show the local branch name (strip remote prefix if remote branch)
-                        let display_name = match branch_type {
-                            crate::git::BranchType::Remote => {
-                                if let Some(pos) = name.find('/') {
-                                    &name[pos + 1..]
-                                } else {
-                                    &name
-                                }
-                            }
-                            crate::git::BranchType::Local => &name,
-                        };
-                        self.message = Some(format!("Switched to: {}", display_name));
-                        self.refresh_all()?;
+                        let target =
+                            CheckoutTarget::Branch(branch.name.clone(), branch.branch_type);
+                        self.request_checkout(target)?;
                     }
                 }
             }
@@ -4814,6 +7440,9 @@ impl App {
                 // Pop selected stash (Enter = pop)
                 if let Some(stash) = self.stash_view.selected_stash() {
                     let index = stash.index;
+                    let Some(_hold) = self.hold_local_refs() else {
+                        return Ok(());
+                    };
                     match self.repo.stash_pop(index) {
                         Ok(()) => {
                             self.message = Some(format!("Popped stash@{{{}}}", index));
@@ -4826,6 +7455,9 @@ impl App {
                 }
             }
             PanelType::Diff => {
+                if self.diff_view.has_large_file() {
+                    self.load_preview_anyway()?;
+                }
                 // Stage/unstage hunk (TODO)
             }
             PanelType::Tags => {
@@ -4835,23 +7467,57 @@ impl App {
                 // Fetch remote (TODO)
             }
             PanelType::Worktrees => {
-                // Switch to worktree (TODO)
+                if let Some(wt) = self.worktree_view.selected_worktree() {
+                    if wt.is_prunable {
+                        self.message = Some(format!("Worktree '{}' is missing", wt.name));
+                    } else if !wt.is_current {
+                        let path = PathBuf::from(&wt.path);
+                        let name = wt.name.clone();
+                        match self.switch_worktree(&path) {
+                            Ok(()) => {
+                                self.message = Some(format!("Switched to worktree: {}", name));
+                            }
+                            Err(e) => {
+                                self.message = Some(format!("Switch failed: {}", e));
+                            }
+                        }
+                    }
+                }
             }
             PanelType::Submodules => {
-                // Update submodule
+                // Drill into the submodule repository
                 if let Some(sub) = self.submodules_view.selected_submodule() {
-                    let path = sub.path.clone();
-                    match self.repo.submodule_update(&path) {
-                        Ok(()) => {
-                            self.message = Some(format!("Updated submodule: {}", path));
-                            self.refresh_submodules()?;
-                        }
-                        Err(e) => {
-                            self.message = Some(format!("Submodule update failed: {}", e));
+                    let name = sub.name.clone();
+                    if sub.is_initialized {
+                        let path = self.repo_path.join(&sub.path);
+                        let parent = self.repo_path.clone();
+                        match self.load_repo(&path) {
+                            Ok(()) => {
+                                self.repo_stack.push(parent);
+                                self.message = Some(format!("Entered submodule: {}", name));
+                            }
+                            Err(e) => {
+                                self.message = Some(format!("Cannot open submodule: {}", e));
+                            }
                         }
+                    } else {
+                        self.message = Some(format!(
+                            "Submodule '{}' is not checked out (press u to update)",
+                            name
+                        ));
                     }
                 }
             }
+            PanelType::Reflog => {
+                // Show the entry's commit in the preview
+                self.refresh_reflog_preview();
+                self.focused_panel = PanelType::Diff;
+            }
+            PanelType::Trash => {
+                self.refresh_trash_preview();
+                self.focused_panel = PanelType::Diff;
+            }
+            PanelType::Hooks => {}
             PanelType::Blame => {
                 // Nothing to do for blame
             }
@@ -4913,41 +7579,95 @@ impl App {
             }
             ["branch", name] => {
                 let name = name.to_string();
+                let Some(_hold) = self.hold_local_refs() else {
+                    return Ok(());
+                };
                 self.repo.create_branch(&name, None)?;
                 self.refresh_branches()?;
                 self.message = Some(format!("Created branch: {}", name));
             }
             ["checkout", name] => {
-                let name = name.to_string();
                 // Command-line checkout assumes local branch
-                self.repo
-                    .switch_branch(&name, crate::git::BranchType::Local)?;
-                self.refresh_all()?;
-                self.message = Some(format!("Switched to: {}", name));
+                let target =
+                    CheckoutTarget::Branch(name.to_string(), crate::git::BranchType::Local);
+                self.request_checkout(target)?;
             }
             ["stash"] => {
+                let Some(_hold) = self.hold_local_refs() else {
+                    return Ok(());
+                };
                 self.repo.stash_save(None)?;
                 self.refresh_status()?;
                 self.message = Some("Changes stashed".to_string());
             }
             ["stash", "pop"] => {
+                let Some(_hold) = self.hold_local_refs() else {
+                    return Ok(());
+                };
                 self.repo.stash_pop(0)?;
                 self.refresh_status()?;
                 self.message = Some("Stash popped".to_string());
             }
             ["tag", name] => {
                 let name = name.to_string();
+                let Some(_hold) = self.hold_local_refs() else {
+                    return Ok(());
+                };
                 self.repo.create_tag(&name, None)?;
                 self.message = Some(format!("Created tag: {}", name));
             }
+            ["log"] => {
+                self.set_commits_path(None)?;
+                self.message = Some("Showing all commits".to_string());
+            }
+            ["log", path] => {
+                self.set_commits_path(Some(path.to_string()))?;
+                self.focus_panel(PanelType::Commits);
+            }
+            ["theme"] => self.open_theme_picker(),
+            ["theme", name] => match self.config.theme_named(name) {
+                Some(theme) => {
+                    self.config.theme = theme;
+                    self.config.save_theme_name();
+                    self.message = Some(format!("Theme: {}", name));
+                }
+                None => self.message = Some(format!("Unknown theme: {}", name)),
+            },
+            ["apply", file] => {
+                self.preview_patch(file)?;
+            }
+            ["am", "--continue"] => self.continue_am(false)?,
+            ["am", "--skip"] => self.continue_am(true)?,
+            ["am", "--abort"] => {
+                let Some(_hold) = self.hold_local_refs() else {
+                    return Ok(());
+                };
+                match self.repo.abort_am() {
+                    Ok(msg) => self.message = Some(msg),
+                    Err(e) => self.message = Some(format!("Abort failed: {}", e)),
+                }
+                self.refresh_all()?;
+            }
+            ["am", file] => {
+                let Some(_hold) = self.hold_local_refs() else {
+                    return Ok(());
+                };
+                let result = std::fs::read_to_string(self.repo_path.join(file))
+                    .map_err(crate::error::Error::from)
+                    .and_then(|text| self.repo.apply_mailbox(&text));
+                match result {
+                    Ok(outcome) => self.message = Some(outcome.summary()),
+                    Err(e) => self.message = Some(format!("am failed: {}", e)),
+                }
+                self.refresh_all()?;
+            }
             ["fetch"] => {
                 let remotes = self.repo.remotes()?;
-                if let Some(remote) = remotes.first() {
-                    self.repo.fetch(remote)?;
-                    self.refresh_branches()?;
-                    self.message = Some(format!("Fetched from: {}", remote));
+                if let Some(remote) = remotes.into_iter().next() {
+                    self.start_async_fetch_remote(remote);
                 }
             }
+            ["jobs"] => self.open_jobs(),
             _ => {
                 self.message = Some(format!("Unknown command: {}", input));
             }
@@ -4956,17 +7676,65 @@ impl App {
         Ok(())
     }
 
+    /// Show a patch file in the diff panel and ask where to apply it
+    fn preview_patch(&mut self, file: &str) -> Result<()> {
+        let text = match std::fs::read_to_string(self.repo_path.join(file)) {
+            Ok(text) => text,
+            Err(e) => {
+                self.message = Some(format!("Cannot read {}: {}", file, e));
+                return Ok(());
+            }
+        };
+        match self.repo.patch_preview(&text) {
+            Ok(diff) => {
+                self.diff_view.clear_commit_preview();
+                self.diff_view.update(diff);
+                self.confirm_target = Some(file.to_string());
+                self.pending_patch = Some((file.to_string(), text));
+                self.select_index = 0;
+                self.mode = Mode::Select(SelectAction::ApplyPatch);
+            }
+            Err(e) => self.message = Some(format!("Not a patch: {}", e)),
+        }
+        Ok(())
+    }
+
+    /// Continue a paused patch series after resolving, or skip the paused patch
+    fn continue_am(&mut self, skip: bool) -> Result<()> {
+        let Some(_hold) = self.hold_local_refs() else {
+            return Ok(());
+        };
+        let result = if skip {
+            self.repo.skip_am()
+        } else {
+            self.repo.continue_am()
+        };
+        match result {
+            Ok(outcome) => self.message = Some(outcome.summary()),
+            Err(e) => self.message = Some(format!("am failed: {}", e)),
+        }
+        self.refresh_all()
+    }
+
     fn submit_input(&mut self, ctx: InputContext) -> Result<()> {
         match ctx {
             InputContext::CommitMessage => {
                 if !self.input_buffer.is_empty() {
-                    let oid = self.repo.commit(&self.input_buffer)?;
-                    self.message = Some(format!("Created commit: {}", &oid[..7]));
-                    self.refresh_all()?;
+                    // Keep the prepared shortlog body when its subject was accepted unchanged
+                    let message = match self.repo.prepared_message() {
+                        Some(prepared) if prepared.lines().next() == Some(&self.input_buffer) => {
+                            prepared
+                        }
+                        _ => self.input_buffer.clone(),
+                    };
+                    self.start_async_commit(message);
                 }
             }
             InputContext::BranchName => {
                 if !self.input_buffer.is_empty() {
+                    let Some(_hold) = self.hold_local_refs() else {
+                        return Ok(());
+                    };
                     let from = self.branch_create_from.take();
                     self.repo
                         .create_branch(&self.input_buffer, from.as_deref())?;
@@ -4989,11 +7757,17 @@ impl App {
             }
             InputContext::TagName => {
                 if !self.input_buffer.is_empty() {
+                    let Some(_hold) = self.hold_local_refs() else {
+                        return Ok(());
+                    };
                     self.repo.create_tag(&self.input_buffer, None)?;
                     self.message = Some(format!("Created tag: {}", self.input_buffer));
                 }
             }
             InputContext::StashMessage => {
+                let Some(_hold) = self.hold_local_refs() else {
+                    return Ok(());
+                };
                 let msg = if self.input_buffer.is_empty() {
                     None
                 } else {
@@ -5042,9 +7816,133 @@ impl App {
                     }
                 }
             }
+            InputContext::WorktreePath => {
+                let from = self.worktree_create_from.take();
+                let mut parts = self.input_buffer.split_whitespace();
+                if let Some(path) = parts.next() {
+                    let target = from.or_else(|| parts.next().map(|s| s.to_string()));
+                    match self.repo.worktree_add(path, target.as_deref()) {
+                        Ok(name) => {
+                            self.message = Some(format!("Created worktree '{}'", name));
+                            self.refresh_worktrees()?;
+                            self.refresh_branches()?;
+                        }
+                        Err(e) => {
+                            self.message = Some(format!("Worktree add failed: {}", e));
+                        }
+                    }
+                }
+            }
+            InputContext::SubmoduleAdd => {
+                let parts: Vec<&str> = self.input_buffer.split_whitespace().collect();
+                match parts.as_slice() {
+                    [url, path] => {
+                        let (url, path) = (url.to_string(), path.to_string());
+                        self.start_async_submodule_add(url, path);
+                    }
+                    _ => {
+                        self.message = Some("Usage: <url> <path>".to_string());
+                    }
+                }
+            }
+            InputContext::WorktreeLockReason => {
+                if let Some(name) = self.confirm_target.take() {
+                    let reason = self.input_buffer.trim();
+                    let reason = (!reason.is_empty()).then_some(reason);
+                    match self.repo.worktree_lock(&name, reason) {
+                        Ok(()) => {
+                            self.message = Some(format!("Locked worktree '{}'", name));
+                            self.refresh_worktrees()?;
+                        }
+                        Err(e) => {
+                            self.message = Some(format!("Lock failed: {}", e));
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Re-target the whole app at another worktree of the same repository
+    fn switch_worktree(&mut self, path: &std::path::Path) -> Result<()> {
+        self.reopen_repo(path)
+    }
+
+    /// Take HEAD, the branches and the working tree for a change made on the
+    /// UI thread; jobs started while the hold is kept wait in turn. None, with
+    /// a message, while a background job (pull, commit, branch deletion) has
+    /// them: waiting here would freeze input until it is done.
+    fn hold_local_refs(&mut self) -> Option<RefHold> {
+        match self.jobs.try_hold(&["HEAD"]) {
+            Ok(hold) => Some(hold),
+            Err(label) => {
+                self.message = Some(format!("Waiting for {} (& lists jobs)", label));
+                None
+            }
+        }
+    }
+
+    fn undo_operation(&mut self) -> Result<()> {
+        let Some(_hold) = self.hold_local_refs() else {
+            return Ok(());
+        };
+        match self.repo.undo() {
+            Ok(Some(description)) => {
+                self.message = Some(format!("Undid: {} (Ctrl+r to redo)", description));
+                self.refresh_all()?;
+            }
+            Ok(None) => {
+                self.message = Some("Nothing to undo".to_string());
+            }
+            Err(e) => {
+                self.message = Some(format!("Undo failed: {}", e));
+            }
+        }
+        Ok(())
+    }
+
+    fn redo_operation(&mut self) -> Result<()> {
+        let Some(_hold) = self.hold_local_refs() else {
+            return Ok(());
+        };
+        match self.repo.redo() {
+            Ok(Some(description)) => {
+                self.message = Some(format!("Redid: {}", description));
+                self.refresh_all()?;
+            }
+            Ok(None) => {
+                self.message = Some("Nothing to redo".to_string());
+            }
+            Err(e) => {
+                self.message = Some(format!("Redo failed: {}", e));
+            }
         }
         Ok(())
     }
+
+    /// Re-target the app at the repository at `path`, leaving any submodule
+    /// navigation: Backspace must not return to a superproject of the old one
+    fn reopen_repo(&mut self, path: &std::path::Path) -> Result<()> {
+        self.load_repo(path)?;
+        self.repo_stack.clear();
+        Ok(())
+    }
+
+    /// Open the repository at `path` in place of the current one, keeping `repo_stack`
+    fn load_repo(&mut self, path: &std::path::Path) -> Result<()> {
+        self.repo = Repository::open(path)?
+            .with_hook_log(self.hook_log.clone())
+            .with_size_limit(self.config.size_limit());
+        self.repo_path = self.repo.path().to_path_buf();
+        self.start_watcher();
+        self.commits_view.clear_marks();
+        self.filetree_view.clear_filter();
+        self.diff_view.clear();
+        self.refresh_all()?;
+        self.terminal.force_full_redraw();
+        Ok(())
+    }
 }
 
 fn panel_type_to_string(panel: PanelType) -> &'static str {
@@ -5058,6 +7956,9 @@ fn panel_type_to_string(panel: PanelType) -> &'static str {
         PanelType::Remotes => "remotes",
         PanelType::Worktrees => "worktrees",
         PanelType::Submodules => "submodules",
+        PanelType::Reflog => "reflog",
+        PanelType::Trash => "trash",
+        PanelType::Hooks => "hooks",
         PanelType::Blame => "blame",
         PanelType::Files => "files",
         PanelType::Conflicts => "conflicts",
@@ -5068,6 +7969,29 @@ fn panel_type_to_string(panel: PanelType) -> &'static str {
     }
 }
 
+/// Lock name for jobs that move the remote-tracking branches of `remote`
+fn remote_refs(remote: &str) -> String {
+    format!("refs/remotes/{}", remote)
+}
+
+/// Short label for a trash ref name ("refs/g/trash/<time>-<id>" -> "<id>")
+fn trash_label(refname: &str) -> &str {
+    refname.rsplit('-').next().unwrap_or(refname)
+}
+
+/// Load the reflog ref list and the entries for `refname`, falling back to HEAD
+/// when the ref has gone away (e.g. a deleted branch)
+fn load_reflog(repo: &Repository, refname: &str) -> Result<(Vec<String>, Vec<ReflogEntry>)> {
+    let refs = repo.reflog_refs()?;
+    let refname = if refs.iter().any(|r| r == refname) {
+        refname
+    } else {
+        "HEAD"
+    };
+    let entries = repo.reflog(refname)?;
+    Ok((refs, entries))
+}
+
 /// Fetch pull requests from GitHub (runs in background thread)
 fn fetch_pull_requests(repo_path: &PathBuf) -> std::result::Result<Vec<PullRequestInfo>, String> {
     let output = std::process::Command::new("gh")
@@ -0,0 +1,370 @@
+//! Non-interactive subcommands (`g status --json`, ...) for scripts, CI and shell prompts.
+//!
+//! Exit codes: 0 on success, 1 when the command fails (e.g. not a repository),
+//! 2 for usage errors.
+
+use crate::app::{Startup, StartupTarget};
+use crate::config::LayoutConfig;
+use crate::error::Result;
+use crate::git::{FileStatus, Repository};
+use crate::views::PanelType;
+use serde::Serialize;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+pub const EXIT_OK: i32 = 0;
+pub const EXIT_ERROR: i32 = 1;
+pub const EXIT_USAGE: i32 = 2;
+
+pub const USAGE: &str = "\
+Usage: g [-C <path>] [--panel <name>] [command] [--json]
+
+Without a command, g starts the TUI.
+
+Commands:
+  status               Changed files (XY path, as git status --short)
+  log [-n <count>]     Commits on HEAD, newest first (default 100)
+  log <path>           Open the TUI on the commits touching <path>
+  branches [-a]        Local branches; -a includes remote branches
+  prs                  Open pull requests (needs the gh CLI)
+  blame <file>[:line]  Open the TUI on the blame of <file>
+  show <rev>           Open the TUI on commit <rev>
+  fsmonitor <v> <tok>  Hook for core.fsmonitor: paths changed since <tok>
+
+Options:
+  -C <path>            Run as if started in <path>
+  --panel <name>       Focus this panel on startup (commits, branches, ...)
+  --json               Print JSON instead of text
+  -h, --help           Show this help
+  -V, --version        Show the version";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    /// Start the TUI
+    Tui {
+        panel: Option<PanelType>,
+        target: Option<StartupTarget>,
+    },
+    Status,
+    Log {
+        max_count: usize,
+        path: Option<String>,
+    },
+    Branches {
+        all: bool,
+    },
+    PullRequests,
+    /// The `core.fsmonitor` hook, as git runs it
+    Fsmonitor {
+        version: String,
+        token: String,
+    },
+    Help,
+    Version,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Invocation {
+    /// Directory given with `-C`
+    pub repo_dir: Option<PathBuf>,
+    pub command: Command,
+    pub json: bool,
+}
+
+impl Invocation {
+    /// Startup options for the TUI, if this invocation starts it
+    pub fn startup(&self) -> Option<Startup> {
+        match &self.command {
+            Command::Tui { panel, target } => Some(Startup {
+                repo_dir: self.repo_dir.clone(),
+                panel: *panel,
+                target: target.clone(),
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// Parse the arguments after the program name
+pub fn parse(args: &[String]) -> std::result::Result<Invocation, String> {
+    let mut json = false;
+    let mut repo_dir = None;
+    let mut panel = None;
+    let mut max_count = 100;
+    let mut all = false;
+    let mut words = Vec::new();
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        let mut value = |name: &str| {
+            iter.next()
+                .map(|v| v.as_str())
+                .ok_or_else(|| format!("{} needs a value", name))
+        };
+        match arg.as_str() {
+            "--json" => json = true,
+            "-h" | "--help" => {
+                return Ok(Invocation {
+                    repo_dir,
+                    command: Command::Help,
+                    json,
+                })
+            }
+            "-V" | "--version" => {
+                return Ok(Invocation {
+                    repo_dir,
+                    command: Command::Version,
+                    json,
+                })
+            }
+            "-C" => repo_dir = Some(PathBuf::from(value("-C")?)),
+            "--panel" => {
+                let name = value("--panel")?;
+                panel = Some(
+                    LayoutConfig::parse_panel_type(name)
+                        .ok_or_else(|| format!("unknown panel '{}'", name))?,
+                );
+            }
+            "-n" => {
+                let count = value("-n")?;
+                max_count = count
+                    .parse()
+                    .map_err(|_| format!("invalid count '{}'", count))?;
+            }
+            "-a" | "--all" => all = true,
+            option if option.starts_with('-') => {
+                return Err(format!("unknown option '{}'", option))
+            }
+            word => words.push(word),
+        }
+    }
+
+    let tui = |target| Command::Tui { panel, target };
+    let command = match words.as_slice() {
+        [] => tui(None),
+        ["status"] => Command::Status,
+        ["log"] => Command::Log {
+            max_count,
+            path: None,
+        },
+        ["log", path] if json => Command::Log {
+            max_count,
+            path: Some(path.to_string()),
+        },
+        ["log", path] => tui(Some(StartupTarget::Log {
+            path: path.to_string(),
+        })),
+        ["branches"] => Command::Branches { all },
+        ["prs"] => Command::PullRequests,
+        ["blame", spec] => {
+            // "file:line", unless the part after the last ':' is not a number
+            let (path, line) = match spec.rsplit_once(':') {
+                Some((path, line)) if line.parse::<usize>().is_ok() => (path, line.parse().ok()),
+                _ => (*spec, None),
+            };
+            tui(Some(StartupTarget::Blame {
+                path: path.to_string(),
+                line,
+            }))
+        }
+        ["show", rev] => tui(Some(StartupTarget::Show {
+            rev: rev.to_string(),
+        })),
+        ["fsmonitor", version, token] => Command::Fsmonitor {
+            version: version.to_string(),
+            token: token.to_string(),
+        },
+        [command, ..] => return Err(format!("unknown command or arguments: {}", command)),
+    };
+    if json && matches!(command, Command::Tui { .. }) {
+        return Err("--json needs status, log, branches or prs".to_string());
+    }
+    Ok(Invocation {
+        repo_dir,
+        command,
+        json,
+    })
+}
+
+/// Run a subcommand, printing to stdout; returns the process exit code
+pub fn run(invocation: &Invocation) -> i32 {
+    if let Command::Fsmonitor { version, token } = &invocation.command {
+        return fsmonitor(invocation, version, token);
+    }
+    let output = match invocation.command {
+        Command::Help => Ok(USAGE.to_string()),
+        Command::Version => Ok(format!("g {}", env!("CARGO_PKG_VERSION"))),
+        _ => {
+            let dir = invocation.repo_dir.as_deref().unwrap_or(Path::new("."));
+            Repository::discover_from(dir).and_then(|repo| render(&repo, invocation))
+        }
+    };
+    match output {
+        Ok(text) => {
+            if !text.is_empty() {
+                println!("{}", text);
+            }
+            EXIT_OK
+        }
+        Err(e) => {
+            eprintln!("g: {}", e);
+            EXIT_ERROR
+        }
+    }
+}
+
+/// Answer git's fsmonitor query; its response is NUL-separated, not text
+fn fsmonitor(invocation: &Invocation, version: &str, token: &str) -> i32 {
+    let dir = invocation.repo_dir.as_deref().unwrap_or(Path::new("."));
+    let response = match Repository::discover_from(dir) {
+        Ok(repo) => repo.fsmonitor_hook(version, token),
+        Err(e) => {
+            eprintln!("g: {}", e);
+            return EXIT_ERROR;
+        }
+    };
+    let mut stdout = std::io::stdout();
+    match stdout.write_all(&response).and_then(|_| stdout.flush()) {
+        Ok(()) => EXIT_OK,
+        Err(_) => EXIT_ERROR,
+    }
+}
+
+fn render(repo: &Repository, invocation: &Invocation) -> Result<String> {
+    let json = invocation.json;
+    match invocation.command {
+        Command::Status => {
+            let entries = repo.status()?;
+            if json {
+                return to_json(&entries);
+            }
+            Ok(entries
+                .iter()
+                .map(|e| {
+                    let (x, y) = if e.unstaged == FileStatus::Untracked {
+                        ('?', '?')
+                    } else {
+                        (e.staged.symbol(), e.unstaged.symbol())
+                    };
+                    format!("{}{} {}", x, y, e.path)
+                })
+                .collect::<Vec<_>>()
+                .join("\n"))
+        }
+        Command::Log {
+            max_count,
+            ref path,
+        } => {
+            let commits = match path {
+                Some(path) => {
+                    let base = invocation.repo_dir.clone().unwrap_or_default();
+                    let path = repo
+                        .relative_path(&base.join(path))
+                        .unwrap_or_else(|| path.clone());
+                    repo.file_commits(&path, max_count)?
+                }
+                None => repo.commits(max_count)?,
+            };
+            if json {
+                return to_json(&commits);
+            }
+            Ok(commits
+                .iter()
+                .map(|c| format!("{} {}", c.short_id, c.message))
+                .collect::<Vec<_>>()
+                .join("\n"))
+        }
+        Command::Branches { all } => {
+            let branches = repo.branches(all)?;
+            if json {
+                return to_json(&branches);
+            }
+            Ok(branches
+                .iter()
+                .map(|b| format!("{} {}", if b.is_head { '*' } else { ' ' }, b.name))
+                .collect::<Vec<_>>()
+                .join("\n"))
+        }
+        Command::PullRequests => {
+            let prs = repo.pull_requests()?;
+            if json {
+                return to_json(&prs);
+            }
+            Ok(prs
+                .iter()
+                .map(|pr| format!("#{}\t{}\t{}", pr.number, pr.head_ref_name, pr.title))
+                .collect::<Vec<_>>()
+                .join("\n"))
+        }
+        Command::Tui { .. } | Command::Fsmonitor { .. } | Command::Help | Command::Version => {
+            Ok(String::new())
+        }
+    }
+}
+
+fn to_json<T: Serialize + ?Sized>(value: &T) -> Result<String> {
+    Ok(serde_json::to_string(value).map_err(std::io::Error::from)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse() {
+        let tui = parse(&args(&["-C", "../other", "--panel", "commits"])).unwrap();
+        assert_eq!(
+            tui.startup(),
+            Some(Startup {
+                repo_dir: Some(PathBuf::from("../other")),
+                panel: Some(PanelType::Commits),
+                target: None,
+            })
+        );
+        assert_eq!(
+            parse(&args(&["log", "-n", "5", "--json"])).unwrap().command,
+            Command::Log {
+                max_count: 5,
+                path: None,
+            }
+        );
+        assert_eq!(
+            parse(&args(&["log", "src"])).unwrap().command,
+            Command::Tui {
+                panel: None,
+                target: Some(StartupTarget::Log {
+                    path: "src".to_string()
+                }),
+            }
+        );
+        assert_eq!(
+            parse(&args(&["blame", "src/main.rs:42"])).unwrap().command,
+            Command::Tui {
+                panel: None,
+                target: Some(StartupTarget::Blame {
+                    path: "src/main.rs".to_string(),
+                    line: Some(42),
+                }),
+            }
+        );
+        assert_eq!(
+            parse(&args(&["--json", "branches", "-a"])).unwrap().command,
+            Command::Branches { all: true }
+        );
+        assert_eq!(
+            parse(&args(&["fsmonitor", "2", ""])).unwrap().command,
+            Command::Fsmonitor {
+                version: "2".to_string(),
+                token: String::new(),
+            }
+        );
+        assert!(parse(&args(&["log", "-n", "x"])).is_err());
+        assert!(parse(&args(&["frobnicate"])).is_err());
+        assert!(parse(&args(&["--panel", "nope"])).is_err());
+        assert!(parse(&args(&["--json"])).is_err());
+    }
+}
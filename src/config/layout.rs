@@ -172,7 +172,7 @@ impl LayoutConfig {
         config
     }
 
-    fn parse_panel_type(s: &str) -> Option<PanelType> {
+    pub fn parse_panel_type(s: &str) -> Option<PanelType> {
         match s.to_lowercase().as_str() {
             "status" => Some(PanelType::Status),
             "branches" => Some(PanelType::Branches),
@@ -183,6 +183,9 @@ impl LayoutConfig {
             "remotes" => Some(PanelType::Remotes),
             "worktrees" => Some(PanelType::Worktrees),
             "submodules" => Some(PanelType::Submodules),
+            "reflog" => Some(PanelType::Reflog),
+            "trash" => Some(PanelType::Trash),
+            "hooks" => Some(PanelType::Hooks),
             "blame" => Some(PanelType::Blame),
             "files" => Some(PanelType::Files),
             "conflicts" => Some(PanelType::Conflicts),
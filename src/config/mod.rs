@@ -1,12 +1,17 @@
 mod layout;
 mod parser;
+mod session;
 mod theme;
+mod workspace;
 
 pub use layout::{Column, LayoutConfig, PanelHeight};
-pub use theme::{Theme, HIGHLIGHT_COLORS};
+pub use session::Session;
+pub use theme::{Theme, BUILTIN_THEMES, HIGHLIGHT_COLORS};
+pub use workspace::RepoList;
 
 use crate::error::Result;
-use crate::tui::Color;
+use crate::git::{PullStrategy, UntrackedFiles};
+use crate::tui::{Color, ColorDepth, InputModes};
 use std::collections::HashMap;
 use std::path::PathBuf;
 
@@ -45,12 +50,20 @@ impl Default for ViewDefaults {
 #[derive(Debug, Clone)]
 pub struct Config {
     pub theme: Theme,
+    /// Colors set in `[theme]`, applied on top of whichever theme is selected
+    pub theme_overrides: HashMap<String, parser::Value>,
     pub show_line_numbers: bool,
     pub diff_context_lines: u32,
+    /// Files bigger than this (KiB) show collapsed in the preview until
+    /// loaded anyway. 0 = no limit.
+    pub large_file_kb: u64,
     pub max_commits: usize,
     pub date_format: DateFormat,
     /// Git data refresh interval in seconds (status, branches). 0 = disabled.
     pub auto_refresh: u32,
+    /// Refresh views when files or refs change on disk (inotify); polling with
+    /// `auto_refresh` remains for what the watcher doesn't cover
+    pub watch_files: bool,
     /// Auto fetch interval in seconds (fetch from all remotes). 0 = disabled.
     pub auto_fetch_interval: u32,
     /// GitHub API refresh interval in seconds (PRs, Issues, Actions, Releases). 0 = disabled.
@@ -58,9 +71,15 @@ pub struct Config {
     pub confirm_destructive: bool,
     pub editor: Option<String>,
     pub keybindings: HashMap<String, Vec<String>>,
+    /// Colors the terminal can show; None detects them from the environment
+    pub color_depth: Option<ColorDepth>,
+    /// Input protocols requested from the terminal (`[input]`)
+    pub input: InputModes,
     pub git: GitConfig,
     pub layout: LayoutConfig,
     pub view_defaults: ViewDefaults,
+    /// Repositories shown in the repository switcher (`[workspace] repos`)
+    pub workspace_repos: Vec<PathBuf>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -75,25 +94,39 @@ pub struct GitConfig {
     pub default_remote: String,
     pub default_branch: String,
     pub sign_commits: bool,
+    /// Pull strategy; None defers to git's pull.rebase / pull.ff
+    pub pull_strategy: Option<PullStrategy>,
+    /// Autostash before pulling; None defers to git's rebase/merge.autoStash
+    pub pull_autostash: Option<bool>,
+    /// Days to keep discarded changes under refs/g/trash/
+    pub trash_days: u32,
+    /// Untracked files in status; None defers to git's status.showUntrackedFiles
+    pub show_untracked: Option<UntrackedFiles>,
 }
 
 impl Default for Config {
     fn default() -> Self {
         Self {
             theme: Theme::default(),
+            theme_overrides: HashMap::new(),
             show_line_numbers: true,
             diff_context_lines: 3,
+            large_file_kb: 1024,
             max_commits: 1000,
             date_format: DateFormat::Relative,
-            auto_refresh: 60,       // Default: refresh git data every 60 seconds
+            auto_refresh: 60, // Default: refresh git data every 60 seconds
+            watch_files: true,
             auto_fetch_interval: 0, // Default: disabled (set to e.g. 300 for 5 min)
             github_refresh_interval: 60, // Default: refresh every 60 seconds
             confirm_destructive: true,
             editor: None,
             keybindings: HashMap::new(),
+            color_depth: None,
+            input: InputModes::default(),
             git: GitConfig::default(),
             layout: LayoutConfig::default(),
             view_defaults: ViewDefaults::default(),
+            workspace_repos: Vec::new(),
         }
     }
 }
@@ -104,6 +137,10 @@ impl Default for GitConfig {
             default_remote: "origin".to_string(),
             default_branch: "main".to_string(),
             sign_commits: false,
+            pull_strategy: None,
+            pull_autostash: None,
+            trash_days: 14,
+            show_untracked: None,
         }
     }
 }
@@ -137,6 +174,28 @@ impl Config {
         }
     }
 
+    /// Where state that is not configuration (sessions, recent repositories) is kept
+    pub fn state_dir() -> PathBuf {
+        #[cfg(windows)]
+        {
+            let local = std::env::var("LOCALAPPDATA")
+                .or_else(|_| std::env::var("APPDATA"))
+                .unwrap_or_else(|_| ".".to_string());
+            PathBuf::from(local).join("g")
+        }
+
+        #[cfg(not(windows))]
+        {
+            match std::env::var("XDG_STATE_HOME") {
+                Ok(dir) if !dir.is_empty() => PathBuf::from(dir).join("g"),
+                _ => {
+                    let home = std::env::var("HOME").unwrap_or_else(|_| ".".to_string());
+                    PathBuf::from(home).join(".local").join("state").join("g")
+                }
+            }
+        }
+    }
+
     pub fn parse(content: &str) -> Result<Self> {
         let toml = parser::parse(content)?;
         let mut config = Self::default();
@@ -149,6 +208,10 @@ impl Config {
             config.diff_context_lines = *n as u32;
         }
 
+        if let Some(parser::Value::Integer(n)) = toml.get("large_file_kb") {
+            config.large_file_kb = (*n).max(0) as u64;
+        }
+
         if let Some(parser::Value::Integer(n)) = toml.get("max_commits") {
             config.max_commits = *n as usize;
         }
@@ -166,6 +229,10 @@ impl Config {
             config.auto_refresh = *n as u32;
         }
 
+        if let Some(parser::Value::Boolean(b)) = toml.get("watch_files") {
+            config.watch_files = *b;
+        }
+
         if let Some(parser::Value::Integer(n)) = toml.get("auto_fetch_interval") {
             config.auto_fetch_interval = *n as u32;
         }
@@ -178,6 +245,10 @@ impl Config {
             config.confirm_destructive = *b;
         }
 
+        if let Some(parser::Value::String(s)) = toml.get("color_depth") {
+            config.color_depth = ColorDepth::parse(s);
+        }
+
         if let Some(parser::Value::String(s)) = toml.get("editor") {
             config.editor = Some(s.clone());
         }
@@ -185,13 +256,60 @@ impl Config {
         // Parse layout config
         config.layout = LayoutConfig::from_toml(&toml);
 
-        // Parse theme
+        // Parse theme: `name` picks a built-in theme or theme file, other keys override its colors
         if let Some(parser::Value::Table(theme_table)) = toml.get("theme") {
-            if let Some(parser::Value::String(s)) = theme_table.get("selection_color") {
-                if let Some(color) = Color::from_hex(s) {
-                    config.theme.selection = color;
-                    config.theme.border_focused = color;
-                }
+            config.theme_overrides = theme_table.clone();
+            let name = match config.theme_overrides.remove("name") {
+                Some(parser::Value::String(name)) => name,
+                _ => "dark".to_string(),
+            };
+            config.theme = config
+                .theme_named(&name)
+                .or_else(|| config.theme_named("dark"))
+                .unwrap_or_else(Theme::default);
+        }
+
+        if let Some(parser::Value::Table(git)) = toml.get("git") {
+            if let Some(parser::Value::String(s)) = git.get("pull") {
+                config.git.pull_strategy = match s.as_str() {
+                    "merge" => Some(PullStrategy::Merge),
+                    "rebase" => Some(PullStrategy::Rebase),
+                    "ff-only" | "ff_only" => Some(PullStrategy::FastForwardOnly),
+                    _ => None,
+                };
+            }
+            if let Some(parser::Value::Boolean(b)) = git.get("pull_autostash") {
+                config.git.pull_autostash = Some(*b);
+            }
+            if let Some(parser::Value::Integer(n)) = git.get("trash_days") {
+                config.git.trash_days = (*n).max(0) as u32;
+            }
+            if let Some(parser::Value::String(s)) = git.get("show_untracked") {
+                config.git.show_untracked = UntrackedFiles::parse(s);
+            }
+        }
+
+        if let Some(parser::Value::Table(input)) = toml.get("input") {
+            if let Some(parser::Value::Boolean(b)) = input.get("bracketed_paste") {
+                config.input.bracketed_paste = *b;
+            }
+            if let Some(parser::Value::Boolean(b)) = input.get("focus_events") {
+                config.input.focus_events = *b;
+            }
+            if let Some(parser::Value::Boolean(b)) = input.get("kitty_keyboard") {
+                config.input.kitty_keyboard = *b;
+            }
+        }
+
+        if let Some(parser::Value::Table(workspace)) = toml.get("workspace") {
+            if let Some(parser::Value::Array(repos)) = workspace.get("repos") {
+                config.workspace_repos = repos
+                    .iter()
+                    .filter_map(|v| match v {
+                        parser::Value::String(s) => Some(workspace::expand_home(s)),
+                        _ => None,
+                    })
+                    .collect();
             }
         }
 
@@ -223,21 +341,50 @@ impl Config {
         Ok(config)
     }
 
+    /// `~/.config/g/themes`, holding `<name>.toml` theme files
+    pub fn themes_dir() -> PathBuf {
+        Self::config_path()
+            .parent()
+            .map(|dir| dir.join("themes"))
+            .unwrap_or_else(|| PathBuf::from("themes"))
+    }
+
+    /// The theme `name` with the `[theme]` overrides applied
+    /// `large_file_kb` in bytes, None for no limit
+    pub fn size_limit(&self) -> Option<u64> {
+        (self.large_file_kb > 0).then(|| self.large_file_kb * 1024)
+    }
+
+    pub fn theme_named(&self, name: &str) -> Option<Theme> {
+        let mut theme = Theme::load(name)?;
+        theme.apply(&self.theme_overrides);
+        Some(theme)
+    }
+
     pub fn save_highlight_color(&self) {
+        if let Some(hex) = self.theme.selection.to_hex() {
+            self.save_theme_value("selection_color", &hex);
+        }
+    }
+
+    /// Remember the selected theme in the config file
+    pub fn save_theme_name(&self) {
+        self.save_theme_value("name", &self.theme.name);
+    }
+
+    /// Set `key = "value"` in the `[theme]` section, keeping the rest of the file
+    fn save_theme_value(&self, key: &str, value: &str) {
         let config_path = Self::config_path();
 
         if let Some(parent) = config_path.parent() {
             let _ = std::fs::create_dir_all(parent);
         }
 
-        let hex = match self.theme.selection.to_hex() {
-            Some(h) => h,
-            None => return,
-        };
+        let line_for = |key: &str| format!("{} = \"{}\"\n", key, value);
 
         let mut content = String::new();
         let mut found_theme_section = false;
-        let mut replaced_selection = false;
+        let mut replaced = false;
 
         if let Ok(existing) = std::fs::read_to_string(&config_path) {
             let mut in_theme_section = false;
@@ -249,23 +396,23 @@ impl Config {
                     content.push('\n');
                     continue;
                 }
-                if in_theme_section && line.trim_start().starts_with("selection_color") {
-                    content.push_str(&format!("selection_color = \"{}\"\n", hex));
-                    replaced_selection = true;
+                if in_theme_section && line.split('=').next().map(str::trim) == Some(key) {
+                    content.push_str(&line_for(key));
+                    replaced = true;
                     continue;
                 }
                 if in_theme_section && line.starts_with('[') {
-                    if !replaced_selection {
-                        content.push_str(&format!("selection_color = \"{}\"\n", hex));
-                        replaced_selection = true;
+                    if !replaced {
+                        content.push_str(&line_for(key));
+                        replaced = true;
                     }
                     in_theme_section = false;
                 }
                 content.push_str(line);
                 content.push('\n');
             }
-            if found_theme_section && !replaced_selection {
-                content.push_str(&format!("selection_color = \"{}\"\n", hex));
+            if found_theme_section && !replaced {
+                content.push_str(&line_for(key));
             }
         }
 
@@ -273,7 +420,8 @@ impl Config {
             if !content.is_empty() && !content.ends_with('\n') {
                 content.push('\n');
             }
-            content.push_str(&format!("\n[theme]\nselection_color = \"{}\"\n", hex));
+            content.push_str("\n[theme]\n");
+            content.push_str(&line_for(key));
         }
 
         let _ = std::fs::write(&config_path, content);
@@ -306,6 +454,26 @@ selection_color = "#89b4fa"
         assert_eq!(config.theme.selection, Color::Rgb(137, 180, 250));
     }
 
+    #[test]
+    fn test_parse_named_theme_with_overrides() {
+        let content = r##"
+[theme]
+name = "light"
+diff_add = "#000001"
+"##;
+        let config = Config::parse(content).unwrap();
+        let light = Theme::builtin("light").unwrap();
+        assert_eq!(config.theme.name, "light");
+        assert_eq!(config.theme.foreground, light.foreground);
+        assert_eq!(config.theme.diff_add, Color::Rgb(0, 0, 1));
+        // Overrides follow the theme picked at runtime
+        assert_eq!(
+            config.theme_named("gruvbox").unwrap().diff_add,
+            Color::Rgb(0, 0, 1)
+        );
+        assert!(config.theme_named("no-such-theme").is_none());
+    }
+
     #[test]
     fn test_parse_default_selection_color_without_theme_section() {
         let content = "";
@@ -0,0 +1,80 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// UI state of one repository, restored on the next launch.
+///
+/// Selections are kept by name or id rather than index where possible, and
+/// anything that no longer exists (a deleted branch, a rewritten commit) is
+/// skipped on restore.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Session {
+    /// Working tree this session belongs to
+    pub repo: PathBuf,
+    pub focused_panel: Option<String>,
+    pub zoomed: bool,
+    pub commits_mode: Option<String>,
+    pub diff_mode: Option<String>,
+    pub files_mode: Option<String>,
+    /// Path the Commits panel is limited to
+    pub commits_path: Option<String>,
+    pub selected_commit: Option<String>,
+    pub selected_branch: Option<String>,
+    pub marked_commits: Vec<String>,
+    /// Selected row of panels without a stable key, by panel name
+    pub positions: HashMap<String, usize>,
+    /// Search queries by panel name
+    pub searches: HashMap<String, String>,
+}
+
+impl Session {
+    /// The saved session for `repo`, if any
+    pub fn load(repo: &Path) -> Option<Self> {
+        let content = std::fs::read_to_string(Self::path(repo)).ok()?;
+        let session: Session = serde_json::from_str(&content).ok()?;
+        // Guard against hash collisions
+        (session.repo == repo).then_some(session)
+    }
+
+    pub fn save(&self) -> std::io::Result<()> {
+        let path = Self::path(&self.repo);
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(path, serde_json::to_string_pretty(self)?)
+    }
+
+    /// `<state dir>/g/sessions/<hash of the repo path>.json`
+    pub fn path(repo: &Path) -> PathBuf {
+        super::Config::state_dir().join("sessions").join(format!(
+            "{:016x}.json",
+            fnv1a(repo.to_string_lossy().as_bytes())
+        ))
+    }
+}
+
+/// Stable across builds, unlike `DefaultHasher`
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, &b| {
+        (hash ^ b as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_session_tolerates_old_files() {
+        // Fields added later default; ones removed later are ignored
+        let session: Session =
+            serde_json::from_str(r#"{"repo":"/r","focused_panel":"commits","gone":1}"#).unwrap();
+        assert_eq!(session.focused_panel.as_deref(), Some("commits"));
+        assert!(session.marked_commits.is_empty());
+        assert_ne!(
+            Session::path(Path::new("/a")),
+            Session::path(Path::new("/b"))
+        );
+    }
+}
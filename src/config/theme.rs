@@ -1,7 +1,11 @@
+use super::parser::{self, Value};
 use crate::tui::Color;
+use std::collections::HashMap;
 
 #[derive(Debug, Clone)]
 pub struct Theme {
+    /// Built-in or theme file name this theme was loaded from
+    pub name: String,
     pub foreground: Color,
     pub border: Color,
     pub border_focused: Color,
@@ -25,8 +29,66 @@ pub struct Theme {
     pub commit_message: Color,
     pub commit_time: Color,
     pub commit_refs: Color,
+    // File view
+    pub line_number: Color,
+    pub cursor_line: Color,
+    pub visual_selection: Color,
+    // Syntax highlighting
+    pub syntax_keyword: Color,
+    pub syntax_function: Color,
+    pub syntax_type: Color,
+    pub syntax_string: Color,
+    pub syntax_number: Color,
+    pub syntax_comment: Color,
+    /// Colors of the commit graph lanes, used in turn
+    pub graph_lanes: Vec<Color>,
 }
 
+/// Themes compiled into g; `~/.config/g/themes/<name>.toml` adds more
+pub const BUILTIN_THEMES: &[&str] = &[
+    "dark",
+    "light",
+    "high-contrast",
+    "solarized-dark",
+    "solarized-light",
+    "gruvbox",
+];
+
+/// Keys of a `[theme]` table or theme file that hold one color
+pub const COLOR_KEYS: &[&str] = &[
+    "foreground",
+    "border",
+    "border_focused",
+    "border_unfocused",
+    "selection",
+    "selection_text",
+    "diff_add",
+    "diff_remove",
+    "diff_add_bg",
+    "diff_remove_bg",
+    "diff_hunk",
+    "staged",
+    "unstaged",
+    "untracked",
+    "branch_current",
+    "branch_local",
+    "branch_remote",
+    "commit_hash",
+    "commit_author",
+    "commit_message",
+    "commit_time",
+    "commit_refs",
+    "line_number",
+    "cursor_line",
+    "visual_selection",
+    "syntax_keyword",
+    "syntax_function",
+    "syntax_type",
+    "syntax_string",
+    "syntax_number",
+    "syntax_comment",
+];
+
 pub const HIGHLIGHT_COLORS: &[(Color, &str)] = &[
     (Color::Rgb(255, 140, 0), "orange"),
     (Color::Rgb(137, 180, 250), "blue"),
@@ -43,30 +105,335 @@ pub const HIGHLIGHT_COLORS: &[(Color, &str)] = &[
 impl Theme {
     pub fn default() -> Self {
         Self {
-            foreground: Color::Rgb(205, 214, 244),     // #cdd6f4
-            border: Color::Rgb(108, 112, 134),         // #6c7086
+            name: "dark".to_string(),
+            foreground: Color::Rgb(205, 214, 244), // #cdd6f4
+            border: Color::Rgb(108, 112, 134),     // #6c7086
             border_focused: Color::Rgb(137, 180, 250), // #89b4fa
-            border_unfocused: Color::Rgb(69, 71, 90),  // #45475a (dimmer)
-            selection: Color::Rgb(255, 140, 0),        // Orange background
-            selection_text: Color::Rgb(0, 0, 0),       // Black text for contrast
-            diff_add: Color::Rgb(166, 227, 161),       // #a6e3a1
-            diff_remove: Color::Rgb(243, 139, 168),    // #f38ba8
-            diff_add_bg: Color::Rgb(30, 60, 30),       // Dark green background
-            diff_remove_bg: Color::Rgb(60, 30, 30),    // Dark red background
-            diff_hunk: Color::Rgb(137, 220, 235),      // #89dceb
-            staged: Color::Rgb(166, 227, 161),         // #a6e3a1
-            unstaged: Color::Rgb(147, 153, 178),       // #9399b2 (gray)
-            untracked: Color::Rgb(108, 112, 134),      // #6c7086
+            border_unfocused: Color::Rgb(69, 71, 90), // #45475a (dimmer)
+            selection: Color::Rgb(255, 140, 0),    // Orange background
+            selection_text: Color::Rgb(0, 0, 0),   // Black text for contrast
+            diff_add: Color::Rgb(166, 227, 161),   // #a6e3a1
+            diff_remove: Color::Rgb(243, 139, 168), // #f38ba8
+            diff_add_bg: Color::Rgb(30, 60, 30),   // Dark green background
+            diff_remove_bg: Color::Rgb(60, 30, 30), // Dark red background
+            diff_hunk: Color::Rgb(137, 220, 235),  // #89dceb
+            staged: Color::Rgb(166, 227, 161),     // #a6e3a1
+            unstaged: Color::Rgb(147, 153, 178),   // #9399b2 (gray)
+            untracked: Color::Rgb(108, 112, 134),  // #6c7086
             branch_current: Color::Rgb(166, 227, 161), // #a6e3a1
-            branch_local: Color::Rgb(137, 180, 250),   // #89b4fa
-            branch_remote: Color::Rgb(203, 166, 247),  // #cba6f7
+            branch_local: Color::Rgb(137, 180, 250), // #89b4fa
+            branch_remote: Color::Rgb(203, 166, 247), // #cba6f7
             // Commit-specific colors
             commit_hash: Color::Rgb(137, 220, 235), // #89dceb (cyan)
             commit_author: Color::Rgb(108, 112, 134), // #6c7086 (dim gray, same as time)
             commit_message: Color::Rgb(205, 214, 244), // #cdd6f4 (foreground)
             commit_time: Color::Rgb(108, 112, 134), // #6c7086 (dim)
             commit_refs: Color::Rgb(166, 227, 161), // #a6e3a1 (green)
+            // File view
+            line_number: Color::Rgb(108, 112, 134), // #6c7086
+            cursor_line: Color::Rgb(45, 45, 55),    // Subtle highlight
+            visual_selection: Color::Rgb(60, 60, 100), // Dark blue
+            // Syntax highlighting
+            syntax_keyword: Color::Rgb(203, 166, 247), // #cba6f7 (mauve)
+            syntax_function: Color::Rgb(137, 180, 250), // #89b4fa (blue)
+            syntax_type: Color::Rgb(249, 226, 175),    // #f9e2af (yellow)
+            syntax_string: Color::Rgb(166, 227, 161),  // #a6e3a1 (green)
+            syntax_number: Color::Rgb(250, 179, 135),  // #fab387 (peach)
+            syntax_comment: Color::Rgb(108, 112, 134), // #6c7086
+            graph_lanes: vec![
+                Color::Rgb(243, 139, 168), // red
+                Color::Rgb(166, 227, 161), // green
+                Color::Rgb(249, 226, 175), // yellow
+                Color::Rgb(137, 180, 250), // blue
+                Color::Rgb(203, 166, 247), // mauve
+                Color::Rgb(137, 220, 235), // cyan
+            ],
+        }
+    }
+
+    /// A built-in theme by name
+    pub fn builtin(name: &str) -> Option<Self> {
+        let theme = match name {
+            "dark" => Self::default(),
+            "light" => Self::from_palette(
+                name,
+                &[
+                    ("foreground", 0x4c4f69),
+                    ("border", 0x9ca0b0),
+                    ("border_focused", 0x1e66f5),
+                    ("border_unfocused", 0xccd0da),
+                    ("selection", 0xfe640b),
+                    ("selection_text", 0xeff1f5),
+                    ("diff_add", 0x40a02b),
+                    ("diff_remove", 0xd20f39),
+                    ("diff_add_bg", 0xdcf0d7),
+                    ("diff_remove_bg", 0xfadce1),
+                    ("diff_hunk", 0x04a5e5),
+                    ("staged", 0x40a02b),
+                    ("unstaged", 0x7c7f93),
+                    ("untracked", 0x8c8fa1),
+                    ("branch_current", 0x40a02b),
+                    ("branch_local", 0x1e66f5),
+                    ("branch_remote", 0x8839ef),
+                    ("commit_hash", 0x179299),
+                    ("commit_author", 0x8c8fa1),
+                    ("commit_message", 0x4c4f69),
+                    ("commit_time", 0x8c8fa1),
+                    ("commit_refs", 0x40a02b),
+                    ("line_number", 0x9ca0b0),
+                    ("cursor_line", 0xe6e9ef),
+                    ("visual_selection", 0xbcc0cc),
+                    ("syntax_keyword", 0x8839ef),
+                    ("syntax_function", 0x1e66f5),
+                    ("syntax_type", 0xdf8e1d),
+                    ("syntax_string", 0x40a02b),
+                    ("syntax_number", 0xfe640b),
+                    ("syntax_comment", 0x9ca0b0),
+                ],
+                &[0xd20f39, 0x40a02b, 0xdf8e1d, 0x1e66f5, 0x8839ef, 0x04a5e5],
+            ),
+            "high-contrast" => Self::from_palette(
+                name,
+                &[
+                    ("foreground", 0xffffff),
+                    ("border", 0xc8c8c8),
+                    ("border_focused", 0xffff00),
+                    ("border_unfocused", 0x808080),
+                    ("selection", 0xffff00),
+                    ("selection_text", 0x000000),
+                    ("diff_add", 0x00ff00),
+                    ("diff_remove", 0xff4040),
+                    ("diff_add_bg", 0x005000),
+                    ("diff_remove_bg", 0x640000),
+                    ("diff_hunk", 0x00ffff),
+                    ("staged", 0x00ff00),
+                    ("unstaged", 0xffaa00),
+                    ("untracked", 0xc8c8c8),
+                    ("branch_current", 0x00ff00),
+                    ("branch_local", 0x00c8ff),
+                    ("branch_remote", 0xff64ff),
+                    ("commit_hash", 0x00ffff),
+                    ("commit_author", 0xdcdcdc),
+                    ("commit_message", 0xffffff),
+                    ("commit_time", 0xc8c8c8),
+                    ("commit_refs", 0x00ff00),
+                    ("line_number", 0xc8c8c8),
+                    ("cursor_line", 0x282828),
+                    ("visual_selection", 0x0000a0),
+                    ("syntax_keyword", 0xff64ff),
+                    ("syntax_function", 0x00c8ff),
+                    ("syntax_type", 0xffff00),
+                    ("syntax_string", 0x00ff00),
+                    ("syntax_number", 0xffaa00),
+                    ("syntax_comment", 0xb4b4b4),
+                ],
+                &[0xff4040, 0x00ff00, 0xffff00, 0x00c8ff, 0xff64ff, 0x00ffff],
+            ),
+            "solarized-dark" | "solarized-light" => {
+                let dark = name == "solarized-dark";
+                // base0/base00 swap between the two variants, as do base02/base2 etc.
+                let (fg, dim, faint, highlight, contrast) = if dark {
+                    (0x839496, 0x586e75, 0x073642, 0x073642, 0x002b36)
+                } else {
+                    (0x657b83, 0x93a1a1, 0xeee8d5, 0xeee8d5, 0xfdf6e3)
+                };
+                Self::from_palette(
+                    name,
+                    &[
+                        ("foreground", fg),
+                        ("border", dim),
+                        ("border_focused", 0x268bd2),
+                        ("border_unfocused", faint),
+                        ("selection", 0xb58900),
+                        ("selection_text", contrast),
+                        ("diff_add", 0x859900),
+                        ("diff_remove", 0xdc322f),
+                        ("diff_add_bg", if dark { 0x143c1e } else { 0xe1ebc8 }),
+                        ("diff_remove_bg", if dark { 0x46191e } else { 0xf5d7cd }),
+                        ("diff_hunk", 0x2aa198),
+                        ("staged", 0x859900),
+                        ("unstaged", if dark { 0x657b83 } else { 0x839496 }),
+                        ("untracked", dim),
+                        ("branch_current", 0x859900),
+                        ("branch_local", 0x268bd2),
+                        ("branch_remote", 0x6c71c4),
+                        ("commit_hash", 0x2aa198),
+                        ("commit_author", dim),
+                        ("commit_message", fg),
+                        ("commit_time", dim),
+                        ("commit_refs", 0x859900),
+                        ("line_number", dim),
+                        ("cursor_line", highlight),
+                        ("visual_selection", if dark { 0x144b5f } else { 0xd7d7be }),
+                        ("syntax_keyword", 0x859900),
+                        ("syntax_function", 0x268bd2),
+                        ("syntax_type", 0xb58900),
+                        ("syntax_string", 0x2aa198),
+                        ("syntax_number", 0xd33682),
+                        ("syntax_comment", dim),
+                    ],
+                    &[0xdc322f, 0x859900, 0xb58900, 0x268bd2, 0xd33682, 0x2aa198],
+                )
+            }
+            "gruvbox" => Self::from_palette(
+                name,
+                &[
+                    ("foreground", 0xebdbb2),
+                    ("border", 0x928374),
+                    ("border_focused", 0x83a598),
+                    ("border_unfocused", 0x504945),
+                    ("selection", 0xfe8019),
+                    ("selection_text", 0x282828),
+                    ("diff_add", 0xb8bb26),
+                    ("diff_remove", 0xfb4934),
+                    ("diff_add_bg", 0x323c14),
+                    ("diff_remove_bg", 0x461e19),
+                    ("diff_hunk", 0x8ec07c),
+                    ("staged", 0xb8bb26),
+                    ("unstaged", 0xa89984),
+                    ("untracked", 0x928374),
+                    ("branch_current", 0xb8bb26),
+                    ("branch_local", 0x83a598),
+                    ("branch_remote", 0xd3869b),
+                    ("commit_hash", 0x8ec07c),
+                    ("commit_author", 0x928374),
+                    ("commit_message", 0xebdbb2),
+                    ("commit_time", 0x928374),
+                    ("commit_refs", 0xb8bb26),
+                    ("line_number", 0x7c6f64),
+                    ("cursor_line", 0x3c3836),
+                    ("visual_selection", 0x504945),
+                    ("syntax_keyword", 0xfb4934),
+                    ("syntax_function", 0x8ec07c),
+                    ("syntax_type", 0xfabd2f),
+                    ("syntax_string", 0xb8bb26),
+                    ("syntax_number", 0xd3869b),
+                    ("syntax_comment", 0x928374),
+                ],
+                &[0xfb4934, 0xb8bb26, 0xfabd2f, 0x83a598, 0xd3869b, 0x8ec07c],
+            ),
+            _ => return None,
+        };
+        Some(theme)
+    }
+
+    fn from_palette(name: &str, colors: &[(&str, u32)], lanes: &[u32]) -> Self {
+        let mut theme = Self::default();
+        theme.name = name.to_string();
+        for (key, rgb) in colors {
+            if let Some(color) = theme.color_mut(key) {
+                *color = hex(*rgb);
+            }
+        }
+        theme.graph_lanes = lanes.iter().map(|rgb| hex(*rgb)).collect();
+        theme
+    }
+
+    /// A theme file from the themes directory, or else a built-in theme
+    pub fn load(name: &str) -> Option<Self> {
+        let path = super::Config::themes_dir().join(format!("{}.toml", name));
+        let Ok(content) = std::fs::read_to_string(path) else {
+            return Self::builtin(name);
+        };
+        let table = parser::parse(&content).ok()?;
+        // A theme file only lists what differs from its base
+        let base = match table.get("base") {
+            Some(Value::String(base)) => base.as_str(),
+            _ => "dark",
+        };
+        let mut theme = Self::builtin(base).unwrap_or_else(Self::default);
+        theme.apply(&table);
+        theme.name = name.to_string();
+        Some(theme)
+    }
+
+    /// Built-in themes followed by the theme files, for the theme picker
+    pub fn available() -> Vec<String> {
+        let mut names: Vec<String> = BUILTIN_THEMES.iter().map(|s| s.to_string()).collect();
+        let mut files: Vec<String> = std::fs::read_dir(super::Config::themes_dir())
+            .into_iter()
+            .flatten()
+            .flatten()
+            .filter_map(|entry| {
+                let path = entry.path();
+                if path.extension()? != "toml" {
+                    return None;
+                }
+                Some(path.file_stem()?.to_string_lossy().to_string())
+            })
+            .filter(|name| !names.contains(name))
+            .collect();
+        files.sort();
+        names.extend(files);
+        names
+    }
+
+    /// Override colors from a `[theme]` table or theme file; unknown keys
+    /// and unparsable colors are ignored
+    pub fn apply(&mut self, table: &HashMap<String, Value>) {
+        // Older configs only had the highlight color. It goes first so that
+        // `selection` or `border_focused` set next to it win, whatever order
+        // the table iterates in.
+        if let Some(color) = table.get("selection_color").and_then(parse_color) {
+            self.selection = color;
+            self.border_focused = color;
         }
+        for (key, value) in table {
+            match key.as_str() {
+                "selection_color" => {}
+                "graph_lanes" => {
+                    if let Value::Array(values) = value {
+                        let lanes: Vec<Color> = values.iter().filter_map(parse_color).collect();
+                        if !lanes.is_empty() {
+                            self.graph_lanes = lanes;
+                        }
+                    }
+                }
+                key => {
+                    if let (Some(color), Some(field)) = (parse_color(value), self.color_mut(key)) {
+                        *field = color;
+                    }
+                }
+            }
+        }
+    }
+
+    fn color_mut(&mut self, key: &str) -> Option<&mut Color> {
+        let field = match key {
+            "foreground" => &mut self.foreground,
+            "border" => &mut self.border,
+            "border_focused" => &mut self.border_focused,
+            "border_unfocused" => &mut self.border_unfocused,
+            "selection" => &mut self.selection,
+            "selection_text" => &mut self.selection_text,
+            "diff_add" => &mut self.diff_add,
+            "diff_remove" => &mut self.diff_remove,
+            "diff_add_bg" => &mut self.diff_add_bg,
+            "diff_remove_bg" => &mut self.diff_remove_bg,
+            "diff_hunk" => &mut self.diff_hunk,
+            "staged" => &mut self.staged,
+            "unstaged" => &mut self.unstaged,
+            "untracked" => &mut self.untracked,
+            "branch_current" => &mut self.branch_current,
+            "branch_local" => &mut self.branch_local,
+            "branch_remote" => &mut self.branch_remote,
+            "commit_hash" => &mut self.commit_hash,
+            "commit_author" => &mut self.commit_author,
+            "commit_message" => &mut self.commit_message,
+            "commit_time" => &mut self.commit_time,
+            "commit_refs" => &mut self.commit_refs,
+            "line_number" => &mut self.line_number,
+            "cursor_line" => &mut self.cursor_line,
+            "visual_selection" => &mut self.visual_selection,
+            "syntax_keyword" => &mut self.syntax_keyword,
+            "syntax_function" => &mut self.syntax_function,
+            "syntax_type" => &mut self.syntax_type,
+            "syntax_string" => &mut self.syntax_string,
+            "syntax_number" => &mut self.syntax_number,
+            "syntax_comment" => &mut self.syntax_comment,
+            _ => return None,
+        };
+        Some(field)
     }
 
     pub fn highlight_color_index(&self) -> usize {
@@ -86,6 +453,19 @@ impl Theme {
     }
 }
 
+const fn hex(rgb: u32) -> Color {
+    Color::Rgb((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8)
+}
+
+/// "#rrggbb", an ANSI color name ("red", "lightblue", ...) or a 256-color index
+pub fn parse_color(value: &Value) -> Option<Color> {
+    match value {
+        Value::String(s) => Color::parse(s),
+        Value::Integer(n) => u8::try_from(*n).ok().map(Color::Indexed),
+        _ => None,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -123,4 +503,49 @@ mod tests {
         theme.selection = Color::Rgb(1, 2, 3);
         assert_eq!(theme.highlight_color_index(), 0);
     }
+
+    #[test]
+    fn test_builtin_themes_and_overrides() {
+        for name in BUILTIN_THEMES {
+            let theme = Theme::builtin(name).unwrap();
+            assert_eq!(theme.name, *name);
+            assert!(!theme.graph_lanes.is_empty());
+        }
+        // Every color key maps to a field
+        let mut theme = Theme::default();
+        for key in COLOR_KEYS {
+            assert!(theme.color_mut(key).is_some(), "{}", key);
+        }
+
+        let table = parser::parse(
+            r##"
+foreground = "#010203"
+border = "red"
+commit_hash = 208
+graph_lanes = ["#ffffff", "blue"]
+unknown = "#000000"
+"##,
+        )
+        .unwrap();
+        theme.apply(&table);
+        assert_eq!(theme.foreground, Color::Rgb(1, 2, 3));
+        assert_eq!(theme.border, Color::Red);
+        assert_eq!(theme.commit_hash, Color::Indexed(208));
+        assert_eq!(
+            theme.graph_lanes,
+            vec![Color::Rgb(255, 255, 255), Color::Blue]
+        );
+
+        // The specific keys win over the legacy one, in any table order
+        let table = parser::parse(
+            r##"
+selection = "green"
+selection_color = "red"
+"##,
+        )
+        .unwrap();
+        theme.apply(&table);
+        assert_eq!(theme.selection, Color::Green);
+        assert_eq!(theme.border_focused, Color::Red);
+    }
 }
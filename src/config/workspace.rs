@@ -0,0 +1,64 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// How many recently opened repositories are remembered
+const MAX_RECENT: usize = 20;
+
+/// Recently opened and favorite repositories, kept in the state directory
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RepoList {
+    /// Most recent first
+    pub recent: Vec<PathBuf>,
+    pub favorites: Vec<PathBuf>,
+}
+
+impl RepoList {
+    pub fn load() -> Self {
+        std::fs::read_to_string(Self::path())
+            .ok()
+            .and_then(|s| serde_json::from_str(&s).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) -> std::io::Result<()> {
+        let path = Self::path();
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(path, serde_json::to_string_pretty(self)?)
+    }
+
+    fn path() -> PathBuf {
+        super::Config::state_dir().join("repos.json")
+    }
+
+    /// Move `repo` to the front of the recent list
+    pub fn touch(&mut self, repo: &Path) {
+        self.recent.retain(|p| p != repo);
+        self.recent.insert(0, repo.to_path_buf());
+        self.recent.truncate(MAX_RECENT);
+    }
+
+    /// Add or remove `repo` from the favorites; returns whether it is now a favorite
+    pub fn toggle_favorite(&mut self, repo: &Path) -> bool {
+        if self.favorites.iter().any(|p| p == repo) {
+            self.favorites.retain(|p| p != repo);
+            false
+        } else {
+            self.favorites.push(repo.to_path_buf());
+            true
+        }
+    }
+}
+
+/// "~/src/g" -> "$HOME/src/g"
+pub(super) fn expand_home(path: &str) -> PathBuf {
+    match path.strip_prefix("~/") {
+        Some(rest) => match std::env::var("HOME").or_else(|_| std::env::var("USERPROFILE")) {
+            Ok(home) => PathBuf::from(home).join(rest),
+            Err(_) => PathBuf::from(path),
+        },
+        None => PathBuf::from(path),
+    }
+}
@@ -6,6 +6,8 @@ pub enum Error {
     Io(std::io::Error),
     Config(String),
     Terminal(String),
+    /// A git hook rejected the operation
+    Hook(String),
 }
 
 impl fmt::Display for Error {
@@ -15,6 +17,7 @@ impl fmt::Display for Error {
             Error::Io(e) => write!(f, "IO error: {}", e),
             Error::Config(msg) => write!(f, "Config error: {}", msg),
             Error::Terminal(msg) => write!(f, "Terminal error: {}", msg),
+            Error::Hook(msg) => write!(f, "Hook error: {}", msg),
         }
     }
 }
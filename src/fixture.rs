@@ -0,0 +1,143 @@
+//! Repositories in temporary directories for tests, built with git2
+
+use crate::git::Repository;
+use std::path::{Path, PathBuf};
+
+/// A repository in a temporary directory, removed on drop
+pub struct Fixture {
+    root: PathBuf,
+    pub dir: PathBuf,
+    pub repo: git2::Repository,
+    /// Commits in creation order, for redacting their ids in snapshots
+    pub commits: Vec<git2::Oid>,
+}
+
+impl Fixture {
+    /// An empty repository on `main`. The directory is always named
+    /// "fixture" because the repository name shows in the footer.
+    pub fn new(name: &str) -> Self {
+        let root = Self::root(name);
+        let dir = root.join("fixture");
+        let mut options = git2::RepositoryInitOptions::new();
+        options.initial_head("main");
+        let repo = git2::Repository::init_opts(&dir, &options).unwrap();
+        Self::configured(root, dir, repo)
+    }
+
+    /// A clone of `url`
+    pub fn clone(name: &str, url: &str) -> Self {
+        let root = Self::root(name);
+        let dir = root.join("fixture");
+        let repo = git2::Repository::clone(url, &dir).unwrap();
+        Self::configured(root, dir, repo)
+    }
+
+    fn root(name: &str) -> PathBuf {
+        let root = std::env::temp_dir().join(format!("g-test-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        root
+    }
+
+    fn configured(root: PathBuf, dir: PathBuf, repo: git2::Repository) -> Self {
+        let mut config = repo.config().unwrap();
+        config.set_str("user.name", "Test").unwrap();
+        config.set_str("user.email", "test@example.com").unwrap();
+        Self {
+            root,
+            dir,
+            repo,
+            commits: Vec::new(),
+        }
+    }
+
+    /// The repository through the app's wrapper
+    pub fn open(&self) -> Repository {
+        Repository::open(&self.dir).unwrap()
+    }
+
+    /// A bare repository next to this one, added as remote `name`; returns its url
+    pub fn bare_remote(&self, name: &str) -> String {
+        let path = self.root.join(format!("{}.git", name));
+        let mut options = git2::RepositoryInitOptions::new();
+        options.bare(true).initial_head("main");
+        git2::Repository::init_opts(&path, &options).unwrap();
+        let url = format!("file://{}", path.display());
+        self.repo.remote(name, &url).unwrap();
+        url
+    }
+
+    pub fn write(&self, path: &str, contents: &str) {
+        let path = self.dir.join(path);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).unwrap();
+        }
+        std::fs::write(path, contents).unwrap();
+    }
+
+    pub fn read(&self, path: &str) -> String {
+        std::fs::read_to_string(self.dir.join(path)).unwrap()
+    }
+
+    /// Write `files`, stage them and commit on HEAD. Commits are dated two
+    /// days ago, so the relative dates shown stay the same from run to run.
+    pub fn commit(&mut self, message: &str, files: &[(&str, &str)]) -> git2::Oid {
+        let now = chrono::Utc::now().timestamp();
+        let sig = git2::Signature::new(
+            "Test",
+            "test@example.com",
+            &git2::Time::new(now - 2 * 86400, 0),
+        )
+        .unwrap();
+        self.commit_as(message, files, &sig)
+    }
+
+    /// `commit` with another author
+    pub fn commit_as(
+        &mut self,
+        message: &str,
+        files: &[(&str, &str)],
+        author: &git2::Signature,
+    ) -> git2::Oid {
+        let mut index = self.repo.index().unwrap();
+        for (path, contents) in files {
+            self.write(path, contents);
+            index.add_path(Path::new(path)).unwrap();
+        }
+        index.write().unwrap();
+        let tree = self.repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let committer = git2::Signature::new("Test", "test@example.com", &author.when()).unwrap();
+        let parent = self.repo.head().ok().and_then(|h| h.peel_to_commit().ok());
+        let parents: Vec<&git2::Commit> = parent.iter().collect();
+        let oid = self
+            .repo
+            .commit(Some("HEAD"), author, &committer, message, &tree, &parents)
+            .unwrap();
+        self.commits.push(oid);
+        oid
+    }
+
+    /// Create a branch at HEAD without checking it out
+    pub fn branch(&self, name: &str) {
+        let head = self.repo.head().unwrap().peel_to_commit().unwrap();
+        self.repo.branch(name, &head, false).unwrap();
+    }
+
+    /// Point HEAD at branch `name` and force the working tree to match
+    pub fn switch(&self, name: &str) {
+        self.repo.set_head(&format!("refs/heads/{}", name)).unwrap();
+        self.repo
+            .checkout_head(Some(git2::build::CheckoutBuilder::new().force()))
+            .unwrap();
+    }
+
+    /// The commit HEAD points to
+    pub fn head(&self) -> git2::Oid {
+        self.repo.head().unwrap().target().unwrap()
+    }
+}
+
+impl Drop for Fixture {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.root);
+    }
+}
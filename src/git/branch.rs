@@ -1,6 +1,7 @@
 use super::commit::CommitInfo;
+use serde::Serialize;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct BranchInfo {
     pub name: String,
     pub branch_type: BranchType,
@@ -12,13 +13,14 @@ pub struct BranchInfo {
     pub upstream: Option<UpstreamInfo>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct UpstreamInfo {
     pub name: String,
     pub short_id: String,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
 pub enum BranchType {
     Local,
     Remote,
@@ -0,0 +1,100 @@
+use super::branch::BranchType;
+
+/// Something HEAD can be moved to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CheckoutTarget {
+    /// Local branch, or remote branch ("origin/x") that gets a tracking branch
+    Branch(String, BranchType),
+    /// Commit-ish, checked out as a detached HEAD
+    Commit(String),
+}
+
+impl CheckoutTarget {
+    /// Name shown to the user (remote branches by the local name they switch to)
+    pub fn display_name(&self) -> &str {
+        match self {
+            CheckoutTarget::Branch(name, BranchType::Remote) => {
+                name.split_once('/').map(|(_, b)| b).unwrap_or(name)
+            }
+            CheckoutTarget::Branch(name, BranchType::Local) => name,
+            CheckoutTarget::Commit(id) => &id[..7.min(id.len())],
+        }
+    }
+}
+
+/// What happens to uncommitted changes during a checkout
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CheckoutMode {
+    /// Keep them in the working tree; refused if the switch would overwrite any
+    #[default]
+    Carry,
+    /// Stash them, switch, then re-apply the stash
+    Stash,
+}
+
+/// Uncommitted changes relative to a checkout
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CheckoutPreview {
+    /// Modified or staged tracked files
+    pub changed: Vec<String>,
+    /// Changed files the target differs in, which a carrying checkout would overwrite
+    pub blocking: Vec<String>,
+}
+
+impl CheckoutPreview {
+    pub fn is_clean(&self) -> bool {
+        self.changed.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CheckoutOutcome {
+    /// Stashed changes conflicted with the target and remain in the stash list
+    pub stash_kept: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture::Fixture;
+
+    /// main has a=1, b=1; branch "other" has a=2
+    fn setup() -> Fixture {
+        let mut fixture = Fixture::new("checkout");
+        fixture.commit("base", &[("a", "1"), ("b", "1")]);
+        fixture.branch("other");
+        fixture.switch("other");
+        fixture.commit("change a", &[("a", "2")]);
+        fixture.switch("main");
+        fixture
+    }
+
+    #[test]
+    fn test_dirty_checkout_carry_and_stash() {
+        let fixture = setup();
+        let mut repo = fixture.open();
+        let other = CheckoutTarget::Branch("other".to_string(), BranchType::Local);
+
+        // A change the switch does not touch is carried along
+        fixture.write("b", "local");
+        let preview = repo.checkout_preview(&other).unwrap();
+        assert_eq!(preview.changed, vec!["b".to_string()]);
+        assert!(preview.blocking.is_empty());
+        repo.checkout(&other, CheckoutMode::Carry).unwrap();
+        assert_eq!(fixture.read("b"), "local");
+
+        // A change to a file the switch rewrites blocks carrying
+        let base = CheckoutTarget::Commit("other~1".to_string());
+        fixture.write("a", "local");
+        let preview = repo.checkout_preview(&base).unwrap();
+        assert_eq!(preview.blocking, vec!["a".to_string()]);
+        let err = repo.checkout(&base, CheckoutMode::Carry).unwrap_err();
+        assert!(err.to_string().contains("would be overwritten: a"));
+
+        // Stashing switches anyway; the conflicting change stays in the stash
+        let outcome = repo.checkout(&base, CheckoutMode::Stash).unwrap();
+        assert!(outcome.stash_kept);
+        assert!(fixture.read("a").contains("local"));
+        assert_eq!(repo.stash_list().unwrap().len(), 1);
+    }
+}
@@ -1,3 +1,5 @@
+use super::transfer::format_bytes;
+
 #[derive(Debug, Clone)]
 pub struct DiffInfo {
     pub files: Vec<FileDiff>,
@@ -35,6 +37,32 @@ impl DiffInfo {
 pub struct FileDiff {
     pub path: String,
     pub hunks: Vec<Hunk>,
+    /// Set when the hunks were not read
+    pub omitted: Option<Omitted>,
+}
+
+/// Why a file's content was left out of a diff or preview
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Omitted {
+    Binary,
+    /// Bigger than the size limit; the size in bytes
+    TooLarge(u64),
+}
+
+impl Omitted {
+    pub fn describe(&self) -> String {
+        match self {
+            Omitted::Binary => "Binary file".to_string(),
+            Omitted::TooLarge(bytes) => format!("Large file ({})", format_bytes(*bytes as usize)),
+        }
+    }
+}
+
+/// A file read for the preview
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FileText {
+    Text(String),
+    Omitted(Omitted),
 }
 
 impl FileDiff {
@@ -79,3 +107,69 @@ pub enum LineType {
     Addition,
     Deletion,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture::Fixture;
+
+    #[test]
+    fn test_size_limit_and_binary() {
+        let mut fixture = Fixture::new("diff");
+        let big = "x\n".repeat(1000);
+        fixture.commit(
+            "base",
+            &[
+                ("small.txt", "one\n"),
+                ("big.txt", &big),
+                ("image.bin", "PNG\0\0data"),
+            ],
+        );
+        fixture.write("small.txt", "one\ntwo\n");
+        fixture.write("big.txt", &"y\n".repeat(1000));
+        fixture.write("image.bin", "PNG\0\0other");
+
+        let repo = fixture.open().with_size_limit(Some(1024));
+        let omitted = |diff: DiffInfo| -> Vec<(String, Option<Omitted>)> {
+            diff.files
+                .into_iter()
+                .map(|f| (f.path, f.omitted))
+                .collect()
+        };
+        let commit = repo.diff_commit("HEAD").unwrap();
+        assert_eq!(
+            omitted(commit),
+            [
+                ("big.txt".to_string(), Some(Omitted::TooLarge(2000))),
+                ("image.bin".to_string(), Some(Omitted::Binary)),
+                ("small.txt".to_string(), None),
+            ]
+        );
+        let small = repo.diff_file("small.txt", false).unwrap();
+        assert_eq!(small.files.len(), 1);
+        assert_eq!(small.files[0].hunks[0].header, "@@ -1,1 +1,2 @@");
+        assert_eq!(small.additions(), 1);
+        let big = repo.diff_file("big.txt", false).unwrap();
+        assert_eq!(big.files[0].omitted, Some(Omitted::TooLarge(2000)));
+        let image = repo.diff_file("image.bin", false).unwrap();
+        assert_eq!(image.files[0].omitted, Some(Omitted::Binary));
+        let unlimited = fixture.open();
+        assert_eq!(
+            unlimited.diff_file("big.txt", false).unwrap().additions(),
+            1000
+        );
+
+        assert_eq!(
+            repo.read_file_text("small.txt").unwrap(),
+            FileText::Text("one\ntwo\n".to_string())
+        );
+        assert_eq!(
+            repo.read_file_text("big.txt").unwrap(),
+            FileText::Omitted(Omitted::TooLarge(2000))
+        );
+        assert_eq!(
+            repo.read_file_text("image.bin").unwrap(),
+            FileText::Omitted(Omitted::Binary)
+        );
+    }
+}
@@ -0,0 +1,247 @@
+//! A git fsmonitor hook (protocol version 2) answered from the watcher.
+//!
+//! With `core.fsmonitor = "g fsmonitor"`, `git status` runs `g fsmonitor 2
+//! <token>` to learn which paths changed since `token` instead of checking
+//! every file. While g runs, its watcher appends each working-tree path it
+//! sees change to a journal in the git directory; the hook answers from it.
+//! When no g is watching the repository the hook answers "everything", which
+//! is always correct.
+//!
+//! Journal (`<git dir>/g-fsmonitor`): a line "g-fsmonitor <pid> <epoch>",
+//! then NUL-terminated records, paths relative to the working tree
+//! (directories end in '/'). Records starting with '/' are cookies. A token
+//! is "g:<epoch>:<offset>", the journal length when it was handed out.
+
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+const JOURNAL: &str = "g-fsmonitor";
+/// Files the hook creates in the git directory to sync with the watcher
+pub(crate) const COOKIE_PREFIX: &str = "g-fsmonitor-cookie.";
+/// Start over past this size; older tokens then get "everything"
+const MAX_JOURNAL: u64 = 8 * 1024 * 1024;
+/// How long the hook waits for the watcher to catch up
+const COOKIE_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Whether `core.fsmonitor` names a hook command (rather than git's own daemon)
+pub(crate) fn hook_configured(repo: &git2::Repository) -> bool {
+    let Ok(config) = repo.config() else {
+        return false;
+    };
+    config.get_bool("core.fsmonitor").is_err()
+        && config
+            .get_string("core.fsmonitor")
+            .is_ok_and(|hook| !hook.is_empty())
+}
+
+fn process_alive(pid: u32) -> bool {
+    #[cfg(unix)]
+    unsafe {
+        libc::kill(pid as libc::pid_t, 0) == 0
+    }
+    #[cfg(not(unix))]
+    {
+        pid == std::process::id()
+    }
+}
+
+/// Owner pid, epoch and header length of a journal
+fn parse_header(data: &[u8]) -> Option<(u32, &str, usize)> {
+    let end = data.iter().position(|&b| b == b'\n')?;
+    let line = std::str::from_utf8(&data[..end]).ok()?;
+    let mut words = line.split(' ');
+    if words.next()? != "g-fsmonitor" {
+        return None;
+    }
+    let pid = words.next()?.parse().ok()?;
+    let epoch = words.next()?;
+    Some((pid, epoch, end + 1))
+}
+
+/// The watcher's side: records changed paths while g runs
+pub(crate) struct Journal {
+    path: PathBuf,
+    file: File,
+    epoch: String,
+}
+
+impl Journal {
+    /// Start a journal, unless another running g already keeps one
+    pub(crate) fn create(git_dir: &Path) -> Option<Self> {
+        let path = git_dir.join(JOURNAL);
+        if let Ok(data) = std::fs::read(&path) {
+            if let Some((pid, _, _)) = parse_header(&data) {
+                if pid != std::process::id() && process_alive(pid) {
+                    return None;
+                }
+            }
+        }
+        let (file, epoch) = Self::start(&path)?;
+        Some(Self { path, file, epoch })
+    }
+
+    fn start(path: &Path) -> Option<(File, String)> {
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_nanos())
+            .unwrap_or(0);
+        let epoch = format!("{}", nanos);
+        let mut file = File::create(path).ok()?;
+        writeln!(file, "g-fsmonitor {} {}", std::process::id(), epoch).ok()?;
+        let file = OpenOptions::new().append(true).open(path).ok()?;
+        Some((file, epoch))
+    }
+
+    /// Forget what was recorded: every token handed out so far means "everything"
+    pub(crate) fn reset(&mut self) {
+        if let Some((file, epoch)) = Self::start(&self.path) {
+            self.file = file;
+            self.epoch = epoch;
+        }
+    }
+
+    /// A working-tree path (relative, '/' separated) changed
+    pub(crate) fn record(&mut self, path: &str) {
+        let full = self.file.metadata().is_ok_and(|m| m.len() > MAX_JOURNAL);
+        if full {
+            self.reset();
+        }
+        // One write per record, so the hook never reads half of one
+        let _ = self.file.write_all(format!("{}\0", path).as_bytes());
+    }
+
+    /// The hook created cookie `name`: everything before it is recorded
+    pub(crate) fn cookie(&mut self, name: &str) {
+        let _ = self.file.write_all(format!("/{}\0", name).as_bytes());
+    }
+}
+
+impl Drop for Journal {
+    fn drop(&mut self) {
+        // Only if it is still ours
+        let ours = std::fs::read(&self.path).is_ok_and(|data| {
+            parse_header(&data)
+                .is_some_and(|(pid, epoch, _)| pid == std::process::id() && epoch == self.epoch)
+        });
+        if ours {
+            let _ = std::fs::remove_file(&self.path);
+        }
+    }
+}
+
+/// Answer `token` from journal contents: the token for the journal as it is
+/// now, and the paths changed since `token`, or None when the journal cannot
+/// tell and the answer is "everything"
+fn changes_since(data: &[u8], token: &str) -> Option<(String, Option<Vec<String>>)> {
+    let (_, epoch, header) = parse_header(data)?;
+    // Up to the last complete record
+    let end = data
+        .iter()
+        .rposition(|&b| b == 0)
+        .map(|i| i + 1)
+        .unwrap_or(header)
+        .max(header);
+    let new_token = format!("g:{}:{}", epoch, end);
+
+    let mut parts = token.splitn(3, ':');
+    let same_journal = parts.next() == Some("g") && parts.next() == Some(epoch);
+    let offset = parts
+        .next()
+        .and_then(|o| o.parse::<usize>().ok())
+        .filter(|&o| same_journal && o >= header && o <= end);
+    let Some(offset) = offset else {
+        return Some((new_token, None));
+    };
+    let mut paths: Vec<String> = data[offset..end]
+        .split(|&b| b == 0)
+        .filter(|record| !record.is_empty() && record[0] != b'/')
+        .map(|record| String::from_utf8_lossy(record).into_owned())
+        .collect();
+    paths.sort();
+    paths.dedup();
+    Some((new_token, Some(paths)))
+}
+
+/// The hook: `g fsmonitor <version> <token>` prints this response
+pub fn hook_response(git_dir: &Path, version: &str, token: &str) -> Vec<u8> {
+    let journal = git_dir.join(JOURNAL);
+    let everything = |token: String| format!("{}\0/\0", token).into_bytes();
+    if version != "2" {
+        // Version 1 takes a timestamp the journal can't answer: everything
+        return b"/\0".to_vec();
+    }
+
+    let owner = std::fs::read(&journal)
+        .ok()
+        .and_then(|data| parse_header(&data).map(|(pid, _, _)| pid));
+    let Some(pid) = owner.filter(|&pid| process_alive(pid)) else {
+        return everything("g:none:0".to_string());
+    };
+
+    // Wait until the watcher has seen a file created now, so every change
+    // made before this call is in the journal
+    let name = format!("{}{}", COOKIE_PREFIX, std::process::id());
+    let cookie = git_dir.join(&name);
+    let marker = format!("/{}\0", name).into_bytes();
+    let synced = File::create(&cookie).is_ok() && {
+        let started = Instant::now();
+        loop {
+            let data = std::fs::read(&journal).unwrap_or_default();
+            if data.windows(marker.len()).any(|w| w == marker.as_slice()) {
+                break true;
+            }
+            if started.elapsed() >= COOKIE_TIMEOUT || !process_alive(pid) {
+                break false;
+            }
+            std::thread::sleep(Duration::from_millis(5));
+        }
+    };
+    let _ = std::fs::remove_file(&cookie);
+
+    let data = std::fs::read(&journal).unwrap_or_default();
+    match changes_since(&data, token) {
+        Some((new_token, Some(paths))) if synced => {
+            let mut response = format!("{}\0", new_token).into_bytes();
+            for path in paths {
+                response.extend_from_slice(path.as_bytes());
+                response.push(0);
+            }
+            response
+        }
+        Some((new_token, _)) => everything(new_token),
+        // The journal went away meanwhile
+        None => everything("g:none:0".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_changes_since() {
+        let mut data = b"g-fsmonitor 1 42\n".to_vec();
+        let start = data.len();
+        data.extend_from_slice(b"src/a.rs\0/g-fsmonitor-cookie.7\0docs/\0src/a.rs\0");
+        let end = data.len();
+        data.extend_from_slice(b"partial");
+
+        let (token, paths) = changes_since(&data, &format!("g:42:{}", start)).unwrap();
+        assert_eq!(token, format!("g:42:{}", end));
+        assert_eq!(paths.unwrap(), ["docs/", "src/a.rs"]);
+        let (_, paths) = changes_since(&data, &token).unwrap();
+        assert!(paths.unwrap().is_empty());
+
+        // Tokens of another journal, or from before this one, can't be answered
+        for old in [
+            format!("g:41:{}", start),
+            "g:none:0".into(),
+            "g:42:3".into(),
+        ] {
+            assert_eq!(changes_since(&data, &old), Some((token.clone(), None)));
+        }
+        assert!(changes_since(b"", &token).is_none());
+    }
+}
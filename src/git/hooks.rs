@@ -0,0 +1,295 @@
+use crate::error::{Error, Result};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HookLineKind {
+    /// "$ pre-commit" header written when a hook starts
+    Start,
+    Output,
+    Passed,
+    Failed,
+}
+
+#[derive(Debug, Clone)]
+pub struct HookLine {
+    pub kind: HookLineKind,
+    pub text: String,
+}
+
+/// Hook output shared between the thread running a hook and the UI
+#[derive(Debug, Clone, Default)]
+pub struct HookLog {
+    lines: Arc<Mutex<Vec<HookLine>>>,
+    /// Bumped on every change so the UI knows when to copy the lines
+    version: Arc<AtomicUsize>,
+}
+
+impl HookLog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn lines(&self) -> Vec<HookLine> {
+        self.lines.lock().map(|l| l.clone()).unwrap_or_default()
+    }
+
+    pub fn version(&self) -> usize {
+        self.version.load(Ordering::SeqCst)
+    }
+
+    pub fn clear(&self) {
+        if let Ok(mut lines) = self.lines.lock() {
+            lines.clear();
+        }
+        self.version.fetch_add(1, Ordering::SeqCst);
+    }
+
+    fn push(&self, kind: HookLineKind, text: String) {
+        if let Ok(mut lines) = self.lines.lock() {
+            lines.push(HookLine { kind, text });
+        }
+        self.version.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+/// The hooks directory of a repository and how to run what is in it
+pub struct Hooks {
+    dir: PathBuf,
+    workdir: PathBuf,
+    git_dir: PathBuf,
+    log: HookLog,
+}
+
+impl Hooks {
+    /// Hooks from core.hooksPath, or the hooks directory shared by all worktrees
+    pub fn discover(repo: &git2::Repository, log: HookLog) -> Self {
+        let git_dir = repo.path().to_path_buf();
+        let workdir = repo.workdir().unwrap_or(repo.path()).to_path_buf();
+        let configured = repo
+            .config()
+            .and_then(|c| c.get_path("core.hooksPath"))
+            .ok();
+        let dir = match configured {
+            // Relative paths are relative to where hooks run: the working tree
+            Some(path) => workdir.join(path),
+            None => {
+                let common = std::fs::read_to_string(git_dir.join("commondir"))
+                    .map(|c| git_dir.join(c.trim()))
+                    .unwrap_or_else(|_| git_dir.clone());
+                common.join("hooks")
+            }
+        };
+        Self {
+            dir,
+            workdir,
+            git_dir,
+            log,
+        }
+    }
+
+    pub fn git_dir(&self) -> &Path {
+        &self.git_dir
+    }
+
+    /// Run hook `name` if it is installed; a non-zero exit is an error
+    pub fn run(&self, name: &str, args: &[&str], stdin: Option<&str>) -> Result<()> {
+        let path = self.dir.join(name);
+        if !path.is_file() {
+            return Ok(());
+        }
+        if !is_executable(&path) {
+            self.log.push(
+                HookLineKind::Output,
+                format!(
+                    "hint: the '{}' hook was ignored because it's not set as executable",
+                    name
+                ),
+            );
+            return Ok(());
+        }
+
+        self.log.push(
+            HookLineKind::Start,
+            format!("$ {} {}", name, args.join(" "))
+                .trim_end()
+                .to_string(),
+        );
+        let mut child = Command::new(&path)
+            .args(args)
+            .current_dir(&self.workdir)
+            .env("GIT_DIR", &self.git_dir)
+            .env("GIT_INDEX_FILE", self.git_dir.join("index"))
+            .env("GIT_EDITOR", ":")
+            .stdin(if stdin.is_some() {
+                Stdio::piped()
+            } else {
+                Stdio::null()
+            })
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+
+        // Stream both pipes into the log as lines arrive
+        let readers: Vec<_> = [
+            child
+                .stdout
+                .take()
+                .map(|s| Box::new(s) as Box<dyn std::io::Read + Send>),
+            child
+                .stderr
+                .take()
+                .map(|s| Box::new(s) as Box<dyn std::io::Read + Send>),
+        ]
+        .into_iter()
+        .flatten()
+        .map(|pipe| {
+            let log = self.log.clone();
+            thread::spawn(move || {
+                for line in BufReader::new(pipe).lines().map_while(|l| l.ok()) {
+                    log.push(HookLineKind::Output, line);
+                }
+            })
+        })
+        .collect();
+
+        if let (Some(input), Some(mut pipe)) = (stdin, child.stdin.take()) {
+            // A hook may exit without reading its input
+            let _ = pipe.write_all(input.as_bytes());
+        }
+        let status = child.wait()?;
+        for reader in readers {
+            let _ = reader.join();
+        }
+
+        if status.success() {
+            self.log
+                .push(HookLineKind::Passed, format!("{} passed", name));
+            Ok(())
+        } else {
+            let code = status
+                .code()
+                .map(|c| c.to_string())
+                .unwrap_or_else(|| "signal".to_string());
+            self.log.push(
+                HookLineKind::Failed,
+                format!("{} failed (exit {})", name, code),
+            );
+            Err(Error::Hook(format!("{} hook failed (exit {})", name, code)))
+        }
+    }
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::metadata(path)
+        .map(|m| m.permissions().mode() & 0o111 != 0)
+        .unwrap_or(false)
+}
+
+#[cfg(not(unix))]
+fn is_executable(_path: &Path) -> bool {
+    true
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::fixture::Fixture;
+    use crate::git::{PushRequest, TransferProgress};
+    use std::os::unix::fs::PermissionsExt;
+
+    fn install(dir: &Path, name: &str, script: &str) {
+        std::fs::create_dir_all(dir).unwrap();
+        let path = dir.join(name);
+        std::fs::write(&path, format!("#!/bin/sh\n{}\n", script)).unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+    }
+
+    #[test]
+    fn test_commit_hooks() {
+        let mut fixture = Fixture::new("hooks");
+        fixture
+            .repo
+            .config()
+            .unwrap()
+            .set_str("core.hooksPath", "custom-hooks")
+            .unwrap();
+        let base = fixture.commit("base", &[]);
+        let git = &fixture.repo;
+        let hooks = fixture.dir.join("custom-hooks");
+        install(&hooks, "pre-commit", "echo lint failed >&2; exit 1");
+        install(&hooks, "commit-msg", "echo 'Signed-off-by: Test' >> \"$1\"");
+
+        let log = HookLog::new();
+        let repo = fixture.open().with_hook_log(log.clone());
+        fixture.write("a", "1");
+        repo.stage_file("a").unwrap();
+
+        // A failing pre-commit aborts and its output is kept
+        let err = repo.commit("first", false).unwrap_err();
+        assert!(matches!(err, Error::Hook(_)));
+        assert_eq!(git.head().unwrap().target(), Some(base));
+        let lines = log.lines();
+        assert!(lines.iter().any(|l| l.text == "lint failed"));
+        assert_eq!(lines.last().unwrap().kind, HookLineKind::Failed);
+
+        // Skipping hooks skips commit-msg too
+        let oid = repo.commit("first", true).unwrap();
+        let commit = git.find_commit(git2::Oid::from_str(&oid).unwrap()).unwrap();
+        assert_eq!(commit.message(), Some("first"));
+
+        // commit-msg may rewrite the message
+        install(&hooks, "pre-commit", "exit 0");
+        fixture.write("a", "2");
+        repo.stage_file("a").unwrap();
+        let oid = repo.commit("second", false).unwrap();
+        let commit = git.find_commit(git2::Oid::from_str(&oid).unwrap()).unwrap();
+        assert_eq!(commit.message(), Some("second\nSigned-off-by: Test"));
+    }
+
+    #[test]
+    fn test_pre_push_input() {
+        let mut fixture = Fixture::new("pre-push");
+        fixture.bare_remote("origin");
+        let head = fixture.commit("base", &[("a", "1")]);
+        let repo = fixture.open();
+        repo.push_set_upstream("origin", "main", &TransferProgress::new())
+            .unwrap();
+        let target = fixture.repo.find_object(head, None).unwrap();
+        let signature = fixture.repo.signature().unwrap();
+        let tag = fixture
+            .repo
+            .tag("v1", &target, &signature, "Release", false)
+            .unwrap();
+        install(
+            &fixture.dir.join(".git/hooks"),
+            "pre-push",
+            "cat > \"$GIT_DIR/pushed\"",
+        );
+
+        let request = PushRequest {
+            remote: "origin".to_string(),
+            source: "main".to_string(),
+            destination: "main".to_string(),
+            tags: true,
+            ..Default::default()
+        };
+        repo.push_with(&request, &TransferProgress::new()).unwrap();
+        // The annotated tag is reported with its own id, not the commit's
+        let pushed = std::fs::read_to_string(fixture.dir.join(".git/pushed")).unwrap();
+        let zero = git2::Oid::zero();
+        assert_eq!(
+            pushed,
+            format!(
+                "refs/heads/main {head} refs/heads/main {head}\n\
+                 refs/tags/v1 {tag} refs/tags/v1 {zero}\n"
+            )
+        );
+    }
+}
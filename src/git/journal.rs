@@ -0,0 +1,226 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// Maximum number of operations kept on the undo stack
+const MAX_OPERATIONS: usize = 100;
+
+/// How long to wait for another writer before giving up on the journal
+const LOCK_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// A lock file older than this was left behind by a process that died
+const STALE_LOCK: Duration = Duration::from_secs(30);
+
+/// State of a single reference at one point in time
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RefState {
+    pub name: String,
+    /// Commit id, `ref: <name>` for a symbolic ref, or None when the ref did not exist
+    pub target: Option<String>,
+}
+
+/// A stash commit together with the message it was listed under
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StashRecord {
+    pub id: String,
+    pub message: String,
+}
+
+/// One recorded mutating operation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Operation {
+    pub description: String,
+    pub time: i64,
+    pub before: Vec<RefState>,
+    pub after: Vec<RefState>,
+    /// The operation moved the working tree, so undo/redo has to check out as well
+    pub checkout: bool,
+    /// Stashes removed by the operation (stored again on undo)
+    #[serde(default)]
+    pub dropped_stashes: Vec<StashRecord>,
+    /// Stash created by the operation (popped on undo)
+    #[serde(default)]
+    pub created_stash: Option<StashRecord>,
+}
+
+impl Operation {
+    pub fn is_noop(&self) -> bool {
+        self.before == self.after && self.dropped_stashes.is_empty() && self.created_stash.is_none()
+    }
+}
+
+/// Undo/redo stacks, persisted per repository so background operations are recorded too
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Journal {
+    pub undo: Vec<Operation>,
+    pub redo: Vec<Operation>,
+}
+
+impl Journal {
+    pub fn load(git_dir: &Path) -> Self {
+        std::fs::read_to_string(journal_path(git_dir))
+            .ok()
+            .and_then(|s| serde_json::from_str(&s).ok())
+            .unwrap_or_default()
+    }
+
+    /// Take the journal lock, so that background jobs and the UI thread (or
+    /// two instances on one repository) don't lose each other's entries
+    /// between `load` and `save`
+    pub fn lock(git_dir: &Path) -> std::io::Result<JournalLock> {
+        let path = git_dir.join("g").join("journal.lock");
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let start = SystemTime::now();
+        loop {
+            match std::fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&path)
+            {
+                Ok(_) => return Ok(JournalLock { path }),
+                Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+                    let stale = std::fs::metadata(&path)
+                        .and_then(|m| m.modified())
+                        .ok()
+                        .and_then(|modified| modified.elapsed().ok())
+                        .is_some_and(|age| age > STALE_LOCK);
+                    if stale {
+                        let _ = std::fs::remove_file(&path);
+                        continue;
+                    }
+                    if start.elapsed().unwrap_or_default() > LOCK_TIMEOUT {
+                        return Err(std::io::Error::new(
+                            std::io::ErrorKind::WouldBlock,
+                            format!("{} is held by another process", path.display()),
+                        ));
+                    }
+                    std::thread::sleep(Duration::from_millis(10));
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Write through a temporary file so readers never see a partial journal
+    pub fn save(&self, git_dir: &Path) -> std::io::Result<()> {
+        let path = journal_path(git_dir);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let json = serde_json::to_string(self).map_err(std::io::Error::other)?;
+        let temp = path.with_extension("json.tmp");
+        std::fs::write(&temp, json)?;
+        std::fs::rename(temp, path)
+    }
+
+    /// Push a new operation; any redo history is discarded
+    pub fn record(&mut self, op: Operation) {
+        self.undo.push(op);
+        if self.undo.len() > MAX_OPERATIONS {
+            let excess = self.undo.len() - MAX_OPERATIONS;
+            self.undo.drain(..excess);
+        }
+        self.redo.clear();
+    }
+}
+
+/// Held while the journal is read, changed and written back; removed on drop
+pub struct JournalLock {
+    path: PathBuf,
+}
+
+impl Drop for JournalLock {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+fn journal_path(git_dir: &Path) -> PathBuf {
+    git_dir.join("g").join("journal.json")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fixture::Fixture;
+
+    #[test]
+    fn test_undo_redo_round_trip() {
+        let mut fixture = Fixture::new("journal");
+        let base = fixture.commit("base", &[("a", "1")]);
+        let second = fixture.commit("second", &[("a", "2")]);
+        let mut repo = fixture.open();
+
+        repo.create_branch("feature", None).unwrap();
+        assert_eq!(
+            repo.undo().unwrap().as_deref(),
+            Some("create branch feature")
+        );
+        assert!(fixture
+            .repo
+            .find_branch("feature", git2::BranchType::Local)
+            .is_err());
+        assert_eq!(
+            repo.redo().unwrap().as_deref(),
+            Some("create branch feature")
+        );
+        assert!(fixture
+            .repo
+            .find_branch("feature", git2::BranchType::Local)
+            .is_ok());
+
+        repo.reset_to_commit(&base.to_string(), "hard").unwrap();
+        assert_eq!(fixture.read("a"), "1");
+        repo.undo().unwrap();
+        assert_eq!(fixture.head(), second);
+        assert_eq!(fixture.read("a"), "2");
+        repo.redo().unwrap();
+        assert_eq!(fixture.head(), base);
+        assert_eq!(fixture.read("a"), "1");
+    }
+
+    #[test]
+    fn test_undo_refuses_moved_refs() {
+        let mut fixture = Fixture::new("journal-drift");
+        let base = fixture.commit("base", &[("a", "1")]);
+        let mut repo = fixture.open();
+
+        // Undoing the branch creation would delete the commit made on it since
+        repo.create_branch("feature", None).unwrap();
+        fixture.switch("feature");
+        let work = fixture.commit("work", &[("a", "2")]);
+        let err = repo.undo().unwrap_err();
+        assert!(err.to_string().contains("refs/heads/feature changed since"));
+        let feature = fixture.repo.find_branch("feature", git2::BranchType::Local);
+        assert_eq!(feature.unwrap().get().target(), Some(work));
+        // The entry stays for when the refs are back
+        assert_eq!(
+            repo.last_operation().as_deref(),
+            Some("create branch feature")
+        );
+
+        // A commit made outside after a reset is not thrown away either
+        fixture.switch("main");
+        let second = fixture.commit("second", &[("a", "3")]);
+        repo.reset_to_commit(&base.to_string(), "hard").unwrap();
+        let outside = fixture.commit("outside", &[("a", "4")]);
+        assert!(repo.undo().is_err());
+        assert_eq!(fixture.head(), outside);
+
+        // Undone operations are checked the same way before a redo
+        fixture
+            .repo
+            .reset(
+                &fixture.repo.find_object(base, None).unwrap(),
+                git2::ResetType::Hard,
+                None,
+            )
+            .unwrap();
+        repo.undo().unwrap();
+        assert_eq!(fixture.head(), second);
+        fixture.commit("more", &[("a", "5")]);
+        assert!(repo.redo().is_err());
+        assert_eq!(fixture.read("a"), "5");
+    }
+}
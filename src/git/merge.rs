@@ -0,0 +1,110 @@
+/// How a merge may move HEAD
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MergeMode {
+    /// Fast-forward when possible, otherwise create a merge commit
+    #[default]
+    FastForward,
+    /// Always create a merge commit
+    NoFastForward,
+    /// Refuse unless HEAD can be fast-forwarded
+    FastForwardOnly,
+    /// Stage the combined changes for an ordinary commit, recording no merge
+    Squash,
+}
+
+impl MergeMode {
+    pub const ALL: [MergeMode; 4] = [
+        MergeMode::FastForward,
+        MergeMode::NoFastForward,
+        MergeMode::FastForwardOnly,
+        MergeMode::Squash,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            MergeMode::FastForward => "ff",
+            MergeMode::NoFastForward => "no-ff",
+            MergeMode::FastForwardOnly => "ff-only",
+            MergeMode::Squash => "squash",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct MergeRequest {
+    /// Local branch, remote-tracking branch ("origin/main") or any commit-ish
+    pub target: String,
+    pub mode: MergeMode,
+    /// Commit message; None uses the generated one
+    pub message: Option<String>,
+    /// Skip the pre-merge-commit and commit-msg hooks
+    pub no_verify: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture::Fixture;
+    use crate::git::MergeResult;
+
+    /// "feature" is two commits ahead of main
+    fn setup(name: &str) -> Fixture {
+        let mut fixture = Fixture::new(name);
+        fixture.commit("base", &[("base", "base")]);
+        fixture.branch("feature");
+        fixture.switch("feature");
+        fixture.commit("one", &[("one", "one")]);
+        fixture.commit("two", &[("two", "two")]);
+        fixture.switch("main");
+        fixture
+    }
+
+    #[test]
+    fn test_no_ff_merge_uses_shortlog_message() {
+        let fixture = setup("no-ff");
+        let repo = fixture.open();
+        let message = repo.merge_message("feature").unwrap();
+        assert_eq!(
+            message,
+            "Merge branch 'feature'\n\n* feature:\n  one\n  two"
+        );
+
+        let request = MergeRequest {
+            target: "feature".to_string(),
+            mode: MergeMode::NoFastForward,
+            message: None,
+            ..Default::default()
+        };
+        assert_eq!(repo.merge_with(&request).unwrap(), MergeResult::Merged);
+        let head = fixture.repo.head().unwrap().peel_to_commit().unwrap();
+        assert_eq!(head.parent_count(), 2);
+        assert_eq!(head.message(), Some(message.as_str()));
+    }
+
+    #[test]
+    fn test_squash_merge_stages_without_merge_parent() {
+        let fixture = setup("squash");
+        let repo = fixture.open();
+        let request = MergeRequest {
+            target: "feature".to_string(),
+            mode: MergeMode::Squash,
+            message: Some("Add one and two".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(repo.merge_with(&request).unwrap(), MergeResult::Squashed);
+        assert!(fixture.dir.join("two").exists());
+        assert_eq!(repo.prepared_message().as_deref(), Some("Add one and two"));
+
+        repo.commit("Add one and two", false).unwrap();
+        let head = fixture.repo.head().unwrap().peel_to_commit().unwrap();
+        assert_eq!(head.parent_count(), 1);
+        assert_eq!(repo.prepared_message(), None);
+        let ff_only = MergeRequest {
+            target: "feature".to_string(),
+            mode: MergeMode::FastForwardOnly,
+            message: None,
+            ..Default::default()
+        };
+        assert_eq!(repo.merge_with(&ff_only).unwrap(), MergeResult::Aborted);
+    }
+}
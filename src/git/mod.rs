@@ -2,37 +2,60 @@ mod actions;
 mod blame;
 mod branch;
 mod branchgraph;
+mod checkout;
 mod commit;
 mod conflict;
 mod diff;
 mod filetree;
+mod fsmonitor;
+mod hooks;
 mod issue;
+mod journal;
 mod loggraph;
+mod merge;
+mod patch;
 mod pullrequest;
+mod reflog;
 mod release;
 mod remote;
 mod repository;
 mod stash;
 mod status;
 mod submodule;
+mod summary;
 mod tag;
+mod transfer;
+mod trash;
+mod watcher;
 mod worktree;
 
 pub use actions::WorkflowRun;
 pub use blame::{BlameInfo, BlameLine};
 pub use branch::{BranchInfo, BranchType, UpstreamInfo};
 pub use branchgraph::{BranchGraph, BranchGraphEntry};
+pub use checkout::{CheckoutMode, CheckoutOutcome, CheckoutPreview, CheckoutTarget};
 pub use commit::CommitInfo;
 pub use conflict::{ConflictEntry, ConflictType};
-pub use diff::{DiffInfo, DiffLine, FileDiff, Hunk, LineType};
+pub use diff::{DiffInfo, DiffLine, FileDiff, FileText, Hunk, LineType, Omitted};
 pub use filetree::{FileTreeEntry, FileTreeStatus};
+pub use hooks::{HookLine, HookLineKind, HookLog, Hooks};
 pub use issue::{IssueComment, IssueInfo};
+pub use journal::{Journal, Operation, RefState, StashRecord};
 pub use loggraph::{GraphCommit, GraphLine};
+pub use merge::{MergeMode, MergeRequest};
+pub use patch::{AmOutcome, MailPatch, PatchTarget};
 pub use pullrequest::{PullRequestComment, PullRequestFile, PullRequestInfo};
+pub use reflog::ReflogEntry;
 pub use release::ReleaseInfo;
-pub use repository::{MergeResult, RemoteInfo, Repository};
+pub use repository::{MergeResult, PullOutcome, RemoteInfo, Repository};
 pub use stash::StashEntry;
-pub use status::{FileStatus, StatusEntry};
+pub use status::{merge_status, FileStatus, StatusEntry, StatusQuery, UntrackedFiles};
 pub use submodule::SubmoduleInfo;
+pub use summary::RepoSummary;
 pub use tag::TagInfo;
+pub use transfer::{
+    ProgressSnapshot, PullOptions, PullStrategy, PushRequest, TransferProgress, TransferStage,
+};
+pub use trash::{TrashEntry, TRASH_PREFIX};
+pub use watcher::{RepoChanges, Watcher};
 pub use worktree::WorktreeInfo;
@@ -0,0 +1,555 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Where a patch file is applied
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PatchTarget {
+    #[default]
+    WorkingTree,
+    Index,
+    /// Working tree and index, like `git apply --index`
+    Both,
+}
+
+impl PatchTarget {
+    pub const ALL: [PatchTarget; 3] = [
+        PatchTarget::WorkingTree,
+        PatchTarget::Index,
+        PatchTarget::Both,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            PatchTarget::WorkingTree => "working tree",
+            PatchTarget::Index => "index",
+            PatchTarget::Both => "working tree and index",
+        }
+    }
+
+    pub(crate) fn location(&self) -> git2::ApplyLocation {
+        match self {
+            PatchTarget::WorkingTree => git2::ApplyLocation::WorkDir,
+            PatchTarget::Index => git2::ApplyLocation::Index,
+            PatchTarget::Both => git2::ApplyLocation::Both,
+        }
+    }
+}
+
+/// One message of an mbox patch series
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MailPatch {
+    pub author_name: String,
+    pub author_email: String,
+    /// Author date as seconds since the epoch and offset in minutes
+    pub time: i64,
+    pub offset: i32,
+    /// Subject with the "[PATCH n/m]" prefix removed
+    pub subject: String,
+    pub body: String,
+    /// Everything from the first "diff --git" line
+    pub diff: String,
+}
+
+impl MailPatch {
+    pub fn message(&self) -> String {
+        if self.body.is_empty() {
+            self.subject.clone()
+        } else {
+            format!("{}\n\n{}", self.subject, self.body)
+        }
+    }
+}
+
+/// A patch series being applied as commits; persisted so a conflict can pause it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AmState {
+    /// HEAD before the first patch, restored by abort
+    pub orig_head: String,
+    pub patches: Vec<MailPatch>,
+    /// Index of the patch being applied
+    pub next: usize,
+}
+
+impl AmState {
+    pub fn load(git_dir: &Path) -> Option<Self> {
+        std::fs::read_to_string(am_path(git_dir))
+            .ok()
+            .and_then(|s| serde_json::from_str(&s).ok())
+    }
+
+    pub fn save(&self, git_dir: &Path) -> std::io::Result<()> {
+        let path = am_path(git_dir);
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(path, serde_json::to_string(self)?)
+    }
+
+    pub fn remove(git_dir: &Path) {
+        let _ = std::fs::remove_file(am_path(git_dir));
+    }
+}
+
+fn am_path(git_dir: &Path) -> PathBuf {
+    git_dir.join("g").join("am.json")
+}
+
+/// Result of applying (part of) a patch series
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AmOutcome {
+    pub applied: usize,
+    pub total: usize,
+    /// Subject of the patch that did not apply cleanly; the series is paused on it
+    pub stopped: Option<String>,
+}
+
+impl AmOutcome {
+    pub fn summary(&self) -> String {
+        match &self.stopped {
+            Some(subject) => format!(
+                "Patch {}/{} \"{}\" has conflicts; resolve, then C to continue or A to abort",
+                self.applied + 1,
+                self.total,
+                subject
+            ),
+            None => format!("Applied {} patch(es)", self.applied),
+        }
+    }
+}
+
+/// File name `git format-patch` would use: "0001-fix-the-thing.patch"
+pub fn patch_file_name(number: usize, subject: &str) -> String {
+    let mut slug = String::new();
+    for c in subject.chars() {
+        if c.is_ascii_alphanumeric() || c == '.' || c == '_' {
+            slug.push(c);
+        } else if !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    let slug: String = slug
+        .trim_matches(|c| c == '-' || c == '.')
+        .chars()
+        .take(52)
+        .collect();
+    format!("{:04}-{}.patch", number, slug.trim_end_matches('-'))
+}
+
+/// The diff part of a patch file or mail, without mail headers or signature
+pub fn extract_diff(text: &str) -> &str {
+    let start = if text.starts_with("diff --git") {
+        0
+    } else {
+        text.find("\ndiff --git").map(|i| i + 1).unwrap_or(0)
+    };
+    let diff = &text[start..];
+    // format-patch ends with a "-- " (or libgit2's "--") line and a version
+    let end = ["\n-- \n", "\n--\n"]
+        .iter()
+        .filter_map(|sep| diff.rfind(sep))
+        .max()
+        .filter(|&i| diff[i..].lines().filter(|l| !l.trim().is_empty()).count() <= 2)
+        .map(|i| i + 1)
+        .unwrap_or(diff.len());
+    &diff[..end]
+}
+
+/// Split an mbox (as written by format-patch) into patches
+pub fn parse_mbox(text: &str) -> Vec<MailPatch> {
+    let mut messages: Vec<Vec<&str>> = Vec::new();
+    for line in text.lines() {
+        if is_mbox_separator(line) || messages.is_empty() {
+            messages.push(Vec::new());
+        }
+        if let Some(message) = messages.last_mut() {
+            message.push(line);
+        }
+    }
+    messages
+        .iter()
+        .filter_map(|lines| parse_mail(lines))
+        .collect()
+}
+
+/// "From <40 hex digits> <date>", the line format-patch starts each message
+/// with; a body line like "From what I can tell" is not one
+fn is_mbox_separator(line: &str) -> bool {
+    line.strip_prefix("From ")
+        .and_then(|rest| rest.get(..41))
+        .is_some_and(|id| id.ends_with(' ') && id[..40].bytes().all(|b| b.is_ascii_hexdigit()))
+}
+
+fn parse_mail(lines: &[&str]) -> Option<MailPatch> {
+    let mut headers: Vec<(String, String)> = Vec::new();
+    let mut rest = 0;
+    for (i, line) in lines.iter().enumerate() {
+        if i == 0 && line.starts_with("From ") {
+            continue;
+        }
+        if line.is_empty() {
+            rest = i + 1;
+            break;
+        }
+        if line.starts_with([' ', '\t']) {
+            // Folded header continues the previous one
+            if let Some((_, value)) = headers.last_mut() {
+                value.push(' ');
+                value.push_str(line.trim());
+            }
+        } else if let Some((name, value)) = line.split_once(':') {
+            headers.push((name.to_ascii_lowercase(), value.trim().to_string()));
+        }
+    }
+    let header = |name: &str| {
+        headers
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    };
+
+    let (author_name, author_email) = parse_address(&decode_header(header("from")?));
+    let date = header("date")
+        .and_then(|d| chrono::DateTime::parse_from_rfc2822(d).ok())
+        .unwrap_or_else(|| chrono::Utc::now().fixed_offset());
+    let subject = strip_patch_prefix(&decode_header(header("subject").unwrap_or_default()));
+
+    let charset = header("content-type")
+        .and_then(|value| {
+            value
+                .split(';')
+                .find_map(|p| p.trim().strip_prefix("charset="))
+        })
+        .map(|charset| charset.trim_matches('"'))
+        .unwrap_or("utf-8");
+    let text = lines[rest.min(lines.len())..].join("\n") + "\n";
+    let text = match header("content-transfer-encoding").map(str::to_ascii_lowercase) {
+        Some(encoding) if encoding == "quoted-printable" => {
+            decode_charset(&decode_quoted_printable(&text), charset)
+        }
+        Some(encoding) if encoding == "base64" => match decode_base64(&text) {
+            Some(bytes) => decode_charset(&bytes, charset),
+            None => text,
+        },
+        _ => text,
+    };
+    let diff = extract_diff(&text).to_string();
+    if diff.is_empty() || !diff.starts_with("diff --git") {
+        return None;
+    }
+    // The body ends at the "---" line before the diffstat
+    let padded = format!("\n{}", text);
+    let body_end = padded
+        .find("\n---\n")
+        .or(padded.find("\ndiff --git"))
+        .unwrap_or(0);
+    let body = padded[..body_end].trim().to_string();
+
+    Some(MailPatch {
+        author_name,
+        author_email,
+        time: date.timestamp(),
+        offset: date.offset().local_minus_utc() / 60,
+        subject,
+        body,
+        diff,
+    })
+}
+
+/// Decode the RFC 2047 encoded-words in a header:
+/// "=?UTF-8?q?J=C3=B6ran?= <j@example.com>" -> "Jöran <j@example.com>"
+fn decode_header(value: &str) -> String {
+    let mut decoded = String::new();
+    let mut rest = value;
+    let mut after_word = false;
+    while let Some(start) = rest.find("=?") {
+        let (before, candidate) = rest.split_at(start);
+        match decode_word(candidate) {
+            Some((text, len)) => {
+                // Whitespace between two encoded-words (a folded header) is dropped
+                if !(after_word && before.trim().is_empty()) {
+                    decoded.push_str(before);
+                }
+                decoded.push_str(&text);
+                rest = &candidate[len..];
+                after_word = true;
+            }
+            None => {
+                decoded.push_str(before);
+                decoded.push_str("=?");
+                rest = &candidate[2..];
+                after_word = false;
+            }
+        }
+    }
+    decoded.push_str(rest);
+    decoded
+}
+
+/// The "=?charset?encoding?text?=" word `value` starts with, decoded, and its length
+fn decode_word(value: &str) -> Option<(String, usize)> {
+    let mut parts = value[2..].splitn(3, '?');
+    let charset = parts.next()?;
+    let encoding = parts.next()?;
+    let rest = parts.next()?;
+    let end = rest.find("?=")?;
+    let text = &rest[..end];
+    if text.contains(char::is_whitespace) {
+        return None;
+    }
+    let bytes = match encoding {
+        "Q" | "q" => decode_quoted_printable(&text.replace('_', " ")),
+        "B" | "b" => decode_base64(text)?,
+        _ => return None,
+    };
+    let len = 2 + charset.len() + 1 + encoding.len() + 1 + end + 2;
+    Some((decode_charset(&bytes, charset), len))
+}
+
+/// "caf=C3=A9" -> "café" bytes; "=" at the end of a line is a soft line break
+fn decode_quoted_printable(text: &str) -> Vec<u8> {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] != b'=' {
+            decoded.push(bytes[i]);
+            i += 1;
+            continue;
+        }
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        if let Some(byte) = hex {
+            decoded.push(byte);
+            i += 3;
+        } else if bytes[i + 1..].starts_with(b"\r\n") {
+            i += 3;
+        } else if bytes[i + 1..].starts_with(b"\n") {
+            i += 2;
+        } else {
+            decoded.push(b'=');
+            i += 1;
+        }
+    }
+    decoded
+}
+
+/// Decode base64, ignoring line breaks; None if `text` is not base64
+fn decode_base64(text: &str) -> Option<Vec<u8>> {
+    let mut decoded = Vec::with_capacity(text.len() * 3 / 4);
+    let mut buffer = 0u32;
+    let mut bits = 0;
+    for c in text.bytes() {
+        let value = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            b'=' => break,
+            c if c.is_ascii_whitespace() => continue,
+            _ => return None,
+        };
+        buffer = (buffer << 6) | value as u32;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            decoded.push((buffer >> bits) as u8);
+        }
+    }
+    Some(decoded)
+}
+
+/// Text from `bytes` in `charset`; Latin-1 is mapped, anything else read as UTF-8
+fn decode_charset(bytes: &[u8], charset: &str) -> String {
+    let charset = charset.split('*').next().unwrap_or_default();
+    if ["iso-8859-1", "latin1"]
+        .iter()
+        .any(|name| charset.eq_ignore_ascii_case(name))
+    {
+        bytes.iter().map(|&b| b as char).collect()
+    } else {
+        String::from_utf8_lossy(bytes).into_owned()
+    }
+}
+
+/// "Jane Doe <jane@example.com>" -> ("Jane Doe", "jane@example.com")
+fn parse_address(value: &str) -> (String, String) {
+    match (value.rfind('<'), value.rfind('>')) {
+        (Some(start), Some(end)) if start < end => (
+            value[..start].trim().trim_matches('"').to_string(),
+            value[start + 1..end].to_string(),
+        ),
+        _ => (value.to_string(), value.to_string()),
+    }
+}
+
+/// "[PATCH 2/3] Fix it" -> "Fix it"
+fn strip_patch_prefix(subject: &str) -> String {
+    let subject = subject.trim();
+    match (subject.starts_with('['), subject.find(']')) {
+        (true, Some(end)) => subject[end + 1..].trim().to_string(),
+        _ => subject.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_mbox() {
+        let mbox = "From 1234567890123456789012345678901234567890 Mon Sep 17 00:00:00 2001\n\
+            From: Jane Doe <jane@example.com>\n\
+            Date: Tue, 2 Jan 2024 10:00:00 +0100\n\
+            Subject: [PATCH 1/2] Add a\n\
+            \n\
+            Longer description.\n\
+            From what I can tell, this line is long enough to look like a separator.\n\
+            ---\n \
+            a | 1 +\n\
+            \n\
+            diff --git a/a b/a\n\
+            new file mode 100644\n\
+            --- /dev/null\n\
+            +++ b/a\n\
+            @@ -0,0 +1 @@\n\
+            +a\n\
+            -- \n\
+            2.43.0\n\
+            \n\
+            From 2234567890123456789012345678901234567890 Mon Sep 17 00:00:00 2001\n\
+            From: Joe <joe@example.com>\n\
+            Subject: [PATCH 2/2] Add b\n \
+            with a folded subject\n\
+            \n\
+            ---\n\
+            diff --git a/b b/b\n\
+            new file mode 100644\n\
+            --- /dev/null\n\
+            +++ b/b\n\
+            @@ -0,0 +1 @@\n\
+            +b\n";
+        let patches = parse_mbox(mbox);
+        assert_eq!(patches.len(), 2);
+        assert_eq!(patches[0].author_name, "Jane Doe");
+        assert_eq!(patches[0].author_email, "jane@example.com");
+        assert_eq!(patches[0].offset, 60);
+        assert_eq!(
+            patches[0].message(),
+            "Add a\n\nLonger description.\n\
+             From what I can tell, this line is long enough to look like a separator."
+        );
+        assert!(patches[0].diff.ends_with("+a\n"));
+        assert_eq!(patches[1].message(), "Add b with a folded subject");
+        assert_eq!(
+            patch_file_name(1, "Fix: the thing (again)"),
+            "0001-Fix-the-thing-again.patch"
+        );
+    }
+
+    /// `git format-patch` output for a commit by an author with a non-ASCII name
+    const ENCODED_MAIL: &str = "\
+From d19e6c371ed0d4a6304d3c10c299209a8554d7c0 Mon Sep 17 00:00:00 2001
+From: =?UTF-8?q?J=C3=B6ran=20=C5=81ukasz?= <joran@example.com>
+Date: Tue, 2 Jan 2024 10:00:00 +0100
+Subject: [PATCH] =?UTF-8?q?=C3=9Cbersetze=20die=20Beschreibung=20vom=20Caf?=
+ =?UTF-8?q?=C3=A9=20in=20der=20=C3=9Cbersicht=20ins=20Deutsche?=
+MIME-Version: 1.0
+Content-Type: text/plain; charset=UTF-8
+Content-Transfer-Encoding: 8bit
+
+Der Körper erwähnt naïve Änderungen.
+---
+ a | 2 +-
+ 1 file changed, 1 insertion(+), 1 deletion(-)
+
+diff --git a/a b/a
+index 7898192..572eb43 100644
+--- a/a
++++ b/a
+@@ -1 +1 @@
+-a
++café
+-- 
+2.39.5
+";
+
+    #[test]
+    fn test_parse_encoded_mail() {
+        let patches = parse_mbox(ENCODED_MAIL);
+        assert_eq!(patches.len(), 1);
+        let patch = &patches[0];
+        assert_eq!(patch.author_name, "Jöran Łukasz");
+        assert_eq!(patch.author_email, "joran@example.com");
+        assert_eq!(
+            patch.subject,
+            "Übersetze die Beschreibung vom Café in der Übersicht ins Deutsche"
+        );
+        assert_eq!(patch.body, "Der Körper erwähnt naïve Änderungen.");
+        assert!(patch.diff.ends_with("+café\n"));
+
+        // The same mail as a mailer sending 7bit would encode its body
+        let (headers, _) = ENCODED_MAIL.split_once("\n\n").unwrap();
+        let quoted = "Der K=C3=B6rper erw=C3=A4hnt na=C3=AFve =C3=84nderungen.\n\
+            ---\n a | 2 +-\n 1 file changed, 1 insertion(+), 1 deletion(-)\n\n\
+            diff --git a/a b/a\nindex 7898192..572eb43 100644\n--- a/a\n+++ b/a\n\
+            @@ -1 +1 @@\n-a\n+caf=C3=\n=A9\n--=20\n2.39.5\n";
+        let base64 =
+            "RGVyIEvDtnJwZXIgZXJ3w6RobnQgbmHDr3ZlIMOEbmRlcnVuZ2VuLgotLS0KIGEgfCAyICstCiAx\n\
+            IGZpbGUgY2hhbmdlZCwgMSBpbnNlcnRpb24oKyksIDEgZGVsZXRpb24oLSkKCmRpZmYgLS1naXQg\n\
+            YS9hIGIvYQppbmRleCA3ODk4MTkyLi41NzJlYjQzIDEwMDY0NAotLS0gYS9hCisrKyBiL2EKQEAg\n\
+            LTEgKzEgQEAKLWEKK2NhZsOpCi0tIAoyLjM5LjUKCg==\n";
+        for (encoding, body) in [("quoted-printable", quoted), ("base64", base64)] {
+            let mail = format!("{}\n\n{}", headers.replace("8bit", encoding), body);
+            assert_eq!(parse_mbox(&mail), patches, "{}", encoding);
+        }
+    }
+
+    #[test]
+    fn test_am_round_trip() {
+        use crate::fixture::Fixture;
+
+        let mut fixture = Fixture::new("am");
+        let repo = fixture.open();
+        let base = fixture.commit("Change a", &[("a", "1\n2\n3\n")]);
+        let jane = git2::Signature::new(
+            "Jane",
+            "jane@example.com",
+            &git2::Time::new(1_700_000_000, 60),
+        )
+        .unwrap();
+        let change = fixture.commit_as("Change a", &[("a", "1\ntwo\n3\n")], &jane);
+
+        let patches = repo.format_patches(&[change.to_string()]).unwrap();
+        let mbox: String = patches.into_iter().map(|(_, mail)| mail).collect();
+
+        // Applied as a commit that keeps its author and date
+        repo.reset_to_commit(&base.to_string(), "hard").unwrap();
+        let outcome = repo.apply_mailbox(&mbox).unwrap();
+        assert_eq!(outcome.stopped, None);
+        {
+            let head = fixture.repo.head().unwrap().peel_to_commit().unwrap();
+            assert_eq!(head.author().name(), Some("Jane"));
+            assert_eq!(head.author().when().seconds(), 1_700_000_000);
+            assert_eq!(
+                head.tree_id(),
+                fixture.repo.find_commit(change).unwrap().tree_id()
+            );
+        }
+
+        // A conflicting patch pauses the series until it is aborted
+        repo.reset_to_commit(&base.to_string(), "hard").unwrap();
+        let other = fixture.commit("Change a", &[("a", "1\nzwei\n3\n")]);
+        let outcome = repo.apply_mailbox(&mbox).unwrap();
+        assert_eq!(outcome.stopped.as_deref(), Some("Change a"));
+        assert!(repo.am_in_progress());
+        let mut index = fixture.repo.index().unwrap();
+        index.read(true).unwrap();
+        assert!(index.has_conflicts());
+        repo.abort_am().unwrap();
+        assert!(!repo.am_in_progress());
+        assert_eq!(fixture.head(), other);
+    }
+}
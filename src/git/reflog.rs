@@ -0,0 +1,11 @@
+#[derive(Debug, Clone)]
+pub struct ReflogEntry {
+    /// Position in the reflog (0 is the most recent, as in `HEAD@{0}`)
+    pub index: usize,
+    pub id: String,
+    pub short_id: String,
+    pub old_id: String,
+    pub message: String,
+    pub committer: String,
+    pub time: i64,
+}
@@ -1,41 +1,125 @@
 use super::blame::{BlameInfo, BlameLine};
 use super::branch::{BranchInfo, BranchType, UpstreamInfo};
+use super::checkout::{CheckoutMode, CheckoutOutcome, CheckoutPreview, CheckoutTarget};
 use super::commit::CommitInfo;
 use super::conflict::{ConflictEntry, ConflictType};
-use super::diff::{DiffInfo, DiffLine, FileDiff, Hunk, LineType};
+use super::diff::{DiffInfo, DiffLine, FileDiff, FileText, Hunk, LineType, Omitted};
 use super::filetree::{FileTreeEntry, FileTreeStatus};
+use super::hooks::{HookLog, Hooks};
+use super::journal::{Journal, Operation, RefState, StashRecord};
 use super::loggraph::{GraphCommit, GraphLine};
+use super::merge::{MergeMode, MergeRequest};
+use super::patch::{
+    extract_diff, parse_mbox, patch_file_name, AmOutcome, AmState, MailPatch, PatchTarget,
+};
+use super::reflog::ReflogEntry;
 use super::stash::StashEntry;
-use super::status::{FileStatus, StatusEntry};
+use super::status::{parse_porcelain_v2, FileStatus, StatusEntry, StatusQuery, UntrackedFiles};
 use super::submodule::SubmoduleInfo;
+use super::summary::RepoSummary;
 use super::tag::TagInfo;
+use super::transfer::{PullOptions, PullStrategy, PushRequest, TransferProgress};
+use super::trash::{TrashEntry, TRASH_PREFIX};
+use super::watcher::{RepoChanges, Watcher};
 use super::worktree::WorktreeInfo;
 use crate::error::{Error, Result};
+use crate::jobs::CancelToken;
 use git2::{Repository as Git2Repository, Signature};
 use std::path::{Path, PathBuf};
 
 pub struct Repository {
     repo: Git2Repository,
     path: PathBuf,
+    hook_log: HookLog,
+    /// Checked between steps of long walks
+    cancel: CancelToken,
+    /// Files bigger than this (bytes) are left out of diffs and previews
+    size_limit: Option<u64>,
 }
 
 impl Repository {
     pub fn discover() -> Result<Self> {
-        let repo = Git2Repository::discover(".")?;
+        Self::discover_from(".")
+    }
+
+    /// Find the repository containing `dir`, like `git -C <dir>`
+    pub fn discover_from<P: AsRef<Path>>(dir: P) -> Result<Self> {
+        let repo = Git2Repository::discover(dir.as_ref())?;
         let path = repo.workdir().unwrap_or_else(|| repo.path()).to_path_buf();
-        Ok(Self { repo, path })
+        Ok(Self {
+            repo,
+            path,
+            hook_log: HookLog::new(),
+            cancel: CancelToken::new(),
+            size_limit: None,
+        })
     }
 
     pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
         let repo = Git2Repository::open(path.as_ref())?;
         let path = repo.workdir().unwrap_or_else(|| repo.path()).to_path_buf();
-        Ok(Self { repo, path })
+        Ok(Self {
+            repo,
+            path,
+            hook_log: HookLog::new(),
+            cancel: CancelToken::new(),
+            size_limit: None,
+        })
+    }
+
+    /// Send the output of hooks run through this repository to `log`
+    pub fn with_hook_log(mut self, log: HookLog) -> Self {
+        self.hook_log = log;
+        self
+    }
+
+    /// Stop long walks with "Operation cancelled" once `token` is cancelled
+    pub fn with_cancel(mut self, token: CancelToken) -> Self {
+        self.cancel = token;
+        self
+    }
+
+    /// Leave files bigger than `limit` bytes out of diffs and file previews
+    pub fn with_size_limit(mut self, limit: Option<u64>) -> Self {
+        self.size_limit = limit;
+        self
+    }
+
+    fn check_cancelled(&self) -> Result<()> {
+        if self.cancel.is_cancelled() {
+            return Err(Error::Git(git2::Error::from_str("Operation cancelled")));
+        }
+        Ok(())
+    }
+
+    fn hooks(&self) -> Hooks {
+        Hooks::discover(&self.repo, self.hook_log.clone())
     }
 
     pub fn path(&self) -> &Path {
         &self.path
     }
 
+    /// Watch the working tree and refs for changes made outside the app
+    pub fn watch<F>(&self, on_change: F) -> Option<Watcher>
+    where
+        F: FnMut(RepoChanges) -> bool + Send + 'static,
+    {
+        Watcher::start(&self.path, self.repo.path(), on_change)
+    }
+
+    /// `path` relative to the working tree root, with `/` separators; None if outside it
+    pub fn relative_path(&self, path: &Path) -> Option<String> {
+        let root = self.path.canonicalize().ok()?;
+        // A deleted file cannot be canonicalized, but its directory usually can
+        let full = match path.canonicalize() {
+            Ok(full) => full,
+            Err(_) => path.parent()?.canonicalize().ok()?.join(path.file_name()?),
+        };
+        let relative = full.strip_prefix(root).ok()?;
+        Some(relative.to_string_lossy().replace('\\', "/"))
+    }
+
     pub fn name(&self) -> String {
         self.path
             .file_name()
@@ -65,6 +149,21 @@ impl Repository {
         Ok(statuses.is_empty())
     }
 
+    /// Branch, dirty state, ahead/behind and open pull requests, for the dashboard
+    pub fn summary(&self) -> RepoSummary {
+        let (ahead, behind) = self.ahead_behind().unwrap_or((0, 0));
+        let open_prs = self.open_pull_request_count();
+        RepoSummary {
+            path: self.path.clone(),
+            branch: self.head_name().ok().flatten(),
+            dirty: is_dirty(&self.repo),
+            ahead,
+            behind,
+            open_prs,
+            error: None,
+        }
+    }
+
     pub fn ahead_behind(&self) -> Result<(usize, usize)> {
         let head = match self.repo.head() {
             Ok(h) => h,
@@ -178,7 +277,7 @@ impl Repository {
         }
 
         // Sort branches by commit time (most recent first) to match graph view order
-        branches.sort_by(|a, b| b.last_commit.time.cmp(&a.last_commit.time));
+        branches.sort_by_key(|b| std::cmp::Reverse(b.last_commit.time));
 
         Ok(branches)
     }
@@ -208,7 +307,9 @@ impl Repository {
             None => self.repo.head()?.peel_to_commit()?,
         };
 
+        let before = self.journal_begin(&[format!("refs/heads/{}", name)]);
         self.repo.branch(name, &commit, false)?;
+        self.journal_end(format!("create branch {}", name), before, false);
         Ok(())
     }
 
@@ -226,9 +327,13 @@ impl Repository {
                 ))));
             };
 
+        let refname = branch.get().name().unwrap_or_default().to_string();
+        let before = self.journal_begin(&[refname]);
+
         if is_remote {
             // Remote tracking branch - just delete it
             branch.delete()?;
+            self.journal_end(format!("delete branch {}", name), before, false);
             Ok(())
         } else {
             // Local branch deletion
@@ -258,6 +363,7 @@ impl Repository {
             }
 
             branch.delete()?;
+            self.journal_end(format!("delete branch {}", name), before, false);
             Ok(())
         }
     }
@@ -360,67 +466,223 @@ impl Repository {
         Ok((local_merged, remote_merged))
     }
 
-    pub fn switch_branch(&self, name: &str, branch_type: BranchType) -> Result<()> {
-        match branch_type {
-            BranchType::Local => {
-                // Local branch - switch directly
-                let refname = format!("refs/heads/{}", name);
-                let obj = self.repo.revparse_single(&refname)?;
-                self.repo.checkout_tree(&obj, None)?;
-                self.repo.set_head(&refname)?;
-            }
-            BranchType::Remote => {
-                // Remote branch (e.g., "origin/feature-branch")
-                // Extract local branch name from remote ref (strip remote name prefix)
-                let local_name = if let Some(slash_pos) = name.find('/') {
-                    &name[slash_pos + 1..]
-                } else {
-                    name
-                };
+    pub fn checkout(
+        &mut self,
+        target: &CheckoutTarget,
+        mode: CheckoutMode,
+    ) -> Result<CheckoutOutcome> {
+        match target {
+            CheckoutTarget::Branch(name, branch_type) => {
+                self.switch_branch(name, *branch_type, mode)
+            }
+            CheckoutTarget::Commit(id) => self.checkout_commit(id, mode),
+        }
+    }
+
+    pub fn switch_branch(
+        &mut self,
+        name: &str,
+        branch_type: BranchType,
+        mode: CheckoutMode,
+    ) -> Result<CheckoutOutcome> {
+        let local_name = match branch_type {
+            BranchType::Local => name,
+            BranchType::Remote => name.split_once('/').map(|(_, b)| b).unwrap_or(name),
+        }
+        .to_string();
+        let refname = format!("refs/heads/{}", local_name);
+        let before = self.journal_begin(std::slice::from_ref(&refname));
+
+        let has_local = self
+            .repo
+            .find_branch(&local_name, git2::BranchType::Local)
+            .is_ok();
+        let outcome = if branch_type == BranchType::Local || has_local {
+            let id = self.repo.revparse_single(&refname)?.peel_to_commit()?.id();
+            self.checkout_to(id, mode, &format!("switch to {}", local_name), |repo| {
+                repo.set_head(&refname)
+            })?
+        } else {
+            // New local branch tracking the remote one
+            let id = self
+                .repo
+                .find_branch(name, git2::BranchType::Remote)?
+                .get()
+                .peel_to_commit()?
+                .id();
+            self.checkout_to(id, mode, &format!("switch to {}", local_name), |repo| {
+                let commit = repo.find_commit(id)?;
+                let mut branch = repo.branch(&local_name, &commit, false)?;
+                branch.set_upstream(Some(name))?;
+                repo.set_head(&refname)
+            })?
+        };
+        self.journal_end(format!("switch to {}", name), before, true);
+        Ok(outcome)
+    }
+
+    pub fn rename_branch(&self, old_name: &str, new_name: &str) -> Result<()> {
+        let before = self.journal_begin(&[
+            format!("refs/heads/{}", old_name),
+            format!("refs/heads/{}", new_name),
+        ]);
+        let mut branch = self.repo.find_branch(old_name, git2::BranchType::Local)?;
+        branch.rename(new_name, false)?;
+        self.journal_end(
+            format!("rename branch {} to {}", old_name, new_name),
+            before,
+            false,
+        );
+        Ok(())
+    }
+
+    pub fn checkout_commit(
+        &mut self,
+        commit_id: &str,
+        mode: CheckoutMode,
+    ) -> Result<CheckoutOutcome> {
+        let id = self.repo.revparse_single(commit_id)?.peel_to_commit()?.id();
+
+        let before = self.journal_begin(&[]);
+        let outcome = self.checkout_to(id, mode, &format!("checkout {}", commit_id), |repo| {
+            repo.set_head_detached(id)
+        })?;
+        self.journal_end(format!("checkout {}", commit_id), before, true);
+        Ok(outcome)
+    }
 
-                // Check if a local branch with that name already exists
+    /// Uncommitted changes, and which of them checking out `target` would overwrite
+    pub fn checkout_preview(&self, target: &CheckoutTarget) -> Result<CheckoutPreview> {
+        let mut opts = git2::StatusOptions::new();
+        opts.include_untracked(false).include_ignored(false);
+        let changed: Vec<String> = self
+            .repo
+            .statuses(Some(&mut opts))?
+            .iter()
+            .filter_map(|e| e.path().map(String::from))
+            .collect();
+        if changed.is_empty() {
+            return Ok(CheckoutPreview::default());
+        }
+
+        let spec = match target {
+            CheckoutTarget::Branch(name, BranchType::Local) => format!("refs/heads/{}", name),
+            CheckoutTarget::Branch(name, BranchType::Remote) => {
+                let local = name.split_once('/').map(|(_, b)| b).unwrap_or(name);
                 if self
                     .repo
-                    .find_branch(local_name, git2::BranchType::Local)
+                    .find_branch(local, git2::BranchType::Local)
                     .is_ok()
                 {
-                    // Local branch exists, switch to it
-                    let refname = format!("refs/heads/{}", local_name);
-                    let obj = self.repo.revparse_single(&refname)?;
-                    self.repo.checkout_tree(&obj, None)?;
-                    self.repo.set_head(&refname)?;
+                    format!("refs/heads/{}", local)
                 } else {
-                    // Create local branch from remote and switch to it
-                    // Use git checkout -b which sets up tracking automatically
-                    let output = std::process::Command::new("git")
-                        .args(["checkout", "-b", local_name, name])
-                        .current_dir(&self.path)
-                        .output()
-                        .map_err(Error::Io)?;
-
-                    if !output.status.success() {
-                        let stderr = String::from_utf8_lossy(&output.stderr);
-                        return Err(Error::Git(git2::Error::from_str(&stderr)));
-                    }
+                    format!("refs/remotes/{}", name)
                 }
             }
-        }
-        Ok(())
+            CheckoutTarget::Commit(id) => id.clone(),
+        };
+        let commit = self.repo.revparse_single(&spec)?.peel_to_commit()?;
+        Ok(CheckoutPreview {
+            blocking: self.checkout_conflicts(&commit)?,
+            changed,
+        })
     }
 
-    pub fn rename_branch(&self, old_name: &str, new_name: &str) -> Result<()> {
-        let mut branch = self.repo.find_branch(old_name, git2::BranchType::Local)?;
-        branch.rename(new_name, false)?;
-        Ok(())
+    /// Paths a safe checkout of `commit` would refuse to overwrite (dry run)
+    fn checkout_conflicts(&self, commit: &git2::Commit) -> Result<Vec<String>> {
+        let mut paths = Vec::new();
+        {
+            let mut opts = git2::build::CheckoutBuilder::new();
+            opts.safe()
+                .dry_run()
+                .notify_on(git2::CheckoutNotificationType::CONFLICT)
+                .notify(|_, path, _, _, _| {
+                    if let Some(path) = path {
+                        paths.push(path.display().to_string());
+                    }
+                    true
+                });
+            // A dry run reports conflicts through the callback; the error adds nothing
+            let _ = self.repo.checkout_tree(commit.as_object(), Some(&mut opts));
+        }
+        Ok(paths)
     }
 
-    pub fn checkout_commit(&self, commit_id: &str) -> Result<()> {
-        let obj = self.repo.revparse_single(commit_id)?;
-        let commit = obj.peel_to_commit()?;
+    /// Safely check out commit `id`, then let `set_head` move HEAD; with
+    /// `CheckoutMode::Stash` local changes are stashed around the switch
+    fn checkout_to(
+        &mut self,
+        id: git2::Oid,
+        mode: CheckoutMode,
+        description: &str,
+        set_head: impl FnOnce(&Git2Repository) -> std::result::Result<(), git2::Error>,
+    ) -> Result<CheckoutOutcome> {
+        let autostash = if mode == CheckoutMode::Stash && has_tracked_changes(&self.repo) {
+            let signature = self.repo.signature()?;
+            let message = format!("autostash before {}", description);
+            Some(self.repo.stash_save(&signature, &message, None)?)
+        } else {
+            None
+        };
 
-        self.repo.checkout_tree(&commit.into_object(), None)?;
-        self.repo.set_head_detached(obj.id())?;
-        Ok(())
+        let commit = self.repo.find_commit(id)?;
+        let mut opts = git2::build::CheckoutBuilder::new();
+        opts.safe();
+        let result = self
+            .repo
+            .checkout_tree(commit.as_object(), Some(&mut opts))
+            .and_then(|()| set_head(&self.repo));
+        if let Err(e) = result {
+            let blocking = if e.code() == git2::ErrorCode::Conflict {
+                self.checkout_conflicts(&commit)?
+            } else {
+                Vec::new()
+            };
+            drop(commit);
+            if let Some(stash) = autostash {
+                self.restore_autostash(stash);
+            }
+            if blocking.is_empty() {
+                return Err(e.into());
+            }
+            return Err(Error::Git(git2::Error::new(
+                git2::ErrorCode::Conflict,
+                git2::ErrorClass::Checkout,
+                format!(
+                    "Local changes would be overwritten: {}",
+                    blocking.join(", ")
+                ),
+            )));
+        }
+        drop(commit);
+
+        let stash_kept = autostash.is_some_and(|stash| self.restore_autostash(stash));
+        Ok(CheckoutOutcome { stash_kept })
+    }
+
+    /// Re-apply the autostash `stash`, dropping it only if it applied without
+    /// conflicts (libgit2's pop drops it even when conflict markers were
+    /// written). It is looked up by id, since a hook or another process may
+    /// have stashed on top meanwhile. Returns whether the stash was kept.
+    fn restore_autostash(&mut self, stash: git2::Oid) -> bool {
+        let index = match self.stash_index_of(&stash.to_string()) {
+            Some(index) => index,
+            None => {
+                crate::debug!("autostash {} is no longer in the stash list", stash);
+                return false;
+            }
+        };
+        let applied = self.repo.stash_apply(index, None).is_ok();
+        let clean = applied
+            && !self
+                .repo
+                .index()
+                .map(|index| index.has_conflicts())
+                .unwrap_or(true);
+        if clean {
+            let _ = self.repo.stash_drop(index);
+        }
+        !clean
     }
 
     pub fn revert_commit(&self, commit_id: &str) -> Result<()> {
@@ -438,6 +700,7 @@ impl Repository {
         let obj = self.repo.revparse_single(commit_id)?;
         let commit = obj.peel_to_commit()?;
 
+        let description = format!("reset --{} {}", reset_type, commit_id);
         let reset_type = match reset_type {
             "soft" => git2::ResetType::Soft,
             "mixed" => git2::ResetType::Mixed,
@@ -445,13 +708,84 @@ impl Repository {
             _ => git2::ResetType::Mixed,
         };
 
+        if reset_type == git2::ResetType::Hard {
+            self.trash_snapshot(&description, &self.changed_paths(false)?)?;
+        }
+        let before = self.journal_begin(&[]);
         self.repo.reset(commit.as_object(), reset_type, None)?;
+        self.journal_end(description, before, reset_type == git2::ResetType::Hard);
         Ok(())
     }
 
     // Commit operations
     pub fn commits(&self, max_count: usize) -> Result<Vec<CommitInfo>> {
-        // Build a map of commit ID -> refs (branches/tags)
+        let ref_map = self.commit_ref_map();
+        let mut revwalk = self.repo.revwalk()?;
+
+        // Push all branches (local and remote) to include all commits
+        for (branch, _) in (self.repo.branches(None)?).flatten() {
+            if let Some(oid) = branch.get().target() {
+                let _ = revwalk.push(oid);
+            }
+        }
+
+        // Also push HEAD in case it's detached
+        let _ = revwalk.push_head();
+
+        revwalk.set_sorting(git2::Sort::TIME)?;
+
+        let mut commits = Vec::with_capacity(max_count);
+
+        for (i, oid) in revwalk.enumerate() {
+            if i >= max_count {
+                break;
+            }
+            self.check_cancelled()?;
+
+            let oid = oid?;
+            let commit = self.repo.find_commit(oid)?;
+            let refs = ref_map.get(&oid.to_string()).cloned().unwrap_or_default();
+            commits.push(CommitInfo::from_commit(&commit).with_refs(refs));
+        }
+
+        Ok(commits)
+    }
+
+    /// Commits on HEAD that changed `path` (a file or directory), like `git log -- <path>`
+    pub fn file_commits(&self, path: &str, max_count: usize) -> Result<Vec<CommitInfo>> {
+        let ref_map = self.commit_ref_map();
+        let mut revwalk = self.repo.revwalk()?;
+        revwalk.push_head()?;
+        revwalk.set_sorting(git2::Sort::TIME)?;
+
+        let mut opts = git2::DiffOptions::new();
+        opts.pathspec(path);
+        let mut commits = Vec::new();
+        for oid in revwalk {
+            if commits.len() >= max_count {
+                break;
+            }
+            self.check_cancelled()?;
+            let commit = self.repo.find_commit(oid?)?;
+            let parent_tree = commit.parent(0).ok().map(|p| p.tree()).transpose()?;
+            let diff = self.repo.diff_tree_to_tree(
+                parent_tree.as_ref(),
+                Some(&commit.tree()?),
+                Some(&mut opts),
+            )?;
+            if diff.deltas().len() > 0 {
+                let refs = ref_map
+                    .get(&commit.id().to_string())
+                    .cloned()
+                    .unwrap_or_default();
+                commits.push(CommitInfo::from_commit(&commit).with_refs(refs));
+            }
+        }
+        Ok(commits)
+    }
+
+    /// Map of commit ID -> branch and tag names pointing at it
+    fn commit_ref_map(&self) -> std::collections::HashMap<String, Vec<String>> {
         let mut ref_map: std::collections::HashMap<String, Vec<String>> =
             std::collections::HashMap::new();
 
@@ -485,34 +819,7 @@ impl Repository {
             }
         }
 
-        let mut revwalk = self.repo.revwalk()?;
-
-        // Push all branches (local and remote) to include all commits
-        for (branch, _) in (self.repo.branches(None)?).flatten() {
-            if let Some(oid) = branch.get().target() {
-                let _ = revwalk.push(oid);
-            }
-        }
-
-        // Also push HEAD in case it's detached
-        let _ = revwalk.push_head();
-
-        revwalk.set_sorting(git2::Sort::TIME)?;
-
-        let mut commits = Vec::with_capacity(max_count);
-
-        for (i, oid) in revwalk.enumerate() {
-            if i >= max_count {
-                break;
-            }
-
-            let oid = oid?;
-            let commit = self.repo.find_commit(oid)?;
-            let refs = ref_map.get(&oid.to_string()).cloned().unwrap_or_default();
-            commits.push(CommitInfo::from_commit(&commit).with_refs(refs));
-        }
-
-        Ok(commits)
+        ref_map
     }
 
     pub fn search_commits(&self, query: &str, max_count: usize) -> Result<Vec<CommitInfo>> {
@@ -543,31 +850,181 @@ impl Repository {
         Ok(commits)
     }
 
-    pub fn commit(&self, message: &str) -> Result<String> {
+    /// Commit the index. `no_verify` skips the pre-commit and commit-msg hooks, like
+    /// `git commit --no-verify`; prepare-commit-msg and post-commit always run.
+    pub fn commit(&self, message: &str, no_verify: bool) -> Result<String> {
+        let hooks = self.hooks();
+        if !no_verify {
+            hooks.run("pre-commit", &[], None)?;
+        }
+
+        let merging = self.repo.state() == git2::RepositoryState::Merge;
+        let source = if merging {
+            "merge"
+        } else if self.repo.path().join("SQUASH_MSG").exists() {
+            "squash"
+        } else {
+            "message"
+        };
+        let message =
+            self.run_message_hooks(&hooks, "COMMIT_EDITMSG", message, source, no_verify)?;
+
+        // pre-commit may have staged more changes
         let mut index = self.repo.index()?;
+        index.read(false)?;
         let oid = index.write_tree()?;
         let tree = self.repo.find_tree(oid)?;
 
         let signature = self.repo.signature()?;
-        let parent_commit = self.repo.head()?.peel_to_commit()?;
+        let mut parents = vec![self.repo.head()?.peel_to_commit()?];
+
+        // Concluding a conflicted merge records the merged heads as further parents
+        if merging {
+            let heads = std::fs::read_to_string(self.repo.path().join("MERGE_HEAD"))?;
+            for line in heads.lines().filter(|l| !l.trim().is_empty()) {
+                let id = git2::Oid::from_str(line.trim())?;
+                parents.push(self.repo.find_commit(id)?);
+            }
+        }
+        let parent_refs: Vec<&git2::Commit> = parents.iter().collect();
 
+        let before = self.journal_begin(&[]);
         let commit_oid = self.repo.commit(
             Some("HEAD"),
             &signature,
             &signature,
-            message,
+            &message,
             &tree,
-            &[&parent_commit],
+            &parent_refs,
         )?;
+        if merging {
+            self.repo.cleanup_state()?;
+        }
+        let _ = std::fs::remove_file(self.repo.path().join("SQUASH_MSG"));
+        let summary = message.lines().next().unwrap_or_default();
+        self.journal_end(format!("commit \"{}\"", summary), before, false);
+        // Too late to stop anything; a failure only shows up in the hook output
+        let _ = hooks.run("post-commit", &[], None);
 
         Ok(commit_oid.to_string())
     }
 
+    /// Write `message` to `file` in the git dir, let prepare-commit-msg and commit-msg
+    /// edit or reject it, and return the result
+    fn run_message_hooks(
+        &self,
+        hooks: &Hooks,
+        file: &str,
+        message: &str,
+        source: &str,
+        no_verify: bool,
+    ) -> Result<String> {
+        let path = hooks.git_dir().join(file);
+        std::fs::write(&path, format!("{}\n", message.trim_end()))?;
+        let path_arg = path.to_string_lossy();
+        hooks.run("prepare-commit-msg", &[&path_arg, source], None)?;
+        if !no_verify {
+            hooks.run("commit-msg", &[&path_arg], None)?;
+        }
+
+        let edited = std::fs::read_to_string(&path)?;
+        let message = git2::message_prettify(edited, None)?;
+        if message.trim().is_empty() {
+            return Err(Error::Hook(
+                "Aborting commit due to empty commit message".to_string(),
+            ));
+        }
+        Ok(message.trim_end().to_string())
+    }
+
     // Status operations
     pub fn status(&self) -> Result<Vec<StatusEntry>> {
+        self.status_with(&StatusQuery::default())
+    }
+
+    /// Status of the working tree, or of `query.scope` in it.
+    ///
+    /// libgit2 knows neither the untracked cache nor fsmonitor, so when the
+    /// repository enables one (`core.untrackedCache`, `core.fsmonitor`) the
+    /// status comes from `git status`, which uses them.
+    pub fn status_with(&self, query: &StatusQuery) -> Result<Vec<StatusEntry>> {
+        let started = std::time::Instant::now();
+        let (entries, backend) = match self.status_from_git(query) {
+            Some(entries) => (entries, "git"),
+            None => (self.status_from_libgit2(query)?, "libgit2"),
+        };
+        debug!(
+            "Status ({}, untracked={}, {}): {} entries in {:?}",
+            backend,
+            query.untracked.name(),
+            if query.scope.is_empty() {
+                "full".to_string()
+            } else {
+                format!("{} paths", query.scope.len())
+            },
+            entries.len(),
+            started.elapsed()
+        );
+        Ok(entries)
+    }
+
+    /// Answer git's fsmonitor hook (`g fsmonitor <version> <token>`)
+    pub fn fsmonitor_hook(&self, version: &str, token: &str) -> Vec<u8> {
+        super::fsmonitor::hook_response(self.repo.path(), version, token)
+    }
+
+    /// Whether `git status` is faster here than libgit2
+    fn status_needs_git(&self) -> bool {
+        let Ok(config) = self.repo.config() else {
+            return false;
+        };
+        let untracked_cache = config.get_bool("core.untrackedCache").unwrap_or(false);
+        let fsmonitor = match config.get_string("core.fsmonitor") {
+            Ok(value) => !value.is_empty() && value != "false",
+            Err(_) => false,
+        };
+        untracked_cache || fsmonitor
+    }
+
+    /// None when `git status` is not wanted or fails (e.g. git is not installed)
+    fn status_from_git(&self, query: &StatusQuery) -> Option<Vec<StatusEntry>> {
+        if !self.status_needs_git() {
+            return None;
+        }
+        let output = std::process::Command::new("git")
+            .args(["--literal-pathspecs", "status", "--porcelain=v2", "-z"])
+            .arg("--no-renames")
+            .arg(format!("--untracked-files={}", query.untracked.name()))
+            .arg("--")
+            .args(&query.scope)
+            .current_dir(&self.path)
+            .output();
+        match output {
+            Ok(output) if output.status.success() => Some(parse_porcelain_v2(&output.stdout)),
+            Ok(output) => {
+                debug!(
+                    "git status failed, using libgit2: {}",
+                    String::from_utf8_lossy(&output.stderr).trim()
+                );
+                None
+            }
+            Err(e) => {
+                debug!("git status failed, using libgit2: {}", e);
+                None
+            }
+        }
+    }
+
+    fn status_from_libgit2(&self, query: &StatusQuery) -> Result<Vec<StatusEntry>> {
         let mut opts = git2::StatusOptions::new();
-        opts.include_untracked(true);
-        opts.recurse_untracked_dirs(true);
+        opts.include_untracked(query.untracked != UntrackedFiles::No);
+        opts.recurse_untracked_dirs(query.untracked == UntrackedFiles::All);
+        if !query.scope.is_empty() {
+            opts.disable_pathspec_match(true);
+            for path in &query.scope {
+                opts.pathspec(path);
+            }
+        }
 
         let statuses = self.repo.statuses(Some(&mut opts))?;
         let mut entries = Vec::new();
@@ -615,6 +1072,7 @@ impl Repository {
     }
 
     pub fn discard_file(&self, path: &str) -> Result<()> {
+        self.trash_snapshot(&format!("discard {}", path), &[path.to_string()])?;
         let mut opts = git2::build::CheckoutBuilder::new();
         opts.path(path);
         opts.force();
@@ -622,121 +1080,387 @@ impl Repository {
         Ok(())
     }
 
-    // Diff operations
-    pub fn diff_staged(&self) -> Result<DiffInfo> {
-        let head_tree = self.repo.head()?.peel_to_tree()?;
-        let diff = self.repo.diff_tree_to_index(Some(&head_tree), None, None)?;
-        Self::parse_diff(&diff)
+    /// Discard all changes to tracked files
+    pub fn discard_all(&self) -> Result<()> {
+        self.trash_snapshot("discard all changes", &self.changed_paths(false)?)?;
+        let mut opts = git2::build::CheckoutBuilder::new();
+        opts.force();
+        self.repo.checkout_head(Some(&mut opts))?;
+        Ok(())
     }
 
-    pub fn diff_unstaged(&self) -> Result<DiffInfo> {
-        let diff = self.repo.diff_index_to_workdir(None, None)?;
-        Self::parse_diff(&diff)
+    /// Delete untracked (not ignored) files; returns how many were removed
+    pub fn clean_untracked(&self) -> Result<usize> {
+        // Untracked directories are only listed whole when they hold a nested
+        // repository; a snapshot cannot keep those, so they are left alone
+        let untracked: Vec<String> = self
+            .changed_paths(true)?
+            .into_iter()
+            .filter(|path| {
+                std::fs::symlink_metadata(self.path.join(path)).is_ok_and(|m| !m.is_dir())
+            })
+            .filter(|path| {
+                self.repo
+                    .status_file(Path::new(path))
+                    .map(|s| s.contains(git2::Status::WT_NEW))
+                    .unwrap_or(false)
+            })
+            .collect();
+        self.trash_snapshot("clean untracked files", &untracked)?;
+        for path in &untracked {
+            let full = self.path.join(path);
+            std::fs::remove_file(&full)?;
+            // Drop directories the clean left empty
+            let mut dir = full.parent();
+            while let Some(d) = dir.filter(|d| *d != self.path) {
+                if std::fs::remove_dir(d).is_err() {
+                    break;
+                }
+                dir = d.parent();
+            }
+        }
+        Ok(untracked.len())
     }
 
-    /// Get the diff for a specific commit (comparing to its parent)
-    pub fn diff_commit(&self, commit_id: &str) -> Result<DiffInfo> {
-        let obj = self.repo.revparse_single(commit_id)?;
-        let commit = obj.peel_to_commit()?;
-        let commit_tree = commit.tree()?;
-
-        // Get parent tree (None for root commit)
-        let parent_tree = if commit.parent_count() > 0 {
-            Some(commit.parent(0)?.tree()?)
-        } else {
-            None
-        };
-
-        let diff = self
+    /// Paths with uncommitted changes; untracked files only when `untracked` is set
+    fn changed_paths(&self, untracked: bool) -> Result<Vec<String>> {
+        let mut opts = git2::StatusOptions::new();
+        opts.include_untracked(untracked)
+            .recurse_untracked_dirs(untracked)
+            .include_ignored(false);
+        Ok(self
             .repo
-            .diff_tree_to_tree(parent_tree.as_ref(), Some(&commit_tree), None)?;
-        Self::parse_diff(&diff)
+            .statuses(Some(&mut opts))?
+            .iter()
+            .filter_map(|e| e.path().map(String::from))
+            .collect())
     }
 
-    fn parse_diff(diff: &git2::Diff) -> Result<DiffInfo> {
-        let mut files = Vec::new();
-        let mut current_file: Option<FileDiff> = None;
-        let mut current_hunk: Option<Hunk> = None;
-        let mut last_hunk_start: Option<(u32, u32)> = None;
+    /// Save the working-tree content of `paths` under refs/g/trash/ before it is destroyed.
+    /// Like `git stash`, staged content that differs from HEAD is kept as a second
+    /// parent holding the index tree.
+    fn trash_snapshot(&self, reason: &str, paths: &[String]) -> Result<()> {
+        if paths.is_empty() {
+            return Ok(());
+        }
+        let head = self.repo.head().ok().and_then(|h| h.peel_to_commit().ok());
+        let head_tree = head.as_ref().map(|h| h.tree_id());
+        let signature = self
+            .repo
+            .signature()
+            .or_else(|_| Signature::now("g", "g@localhost"))?;
+
+        // A conflicted index has no tree; its stages are not the user's staging anyway
+        let mut staged = self.repo.index()?;
+        let staged = match staged.has_conflicts() {
+            true => None,
+            false => Some(staged.write_tree_to(&self.repo)?),
+        }
+        .filter(|tree| Some(*tree) != head_tree);
+        let index_commit = match staged {
+            Some(tree) => {
+                let tree = self.repo.find_tree(tree)?;
+                let parents: Vec<&git2::Commit> = head.iter().collect();
+                let message = format!("index on {}", reason);
+                let id = self
+                    .repo
+                    .commit(None, &signature, &signature, &message, &tree, &parents)?;
+                Some(self.repo.find_commit(id)?)
+            }
+            None => None,
+        };
 
-        diff.print(git2::DiffFormat::Patch, |delta, hunk, line| {
-            let file_path = delta
-                .new_file()
-                .path()
-                .or_else(|| delta.old_file().path())
-                .map(|p| p.to_string_lossy().to_string())
-                .unwrap_or_default();
+        let mut index = git2::Index::new()?;
+        if let Some(head) = &head {
+            index.read_tree(&head.tree()?)?;
+        }
 
-            // Check if we're starting a new file
-            if current_file.as_ref().map(|f| &f.path) != Some(&file_path) {
-                if let Some(mut file) = current_file.take() {
-                    if let Some(h) = current_hunk.take() {
-                        file.hunks.push(h);
-                    }
-                    files.push(file);
+        for path in paths {
+            let full = self.path.join(path);
+            let metadata = match std::fs::symlink_metadata(&full) {
+                Ok(metadata) => metadata,
+                Err(_) => {
+                    index.remove_path(Path::new(path))?;
+                    continue;
                 }
-                current_file = Some(FileDiff {
-                    path: file_path.clone(),
-                    hunks: Vec::new(),
-                });
-                last_hunk_start = None;
-            }
-
-            // Check if we're starting a new hunk (only when hunk start position changes)
-            if let Some(h) = hunk {
-                let hunk_key = (h.old_start(), h.new_start());
-                if last_hunk_start != Some(hunk_key) {
-                    last_hunk_start = Some(hunk_key);
-                    if let Some(file) = current_file.as_mut() {
-                        if let Some(prev_hunk) = current_hunk.take() {
-                            file.hunks.push(prev_hunk);
-                        }
-                        let header = format!(
-                            "@@ -{},{} +{},{} @@",
-                            h.old_start(),
-                            h.old_lines(),
-                            h.new_start(),
-                            h.new_lines()
-                        );
-                        current_hunk = Some(Hunk {
-                            header,
-                            old_start: h.old_start(),
-                            old_lines: h.old_lines(),
-                            new_start: h.new_start(),
-                            new_lines: h.new_lines(),
-                            lines: Vec::new(),
-                        });
-                    }
+            };
+            let (mode, content) = if metadata.file_type().is_symlink() {
+                let target = std::fs::read_link(&full)?;
+                (0o120000, target.to_string_lossy().into_owned().into_bytes())
+            } else if metadata.is_file() {
+                (file_mode(&metadata), std::fs::read(&full)?)
+            } else {
+                continue;
+            };
+            let id = self.repo.blob(&content)?;
+            index.add(&index_entry(path, mode, id, content.len()))?;
+        }
+
+        let tree_id = index.write_tree_to(&self.repo)?;
+        if head_tree == Some(tree_id) && index_commit.is_none() {
+            return Ok(());
+        }
+        let tree = self.repo.find_tree(tree_id)?;
+        let parents: Vec<&git2::Commit> = head.iter().chain(index_commit.iter()).collect();
+        let id = self
+            .repo
+            .commit(None, &signature, &signature, reason, &tree, &parents)?;
+        let name = format!(
+            "{}{}-{}",
+            TRASH_PREFIX,
+            chrono::Utc::now().timestamp(),
+            &id.to_string()[..7]
+        );
+        self.repo.reference(&name, id, false, reason)?;
+        Ok(())
+    }
+
+    /// Trash snapshots, newest first
+    pub fn trash_entries(&self) -> Result<Vec<TrashEntry>> {
+        let mut entries = Vec::new();
+        for reference in self.repo.references_glob(&format!("{}*", TRASH_PREFIX))? {
+            let reference = reference?;
+            let commit = match reference.peel_to_commit() {
+                Ok(commit) => commit,
+                Err(_) => continue,
+            };
+            let id = commit.id().to_string();
+            entries.push(TrashEntry {
+                refname: reference.name().unwrap_or_default().to_string(),
+                short_id: id[..7].to_string(),
+                id,
+                reason: commit.summary().unwrap_or_default().to_string(),
+                time: commit.time().seconds(),
+                files: self.trash_files(&commit)?,
+            });
+        }
+        entries.sort_by(|a, b| b.time.cmp(&a.time).then(b.refname.cmp(&a.refname)));
+        Ok(entries)
+    }
+
+    /// Paths a snapshot changed in the working tree or in the index
+    fn trash_files(&self, commit: &git2::Commit) -> Result<Vec<String>> {
+        let mut files = self.trash_diff_paths(commit, &commit.tree()?)?;
+        if let Some(staged) = trash_index_tree(commit) {
+            for path in self.trash_diff_paths(commit, &staged)? {
+                if !files.contains(&path) {
+                    files.push(path);
                 }
             }
+        }
+        Ok(files)
+    }
 
-            // Add line to current hunk
-            if let Some(hunk) = current_hunk.as_mut() {
-                let content = String::from_utf8_lossy(line.content()).to_string();
-                let line_type = match line.origin() {
-                    '+' => LineType::Addition,
-                    '-' => LineType::Deletion,
-                    _ => LineType::Context,
-                };
-                hunk.lines.push(DiffLine {
-                    line_type,
-                    content,
-                    old_lineno: line.old_lineno(),
-                    new_lineno: line.new_lineno(),
-                });
+    fn trash_diff_paths(&self, commit: &git2::Commit, tree: &git2::Tree) -> Result<Vec<String>> {
+        let parent_tree = commit.parent(0).ok().and_then(|p| p.tree().ok());
+        let diff = self
+            .repo
+            .diff_tree_to_tree(parent_tree.as_ref(), Some(tree), None)?;
+        Ok(diff
+            .deltas()
+            .filter_map(|d| d.new_file().path().or(d.old_file().path()))
+            .map(|p| p.display().to_string())
+            .collect())
+    }
+
+    /// Write a snapshot's files back into the working tree and drop it from the trash.
+    /// Whatever those files contain now is trashed first, so a restore is undoable too.
+    pub fn restore_trash(&self, refname: &str) -> Result<usize> {
+        let commit = self.repo.find_reference(refname)?.peel_to_commit()?;
+        let tree = commit.tree()?;
+        let files = self.trash_files(&commit)?;
+        let reason = commit.summary().unwrap_or_default().to_string();
+        self.trash_snapshot(&format!("before restoring \"{}\"", reason), &files)?;
+
+        for path in &files {
+            let full = self.path.join(path);
+            match tree.get_path(Path::new(path)) {
+                Ok(entry) => {
+                    let blob = self.repo.find_blob(entry.id())?;
+                    if let Some(parent) = full.parent() {
+                        std::fs::create_dir_all(parent)?;
+                    }
+                    write_worktree_file(&full, blob.content(), entry.filemode())?;
+                }
+                Err(_) => {
+                    let _ = std::fs::remove_file(&full);
+                }
             }
+        }
 
-            true
-        })?;
+        // Stage what was staged when the snapshot was taken
+        if let Some(staged) = trash_index_tree(&commit) {
+            let mut index = self.repo.index()?;
+            for path in self.trash_diff_paths(&commit, &staged)? {
+                match staged.get_path(Path::new(&path)) {
+                    Ok(entry) => {
+                        let size = self.repo.find_blob(entry.id())?.size();
+                        let mode = entry.filemode() as u32;
+                        index.add(&index_entry(&path, mode, entry.id(), size))?;
+                    }
+                    Err(_) => {
+                        let _ = index.remove_path(Path::new(&path));
+                    }
+                }
+            }
+            index.write()?;
+        }
 
-        // Don't forget the last file/hunk
-        if let Some(mut file) = current_file {
-            if let Some(h) = current_hunk {
-                file.hunks.push(h);
+        self.repo.find_reference(refname)?.delete()?;
+        Ok(files.len())
+    }
+
+    pub fn delete_trash(&self, refname: &str) -> Result<()> {
+        self.repo.find_reference(refname)?.delete()?;
+        Ok(())
+    }
+
+    /// Drop snapshots older than `max_age_days`; returns how many were removed
+    pub fn expire_trash(&self, max_age_days: u32) -> Result<usize> {
+        let cutoff = chrono::Utc::now().timestamp() - i64::from(max_age_days) * 86400;
+        let mut expired = 0;
+        for entry in self.trash_entries()? {
+            if entry.time < cutoff {
+                self.delete_trash(&entry.refname)?;
+                expired += 1;
             }
-            files.push(file);
         }
+        Ok(expired)
+    }
 
+    // Diff operations
+    pub fn diff_staged(&self) -> Result<DiffInfo> {
+        let head_tree = self.repo.head()?.peel_to_tree()?;
+        let diff = self.repo.diff_tree_to_index(Some(&head_tree), None, None)?;
+        self.parse_diff(&diff)
+    }
+
+    pub fn diff_unstaged(&self) -> Result<DiffInfo> {
+        let diff = self.repo.diff_index_to_workdir(None, None)?;
+        self.parse_diff(&diff)
+    }
+
+    /// The staged or unstaged diff of one file, without diffing the rest
+    pub fn diff_file(&self, path: &str, staged: bool) -> Result<DiffInfo> {
+        let mut opts = git2::DiffOptions::new();
+        opts.pathspec(path).disable_pathspec_match(true);
+        let diff = if staged {
+            let head_tree = self.repo.head()?.peel_to_tree()?;
+            self.repo
+                .diff_tree_to_index(Some(&head_tree), None, Some(&mut opts))?
+        } else {
+            self.repo.diff_index_to_workdir(None, Some(&mut opts))?
+        };
+        self.parse_diff(&diff)
+    }
+
+    /// Get the diff for a specific commit (comparing to its parent)
+    pub fn diff_commit(&self, commit_id: &str) -> Result<DiffInfo> {
+        let obj = self.repo.revparse_single(commit_id)?;
+        let commit = obj.peel_to_commit()?;
+        let commit_tree = commit.tree()?;
+
+        // Get parent tree (None for root commit)
+        let parent_tree = if commit.parent_count() > 0 {
+            Some(commit.parent(0)?.tree()?)
+        } else {
+            None
+        };
+
+        let diff = self
+            .repo
+            .diff_tree_to_tree(parent_tree.as_ref(), Some(&commit_tree), None)?;
+        self.parse_diff(&diff)
+    }
+
+    /// Files of `diff` with their hunks; binary files and, with a size
+    /// limit, files bigger than it come without (see `with_size_limit`)
+    fn parse_diff(&self, diff: &git2::Diff) -> Result<DiffInfo> {
+        let odb = self.repo.odb().ok();
+        // Size of one side, from the stat or index when known, else the object header
+        let size_of = |file: git2::DiffFile| -> u64 {
+            if file.size() > 0 || file.id().is_zero() {
+                return file.size();
+            }
+            odb.as_ref()
+                .and_then(|odb| odb.read_header(file.id()).ok())
+                .map_or(0, |(size, _)| size as u64)
+        };
+        let is_binary = |delta: &git2::DiffDelta| {
+            delta.flags().is_binary()
+                || delta.old_file().is_binary()
+                || delta.new_file().is_binary()
+        };
+
+        let mut files = Vec::new();
+        for (idx, delta) in diff.deltas().enumerate() {
+            self.check_cancelled()?;
+            let path = delta
+                .new_file()
+                .path()
+                .or_else(|| delta.old_file().path())
+                .map(|p| p.to_string_lossy().to_string())
+                .unwrap_or_default();
+            let mut file = FileDiff {
+                path,
+                hunks: Vec::new(),
+                omitted: None,
+            };
+            if let Some(limit) = self.size_limit {
+                let size = size_of(delta.new_file()).max(size_of(delta.old_file()));
+                if size > limit {
+                    file.omitted = Some(Omitted::TooLarge(size));
+                    files.push(file);
+                    continue;
+                }
+            }
+
+            // Known binary files are skipped before their content is loaded;
+            // the rest are only known once the patch has looked at it
+            let patch = if is_binary(&delta) {
+                None
+            } else {
+                git2::Patch::from_diff(diff, idx)?
+            };
+            let Some(patch) = patch.filter(|p| !is_binary(&p.delta())) else {
+                file.omitted = Some(Omitted::Binary);
+                files.push(file);
+                continue;
+            };
+            for hunk_idx in 0..patch.num_hunks() {
+                let (h, line_count) = patch.hunk(hunk_idx)?;
+                let mut hunk = Hunk {
+                    header: format!(
+                        "@@ -{},{} +{},{} @@",
+                        h.old_start(),
+                        h.old_lines(),
+                        h.new_start(),
+                        h.new_lines()
+                    ),
+                    old_start: h.old_start(),
+                    old_lines: h.old_lines(),
+                    new_start: h.new_start(),
+                    new_lines: h.new_lines(),
+                    lines: Vec::with_capacity(line_count),
+                };
+                for line_idx in 0..line_count {
+                    let line = patch.line_in_hunk(hunk_idx, line_idx)?;
+                    let line_type = match line.origin() {
+                        '+' => LineType::Addition,
+                        '-' => LineType::Deletion,
+                        _ => LineType::Context,
+                    };
+                    hunk.lines.push(DiffLine {
+                        line_type,
+                        content: String::from_utf8_lossy(line.content()).to_string(),
+                        old_lineno: line.old_lineno(),
+                        new_lineno: line.new_lineno(),
+                    });
+                }
+                file.hunks.push(hunk);
+            }
+            files.push(file);
+        }
         Ok(DiffInfo { files })
     }
 
@@ -758,13 +1482,25 @@ impl Repository {
 
     pub fn stash_save(&mut self, message: Option<&str>) -> Result<()> {
         let signature = self.repo.signature()?;
-        self.repo
-            .stash_save(&signature, message.unwrap_or("WIP"), None)?;
+        let message = message.unwrap_or("WIP");
+        let before = self.journal_begin(&[]);
+        let oid = self.repo.stash_save(&signature, message, None)?;
+        let mut op = self.journal_operation("stash".to_string(), before, false);
+        op.created_stash = Some(StashRecord {
+            id: oid.to_string(),
+            message: message.to_string(),
+        });
+        self.journal_push(op);
         Ok(())
     }
 
     pub fn stash_pop(&mut self, index: usize) -> Result<()> {
+        let dropped = self.stash_record(index)?;
+        let before = self.journal_begin(&[]);
         self.repo.stash_pop(index, None)?;
+        let mut op = self.journal_operation(format!("stash pop {}", index), before, false);
+        op.dropped_stashes.push(dropped);
+        self.journal_push(op);
         Ok(())
     }
 
@@ -774,7 +1510,61 @@ impl Repository {
     }
 
     pub fn stash_drop(&mut self, index: usize) -> Result<()> {
+        let dropped = self.stash_record(index)?;
+        let before = self.journal_begin(&[]);
         self.repo.stash_drop(index)?;
+        let mut op = self.journal_operation(format!("stash drop {}", index), before, false);
+        op.dropped_stashes.push(dropped);
+        self.journal_push(op);
+        Ok(())
+    }
+
+    fn stash_record(&mut self, index: usize) -> Result<StashRecord> {
+        let mut record = None;
+        self.repo.stash_foreach(|i, message, oid| {
+            if i == index {
+                record = Some(StashRecord {
+                    id: oid.to_string(),
+                    message: message.to_string(),
+                });
+                false
+            } else {
+                true
+            }
+        })?;
+        record.ok_or_else(|| {
+            Error::Git(git2::Error::from_str(&format!(
+                "stash@{{{}}} not found",
+                index
+            )))
+        })
+    }
+
+    fn stash_index_of(&mut self, id: &str) -> Option<usize> {
+        let mut found = None;
+        let _ = self.repo.stash_foreach(|i, _, oid| {
+            if oid.to_string() == id {
+                found = Some(i);
+                false
+            } else {
+                true
+            }
+        });
+        found
+    }
+
+    /// Put a dropped stash commit back on top of the stash list
+    fn stash_store(&self, record: &StashRecord) -> Result<()> {
+        let oid = git2::Oid::from_str(&record.id)?;
+        self.repo
+            .reference("refs/stash", oid, true, &record.message)?;
+        // libgit2 only logs refs/stash when its reflog already exists
+        let mut reflog = self.repo.reflog("refs/stash")?;
+        if reflog.get(0).map(|e| e.id_new()) != Some(oid) {
+            let signature = self.repo.signature()?;
+            reflog.append(oid, &signature, Some(&record.message))?;
+            reflog.write()?;
+        }
         Ok(())
     }
 
@@ -821,7 +1611,7 @@ impl Repository {
         }
 
         // Sort by timestamp descending (newest first)
-        tags.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
+        tags.sort_by_key(|t| std::cmp::Reverse(t.timestamp));
 
         Ok(tags)
     }
@@ -829,6 +1619,7 @@ impl Repository {
     pub fn create_tag(&self, name: &str, message: Option<&str>) -> Result<()> {
         let head = self.repo.head()?.peel_to_commit()?;
 
+        let before = self.journal_begin(&[format!("refs/tags/{}", name)]);
         match message {
             Some(msg) => {
                 let signature = self.repo.signature()?;
@@ -839,23 +1630,155 @@ impl Repository {
                 self.repo.tag_lightweight(name, head.as_object(), false)?;
             }
         }
+        self.journal_end(format!("create tag {}", name), before, false);
 
         Ok(())
     }
 
     pub fn delete_tag(&self, name: &str) -> Result<()> {
+        let before = self.journal_begin(&[format!("refs/tags/{}", name)]);
         self.repo.tag_delete(name)?;
+        self.journal_end(format!("delete tag {}", name), before, false);
         Ok(())
     }
 
     // Merge operations
-    pub fn merge(&self, branch_name: &str) -> Result<MergeResult> {
-        let branch = self
-            .repo
-            .find_branch(branch_name, git2::BranchType::Local)?;
-        let annotated = self.repo.reference_to_annotated_commit(branch.get())?;
+    pub fn merge_with(&self, request: &MergeRequest) -> Result<MergeResult> {
+        let before = self.journal_begin(&[]);
+        let result = self.merge_request(request)?;
+        self.journal_end(format!("merge {}", request.target), before, true);
+        Ok(result)
+    }
+
+    fn merge_request(&self, request: &MergeRequest) -> Result<MergeResult> {
+        let annotated = self.merge_target(&request.target)?;
+        let message = match &request.message {
+            Some(message) => message.clone(),
+            None => self.merge_message(&request.target)?,
+        };
 
         let (analysis, _) = self.repo.merge_analysis(&[&annotated])?;
+        if analysis.contains(git2::MergeAnalysis::ANALYSIS_UP_TO_DATE) {
+            return Ok(MergeResult::UpToDate);
+        }
+        let can_fast_forward = analysis.contains(git2::MergeAnalysis::ANALYSIS_FASTFORWARD);
+
+        let no_verify = request.no_verify;
+        let result = match request.mode {
+            MergeMode::FastForward => self.merge_annotated(&annotated, &message, no_verify)?,
+            MergeMode::FastForwardOnly if can_fast_forward => {
+                self.merge_annotated(&annotated, &message, no_verify)?
+            }
+            MergeMode::FastForwardOnly => MergeResult::Aborted,
+            MergeMode::NoFastForward => self.merge_commit(&annotated, &message, no_verify)?,
+            MergeMode::Squash => self.squash_merge(&annotated, &message)?,
+        };
+
+        if matches!(
+            result,
+            MergeResult::FastForward | MergeResult::Merged | MergeResult::Squashed
+        ) {
+            let squash = if result == MergeResult::Squashed {
+                "1"
+            } else {
+                "0"
+            };
+            let _ = self.hooks().run("post-merge", &[squash], None);
+        }
+        Ok(result)
+    }
+
+    /// Resolve a local branch, remote-tracking branch or commit-ish to merge
+    fn merge_target(&self, target: &str) -> Result<git2::AnnotatedCommit<'_>> {
+        if let Ok(reference) = self.repo.resolve_reference_from_short_name(target) {
+            return Ok(self.repo.reference_to_annotated_commit(&reference)?);
+        }
+        let commit = self.repo.revparse_single(target)?.peel_to_commit()?;
+        Ok(self.repo.find_annotated_commit(commit.id())?)
+    }
+
+    /// Default merge mode from git's merge.ff
+    pub fn merge_defaults(&self) -> MergeMode {
+        match self
+            .repo
+            .config()
+            .and_then(|c| c.get_string("merge.ff"))
+            .as_deref()
+        {
+            Ok("false") => MergeMode::NoFastForward,
+            Ok("only") => MergeMode::FastForwardOnly,
+            _ => MergeMode::FastForward,
+        }
+    }
+
+    /// Generated merge message: subject plus a shortlog of the commits `target` brings in
+    pub fn merge_message(&self, target: &str) -> Result<String> {
+        let (description, label) = if self
+            .repo
+            .find_branch(target, git2::BranchType::Local)
+            .is_ok()
+        {
+            (format!("branch '{}'", target), target.to_string())
+        } else if self
+            .repo
+            .find_branch(target, git2::BranchType::Remote)
+            .is_ok()
+        {
+            (
+                format!("remote-tracking branch '{}'", target),
+                target.to_string(),
+            )
+        } else {
+            let id = self.repo.revparse_single(target)?.peel_to_commit()?.id();
+            let short = id.to_string()[..7].to_string();
+            (format!("commit '{}'", short), short)
+        };
+
+        let mut message = format!("Merge {}", description);
+        let shortlog = self.merge_shortlog(target)?;
+        if !shortlog.is_empty() {
+            message.push_str(&format!("\n\n* {}:\n{}", label, shortlog));
+        }
+        Ok(message)
+    }
+
+    /// Subjects of commits reachable from `target` but not HEAD, oldest first
+    fn merge_shortlog(&self, target: &str) -> Result<String> {
+        const MAX_ENTRIES: usize = 20;
+
+        let id = self.repo.revparse_single(target)?.peel_to_commit()?.id();
+        let mut revwalk = self.repo.revwalk()?;
+        revwalk.set_sorting(git2::Sort::TOPOLOGICAL | git2::Sort::REVERSE)?;
+        revwalk.push(id)?;
+        if let Ok(head) = self.repo.head().and_then(|h| h.peel_to_commit()) {
+            revwalk.hide(head.id())?;
+        }
+
+        let subjects: Vec<String> = revwalk
+            .filter_map(|oid| oid.ok())
+            .filter_map(|oid| self.repo.find_commit(oid).ok())
+            .filter(|commit| commit.parent_count() < 2)
+            .map(|commit| commit.summary().unwrap_or_default().to_string())
+            .collect();
+        let mut lines: Vec<String> = subjects
+            .iter()
+            .take(MAX_ENTRIES)
+            .map(|subject| format!("  {}", subject))
+            .collect();
+        if subjects.len() > MAX_ENTRIES {
+            lines.push("  ...".to_string());
+        }
+        Ok(lines.join("\n"))
+    }
+
+    /// Merge `annotated` into HEAD, fast-forwarding when possible
+    fn merge_annotated(
+        &self,
+        annotated: &git2::AnnotatedCommit,
+        message: &str,
+        no_verify: bool,
+    ) -> Result<MergeResult> {
+        let (analysis, _) = self.repo.merge_analysis(&[annotated])?;
 
         if analysis.contains(git2::MergeAnalysis::ANALYSIS_UP_TO_DATE) {
             return Ok(MergeResult::UpToDate);
@@ -863,37 +1786,91 @@ impl Repository {
 
         if analysis.contains(git2::MergeAnalysis::ANALYSIS_FASTFORWARD) {
             let target = self.repo.find_commit(annotated.id())?;
+            let mut opts = git2::build::CheckoutBuilder::new();
+            opts.safe();
+            self.repo
+                .checkout_tree(target.as_object(), Some(&mut opts))?;
             let mut head_ref = self.repo.head()?;
             head_ref.set_target(target.id(), "merge: fast-forward")?;
-            self.repo.checkout_head(None)?;
             return Ok(MergeResult::FastForward);
         }
 
-        // Normal merge
-        self.repo.merge(&[&annotated], None, None)?;
+        self.merge_commit(annotated, message, no_verify)
+    }
+
+    /// Merge `annotated` into HEAD with a merge commit, even when a fast-forward is possible
+    fn merge_commit(
+        &self,
+        annotated: &git2::AnnotatedCommit,
+        message: &str,
+        no_verify: bool,
+    ) -> Result<MergeResult> {
+        self.repo.merge(&[annotated], None, None)?;
 
         if self.repo.index()?.has_conflicts() {
+            // The commit that concludes the merge picks the message up from here
+            std::fs::write(self.repo.path().join("MERGE_MSG"), format!("{}\n", message))?;
             Ok(MergeResult::Conflict)
         } else {
-            // Commit the merge
-            self.commit_merge(branch_name)?;
+            self.commit_merge(annotated.id(), message, no_verify)?;
             Ok(MergeResult::Merged)
         }
     }
 
-    fn commit_merge(&self, branch_name: &str) -> Result<()> {
+    /// Apply `annotated`'s changes to the index and working tree without recording a merge
+    fn squash_merge(
+        &self,
+        annotated: &git2::AnnotatedCommit,
+        message: &str,
+    ) -> Result<MergeResult> {
+        self.repo.merge(&[annotated], None, None)?;
+        self.repo.cleanup_state()?;
+        std::fs::write(
+            self.repo.path().join("SQUASH_MSG"),
+            format!("{}\n", message),
+        )?;
+
+        if self.repo.index()?.has_conflicts() {
+            Ok(MergeResult::Conflict)
+        } else {
+            Ok(MergeResult::Squashed)
+        }
+    }
+
+    /// Message prepared by an unfinished merge or a squash, for the next commit
+    pub fn prepared_message(&self) -> Option<String> {
+        let file = if self.repo.state() == git2::RepositoryState::Merge {
+            "MERGE_MSG"
+        } else {
+            "SQUASH_MSG"
+        };
+        std::fs::read_to_string(self.repo.path().join(file))
+            .ok()
+            .map(|m| m.trim_end().to_string())
+            .filter(|m| !m.is_empty())
+    }
+
+    /// Record the merge. If a hook refuses, the merge stays in progress with its
+    /// message in MERGE_MSG so that an ordinary commit can conclude it.
+    fn commit_merge(&self, other: git2::Oid, message: &str, no_verify: bool) -> Result<()> {
+        let hooks = self.hooks();
+        if !no_verify {
+            if let Err(e) = hooks.run("pre-merge-commit", &[], None) {
+                std::fs::write(self.repo.path().join("MERGE_MSG"), format!("{}\n", message))?;
+                return Err(e);
+            }
+        }
+        let message = self.run_message_hooks(&hooks, "MERGE_MSG", message, "merge", no_verify)?;
+
         let mut index = self.repo.index()?;
+        index.read(false)?;
         let oid = index.write_tree()?;
         let tree = self.repo.find_tree(oid)?;
 
         let head = self.repo.head()?.peel_to_commit()?;
-        let branch = self
-            .repo
-            .find_branch(branch_name, git2::BranchType::Local)?;
-        let branch_commit = branch.get().peel_to_commit()?;
+        let other_commit = self.repo.find_commit(other)?;
 
         let signature = self.repo.signature()?;
-        let message = format!("Merge branch '{}'", branch_name);
 
         self.repo.commit(
             Some("HEAD"),
@@ -901,13 +1878,307 @@ impl Repository {
             &signature,
             &message,
             &tree,
-            &[&head, &branch_commit],
+            &[&head, &other_commit],
         )?;
 
         self.repo.cleanup_state()?;
         Ok(())
     }
 
+    // Patch operations
+
+    /// Commits as format-patch mails, oldest first: (file name, mail) pairs
+    pub fn format_patches(&self, ids: &[String]) -> Result<Vec<(String, String)>> {
+        let wanted: Vec<git2::Oid> = ids
+            .iter()
+            .map(|id| Ok(self.repo.revparse_single(id)?.peel_to_commit()?.id()))
+            .collect::<Result<_>>()?;
+        let mut revwalk = self.repo.revwalk()?;
+        revwalk.set_sorting(git2::Sort::TOPOLOGICAL | git2::Sort::REVERSE)?;
+        for id in &wanted {
+            revwalk.push(*id)?;
+        }
+        let ordered: Vec<git2::Oid> = revwalk
+            .filter_map(|id| id.ok())
+            .filter(|id| wanted.contains(id))
+            .collect();
+
+        let mut patches = Vec::new();
+        for (i, id) in ordered.iter().enumerate() {
+            let commit = self.repo.find_commit(*id)?;
+            if commit.parent_count() > 1 {
+                return Err(Error::Git(git2::Error::from_str(&format!(
+                    "Cannot export merge commit {}",
+                    &id.to_string()[..7]
+                ))));
+            }
+            let parent_tree = commit.parent(0).ok().map(|p| p.tree()).transpose()?;
+            let diff =
+                self.repo
+                    .diff_tree_to_tree(parent_tree.as_ref(), Some(&commit.tree()?), None)?;
+            let summary = commit.summary().unwrap_or_default().to_string();
+            let email = git2::Email::from_diff(
+                &diff,
+                i + 1,
+                ordered.len(),
+                id,
+                summary.as_str(),
+                commit.body().unwrap_or_default(),
+                &commit.author(),
+                &mut git2::EmailCreateOptions::new(),
+            )?;
+            patches.push((
+                patch_file_name(i + 1, &summary),
+                String::from_utf8_lossy(email.as_slice()).into_owned(),
+            ));
+        }
+        Ok(patches)
+    }
+
+    /// Write format-patch files for `ids` into `dir`; returns how many were written
+    pub fn export_patches(&self, ids: &[String], dir: &Path) -> Result<usize> {
+        let patches = self.format_patches(ids)?;
+        std::fs::create_dir_all(dir)?;
+        for (name, mail) in &patches {
+            std::fs::write(dir.join(name), mail)?;
+        }
+        Ok(patches.len())
+    }
+
+    /// Diff of a patch file, for previewing before it is applied
+    pub fn patch_preview(&self, text: &str) -> Result<DiffInfo> {
+        let diff = git2::Diff::from_buffer(extract_diff(text).as_bytes())?;
+        self.parse_diff(&diff)
+    }
+
+    /// Apply a patch or diff file without committing, like `git apply [--cached|--index]`
+    pub fn apply_patch(&self, text: &str, target: PatchTarget) -> Result<()> {
+        let diff = git2::Diff::from_buffer(extract_diff(text).as_bytes())?;
+        self.repo.apply(&diff, target.location(), None)?;
+        Ok(())
+    }
+
+    pub fn am_in_progress(&self) -> bool {
+        AmState::load(self.repo.path()).is_some()
+    }
+
+    /// Apply an mbox patch series as commits that keep each patch's author and date,
+    /// like `git am -3`. A patch that conflicts pauses the series.
+    pub fn apply_mailbox(&self, text: &str) -> Result<AmOutcome> {
+        if self.am_in_progress() {
+            return Err(Error::Git(git2::Error::from_str(
+                "A patch series is already being applied; continue or abort it first",
+            )));
+        }
+        if has_tracked_changes(&self.repo) {
+            return Err(Error::Git(git2::Error::from_str(
+                "Commit or stash your changes before applying patches",
+            )));
+        }
+        let patches = parse_mbox(text);
+        if patches.is_empty() {
+            return Err(Error::Git(git2::Error::from_str("No patches found")));
+        }
+        let state = AmState {
+            orig_head: self.repo.head()?.peel_to_commit()?.id().to_string(),
+            patches,
+            next: 0,
+        };
+        let description = format!("am {} patch(es)", state.patches.len());
+        let before = self.journal_begin(&[]);
+        let outcome = self.run_am(state);
+        self.journal_end(description, before, true);
+        outcome
+    }
+
+    /// Commit the paused patch from the index (conflicts resolved) and go on with the series
+    pub fn continue_am(&self) -> Result<AmOutcome> {
+        let mut state = self.am_state()?;
+        // Conflicts may have been resolved with another tool
+        let mut index = self.repo.index()?;
+        index.read(false)?;
+        if index.has_conflicts() {
+            return Err(Error::Git(git2::Error::from_str(
+                "Resolve all conflicts before continuing",
+            )));
+        }
+        let before = self.journal_begin(&[]);
+        self.commit_mail_patch(&state.patches[state.next])?;
+        state.next += 1;
+        let outcome = self.run_am(state);
+        self.journal_end("am --continue".to_string(), before, true);
+        outcome
+    }
+
+    /// Drop the paused patch and go on with the rest of the series
+    pub fn skip_am(&self) -> Result<AmOutcome> {
+        let mut state = self.am_state()?;
+        self.trash_snapshot("am --skip", &self.changed_paths(false)?)?;
+        let head = self.repo.head()?.peel_to_commit()?;
+        self.repo
+            .reset(head.as_object(), git2::ResetType::Hard, None)?;
+        state.next += 1;
+        let before = self.journal_begin(&[]);
+        let outcome = self.run_am(state);
+        self.journal_end("am --skip".to_string(), before, true);
+        outcome
+    }
+
+    /// Return to where HEAD was before the series started
+    pub fn abort_am(&self) -> Result<String> {
+        let state = self.am_state()?;
+        self.trash_snapshot("am --abort", &self.changed_paths(false)?)?;
+        let before = self.journal_begin(&[]);
+        let orig = self
+            .repo
+            .find_commit(git2::Oid::from_str(&state.orig_head)?)?;
+        self.repo
+            .reset(orig.as_object(), git2::ResetType::Hard, None)?;
+        AmState::remove(self.repo.path());
+        self.journal_end("am --abort".to_string(), before, true);
+        Ok("Patch series aborted".to_string())
+    }
+
+    fn am_state(&self) -> Result<AmState> {
+        AmState::load(self.repo.path())
+            .ok_or_else(|| Error::Git(git2::Error::from_str("No patch series in progress")))
+    }
+
+    fn run_am(&self, mut state: AmState) -> Result<AmOutcome> {
+        let total = state.patches.len();
+        while state.next < total {
+            let patch = &state.patches[state.next];
+            let applied = match self.apply_mail_patch(patch) {
+                Ok(applied) => applied,
+                Err(e) => {
+                    // Keep the series so it can be skipped or aborted
+                    state.save(self.repo.path())?;
+                    return Err(e);
+                }
+            };
+            if !applied {
+                let stopped = Some(patch.subject.clone());
+                state.save(self.repo.path())?;
+                return Ok(AmOutcome {
+                    applied: state.next,
+                    total,
+                    stopped,
+                });
+            }
+            self.commit_mail_patch(patch)?;
+            state.next += 1;
+        }
+        AmState::remove(self.repo.path());
+        Ok(AmOutcome {
+            applied: total,
+            total,
+            stopped: None,
+        })
+    }
+
+    /// Apply a patch to the index and working tree; false if it left conflicts
+    fn apply_mail_patch(&self, patch: &MailPatch) -> Result<bool> {
+        let diff = git2::Diff::from_buffer(patch.diff.as_bytes())?;
+        if self
+            .repo
+            .apply(&diff, git2::ApplyLocation::Both, None)
+            .is_ok()
+        {
+            return Ok(true);
+        }
+
+        // Three-way fallback: rebuild the tree the patch was made against from the
+        // blob ids on its "index" lines, then cherry-pick the patched tree onto HEAD
+        let head = self.repo.head()?.peel_to_commit()?;
+        let base_tree = self.patch_base_tree(&diff, &head.tree()?).map_err(|_| {
+            Error::Git(git2::Error::from_str(&format!(
+                "Patch \"{}\" does not apply and its base is not in this repository",
+                patch.subject
+            )))
+        })?;
+        let patched = self
+            .repo
+            .apply_to_tree(&base_tree, &diff, None)?
+            .write_tree_to(&self.repo)?;
+        let signature = self
+            .repo
+            .signature()
+            .or_else(|_| Signature::now("g", "g@localhost"))?;
+        let base = self
+            .repo
+            .commit(None, &signature, &signature, "am base", &base_tree, &[])?;
+        let base = self.repo.find_commit(base)?;
+        let theirs = self.repo.commit(
+            None,
+            &signature,
+            &signature,
+            &patch.subject,
+            &self.repo.find_tree(patched)?,
+            &[&base],
+        )?;
+        self.repo
+            .cherrypick(&self.repo.find_commit(theirs)?, None)?;
+        self.repo.cleanup_state()?;
+        Ok(!self.repo.index()?.has_conflicts())
+    }
+
+    /// HEAD's tree with the patch's pre-image blobs in place of the files it touches
+    fn patch_base_tree(&self, diff: &git2::Diff, head_tree: &git2::Tree) -> Result<git2::Tree<'_>> {
+        let mut index = git2::Index::new()?;
+        index.read_tree(head_tree)?;
+        for delta in diff.deltas() {
+            let old = delta.old_file();
+            let path = old.path().unwrap_or_else(|| Path::new(""));
+            if delta.status() == git2::Delta::Added {
+                let _ = index.remove_path(path);
+                continue;
+            }
+            // Parsed patches carry abbreviated ids, padded with zeros
+            let hex = old.id().to_string();
+            let len = hex.trim_end_matches('0').len().max(7);
+            let blob = self.repo.revparse_single(&hex[..len])?.peel_to_blob()?;
+            let path_bytes = path.to_string_lossy().into_owned().into_bytes();
+            index.add(&git2::IndexEntry {
+                ctime: git2::IndexTime::new(0, 0),
+                mtime: git2::IndexTime::new(0, 0),
+                dev: 0,
+                ino: 0,
+                mode: u32::from(old.mode()),
+                uid: 0,
+                gid: 0,
+                file_size: blob.size() as u32,
+                id: blob.id(),
+                flags: path_bytes.len().min(0xfff) as u16,
+                flags_extended: 0,
+                path: path_bytes,
+            })?;
+        }
+        let id = index.write_tree_to(&self.repo)?;
+        Ok(self.repo.find_tree(id)?)
+    }
+
+    /// Commit the index with the patch's author, date and message
+    fn commit_mail_patch(&self, patch: &MailPatch) -> Result<()> {
+        let mut index = self.repo.index()?;
+        let tree = self.repo.find_tree(index.write_tree()?)?;
+        let author = Signature::new(
+            &patch.author_name,
+            &patch.author_email,
+            &git2::Time::new(patch.time, patch.offset),
+        )?;
+        let committer = self.repo.signature()?;
+        let head = self.repo.head()?.peel_to_commit()?;
+        self.repo.commit(
+            Some("HEAD"),
+            &author,
+            &committer,
+            &patch.message(),
+            &tree,
+            &[&head],
+        )?;
+        Ok(())
+    }
+
     // Remote operations
     pub fn remotes(&self) -> Result<Vec<String>> {
         let remotes = self.repo.remotes()?;
@@ -943,6 +2214,20 @@ impl Repository {
         }
     }
 
+    /// Number of open pull requests, or None if gh cannot tell (not installed, not GitHub)
+    pub fn open_pull_request_count(&self) -> Option<usize> {
+        let repo_dir = self.repo.path().parent().unwrap_or(self.repo.path());
+        let output = std::process::Command::new("gh")
+            .args(["pr", "list", "--json", "number", "--limit", "1000"])
+            .current_dir(repo_dir)
+            .output()
+            .ok()
+            .filter(|out| out.status.success())?;
+        serde_json::from_slice::<Vec<serde_json::Value>>(&output.stdout)
+            .ok()
+            .map(|prs| prs.len())
+    }
+
     /// Fetches GitHub issues using the gh CLI tool.
     /// Returns an empty Vec if gh is not installed or this is not a GitHub repo.
     pub fn issues(&self) -> Result<Vec<super::IssueInfo>> {
@@ -1070,133 +2355,579 @@ impl Repository {
         }
     }
 
-    pub fn fetch(&self, remote_name: &str) -> Result<()> {
-        let output = std::process::Command::new("git")
-            .args(["fetch", "--prune", remote_name])
-            .current_dir(&self.path)
-            .output()
-            .map_err(Error::Io)?;
+    pub fn fetch(&self, remote_name: &str, progress: &TransferProgress) -> Result<()> {
+        self.fetch_refspecs(remote_name, &[], progress)
+    }
 
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            return Err(Error::Git(git2::Error::from_str(&stderr)));
+    pub fn fetch_branch(
+        &self,
+        remote_name: &str,
+        branch: &str,
+        progress: &TransferProgress,
+    ) -> Result<()> {
+        let refspec = format!(
+            "+refs/heads/{}:refs/remotes/{}/{}",
+            branch, remote_name, branch
+        );
+        self.fetch_refspecs(remote_name, &[&refspec], progress)
+    }
+
+    /// Fetch `refspecs` (or the remote's configured ones when empty), pruning on a full fetch
+    fn fetch_refspecs(
+        &self,
+        remote_name: &str,
+        refspecs: &[&str],
+        progress: &TransferProgress,
+    ) -> Result<()> {
+        let mut remote = self.repo.find_remote(remote_name)?;
+        let mut opts = git2::FetchOptions::new();
+        opts.remote_callbacks(progress.callbacks(self.repo.config()?));
+        opts.download_tags(git2::AutotagOption::Auto);
+        if refspecs.is_empty() {
+            opts.prune(git2::FetchPrune::On);
         }
+        progress.finish(remote.fetch(refspecs, Some(&mut opts), None))
+    }
+
+    /// Push the current branch to its upstream branch on `remote_name`
+    /// (or a branch of the same name when no upstream is configured there)
+    pub fn push(&self, remote_name: &str, progress: &TransferProgress) -> Result<()> {
+        let branch = self.current_branch_name()?;
+        let target = self
+            .upstream_branch_name(&branch, remote_name)
+            .unwrap_or_else(|| branch.clone());
+        self.push_refspecs(
+            remote_name,
+            &[format!("refs/heads/{}:refs/heads/{}", branch, target)],
+            None,
+            progress,
+        )
+    }
+
+    /// Push a specific branch to remote
+    pub fn push_branch(
+        &self,
+        remote_name: &str,
+        branch: &str,
+        progress: &TransferProgress,
+    ) -> Result<()> {
+        self.push_refspecs(
+            remote_name,
+            &[format!("refs/heads/{}:refs/heads/{}", branch, branch)],
+            None,
+            progress,
+        )
+    }
+
+    /// Push and record `remote_name/branch` as the branch's upstream
+    pub fn push_set_upstream(
+        &self,
+        remote_name: &str,
+        branch: &str,
+        progress: &TransferProgress,
+    ) -> Result<()> {
+        self.push_branch(remote_name, branch, progress)?;
+        let mut local = self.repo.find_branch(branch, git2::BranchType::Local)?;
+        local.set_upstream(Some(&format!("{}/{}", remote_name, branch)))?;
         Ok(())
     }
 
-    /// Push current branch to remote using git command
-    pub fn push(&self, remote_name: &str) -> Result<()> {
-        let output = std::process::Command::new("git")
-            .args(["push", remote_name])
-            .current_dir(&self.path)
-            .output()
-            .map_err(Error::Io)?;
+    /// Push as described by `request`, returning a summary of what was (or would be) done
+    pub fn push_with(&self, request: &PushRequest, progress: &TransferProgress) -> Result<String> {
+        let source = full_branch_ref(&request.source);
+        let destination = full_branch_ref(&request.destination);
+        let local = self.repo.revparse_single(&source)?.peel_to_commit()?.id();
 
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            return Err(Error::Git(git2::Error::from_str(&stderr)));
+        // Ask the remote where its refs are now rather than trusting remote-tracking refs
+        let advertised = self.remote_heads(&request.remote, progress)?;
+        let current = advertised.get(&destination).copied();
+
+        // Checked here for the dry run and the pre-push hook, and again by push_refspecs
+        if let Some(expected) = &request.force_if_at {
+            if let Some(message) = force_mismatch(&request.remote, &destination, current, expected)
+            {
+                return Err(Error::Git(git2::Error::from_str(&message)));
+            }
         }
-        Ok(())
+        let force = request.force_if_at.is_some();
+
+        let action = match current {
+            None => "create".to_string(),
+            Some(old) if old == local => "up to date".to_string(),
+            Some(old) if self.repo.graph_descendant_of(local, old).unwrap_or(false) => {
+                format!(
+                    "fast-forward {}..{}",
+                    &old.to_string()[..7],
+                    &local.to_string()[..7]
+                )
+            }
+            Some(old) if force => {
+                format!(
+                    "force-update {}...{}",
+                    &old.to_string()[..7],
+                    &local.to_string()[..7]
+                )
+            }
+            Some(_) => return Err(push_rejected(&request.remote, &request.destination)),
+        };
+
+        let mut refspecs = vec![format!(
+            "{}{}:{}",
+            if force { "+" } else { "" },
+            source,
+            destination
+        )];
+        if request.tags {
+            for tag in self.repo.tag_names(None)?.iter().flatten() {
+                let tag_ref = format!("refs/tags/{}", tag);
+                if !advertised.contains_key(&tag_ref) {
+                    refspecs.push(format!("{}:{}", tag_ref, tag_ref));
+                }
+            }
+        }
+        let tag_note = match refspecs.len() - 1 {
+            0 => String::new(),
+            n => format!(" + {} tag(s)", n),
+        };
+
+        if !request.no_verify {
+            self.run_pre_push(&request.remote, &refspecs, &advertised)?;
+        }
+
+        if request.dry_run {
+            return Ok(format!(
+                "Dry run: would {} {}:{} on {}{}",
+                action, request.source, request.destination, request.remote, tag_note
+            ));
+        }
+
+        self.push_refspecs(
+            &request.remote,
+            &refspecs,
+            request.force_if_at.as_deref(),
+            progress,
+        )?;
+
+        if request.set_upstream {
+            if let (Some(local_name), Some(remote_branch)) = (
+                source.strip_prefix("refs/heads/"),
+                destination.strip_prefix("refs/heads/"),
+            ) {
+                let mut branch = self.repo.find_branch(local_name, git2::BranchType::Local)?;
+                branch.set_upstream(Some(&format!("{}/{}", request.remote, remote_branch)))?;
+            }
+        }
+
+        Ok(format!(
+            "Pushed {} to {}/{} ({}){}",
+            request.source, request.remote, request.destination, action, tag_note
+        ))
     }
 
-    /// Push a specific branch to remote
-    pub fn push_branch(&self, remote_name: &str, branch: &str) -> Result<()> {
-        let output = std::process::Command::new("git")
-            .args(["push", remote_name, branch])
-            .current_dir(&self.path)
-            .output()
-            .map_err(Error::Io)?;
+    /// Run pre-push with one "<local ref> <local sha> <remote ref> <remote sha>" line per refspec
+    fn run_pre_push(
+        &self,
+        remote_name: &str,
+        refspecs: &[String],
+        advertised: &std::collections::HashMap<String, git2::Oid>,
+    ) -> Result<()> {
+        let remote = self.repo.find_remote(remote_name)?;
+        let url = remote.pushurl().or(remote.url()).unwrap_or_default();
+        let mut input = String::new();
+        for refspec in refspecs {
+            let (source, destination) = refspec
+                .trim_start_matches('+')
+                .split_once(':')
+                .unwrap_or_default();
+            // The object being pushed, as git reports it: an annotated tag's own id
+            let local = self.repo.revparse_single(source)?.id();
+            let remote_id = advertised
+                .get(destination)
+                .copied()
+                .unwrap_or_else(git2::Oid::zero);
+            input.push_str(&format!(
+                "{} {} {} {}\n",
+                source, local, destination, remote_id
+            ));
+        }
+        self.hooks()
+            .run("pre-push", &[remote_name, url], Some(&input))
+    }
 
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            return Err(Error::Git(git2::Error::from_str(&stderr)));
+    /// Refs advertised by `remote_name`, as seen when connecting for a push
+    fn remote_heads(
+        &self,
+        remote_name: &str,
+        progress: &TransferProgress,
+    ) -> Result<std::collections::HashMap<String, git2::Oid>> {
+        let mut remote = self.repo.find_remote(remote_name)?;
+        let callbacks = progress.callbacks(self.repo.config()?);
+        let connection =
+            progress.finish(remote.connect_auth(git2::Direction::Push, Some(callbacks), None))?;
+        Ok(connection
+            .list()?
+            .iter()
+            .map(|head| (head.name().to_string(), head.oid()))
+            .collect())
+    }
+
+    /// Upstream of a local branch as (remote, remote branch), from branch.<name>.remote/merge
+    pub fn branch_upstream(&self, branch: &str) -> Option<(String, String)> {
+        let config = self.repo.config().ok()?;
+        let remote = config
+            .get_string(&format!("branch.{}.remote", branch))
+            .ok()?;
+        let merge = config
+            .get_string(&format!("branch.{}.merge", branch))
+            .ok()?;
+        let merge = merge.strip_prefix("refs/heads/")?.to_string();
+        Some((remote, merge))
+    }
+
+    /// Commit id of the remote-tracking ref `remote/branch`, if it exists
+    pub fn remote_tracking_id(&self, remote: &str, branch: &str) -> Option<String> {
+        self.repo
+            .find_reference(&format!("refs/remotes/{}/{}", remote, branch))
+            .ok()?
+            .target()
+            .map(|oid| oid.to_string())
+    }
+
+    /// Push `refspecs`. With `expected`, the first refspec's destination must
+    /// still be at that commit ("" = must not exist) when the push connection
+    /// negotiates, so a push that landed since it was last checked is not
+    /// overwritten.
+    fn push_refspecs(
+        &self,
+        remote_name: &str,
+        refspecs: &[String],
+        expected: Option<&str>,
+        progress: &TransferProgress,
+    ) -> Result<()> {
+        let mut remote = self.repo.find_remote(remote_name)?;
+        let rejected = std::cell::RefCell::new(Vec::new());
+        let destination = refspecs[0].rsplit(':').next().unwrap_or_default();
+        let mut callbacks = progress.push_callbacks(self.repo.config()?, move |updates| {
+            let Some(expected) = expected else {
+                return Ok(());
+            };
+            let update = updates
+                .iter()
+                .find(|update| update.dst_refname() == Some(destination));
+            if let Some(update) = update {
+                let actual = Some(update.src()).filter(|oid| !oid.is_zero());
+                if let Some(message) = force_mismatch(remote_name, destination, actual, expected) {
+                    return Err(git2::Error::from_str(&message));
+                }
+            }
+            Ok(())
+        });
+        callbacks.push_update_reference(|refname, status| {
+            if let Some(reason) = status {
+                rejected
+                    .borrow_mut()
+                    .push(format!("{} ({})", refname, reason));
+            }
+            Ok(())
+        });
+        let mut opts = git2::PushOptions::new();
+        opts.remote_callbacks(callbacks);
+        match progress.finish(remote.push(refspecs, Some(&mut opts))) {
+            Err(Error::Git(e)) if e.code() == git2::ErrorCode::NotFastForward => {
+                return Err(push_rejected(
+                    remote_name,
+                    &refspec_destination(&refspecs[0]),
+                ));
+            }
+            other => other?,
+        }
+        drop(opts);
+
+        let rejected = rejected.into_inner();
+        // The server refused because its ref moved on (e.g. "fetch first")
+        if rejected
+            .iter()
+            .any(|r| r.contains("non-fast-forward") || r.contains("fetch first"))
+        {
+            return Err(push_rejected(
+                remote_name,
+                &refspec_destination(&refspecs[0]),
+            ));
+        }
+        if !rejected.is_empty() {
+            return Err(Error::Git(git2::Error::from_str(&format!(
+                "Push rejected: {}",
+                rejected.join(", ")
+            ))));
         }
         Ok(())
     }
 
-    /// Push with upstream tracking (-u flag)
-    pub fn push_set_upstream(&self, remote_name: &str, branch: &str) -> Result<()> {
-        let output = std::process::Command::new("git")
-            .args(["push", "-u", remote_name, branch])
-            .current_dir(&self.path)
-            .output()
-            .map_err(Error::Io)?;
+    /// Untracked files status lists per git's `status.showUntrackedFiles`
+    pub fn untracked_default(&self) -> UntrackedFiles {
+        self.repo
+            .config()
+            .and_then(|config| config.get_string("status.showUntrackedFiles"))
+            .ok()
+            .and_then(|value| UntrackedFiles::parse(&value))
+            .unwrap_or_default()
+    }
 
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            return Err(Error::Git(git2::Error::from_str(&stderr)));
+    /// Pull defaults from git config (pull.rebase, pull.ff, rebase/merge.autoStash)
+    pub fn pull_defaults(&self) -> PullOptions {
+        let config = match self.repo.config() {
+            Ok(config) => config,
+            Err(_) => return PullOptions::default(),
+        };
+        // Any boolean spelling git accepts; "merges" and "interactive" rebase too
+        let rebase = config.get_bool("pull.rebase").unwrap_or_else(|_| {
+            matches!(
+                config.get_string("pull.rebase").as_deref(),
+                Ok("merges" | "m" | "interactive" | "i")
+            )
+        });
+        let ff_only = config
+            .get_string("pull.ff")
+            .map(|v| v == "only")
+            .unwrap_or(false);
+        let strategy = if rebase {
+            PullStrategy::Rebase
+        } else if ff_only {
+            PullStrategy::FastForwardOnly
+        } else {
+            PullStrategy::Merge
+        };
+        let autostash_key = if rebase {
+            "rebase.autoStash"
+        } else {
+            "merge.autoStash"
+        };
+        PullOptions {
+            strategy,
+            autostash: config.get_bool(autostash_key).unwrap_or(false),
         }
-        Ok(())
     }
 
-    pub fn fetch_branch(&self, remote_name: &str, branch: &str) -> Result<()> {
-        let output = std::process::Command::new("git")
-            .args(["fetch", remote_name, branch])
-            .current_dir(&self.path)
-            .output()
-            .map_err(Error::Io)?;
+    /// Pull the current branch from its upstream branch on `remote_name`
+    pub fn pull(
+        &mut self,
+        remote_name: &str,
+        options: PullOptions,
+        progress: &TransferProgress,
+    ) -> Result<PullOutcome> {
+        let branch = self.current_branch_name()?;
+        let upstream = self
+            .upstream_branch_name(&branch, remote_name)
+            .unwrap_or(branch);
+        self.pull_branch(remote_name, &upstream, options, progress)
+    }
 
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            return Err(Error::Git(git2::Error::from_str(&stderr)));
+    /// Fetch `remote_name/branch` and integrate it into HEAD with the chosen strategy
+    pub fn pull_branch(
+        &mut self,
+        remote_name: &str,
+        branch: &str,
+        options: PullOptions,
+        progress: &TransferProgress,
+    ) -> Result<PullOutcome> {
+        self.fetch_branch(remote_name, branch, progress)?;
+
+        let tracking = format!("refs/remotes/{}/{}", remote_name, branch);
+        let url = self
+            .repo
+            .find_remote(remote_name)?
+            .url()
+            .unwrap_or(remote_name)
+            .to_string();
+        let message = format!("Merge branch '{}' of {}", branch, url);
+
+        let autostash = if options.autostash && has_tracked_changes(&self.repo) {
+            let signature = self.repo.signature()?;
+            let message = format!("autostash before pull {}/{}", remote_name, branch);
+            Some(self.repo.stash_save(&signature, &message, None)?)
+        } else {
+            None
+        };
+
+        let before = self.journal_begin(&[]);
+        let old_head = self.head_id();
+        let result = match self.integrate(&tracking, &message, options.strategy) {
+            Ok(result) => result,
+            Err(e) => {
+                if let Some(stash) = autostash {
+                    self.restore_autostash(stash);
+                }
+                return Err(e);
+            }
+        };
+        let new_head = self.head_id();
+        self.journal_end(format!("pull {} {}", remote_name, branch), before, true);
+
+        // While conflicts are pending the stash stays put; the user re-applies it afterwards
+        let stash_kept = match autostash {
+            Some(_) if result.needs_resolution() => true,
+            Some(stash) => self.restore_autostash(stash),
+            None => false,
+        };
+
+        Ok(PullOutcome {
+            result: self.classify(result, old_head.as_deref(), new_head.as_deref()),
+            old_head,
+            new_head,
+            stash_kept,
+        })
+    }
+
+    fn head_id(&self) -> Option<String> {
+        self.repo
+            .head()
+            .ok()
+            .and_then(|h| h.target())
+            .map(|id| id.to_string())
+    }
+
+    /// Cross-check a result against HEAD movement and the repository state
+    fn classify(&self, result: MergeResult, old: Option<&str>, new: Option<&str>) -> MergeResult {
+        match self.repo.state() {
+            git2::RepositoryState::RebaseMerge | git2::RepositoryState::Rebase => {
+                MergeResult::StoppedForConflict
+            }
+            git2::RepositoryState::Merge => MergeResult::Conflict,
+            _ if old == new && result != MergeResult::Aborted => MergeResult::UpToDate,
+            _ => result,
         }
-        Ok(())
     }
 
-    pub fn pull(&self, remote_name: &str) -> Result<MergeResult> {
-        let output = std::process::Command::new("git")
-            .args(["pull", remote_name])
-            .current_dir(&self.path)
-            .output()
-            .map_err(Error::Io)?;
+    /// Integrate the `tracking` ref into HEAD; fast-forwards are taken under every strategy
+    fn integrate(
+        &self,
+        tracking: &str,
+        message: &str,
+        strategy: PullStrategy,
+    ) -> Result<MergeResult> {
+        let reference = self.repo.find_reference(tracking)?;
+        let annotated = self.repo.reference_to_annotated_commit(&reference)?;
+        let (analysis, _) = self.repo.merge_analysis(&[&annotated])?;
+        if analysis.contains(git2::MergeAnalysis::ANALYSIS_UP_TO_DATE)
+            || analysis.contains(git2::MergeAnalysis::ANALYSIS_FASTFORWARD)
+        {
+            return self.merge_annotated(&annotated, message, false);
+        }
+        match strategy {
+            PullStrategy::Merge => self.merge_annotated(&annotated, message, false),
+            PullStrategy::Rebase => self.rebase_onto(&annotated),
+            PullStrategy::FastForwardOnly => Ok(MergeResult::Aborted),
+        }
+    }
 
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        let stderr = String::from_utf8_lossy(&output.stderr);
+    /// Replay HEAD's commits onto `upstream`; a conflict leaves the rebase stopped on disk
+    fn rebase_onto(&self, upstream: &git2::AnnotatedCommit) -> Result<MergeResult> {
+        let mut opts = git2::RebaseOptions::new();
+        let mut rebase = self
+            .repo
+            .rebase(None, Some(upstream), None, Some(&mut opts))?;
+        self.run_rebase(&mut rebase)
+    }
 
-        if !output.status.success() {
-            // Check for conflict
-            if stderr.contains("CONFLICT") || stdout.contains("CONFLICT") {
-                return Ok(MergeResult::Conflict);
+    fn run_rebase(&self, rebase: &mut git2::Rebase) -> Result<MergeResult> {
+        let signature = self.repo.signature()?;
+        while let Some(op) = rebase.next() {
+            if let Err(e) = op {
+                rebase.abort()?;
+                return Err(e.into());
             }
-            return Err(Error::Git(git2::Error::from_str(&stderr)));
+            if self.repo.index()?.has_conflicts() {
+                return Ok(MergeResult::StoppedForConflict);
+            }
+            Self::rebase_commit(rebase, &signature)?;
         }
+        rebase.finish(Some(&signature))?;
+        Ok(MergeResult::Rebased)
+    }
 
-        // Parse output to determine result
-        if stdout.contains("Already up to date") || stdout.contains("Already up-to-date") {
-            Ok(MergeResult::UpToDate)
-        } else if stdout.contains("Fast-forward") {
-            Ok(MergeResult::FastForward)
-        } else {
-            Ok(MergeResult::Merged)
+    fn rebase_commit(rebase: &mut git2::Rebase, signature: &Signature) -> Result<()> {
+        match rebase.commit(None, signature, None) {
+            Ok(_) => Ok(()),
+            // The change is already upstream; drop the now-empty commit
+            Err(e) if e.code() == git2::ErrorCode::Applied => Ok(()),
+            Err(e) => Err(e.into()),
         }
     }
 
-    pub fn pull_branch(&self, remote_name: &str, branch: &str) -> Result<MergeResult> {
-        let output = std::process::Command::new("git")
-            .args(["pull", remote_name, branch])
-            .current_dir(&self.path)
-            .output()
-            .map_err(Error::Io)?;
+    pub fn rebase_in_progress(&self) -> bool {
+        matches!(
+            self.repo.state(),
+            git2::RepositoryState::RebaseMerge
+                | git2::RepositoryState::Rebase
+                | git2::RepositoryState::RebaseInteractive
+        )
+    }
 
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        let stderr = String::from_utf8_lossy(&output.stderr);
+    /// Commit the resolved step of a stopped rebase and replay the remaining commits
+    pub fn continue_rebase(&self) -> Result<MergeResult> {
+        let mut rebase = self.repo.open_rebase(None)?;
+        if self.repo.index()?.has_conflicts() {
+            return Err(Error::Git(git2::Error::from_str(
+                "Resolve all conflicts before continuing the rebase",
+            )));
+        }
+        let branch: Vec<String> = rebase
+            .orig_head_name()
+            .map(String::from)
+            .into_iter()
+            .collect();
+        let before = self.journal_begin(&branch);
+        let signature = self.repo.signature()?;
+        Self::rebase_commit(&mut rebase, &signature)?;
+        let result = self.run_rebase(&mut rebase)?;
+        self.journal_end("rebase continue".to_string(), before, true);
+        Ok(result)
+    }
 
-        if !output.status.success() {
-            // Check for conflict
-            if stderr.contains("CONFLICT") || stdout.contains("CONFLICT") {
-                return Ok(MergeResult::Conflict);
+    /// Abort an in-progress merge or rebase and restore the state before it started
+    pub fn abort_operation(&self) -> Result<String> {
+        if self.am_in_progress() {
+            return self.abort_am();
+        }
+        match self.repo.state() {
+            git2::RepositoryState::RebaseMerge
+            | git2::RepositoryState::Rebase
+            | git2::RepositoryState::RebaseInteractive => {
+                self.trash_snapshot("rebase --abort", &self.changed_paths(false)?)?;
+                self.repo.open_rebase(None)?.abort()?;
+                Ok("Rebase aborted".to_string())
             }
-            return Err(Error::Git(git2::Error::from_str(&stderr)));
+            git2::RepositoryState::Merge => {
+                self.trash_snapshot("merge --abort", &self.changed_paths(false)?)?;
+                let before = self.journal_begin(&[]);
+                let head = self.repo.head()?.peel_to_commit()?;
+                self.repo
+                    .reset(head.as_object(), git2::ResetType::Hard, None)?;
+                self.repo.cleanup_state()?;
+                self.journal_end("merge --abort".to_string(), before, true);
+                Ok("Merge aborted".to_string())
+            }
+            _ => Err(Error::Git(git2::Error::from_str(
+                "No merge or rebase in progress",
+            ))),
         }
+    }
 
-        // Parse output to determine result
-        if stdout.contains("Already up to date") || stdout.contains("Already up-to-date") {
-            Ok(MergeResult::UpToDate)
-        } else if stdout.contains("Fast-forward") {
-            Ok(MergeResult::FastForward)
-        } else {
-            Ok(MergeResult::Merged)
+    fn current_branch_name(&self) -> Result<String> {
+        let head = self.repo.head()?;
+        if !head.is_branch() {
+            return Err(Error::Git(git2::Error::from_str(
+                "HEAD is detached; checkout a branch first",
+            )));
         }
+        Ok(head.shorthand().unwrap_or_default().to_string())
+    }
+
+    /// Name of the branch `branch` tracks on `remote_name`, from branch.<name>.merge
+    fn upstream_branch_name(&self, branch: &str, remote_name: &str) -> Option<String> {
+        self.branch_upstream(branch)
+            .filter(|(remote, _)| remote == remote_name)
+            .map(|(_, merge)| merge)
     }
 
     // Remote info
@@ -1218,32 +2949,67 @@ impl Repository {
     pub fn worktrees(&self) -> Result<Vec<WorktreeInfo>> {
         let mut worktrees = Vec::new();
 
-        // Main worktree
-        let main_path = self.path.to_string_lossy().to_string();
-        let main_head = self
-            .head_name()?
-            .unwrap_or_else(|| self.head_commit_short().unwrap_or_default());
-        worktrees.push(WorktreeInfo {
+        // Main worktree. From a linked worktree the git dir is
+        // `<main>/.git/worktrees/<name>`, whose `commondir` file points back
+        // at `<main>/.git`.
+        let main_path = if self.repo.is_worktree() {
+            std::fs::read_to_string(self.repo.path().join("commondir"))
+                .ok()
+                .and_then(|dir| self.repo.path().join(dir.trim()).canonicalize().ok())
+                .and_then(|common| common.parent().map(|p| p.to_path_buf()))
+                .unwrap_or_else(|| self.path.clone())
+        } else {
+            self.path.clone()
+        };
+        let mut main = WorktreeInfo {
             name: "main".to_string(),
-            path: main_path,
-            head: main_head,
+            path: main_path.to_string_lossy().to_string(),
+            head: String::new(),
+            branch: None,
             is_main: true,
+            is_current: same_path(&main_path, &self.path),
             is_locked: false,
-        });
+            lock_reason: None,
+            is_prunable: false,
+            is_dirty: false,
+            ahead: 0,
+            behind: 0,
+        };
+        if let Ok(repo) = Git2Repository::open(&main_path) {
+            fill_worktree_state(&repo, &mut main);
+        }
+        worktrees.push(main);
 
-        // Additional worktrees
+        // Linked worktrees
         if let Ok(wts) = self.repo.worktrees() {
             for name in wts.iter().flatten() {
                 if let Ok(wt) = self.repo.find_worktree(name) {
-                    let path = wt.path().to_string_lossy().to_string();
-                    let is_locked = wt.is_locked().is_ok();
-                    worktrees.push(WorktreeInfo {
+                    let (is_locked, lock_reason) = match wt.is_locked() {
+                        Ok(git2::WorktreeLockStatus::Locked(reason)) => {
+                            (true, reason.filter(|r| !r.is_empty()))
+                        }
+                        _ => (false, None),
+                    };
+                    let mut info = WorktreeInfo {
                         name: name.to_string(),
-                        path,
-                        head: String::new(), // Would need to open the worktree to get head
+                        path: wt.path().to_string_lossy().to_string(),
+                        head: String::new(),
+                        branch: None,
                         is_main: false,
+                        is_current: same_path(wt.path(), &self.path),
                         is_locked,
-                    });
+                        lock_reason,
+                        is_prunable: wt.validate().is_err(),
+                        is_dirty: false,
+                        ahead: 0,
+                        behind: 0,
+                    };
+                    if !info.is_prunable {
+                        if let Ok(repo) = Git2Repository::open_from_worktree(&wt) {
+                            fill_worktree_state(&repo, &mut info);
+                        }
+                    }
+                    worktrees.push(info);
                 }
             }
         }
@@ -1251,6 +3017,105 @@ impl Repository {
         Ok(worktrees)
     }
 
+    /// Create a linked worktree at `path`.
+    ///
+    /// If `target` names a local branch, that branch is checked out. Any other
+    /// revision (remote branch, tag, commit) gets a new branch named after the
+    /// worktree, like `git worktree add -b <name> <path> <target>`. Without a
+    /// target the new branch starts at HEAD.
+    pub fn worktree_add(&self, path: &str, target: Option<&str>) -> Result<String> {
+        let path = Path::new(path);
+        let path = if path.is_relative() {
+            self.path.join(path)
+        } else {
+            path.to_path_buf()
+        };
+        let name = path
+            .file_name()
+            .map(|s| s.to_string_lossy().to_string())
+            .ok_or_else(|| Error::Git(git2::Error::from_str("Invalid worktree path")))?;
+
+        if self.repo.find_worktree(&name).is_ok() {
+            return Err(Error::Git(git2::Error::from_str(&format!(
+                "Worktree '{}' already exists",
+                name
+            ))));
+        }
+
+        let local = target.and_then(|t| self.repo.find_branch(t, git2::BranchType::Local).ok());
+        let reference = match local {
+            Some(branch) => branch.into_reference(),
+            None => {
+                let commit = match target {
+                    Some(t) => self.repo.revparse_single(t)?.peel_to_commit()?,
+                    None => self.repo.head()?.peel_to_commit()?,
+                };
+                self.repo.branch(&name, &commit, false)?.into_reference()
+            }
+        };
+
+        let mut opts = git2::WorktreeAddOptions::new();
+        opts.reference(Some(&reference));
+        self.repo.worktree(&name, &path, Some(&opts))?;
+        Ok(name)
+    }
+
+    /// Remove a linked worktree and its working directory.
+    ///
+    /// Without `force`, worktrees with uncommitted changes or a lock are refused.
+    pub fn worktree_remove(&self, name: &str, force: bool) -> Result<()> {
+        let wt = self.repo.find_worktree(name)?;
+
+        if !force {
+            if let Ok(git2::WorktreeLockStatus::Locked(_)) = wt.is_locked() {
+                return Err(Error::Git(git2::Error::from_str(&format!(
+                    "Worktree '{}' is locked",
+                    name
+                ))));
+            }
+            if wt.validate().is_ok() {
+                let repo = Git2Repository::open_from_worktree(&wt)?;
+                if is_dirty(&repo) {
+                    return Err(Error::Git(git2::Error::from_str(&format!(
+                        "Worktree '{}' has uncommitted changes",
+                        name
+                    ))));
+                }
+            }
+        }
+
+        let mut opts = git2::WorktreePruneOptions::new();
+        opts.valid(true).locked(force).working_tree(true);
+        wt.prune(Some(&mut opts))?;
+        Ok(())
+    }
+
+    pub fn worktree_lock(&self, name: &str, reason: Option<&str>) -> Result<()> {
+        let wt = self.repo.find_worktree(name)?;
+        wt.lock(reason)?;
+        Ok(())
+    }
+
+    pub fn worktree_unlock(&self, name: &str) -> Result<()> {
+        let wt = self.repo.find_worktree(name)?;
+        wt.unlock()?;
+        Ok(())
+    }
+
+    /// Prune administrative data of worktrees whose directory no longer exists.
+    /// Locked worktrees are kept. Returns the names of pruned worktrees.
+    pub fn worktree_prune(&self) -> Result<Vec<String>> {
+        let mut pruned = Vec::new();
+        for name in self.repo.worktrees()?.iter().flatten() {
+            let wt = self.repo.find_worktree(name)?;
+            if wt.is_prunable(None)? {
+                wt.prune(None)?;
+                pruned.push(name.to_string());
+            }
+        }
+        Ok(pruned)
+    }
+
     // Submodule operations
     pub fn submodules(&self) -> Result<Vec<SubmoduleInfo>> {
         let mut submodules = Vec::new();
@@ -1259,23 +3124,417 @@ impl Repository {
             let name = sm.name().unwrap_or("").to_string();
             let path = sm.path().to_string_lossy().to_string();
             let url = sm.url().unwrap_or("").to_string();
-            let head = sm.head_id().map(|oid| oid.to_string()[..7].to_string());
+            let head_id = sm.head_id();
+            let workdir_id = sm.workdir_id();
+            let head = head_id.map(|oid| oid.to_string()[..7].to_string());
+            let workdir = workdir_id.map(|oid| oid.to_string()[..7].to_string());
+
+            let status = self
+                .repo
+                .submodule_status(&name, git2::SubmoduleIgnore::None)
+                .unwrap_or(git2::SubmoduleStatus::empty());
+            let is_dirty = status.intersects(
+                git2::SubmoduleStatus::WD_INDEX_MODIFIED
+                    | git2::SubmoduleStatus::WD_WD_MODIFIED
+                    | git2::SubmoduleStatus::WD_UNTRACKED,
+            );
+            let has_drift = matches!((head_id, workdir_id), (Some(h), Some(w)) if h != w);
 
             submodules.push(SubmoduleInfo {
                 name,
                 path,
                 url,
                 head,
+                workdir,
                 is_initialized: sm.open().is_ok(),
+                has_drift,
+                is_dirty,
             });
         }
 
         Ok(submodules)
     }
 
-    pub fn submodule_update(&self, name: &str) -> Result<()> {
+    /// Update a submodule to the commit recorded in the superproject, cloning
+    /// it first if needed. With `recursive`, nested submodules are updated too.
+    pub fn submodule_update(&self, name: &str, recursive: bool) -> Result<()> {
         let mut sm = self.repo.find_submodule(name)?;
+
+        // After a deinit the module's git dir is still in .git/modules; libgit2
+        // refuses to clone over it, so reattach the working directory instead
+        let module_dir = self.repo.path().join("modules").join(name);
+        if sm.open().is_err() && module_dir.exists() {
+            let workdir = self.path.join(sm.path());
+            std::fs::create_dir_all(&workdir)?;
+            std::fs::write(
+                workdir.join(".git"),
+                format!("gitdir: {}\n", module_dir.display()),
+            )?;
+            sm.init(false)?;
+            // The deinit emptied the directory; restore the files of its HEAD
+            sm.open()?
+                .checkout_head(Some(git2::build::CheckoutBuilder::new().force()))?;
+        }
+
         sm.update(true, None)?;
+        if recursive {
+            let nested = Repository {
+                path: self.path.join(sm.path()),
+                repo: sm.open()?,
+                hook_log: self.hook_log.clone(),
+                cancel: self.cancel.clone(),
+                size_limit: self.size_limit,
+            };
+            for child in nested.repo.submodules()? {
+                if let Some(child_name) = child.name() {
+                    nested.submodule_update(child_name, true)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Copy the submodule URL from .gitmodules into .git/config
+    pub fn submodule_init(&self, name: &str) -> Result<()> {
+        let mut sm = self.repo.find_submodule(name)?;
+        sm.init(false)?;
+        Ok(())
+    }
+
+    /// Unregister a submodule and empty its working directory, like
+    /// `git submodule deinit`. Refuses when the submodule has local changes
+    /// unless `force` is set.
+    pub fn submodule_deinit(&self, name: &str, force: bool) -> Result<()> {
+        let sm = self.repo.find_submodule(name)?;
+        let workdir = self.path.join(sm.path());
+
+        if !force {
+            let status = self
+                .repo
+                .submodule_status(name, git2::SubmoduleIgnore::None)?;
+            if status.intersects(
+                git2::SubmoduleStatus::WD_INDEX_MODIFIED
+                    | git2::SubmoduleStatus::WD_WD_MODIFIED
+                    | git2::SubmoduleStatus::WD_UNTRACKED,
+            ) {
+                return Err(Error::Git(git2::Error::from_str(&format!(
+                    "Submodule '{}' has local modifications",
+                    name
+                ))));
+            }
+        }
+
+        // Remove the submodule section from the local config
+        let mut config = self.repo.config()?.open_level(git2::ConfigLevel::Local)?;
+        let prefix = format!("submodule.{}.", name);
+        let mut keys = Vec::new();
+        {
+            let mut entries = config.entries(None)?;
+            while let Some(entry) = entries.next() {
+                if let Some(key) = entry?.name() {
+                    if key.starts_with(&prefix) {
+                        keys.push(key.to_string());
+                    }
+                }
+            }
+        }
+        for key in keys {
+            config.remove(&key)?;
+        }
+
+        // Empty the working directory but keep the (now empty) mount point
+        if workdir.exists() {
+            for entry in std::fs::read_dir(&workdir)? {
+                let entry_path = entry?.path();
+                if entry_path.is_dir() {
+                    std::fs::remove_dir_all(&entry_path)?;
+                } else {
+                    std::fs::remove_file(&entry_path)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Re-copy the submodule URL from .gitmodules into .git/config and the
+    /// submodule's own remote
+    pub fn submodule_sync(&self, name: &str) -> Result<()> {
+        let mut sm = self.repo.find_submodule(name)?;
+        sm.sync()?;
+        Ok(())
+    }
+
+    /// Clone `url` into `path` and register it in .gitmodules and the index
+    pub fn submodule_add(&self, url: &str, path: &str) -> Result<()> {
+        let mut sm = self.repo.submodule(url, Path::new(path), true)?;
+        sm.clone(None)?;
+        sm.add_finalize()?;
+        Ok(())
+    }
+
+    // Reflog operations
+    /// Refs that have a reflog worth browsing: HEAD followed by local branches
+    pub fn reflog_refs(&self) -> Result<Vec<String>> {
+        let mut refs = vec!["HEAD".to_string()];
+        for branch in self.repo.branches(Some(git2::BranchType::Local))? {
+            let (branch, _) = branch?;
+            if let Some(name) = branch.get().name() {
+                refs.push(name.to_string());
+            }
+        }
+        Ok(refs)
+    }
+
+    pub fn commit_info(&self, commit_id: &str) -> Result<CommitInfo> {
+        let commit = self.repo.revparse_single(commit_id)?.peel_to_commit()?;
+        Ok(CommitInfo::from_commit(&commit))
+    }
+
+    pub fn reflog(&self, refname: &str) -> Result<Vec<ReflogEntry>> {
+        let reflog = self.repo.reflog(refname)?;
+        Ok(reflog
+            .iter()
+            .enumerate()
+            .map(|(index, entry)| {
+                let id = entry.id_new().to_string();
+                ReflogEntry {
+                    index,
+                    short_id: id[..7].to_string(),
+                    id,
+                    old_id: entry.id_old().to_string(),
+                    message: entry.message().unwrap_or_default().to_string(),
+                    committer: entry.committer().name().unwrap_or_default().to_string(),
+                    time: entry.committer().when().seconds(),
+                }
+            })
+            .collect())
+    }
+
+    // Undo journal
+    /// Description of the operation `undo` would revert
+    pub fn last_operation(&self) -> Option<String> {
+        Journal::load(self.repo.path())
+            .undo
+            .last()
+            .map(|op| op.description.clone())
+    }
+
+    /// Revert the last journaled operation, returning its description
+    pub fn undo(&mut self) -> Result<Option<String>> {
+        let git_dir = self.repo.path().to_path_buf();
+        let _lock = Journal::lock(&git_dir)?;
+        let mut journal = Journal::load(&git_dir);
+        let op = match journal.undo.pop() {
+            Some(op) => op,
+            None => return Ok(None),
+        };
+        self.check_ref_states(&op.after, &op.description)?;
+
+        // Move the working tree first so a blocking dirty tree leaves refs untouched
+        if op.checkout {
+            self.checkout_ref_states(&op.before)?;
+        }
+        if let Some(created) = &op.created_stash {
+            if let Some(index) = self.stash_index_of(&created.id) {
+                self.repo.stash_pop(index, None)?;
+            }
+        }
+        self.apply_ref_states(&op.before, "g: undo")?;
+        for stash in op.dropped_stashes.iter().rev() {
+            self.stash_store(stash)?;
+        }
+
+        let description = op.description.clone();
+        journal.redo.push(op);
+        self.save_journal(&journal);
+        Ok(Some(description))
+    }
+
+    /// Re-apply the last undone operation, returning its description
+    pub fn redo(&mut self) -> Result<Option<String>> {
+        let git_dir = self.repo.path().to_path_buf();
+        let _lock = Journal::lock(&git_dir)?;
+        let mut journal = Journal::load(&git_dir);
+        let mut op = match journal.redo.pop() {
+            Some(op) => op,
+            None => return Ok(None),
+        };
+        self.check_ref_states(&op.before, &op.description)?;
+
+        if op.checkout {
+            self.checkout_ref_states(&op.after)?;
+        }
+        for stash in &op.dropped_stashes {
+            if let Some(index) = self.stash_index_of(&stash.id) {
+                self.repo.stash_drop(index)?;
+            }
+        }
+        if let Some(created) = &mut op.created_stash {
+            let signature = self.repo.signature()?;
+            let oid = self.repo.stash_save(&signature, &created.message, None)?;
+            created.id = oid.to_string();
+        }
+        self.apply_ref_states(&op.after, "g: redo")?;
+
+        let description = op.description.clone();
+        journal.undo.push(op);
+        self.save_journal(&journal);
+        Ok(Some(description))
+    }
+
+    /// Snapshot HEAD, the branch it points to and `extra` refs before an operation
+    fn journal_begin(&self, extra: &[String]) -> Vec<RefState> {
+        let mut names = vec!["HEAD".to_string()];
+        if let Ok(head) = self.repo.find_reference("HEAD") {
+            if let Some(target) = head.symbolic_target() {
+                names.push(target.to_string());
+            }
+        }
+        for name in extra {
+            if !names.contains(name) {
+                names.push(name.clone());
+            }
+        }
+        names.iter().map(|name| self.ref_state(name)).collect()
+    }
+
+    fn journal_operation(
+        &self,
+        description: String,
+        before: Vec<RefState>,
+        checkout: bool,
+    ) -> Operation {
+        let after = before.iter().map(|s| self.ref_state(&s.name)).collect();
+        Operation {
+            description,
+            time: chrono::Utc::now().timestamp(),
+            before,
+            after,
+            checkout,
+            dropped_stashes: Vec::new(),
+            created_stash: None,
+        }
+    }
+
+    fn journal_end(&self, description: String, before: Vec<RefState>, checkout: bool) {
+        let op = self.journal_operation(description, before, checkout);
+        self.journal_push(op);
+    }
+
+    fn journal_push(&self, op: Operation) {
+        if op.is_noop() {
+            return;
+        }
+        let _lock = match Journal::lock(self.repo.path()) {
+            Ok(lock) => lock,
+            Err(e) => {
+                crate::debug!("failed to lock operation journal: {}", e);
+                return;
+            }
+        };
+        let mut journal = Journal::load(self.repo.path());
+        journal.record(op);
+        self.save_journal(&journal);
+    }
+
+    fn save_journal(&self, journal: &Journal) {
+        // The operation itself already succeeded; a lost journal entry only costs undo
+        if let Err(e) = journal.save(self.repo.path()) {
+            crate::debug!("failed to save operation journal: {}", e);
+        }
+    }
+
+    fn ref_state(&self, name: &str) -> RefState {
+        let target = self
+            .repo
+            .find_reference(name)
+            .ok()
+            .and_then(|r| match r.symbolic_target() {
+                Some(symbolic) => Some(format!("ref: {}", symbolic)),
+                None => r.target().map(|oid| oid.to_string()),
+            });
+        RefState {
+            name: name.to_string(),
+            target,
+        }
+    }
+
+    /// Refuse to undo or redo over refs that moved since the journal recorded
+    /// them: forcing them back would drop the commits made in between
+    fn check_ref_states(&self, expected: &[RefState], description: &str) -> Result<()> {
+        let moved: Vec<&str> = expected
+            .iter()
+            .filter(|state| self.ref_state(&state.name) != **state)
+            .map(|state| state.name.as_str())
+            .collect();
+        if moved.is_empty() {
+            return Ok(());
+        }
+        Err(Error::Git(git2::Error::from_str(&format!(
+            "{} changed since \"{}\"",
+            moved.join(", "),
+            description
+        ))))
+    }
+
+    /// Safely check out the commit HEAD resolves to under `states`
+    fn checkout_ref_states(&self, states: &[RefState]) -> Result<()> {
+        let lookup = |name: &str| -> Option<String> {
+            match states.iter().find(|s| s.name == name) {
+                Some(state) => state.target.clone(),
+                None => self.ref_state(name).target,
+            }
+        };
+        let mut target = lookup("HEAD");
+        while let Some(symbolic) = target.as_deref().and_then(|t| t.strip_prefix("ref: ")) {
+            target = lookup(symbolic);
+        }
+        let id = match target {
+            Some(id) => git2::Oid::from_str(&id)?,
+            None => return Ok(()),
+        };
+        let commit = self.repo.find_commit(id)?;
+        let mut opts = git2::build::CheckoutBuilder::new();
+        opts.safe();
+        self.repo
+            .checkout_tree(commit.as_object(), Some(&mut opts))
+            .map_err(|e| {
+                Error::Git(git2::Error::from_str(&format!(
+                    "Local changes would be overwritten: {}",
+                    e.message()
+                )))
+            })?;
+        Ok(())
+    }
+
+    fn apply_ref_states(&self, states: &[RefState], log_message: &str) -> Result<()> {
+        for state in states.iter().filter(|s| s.name != "HEAD") {
+            match state.target.as_deref() {
+                None => {
+                    if let Ok(mut reference) = self.repo.find_reference(&state.name) {
+                        reference.delete()?;
+                    }
+                }
+                Some(target) => match target.strip_prefix("ref: ") {
+                    Some(symbolic) => {
+                        self.repo
+                            .reference_symbolic(&state.name, symbolic, true, log_message)?;
+                    }
+                    None => {
+                        let oid = git2::Oid::from_str(target)?;
+                        self.repo.reference(&state.name, oid, true, log_message)?;
+                    }
+                },
+            }
+        }
+        if let Some(head) = states.iter().find(|s| s.name == "HEAD") {
+            if let Some(target) = head.target.as_deref() {
+                match target.strip_prefix("ref: ") {
+                    Some(symbolic) => self.repo.set_head(symbolic)?,
+                    None => self.repo.set_head_detached(git2::Oid::from_str(target)?)?,
+                }
+            }
+        }
         Ok(())
     }
 
@@ -1320,6 +3579,29 @@ impl Repository {
         Ok(content)
     }
 
+    /// A working-tree file for the preview. Checks the size and looks for
+    /// binary content (a NUL in the first 8000 bytes, as git) before reading
+    /// the rest.
+    pub fn read_file_text(&self, path: &str) -> Result<FileText> {
+        use std::io::Read;
+
+        let mut file = std::fs::File::open(self.path.join(path))?;
+        let size = file.metadata()?.len();
+        if self.size_limit.is_some_and(|limit| size > limit) {
+            return Ok(FileText::Omitted(Omitted::TooLarge(size)));
+        }
+        let mut data = Vec::with_capacity(size as usize);
+        (&mut file).take(8000).read_to_end(&mut data)?;
+        if data.contains(&0) {
+            return Ok(FileText::Omitted(Omitted::Binary));
+        }
+        file.read_to_end(&mut data)?;
+        match String::from_utf8(data) {
+            Ok(text) => Ok(FileText::Text(text)),
+            Err(_) => Ok(FileText::Omitted(Omitted::Binary)),
+        }
+    }
+
     // File tree operations - lazy loading (one level at a time)
     pub fn file_tree(&self, show_ignored: bool) -> Result<Vec<FileTreeEntry>> {
         self.file_tree_dir("", show_ignored)
@@ -1485,7 +3767,7 @@ impl Repository {
 
         let mut entries = Vec::new();
         self.collect_files_recursive("", &status_map, &mut entries)?;
-        entries.sort_by(|a, b| a.path.to_lowercase().cmp(&b.path.to_lowercase()));
+        entries.sort_by_key(|e| e.path.to_lowercase());
         Ok(entries)
     }
 
@@ -1624,14 +3906,16 @@ impl Repository {
     }
 
     pub fn resolve_conflict_theirs(&self, path: &str) -> Result<()> {
-        // For theirs, we need to use MERGE_HEAD
-        let merge_head = self.repo.find_reference("MERGE_HEAD")?;
-        let commit = merge_head.peel_to_commit()?;
-        let tree = commit.tree()?;
-
-        if let Ok(entry) = tree.get_path(Path::new(path)) {
-            let blob = self.repo.find_blob(entry.id())?;
-            std::fs::write(self.path.join(path), blob.content())?;
+        // "Theirs" is the incoming side: MERGE_HEAD for a merge, the replayed commit for a rebase
+        let index = self.repo.index()?;
+        let their = index
+            .conflicts()?
+            .filter_map(|c| c.ok())
+            .find_map(|c| c.their.filter(|e| e.path == path.as_bytes()));
+
+        if let Some(entry) = their {
+            let blob = self.repo.find_blob(entry.id)?;
+            write_worktree_file(&self.path.join(path), blob.content(), entry.mode as i32)?;
         }
 
         self.stage_file(path)?;
@@ -2051,6 +4335,188 @@ pub struct RemoteInfo {
     pub push_url: Option<String>,
 }
 
+/// Fill branch, HEAD, dirty state and upstream ahead/behind of a worktree
+fn fill_worktree_state(repo: &Git2Repository, info: &mut WorktreeInfo) {
+    let head = match repo.head() {
+        Ok(h) => h,
+        Err(_) => return,
+    };
+    if let Some(oid) = head.target() {
+        let id = oid.to_string();
+        info.head = id[..7.min(id.len())].to_string();
+    }
+    if head.is_branch() {
+        info.branch = head.shorthand().map(|s| s.to_string());
+        if let Some(name) = &info.branch {
+            if let Ok(local) = repo.find_branch(name, git2::BranchType::Local) {
+                if let (Some(local_oid), Ok(upstream)) = (local.get().target(), local.upstream()) {
+                    if let Some(upstream_oid) = upstream.get().target() {
+                        let (a, b) = repo
+                            .graph_ahead_behind(local_oid, upstream_oid)
+                            .unwrap_or((0, 0));
+                        info.ahead = a;
+                        info.behind = b;
+                    }
+                }
+            }
+        }
+    }
+    info.is_dirty = is_dirty(repo);
+}
+
+fn full_branch_ref(name: &str) -> String {
+    if name.starts_with("refs/") {
+        name.to_string()
+    } else {
+        format!("refs/heads/{}", name)
+    }
+}
+
+fn refspec_destination(refspec: &str) -> String {
+    let dst = refspec.rsplit(':').next().unwrap_or(refspec);
+    dst.strip_prefix("refs/heads/").unwrap_or(dst).to_string()
+}
+
+fn short_or_none(id: &str) -> &str {
+    if id.is_empty() {
+        "(none)"
+    } else {
+        &id[..7.min(id.len())]
+    }
+}
+
+/// Why forcing `destination` on `remote` is refused when it is at `actual`
+/// rather than `expected` ("" = must not exist); None when it is as expected
+fn force_mismatch(
+    remote: &str,
+    destination: &str,
+    actual: Option<git2::Oid>,
+    expected: &str,
+) -> Option<String> {
+    let actual = actual.map(|oid| oid.to_string()).unwrap_or_default();
+    let matches = if expected.is_empty() {
+        actual.is_empty()
+    } else {
+        !actual.is_empty() && actual.starts_with(expected)
+    };
+    (!matches).then(|| {
+        format!(
+            "{} on {} is at {}, expected {}; fetch and review before forcing",
+            destination
+                .strip_prefix("refs/heads/")
+                .unwrap_or(destination),
+            remote,
+            short_or_none(&actual),
+            short_or_none(expected)
+        )
+    })
+}
+
+/// Non-fast-forward rejection, explained; callers match on `ErrorCode::NotFastForward`
+fn push_rejected(remote: &str, branch: &str) -> Error {
+    Error::Git(git2::Error::new(
+        git2::ErrorCode::NotFastForward,
+        git2::ErrorClass::Reference,
+        format!(
+            "Push rejected: {}/{} has commits that are not in your branch. \
+             Pull (or rebase) first, or force push from the push dialog",
+            remote, branch
+        ),
+    ))
+}
+
+/// The index tree saved with a trash snapshot, if anything was staged
+fn trash_index_tree<'r>(commit: &git2::Commit<'r>) -> Option<git2::Tree<'r>> {
+    commit.parent(1).ok().and_then(|p| p.tree().ok())
+}
+
+/// An index entry for blob `id` at `path`, without stat data
+fn index_entry(path: &str, mode: u32, id: git2::Oid, size: usize) -> git2::IndexEntry {
+    git2::IndexEntry {
+        ctime: git2::IndexTime::new(0, 0),
+        mtime: git2::IndexTime::new(0, 0),
+        dev: 0,
+        ino: 0,
+        mode,
+        uid: 0,
+        gid: 0,
+        file_size: size as u32,
+        id,
+        flags: path.len().min(0xfff) as u16,
+        flags_extended: 0,
+        path: path.as_bytes().to_vec(),
+    }
+}
+
+#[cfg(unix)]
+fn file_mode(metadata: &std::fs::Metadata) -> u32 {
+    use std::os::unix::fs::PermissionsExt;
+    if metadata.permissions().mode() & 0o111 != 0 {
+        0o100755
+    } else {
+        0o100644
+    }
+}
+
+#[cfg(not(unix))]
+fn file_mode(_metadata: &std::fs::Metadata) -> u32 {
+    0o100644
+}
+
+/// Write a blob into the working tree as the tree entry `mode` says: a
+/// symlink for 0o120000, a file with the exec bit for 0o100755
+#[cfg(unix)]
+fn write_worktree_file(path: &Path, content: &[u8], mode: i32) -> std::io::Result<()> {
+    use std::os::unix::ffi::OsStrExt;
+    use std::os::unix::fs::PermissionsExt;
+
+    // Replace rather than write through whatever is there, which may be a symlink
+    if std::fs::symlink_metadata(path).is_ok_and(|m| !m.is_dir()) {
+        std::fs::remove_file(path)?;
+    }
+    if mode == 0o120000 {
+        return std::os::unix::fs::symlink(std::ffi::OsStr::from_bytes(content), path);
+    }
+    std::fs::write(path, content)?;
+    let mut permissions = std::fs::metadata(path)?.permissions();
+    let bits = permissions.mode();
+    permissions.set_mode(if mode == 0o100755 {
+        bits | ((bits & 0o444) >> 2)
+    } else {
+        bits & !0o111
+    });
+    std::fs::set_permissions(path, permissions)
+}
+
+#[cfg(not(unix))]
+fn write_worktree_file(path: &Path, content: &[u8], _mode: i32) -> std::io::Result<()> {
+    std::fs::write(path, content)
+}
+
+/// Modified or staged tracked files (what an autostash would save)
+fn has_tracked_changes(repo: &Git2Repository) -> bool {
+    let mut opts = git2::StatusOptions::new();
+    opts.include_untracked(false).include_ignored(false);
+    repo.statuses(Some(&mut opts))
+        .map(|s| !s.is_empty())
+        .unwrap_or(false)
+}
+
+fn is_dirty(repo: &Git2Repository) -> bool {
+    let mut opts = git2::StatusOptions::new();
+    opts.include_untracked(true).include_ignored(false);
+    repo.statuses(Some(&mut opts))
+        .map(|s| !s.is_empty())
+        .unwrap_or(false)
+}
+
+fn same_path(a: &Path, b: &Path) -> bool {
+    match (a.canonicalize(), b.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
 fn format_timestamp(timestamp: i64) -> String {
     // Simple formatting - in a real app you'd use chrono
     let secs = timestamp;
@@ -2067,5 +4533,71 @@ pub enum MergeResult {
     UpToDate,
     FastForward,
     Merged,
+    /// Merge left conflicts in the index
     Conflict,
+    Rebased,
+    /// Squash merge staged its changes; an ordinary commit finishes it
+    Squashed,
+    /// Rebase stopped at a conflicting commit and can be continued or aborted
+    StoppedForConflict,
+    /// Nothing was changed (e.g. ff-only on diverged branches)
+    Aborted,
+}
+
+impl MergeResult {
+    pub fn needs_resolution(&self) -> bool {
+        matches!(
+            self,
+            MergeResult::Conflict | MergeResult::StoppedForConflict
+        )
+    }
+}
+
+/// Outcome of a pull: the result plus HEAD before and after
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PullOutcome {
+    pub result: MergeResult,
+    pub old_head: Option<String>,
+    pub new_head: Option<String>,
+    /// Autostashed changes were not re-applied and are still in the stash list
+    pub stash_kept: bool,
+}
+
+impl PullOutcome {
+    /// Footer message for pulling `target` (e.g. "origin/main")
+    pub fn summary(&self, target: &str) -> String {
+        let range = match (&self.old_head, &self.new_head) {
+            (Some(old), Some(new)) if old != new => {
+                format!(
+                    " ({}..{})",
+                    &old[..7.min(old.len())],
+                    &new[..7.min(new.len())]
+                )
+            }
+            _ => String::new(),
+        };
+        let text = match self.result {
+            MergeResult::UpToDate => format!("Already up to date with {}", target),
+            MergeResult::FastForward => format!("Fast-forwarded to {}{}", target, range),
+            MergeResult::Merged => format!("Merged {}{}", target, range),
+            MergeResult::Rebased => format!("Rebased onto {}{}", target, range),
+            MergeResult::Squashed => format!("Squashed {} into the index", target),
+            MergeResult::Conflict => format!("Merge conflicts with {}", target),
+            MergeResult::StoppedForConflict => format!(
+                "Rebase onto {} stopped at a conflict (Conflicts: C continue, A abort)",
+                target
+            ),
+            MergeResult::Aborted => {
+                format!(
+                    "{} has diverged; cannot fast-forward, nothing changed",
+                    target
+                )
+            }
+        };
+        if self.stash_kept {
+            format!("{}; local changes kept in stash@{{0}}", text)
+        } else {
+            text
+        }
+    }
 }
@@ -1,11 +1,14 @@
-#[derive(Debug, Clone)]
+use serde::Serialize;
+
+#[derive(Debug, Clone, Serialize)]
 pub struct StatusEntry {
     pub path: String,
     pub staged: FileStatus,
     pub unstaged: FileStatus,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
 pub enum FileStatus {
     Unmodified,
     Modified,
@@ -69,3 +72,233 @@ impl FileStatus {
         !matches!(self, FileStatus::Unmodified | FileStatus::Ignored)
     }
 }
+
+/// Which untracked files status lists, as git's `status.showUntrackedFiles`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum UntrackedFiles {
+    /// None at all; skips scanning for them, the slow part in big trees
+    No,
+    /// Untracked directories as one entry ("dir/")
+    Normal,
+    /// Every untracked file
+    #[default]
+    All,
+}
+
+impl UntrackedFiles {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "no" | "false" => Some(UntrackedFiles::No),
+            "normal" => Some(UntrackedFiles::Normal),
+            "all" | "true" => Some(UntrackedFiles::All),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            UntrackedFiles::No => "no",
+            UntrackedFiles::Normal => "normal",
+            UntrackedFiles::All => "all",
+        }
+    }
+}
+
+/// What `Repository::status_with` reads
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StatusQuery {
+    pub untracked: UntrackedFiles,
+    /// Only these directories ("dir/") and files; empty for the whole tree
+    pub scope: Vec<String>,
+}
+
+/// Whether `path` lies in one of the `scope` entries
+fn in_scope(path: &str, scope: &[String]) -> bool {
+    scope.iter().any(|s| {
+        if s.ends_with('/') {
+            path.starts_with(s.as_str())
+        } else {
+            path == s || path.strip_prefix(s.as_str()) == Some("/")
+        }
+    })
+}
+
+/// Replace the entries of `old` inside `scope` with `fresh`, the status read
+/// for just that scope; sorted by path like a full status
+pub fn merge_status(
+    old: Vec<StatusEntry>,
+    scope: &[String],
+    fresh: Vec<StatusEntry>,
+) -> Vec<StatusEntry> {
+    let mut entries: Vec<StatusEntry> = old
+        .into_iter()
+        .filter(|e| !in_scope(&e.path, scope))
+        .chain(fresh)
+        .collect();
+    entries.sort_by(|a, b| a.path.cmp(&b.path));
+    entries
+}
+
+/// Status from `git status --porcelain=v2 -z --no-renames`
+pub fn parse_porcelain_v2(output: &[u8]) -> Vec<StatusEntry> {
+    let text = String::from_utf8_lossy(output);
+    let mut entries = Vec::new();
+    for record in text.split('\0') {
+        let entry = match record.as_bytes().first() {
+            Some(b'1') => {
+                // "1 XY sub mH mI mW hH hI path"
+                let fields: Vec<&str> = record.splitn(9, ' ').collect();
+                let (Some(xy), Some(path)) = (fields.get(1), fields.get(8)) else {
+                    continue;
+                };
+                let mut xy = xy.chars();
+                StatusEntry {
+                    path: path.to_string(),
+                    staged: FileStatus::from_porcelain(xy.next().unwrap_or('.')),
+                    unstaged: FileStatus::from_porcelain(xy.next().unwrap_or('.')),
+                }
+            }
+            // Conflicts have their own panel; libgit2 lists them as unchanged too
+            Some(b'u') => match record.splitn(11, ' ').nth(10) {
+                Some(path) => StatusEntry {
+                    path: path.to_string(),
+                    staged: FileStatus::Unmodified,
+                    unstaged: FileStatus::Unmodified,
+                },
+                None => continue,
+            },
+            Some(b'?') => StatusEntry {
+                path: record[2..].to_string(),
+                staged: FileStatus::Unmodified,
+                unstaged: FileStatus::Untracked,
+            },
+            _ => continue,
+        };
+        entries.push(entry);
+    }
+    entries
+}
+
+impl FileStatus {
+    /// One side of a porcelain "XY" status
+    fn from_porcelain(c: char) -> Self {
+        match c {
+            'M' | 'T' => FileStatus::Modified,
+            'A' => FileStatus::Added,
+            'D' => FileStatus::Deleted,
+            'R' => FileStatus::Renamed,
+            'C' => FileStatus::Copied,
+            _ => FileStatus::Unmodified,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(path: &str, unstaged: FileStatus) -> StatusEntry {
+        StatusEntry {
+            path: path.to_string(),
+            staged: FileStatus::Unmodified,
+            unstaged,
+        }
+    }
+
+    #[test]
+    fn test_parse_porcelain_v2() {
+        let output = b"1 M. N... 100644 100644 100644 abc abc src/main.rs\0\
+1 .D N... 100644 100644 000000 abc abc old name.txt\0\
+u UU N... 100644 100644 100644 100644 a b c both.txt\0\
+? new/file.txt\0";
+        let entries = parse_porcelain_v2(output);
+        let summary: Vec<(&str, FileStatus, FileStatus)> = entries
+            .iter()
+            .map(|e| (e.path.as_str(), e.staged, e.unstaged))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("src/main.rs", FileStatus::Modified, FileStatus::Unmodified),
+                ("old name.txt", FileStatus::Unmodified, FileStatus::Deleted),
+                ("both.txt", FileStatus::Unmodified, FileStatus::Unmodified),
+                (
+                    "new/file.txt",
+                    FileStatus::Unmodified,
+                    FileStatus::Untracked
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_merge_status() {
+        let old = vec![
+            entry("README.md", FileStatus::Modified),
+            entry("src/a.rs", FileStatus::Modified),
+            entry("src/sub/b.rs", FileStatus::Modified),
+            entry("src2/c.rs", FileStatus::Modified),
+        ];
+        let scope = ["src/".to_string(), "README.md".to_string()];
+        let merged = merge_status(
+            old,
+            &scope,
+            vec![entry("src/new.rs", FileStatus::Untracked)],
+        );
+        let paths: Vec<&str> = merged.iter().map(|e| e.path.as_str()).collect();
+        assert_eq!(paths, ["src/new.rs", "src2/c.rs"]);
+    }
+
+    #[test]
+    fn test_scoped_status() {
+        use crate::fixture::Fixture;
+
+        let fixture = Fixture::new("status");
+        for path in [
+            "top.txt",
+            "src/a.rs",
+            "src/deep/b.rs",
+            "srcx/c.rs",
+            "new/d.txt",
+        ] {
+            fixture.write(path, "x");
+        }
+        let repo = fixture.open();
+        let paths = |query: &StatusQuery| -> Vec<String> {
+            let mut paths: Vec<String> = repo
+                .status_with(query)
+                .unwrap()
+                .into_iter()
+                .map(|e| e.path)
+                .collect();
+            paths.sort();
+            paths
+        };
+
+        let scoped = StatusQuery {
+            scope: vec!["src/".to_string(), "top.txt".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(paths(&scoped), ["src/a.rs", "src/deep/b.rs", "top.txt"]);
+        let normal = StatusQuery {
+            untracked: UntrackedFiles::Normal,
+            ..Default::default()
+        };
+        assert_eq!(paths(&normal), ["new/", "src/", "srcx/", "top.txt"]);
+        let none = StatusQuery {
+            untracked: UntrackedFiles::No,
+            ..Default::default()
+        };
+        assert!(paths(&none).is_empty());
+
+        // With the untracked cache on, `git status` answers the same
+        fixture
+            .repo
+            .config()
+            .unwrap()
+            .set_bool("core.untrackedCache", true)
+            .unwrap();
+        assert_eq!(paths(&scoped), ["src/a.rs", "src/deep/b.rs", "top.txt"]);
+        assert_eq!(paths(&normal), ["new/", "src/", "srcx/", "top.txt"]);
+    }
+}
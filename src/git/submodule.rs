@@ -3,6 +3,70 @@ pub struct SubmoduleInfo {
     pub name: String,
     pub path: String,
     pub url: String,
+    /// Commit recorded in the superproject's HEAD
     pub head: Option<String>,
+    /// Commit currently checked out in the submodule's working directory
+    pub workdir: Option<String>,
     pub is_initialized: bool,
+    /// Checked-out commit differs from the one recorded in the superproject
+    pub has_drift: bool,
+    /// Submodule working tree has uncommitted or untracked changes
+    pub is_dirty: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fixture::Fixture;
+
+    #[test]
+    fn test_list_submodules() {
+        let mut library = Fixture::new("submodule-library");
+        let library_head = library.commit("library", &[("lib.rs", "fn f() {}")]);
+        let mut fixture = Fixture::new("submodules");
+        fixture.commit("base", &[("a", "1")]);
+        let repo = fixture.open();
+        let url = library.dir.to_str().unwrap();
+        repo.submodule_add(url, "libs/library").unwrap();
+        fixture.repo.index().unwrap().read(true).unwrap();
+        fixture.commit("Add library", &[]);
+
+        let submodules = repo.submodules().unwrap();
+        assert_eq!(submodules.len(), 1);
+        let sub = &submodules[0];
+        assert_eq!(sub.name, "libs/library");
+        assert_eq!(sub.path, "libs/library");
+        assert_eq!(sub.url, url);
+        assert_eq!(sub.head.as_deref(), Some(&library_head.to_string()[..7]));
+        assert_eq!(sub.workdir, sub.head);
+        assert!(sub.is_initialized && !sub.has_drift && !sub.is_dirty);
+
+        // A commit in the submodule drifts from the one recorded; a new file dirties it
+        let checkout = git2::Repository::open(fixture.dir.join("libs/library")).unwrap();
+        let signature = git2::Signature::now("Test", "test@example.com").unwrap();
+        let tree = checkout.head().unwrap().peel_to_tree().unwrap();
+        let parent = checkout.head().unwrap().peel_to_commit().unwrap();
+        checkout
+            .commit(
+                Some("HEAD"),
+                &signature,
+                &signature,
+                "moved",
+                &tree,
+                &[&parent],
+            )
+            .unwrap();
+        fixture.write("libs/library/new.rs", "");
+        let sub = &repo.submodules().unwrap()[0];
+        assert!(sub.has_drift && sub.is_dirty);
+        assert_ne!(sub.workdir, sub.head);
+
+        // Deinit refuses local changes unless forced, and update brings it back
+        assert!(repo.submodule_deinit("libs/library", false).is_err());
+        repo.submodule_deinit("libs/library", true).unwrap();
+        assert!(!repo.submodules().unwrap()[0].is_initialized);
+        repo.submodule_update("libs/library", false).unwrap();
+        let sub = &repo.submodules().unwrap()[0];
+        assert!(sub.is_initialized);
+        assert_eq!(fixture.read("libs/library/lib.rs"), "fn f() {}");
+    }
 }
@@ -0,0 +1,36 @@
+use super::Repository;
+use std::path::{Path, PathBuf};
+
+/// One row of the repository dashboard
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RepoSummary {
+    pub path: PathBuf,
+    /// Current branch, or None when HEAD is detached or unborn
+    pub branch: Option<String>,
+    /// Any staged, unstaged or untracked change
+    pub dirty: bool,
+    pub ahead: usize,
+    pub behind: usize,
+    /// None when the count is not known (gh missing, not a GitHub repo)
+    pub open_prs: Option<usize>,
+    /// Why the repository could not be read
+    pub error: Option<String>,
+}
+
+impl RepoSummary {
+    /// Summarize the repository at `path`; failures are kept in `error`
+    pub fn load(path: &Path) -> Self {
+        match Repository::open(path) {
+            // Keyed by the path asked for, which may differ from the discovered workdir
+            Ok(repo) => Self {
+                path: path.to_path_buf(),
+                ..repo.summary()
+            },
+            Err(e) => Self {
+                path: path.to_path_buf(),
+                error: Some(e.to_string()),
+                ..Default::default()
+            },
+        }
+    }
+}
@@ -0,0 +1,530 @@
+use crate::error::{Error, Result};
+use crate::jobs::CancelToken;
+use git2::{Cred, CredentialType, RemoteCallbacks};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TransferStage {
+    #[default]
+    Connecting,
+    Receiving,
+    Resolving,
+    Writing,
+}
+
+/// Point-in-time copy of a transfer's counters
+#[derive(Debug, Clone, Default)]
+pub struct ProgressSnapshot {
+    pub stage: TransferStage,
+    pub current: usize,
+    pub total: usize,
+    pub bytes: usize,
+}
+
+impl ProgressSnapshot {
+    /// Short footer text, e.g. "receiving 120/300 objects, 1.2 MiB"
+    pub fn summary(&self) -> String {
+        match self.stage {
+            TransferStage::Connecting => "connecting".to_string(),
+            TransferStage::Receiving => format!(
+                "receiving {}/{} objects, {}",
+                self.current,
+                self.total,
+                format_bytes(self.bytes)
+            ),
+            TransferStage::Resolving => {
+                format!("resolving deltas {}/{}", self.current, self.total)
+            }
+            TransferStage::Writing => format!(
+                "writing {}/{} objects, {}",
+                self.current,
+                self.total,
+                format_bytes(self.bytes)
+            ),
+        }
+    }
+}
+
+/// Everything the push dialog can choose
+#[derive(Debug, Clone, Default)]
+pub struct PushRequest {
+    pub remote: String,
+    /// Local branch (or full ref) to push
+    pub source: String,
+    /// Branch (or full ref) to update on the remote
+    pub destination: String,
+    /// Force push, but only if the remote ref is still at this commit ("" = must not exist)
+    pub force_if_at: Option<String>,
+    /// Also push tags the remote does not have yet
+    pub tags: bool,
+    pub dry_run: bool,
+    pub set_upstream: bool,
+    /// Skip the pre-push hook
+    pub no_verify: bool,
+}
+
+/// How a pull integrates the fetched branch
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PullStrategy {
+    #[default]
+    Merge,
+    Rebase,
+    FastForwardOnly,
+}
+
+impl PullStrategy {
+    pub const ALL: [PullStrategy; 3] = [
+        PullStrategy::Merge,
+        PullStrategy::Rebase,
+        PullStrategy::FastForwardOnly,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            PullStrategy::Merge => "merge",
+            PullStrategy::Rebase => "rebase",
+            PullStrategy::FastForwardOnly => "ff-only",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PullOptions {
+    pub strategy: PullStrategy,
+    /// Stash local changes before integrating and re-apply them afterwards
+    pub autostash: bool,
+}
+
+/// Progress and cancellation handle shared between the UI and a transfer thread
+#[derive(Debug, Clone, Default)]
+pub struct TransferProgress {
+    state: Arc<Mutex<ProgressSnapshot>>,
+    cancelled: CancelToken,
+}
+
+impl TransferProgress {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Progress of a transfer that stops when the job's `token` is cancelled
+    pub fn with_cancel(token: CancelToken) -> Self {
+        Self {
+            state: Arc::default(),
+            cancelled: token,
+        }
+    }
+
+    /// Ask the transfer to stop at the next progress callback
+    pub fn cancel(&self) {
+        self.cancelled.cancel();
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.is_cancelled()
+    }
+
+    pub fn snapshot(&self) -> ProgressSnapshot {
+        self.state.lock().map(|s| s.clone()).unwrap_or_default()
+    }
+
+    fn update(&self, stage: TransferStage, current: usize, total: usize, bytes: usize) {
+        if let Ok(mut state) = self.state.lock() {
+            *state = ProgressSnapshot {
+                stage,
+                current,
+                total,
+                bytes,
+            };
+        }
+    }
+
+    /// Map a libgit2 result, reporting cancellation instead of the callback error
+    pub(crate) fn finish<T>(&self, result: std::result::Result<T, git2::Error>) -> Result<T> {
+        match result {
+            Err(_) if self.is_cancelled() => {
+                Err(Error::Git(git2::Error::from_str("Operation cancelled")))
+            }
+            other => Ok(other?),
+        }
+    }
+
+    /// Callbacks wiring progress, cancellation and credentials for one transfer
+    pub(crate) fn callbacks(&self, config: git2::Config) -> RemoteCallbacks<'_> {
+        let mut callbacks = RemoteCallbacks::new();
+
+        callbacks.transfer_progress(move |stats| {
+            if stats.received_objects() < stats.total_objects() {
+                self.update(
+                    TransferStage::Receiving,
+                    stats.received_objects(),
+                    stats.total_objects(),
+                    stats.received_bytes(),
+                );
+            } else {
+                self.update(
+                    TransferStage::Resolving,
+                    stats.indexed_deltas(),
+                    stats.total_deltas(),
+                    stats.received_bytes(),
+                );
+            }
+            !self.is_cancelled()
+        });
+        callbacks.push_transfer_progress(move |current, total, bytes| {
+            self.update(TransferStage::Writing, current, total, bytes);
+        });
+        callbacks.sideband_progress(move |_| !self.is_cancelled());
+        callbacks.push_negotiation(move |_| {
+            if self.is_cancelled() {
+                Err(git2::Error::from_str("Operation cancelled"))
+            } else {
+                Ok(())
+            }
+        });
+
+        let mut attempts = CredentialAttempts::default();
+        callbacks.credentials(move |url, username, allowed| {
+            if self.is_cancelled() {
+                return Err(git2::Error::from_str("Operation cancelled"));
+            }
+            attempts.next(&config, url, username, allowed)
+        });
+
+        callbacks
+    }
+
+    /// `callbacks` for a push that also runs `check` on the updates the
+    /// remote negotiates. libgit2 has a single negotiation callback and it
+    /// is the only push callback that can abort, so cancellation is checked
+    /// here too.
+    pub(crate) fn push_callbacks<'a>(
+        &'a self,
+        config: git2::Config,
+        mut check: impl FnMut(&[git2::PushUpdate<'_>]) -> std::result::Result<(), git2::Error> + 'a,
+    ) -> RemoteCallbacks<'a> {
+        let mut callbacks = self.callbacks(config);
+        callbacks.push_negotiation(move |updates| {
+            if self.is_cancelled() {
+                return Err(git2::Error::from_str("Operation cancelled"));
+            }
+            check(updates)
+        });
+        callbacks
+    }
+}
+
+/// Credential sources tried in order; libgit2 calls back again after each rejection
+#[derive(Default)]
+struct CredentialAttempts {
+    agent: bool,
+    key_files: usize,
+    helper: bool,
+    default: bool,
+}
+
+impl CredentialAttempts {
+    fn next(
+        &mut self,
+        config: &git2::Config,
+        url: &str,
+        username: Option<&str>,
+        allowed: CredentialType,
+    ) -> std::result::Result<Cred, git2::Error> {
+        let user = username.unwrap_or("git");
+
+        if allowed.contains(CredentialType::USERNAME) {
+            return Cred::username(user);
+        }
+
+        if allowed.contains(CredentialType::SSH_KEY) {
+            if !self.agent {
+                self.agent = true;
+                if let Ok(cred) = Cred::ssh_key_from_agent(user) {
+                    return Ok(cred);
+                }
+            }
+            // Unencrypted key files only; passphrase-protected keys need the agent
+            let keys = ssh_key_files();
+            while let Some(key) = keys.get(self.key_files) {
+                self.key_files += 1;
+                if key.exists() {
+                    return Cred::ssh_key(user, None, key, None);
+                }
+            }
+        }
+
+        if allowed.contains(CredentialType::USER_PASS_PLAINTEXT) && !self.helper {
+            self.helper = true;
+            if let Ok(cred) = Cred::credential_helper(config, url, username) {
+                return Ok(cred);
+            }
+        }
+
+        if allowed.contains(CredentialType::DEFAULT) && !self.default {
+            self.default = true;
+            return Cred::default();
+        }
+
+        Err(git2::Error::from_str(&format!(
+            "Authentication failed for {}",
+            url
+        )))
+    }
+}
+
+fn ssh_key_files() -> Vec<PathBuf> {
+    let home = match std::env::var_os("HOME") {
+        Some(home) => PathBuf::from(home),
+        None => return Vec::new(),
+    };
+    ["id_ed25519", "id_ecdsa", "id_rsa"]
+        .iter()
+        .map(|name| home.join(".ssh").join(name))
+        .collect()
+}
+
+pub(crate) fn format_bytes(bytes: usize) -> String {
+    if bytes >= 1024 * 1024 {
+        format!("{:.1} MiB", bytes as f64 / (1024.0 * 1024.0))
+    } else if bytes >= 1024 {
+        format!("{:.1} KiB", bytes as f64 / 1024.0)
+    } else {
+        format!("{} B", bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture::Fixture;
+    use crate::git::MergeResult;
+
+    /// "a" with one commit pushed to its bare remote, and "b" cloned after it
+    fn setup(name: &str) -> (Fixture, Fixture) {
+        let mut a = Fixture::new(&format!("{}-a", name));
+        let url = a.bare_remote("origin");
+        a.commit("one", &[("one", "1")]);
+        a.open()
+            .push_set_upstream("origin", "main", &TransferProgress::new())
+            .unwrap();
+        let b = Fixture::clone(&format!("{}-b", name), &url);
+        (a, b)
+    }
+
+    #[test]
+    fn test_push_and_pull_over_file_remote() {
+        let (mut a, b) = setup("pull");
+        let head = a.commit("two", &[("two", "2")]);
+        a.open().push("origin", &TransferProgress::new()).unwrap();
+
+        let progress = TransferProgress::new();
+        let outcome = b
+            .open()
+            .pull("origin", PullOptions::default(), &progress)
+            .unwrap();
+        assert_eq!(outcome.result, MergeResult::FastForward);
+        assert_eq!(outcome.new_head, Some(head.to_string()));
+        assert_eq!(b.head(), head);
+        assert!(b.dir.join("two").exists());
+    }
+
+    #[test]
+    fn test_non_fast_forward_push_is_rejected() {
+        let (mut a, mut b) = setup("reject");
+        a.commit("two", &[("two", "2")]);
+        a.open().push("origin", &TransferProgress::new()).unwrap();
+
+        b.commit("three", &[("three", "3")]);
+        let err = b
+            .open()
+            .push("origin", &TransferProgress::new())
+            .unwrap_err();
+        assert!(matches!(err, Error::Git(ref e) if e.code() == git2::ErrorCode::NotFastForward));
+    }
+
+    #[test]
+    fn test_pull_strategies_on_diverged_branches() {
+        let (mut a, mut b) = setup("strategies");
+        a.commit("two", &[("two", "2")]);
+        a.open().push("origin", &TransferProgress::new()).unwrap();
+        b.commit("three", &[("three", "3")]);
+        std::fs::write(b.dir.join("three"), "dirty").unwrap();
+
+        let mut repo = b.open();
+        let ff_only = PullOptions {
+            strategy: PullStrategy::FastForwardOnly,
+            autostash: false,
+        };
+        let outcome = repo
+            .pull("origin", ff_only, &TransferProgress::new())
+            .unwrap();
+        assert_eq!(outcome.result, MergeResult::Aborted);
+        assert_eq!(outcome.old_head, outcome.new_head);
+
+        let rebase = PullOptions {
+            strategy: PullStrategy::Rebase,
+            autostash: true,
+        };
+        let outcome = repo
+            .pull("origin", rebase, &TransferProgress::new())
+            .unwrap();
+        assert_eq!(outcome.result, MergeResult::Rebased);
+        assert!(!outcome.stash_kept);
+        assert!(b.dir.join("two").exists());
+        assert_eq!(b.read("three"), "dirty");
+        let head = b.repo.head().unwrap().peel_to_commit().unwrap();
+        assert_eq!(head.summary(), Some("three"));
+        assert_eq!(head.parent(0).unwrap().summary(), Some("two"));
+    }
+
+    #[test]
+    fn test_pull_defaults_from_config() {
+        let fixture = Fixture::new("pull-defaults");
+        let repo = fixture.open();
+        let mut config = fixture.repo.config().unwrap();
+        assert_eq!(repo.pull_defaults().strategy, PullStrategy::Merge);
+        for (value, strategy) in [
+            ("true", PullStrategy::Rebase),
+            ("yes", PullStrategy::Rebase),
+            ("merges", PullStrategy::Rebase),
+            ("interactive", PullStrategy::Rebase),
+            ("false", PullStrategy::Merge),
+            ("no", PullStrategy::Merge),
+            ("off", PullStrategy::Merge),
+            ("0", PullStrategy::Merge),
+        ] {
+            config.set_str("pull.rebase", value).unwrap();
+            assert_eq!(repo.pull_defaults().strategy, strategy, "{}", value);
+        }
+        config.set_str("pull.ff", "only").unwrap();
+        assert_eq!(repo.pull_defaults().strategy, PullStrategy::FastForwardOnly);
+    }
+
+    #[test]
+    fn test_force_if_at() {
+        let (mut a, mut b) = setup("force");
+        let stale = b.head();
+        let remote_head = a.commit("two", &[("two", "2")]);
+        a.open().push("origin", &TransferProgress::new()).unwrap();
+        b.commit("three", &[("three", "3")]);
+
+        let repo = b.open();
+        let branch = repo.head_name().unwrap().unwrap();
+        let mut request = PushRequest {
+            remote: "origin".to_string(),
+            source: branch.clone(),
+            destination: branch,
+            force_if_at: Some(stale.to_string()),
+            ..Default::default()
+        };
+        let err = repo
+            .push_with(&request, &TransferProgress::new())
+            .unwrap_err();
+        assert!(err.to_string().contains("expected"));
+
+        request.force_if_at = Some(remote_head.to_string());
+        request.dry_run = true;
+        let summary = repo.push_with(&request, &TransferProgress::new()).unwrap();
+        assert!(summary.starts_with("Dry run"));
+
+        request.dry_run = false;
+        repo.push_with(&request, &TransferProgress::new()).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_force_rechecks_remote_on_push() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let (mut a, mut b) = setup("force-race");
+        let base = b.head();
+        let remote_head = a.commit("two", &[("two", "2")]);
+        a.open().push("origin", &TransferProgress::new()).unwrap();
+        b.commit("three", &[("three", "3")]);
+
+        // Someone else moves the remote branch after the first check, while
+        // pre-push runs
+        let url = b
+            .repo
+            .find_remote("origin")
+            .unwrap()
+            .url()
+            .unwrap()
+            .to_string();
+        let remote = git2::Repository::open(url.trim_start_matches("file://")).unwrap();
+        let hook = b.dir.join(".git/hooks/pre-push");
+        std::fs::create_dir_all(hook.parent().unwrap()).unwrap();
+        std::fs::write(
+            &hook,
+            format!(
+                "#!/bin/sh\ngit --git-dir='{}' update-ref refs/heads/main {}\n",
+                remote.path().display(),
+                base
+            ),
+        )
+        .unwrap();
+        std::fs::set_permissions(&hook, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let repo = b.open();
+        let branch = repo.head_name().unwrap().unwrap();
+        let request = PushRequest {
+            remote: "origin".to_string(),
+            source: branch.clone(),
+            destination: branch,
+            force_if_at: Some(remote_head.to_string()),
+            ..Default::default()
+        };
+        let err = repo
+            .push_with(&request, &TransferProgress::new())
+            .unwrap_err();
+        assert!(err.to_string().contains("expected"), "{}", err);
+        let main = remote.find_reference("refs/heads/main").unwrap();
+        assert_eq!(main.target(), Some(base));
+    }
+
+    #[test]
+    fn test_cancelled_forced_push() {
+        let (mut a, mut b) = setup("force-cancel");
+        let remote_head = a.commit("two", &[("two", "2")]);
+        a.open().push("origin", &TransferProgress::new()).unwrap();
+        b.commit("three", &[("three", "3")]);
+
+        let repo = b.open();
+        let branch = repo.head_name().unwrap().unwrap();
+        let request = PushRequest {
+            remote: "origin".to_string(),
+            source: branch.clone(),
+            destination: branch,
+            force_if_at: Some(remote_head.to_string()),
+            ..Default::default()
+        };
+        let progress = TransferProgress::new();
+        progress.cancel();
+        let err = repo.push_with(&request, &progress).unwrap_err();
+        assert!(err.to_string().contains("cancelled"), "{}", err);
+        let url = a
+            .repo
+            .find_remote("origin")
+            .unwrap()
+            .url()
+            .unwrap()
+            .to_string();
+        let remote = git2::Repository::open(url.trim_start_matches("file://")).unwrap();
+        let main = remote.find_reference("refs/heads/main").unwrap();
+        assert_eq!(main.target(), Some(remote_head));
+    }
+
+    #[test]
+    fn test_cancelled_fetch() {
+        let (mut a, b) = setup("cancel");
+        a.commit("two", &[("two", "2")]);
+        a.open().push("origin", &TransferProgress::new()).unwrap();
+
+        let progress = TransferProgress::new();
+        progress.cancel();
+        let err = b.open().fetch("origin", &progress).unwrap_err();
+        assert!(err.to_string().contains("cancelled"));
+    }
+}
@@ -0,0 +1,150 @@
+/// Namespace holding snapshots of content destroyed by discard, hard reset or clean
+pub const TRASH_PREFIX: &str = "refs/g/trash/";
+
+/// Working-tree content saved before a destructive operation, as a commit on top
+/// of the HEAD it was taken at
+#[derive(Debug, Clone)]
+pub struct TrashEntry {
+    pub refname: String,
+    pub id: String,
+    pub short_id: String,
+    /// What destroyed the content, e.g. "discard src/main.rs"
+    pub reason: String,
+    pub time: i64,
+    pub files: Vec<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fixture::Fixture;
+
+    fn setup() -> Fixture {
+        let mut fixture = Fixture::new("trash");
+        fixture.commit("base", &[("a", "1")]);
+        fixture
+    }
+
+    #[test]
+    fn test_discard_is_recoverable() {
+        let fixture = setup();
+        let dir = &fixture.dir;
+        let repo = fixture.open();
+
+        std::fs::write(dir.join("a"), "edited").unwrap();
+        repo.discard_file("a").unwrap();
+        assert_eq!(std::fs::read_to_string(dir.join("a")).unwrap(), "1");
+
+        std::fs::create_dir(dir.join("new")).unwrap();
+        std::fs::write(dir.join("new/b"), "untracked").unwrap();
+        assert_eq!(repo.clean_untracked().unwrap(), 1);
+        assert!(!dir.join("new").exists());
+
+        let entries = repo.trash_entries().unwrap();
+        assert_eq!(entries.len(), 2);
+        let discard = entries.iter().find(|e| e.reason == "discard a").unwrap();
+        assert_eq!(discard.files, vec!["a".to_string()]);
+
+        // Restoring brings the edit back; the clean HEAD content it replaced needs no snapshot
+        assert_eq!(repo.restore_trash(&discard.refname).unwrap(), 1);
+        assert_eq!(std::fs::read_to_string(dir.join("a")).unwrap(), "edited");
+        assert_eq!(repo.trash_entries().unwrap().len(), 1);
+
+        // Untracked files come back too, parent directories included
+        let clean = repo.trash_entries().unwrap().remove(0);
+        repo.restore_trash(&clean.refname).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(dir.join("new/b")).unwrap(),
+            "untracked"
+        );
+        assert_eq!(repo.trash_entries().unwrap().len(), 0);
+
+        assert_eq!(repo.expire_trash(1).unwrap(), 0);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_clean_keeps_modes_and_nested_repositories() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let fixture = setup();
+        let dir = &fixture.dir;
+        let repo = fixture.open();
+        fixture.write("run.sh", "#!/bin/sh\n");
+        std::fs::set_permissions(dir.join("run.sh"), std::fs::Permissions::from_mode(0o755))
+            .unwrap();
+        std::os::unix::fs::symlink("a", dir.join("link")).unwrap();
+        git2::Repository::init(dir.join("nested")).unwrap();
+        fixture.write("nested/file", "mine");
+
+        // The nested repository is skipped instead of failing the clean halfway
+        assert_eq!(repo.clean_untracked().unwrap(), 2);
+        assert!(!dir.join("run.sh").exists());
+        assert!(std::fs::symlink_metadata(dir.join("link")).is_err());
+        assert_eq!(fixture.read("nested/file"), "mine");
+
+        let entry = repo.trash_entries().unwrap().remove(0);
+        repo.restore_trash(&entry.refname).unwrap();
+        let mode = std::fs::metadata(dir.join("run.sh"))
+            .unwrap()
+            .permissions()
+            .mode();
+        assert_ne!(mode & 0o111, 0);
+        assert_eq!(
+            std::fs::read_link(dir.join("link")).unwrap(),
+            std::path::Path::new("a")
+        );
+    }
+
+    #[test]
+    fn test_staged_content_is_kept() {
+        let mut fixture = Fixture::new("trash-staged");
+        let base = fixture.commit("base", &[("a", "1")]);
+        let repo = fixture.open();
+
+        // Staged "2", then edited on to "3": a hard reset drops both
+        fixture.write("a", "2");
+        repo.stage_file("a").unwrap();
+        fixture.write("a", "3");
+        repo.reset_to_commit(&base.to_string(), "hard").unwrap();
+        assert_eq!(fixture.read("a"), "1");
+
+        let entry = repo.trash_entries().unwrap().remove(0);
+        assert_eq!(entry.files, vec!["a".to_string()]);
+        repo.restore_trash(&entry.refname).unwrap();
+        assert_eq!(fixture.read("a"), "3");
+        let mut index = fixture.repo.index().unwrap();
+        index.read(true).unwrap();
+        let staged = index.get_path(std::path::Path::new("a"), 0).unwrap();
+        assert_eq!(fixture.repo.find_blob(staged.id).unwrap().content(), b"2");
+    }
+
+    #[test]
+    fn test_merge_abort_is_recoverable() {
+        use crate::git::{MergeMode, MergeRequest, MergeResult};
+
+        let mut fixture = Fixture::new("trash-merge");
+        fixture.commit("base", &[("a", "1"), ("b", "1")]);
+        fixture.branch("other");
+        fixture.commit("main", &[("a", "main")]);
+        fixture.switch("other");
+        fixture.commit("other", &[("a", "other")]);
+        fixture.switch("main");
+        let repo = fixture.open();
+
+        let request = MergeRequest {
+            target: "other".to_string(),
+            mode: MergeMode::NoFastForward,
+            message: None,
+            ..Default::default()
+        };
+        assert_eq!(repo.merge_with(&request).unwrap(), MergeResult::Conflict);
+        fixture.write("b", "edited during the merge");
+        repo.abort_operation().unwrap();
+        assert_eq!(fixture.read("b"), "1");
+
+        let entry = repo.trash_entries().unwrap().remove(0);
+        assert_eq!(entry.reason, "merge --abort");
+        repo.restore_trash(&entry.refname).unwrap();
+        assert_eq!(fixture.read("b"), "edited during the merge");
+    }
+}
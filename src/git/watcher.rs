@@ -0,0 +1,547 @@
+//! Watching a repository for changes made outside the app.
+//!
+//! On Linux a thread follows the working tree (minus ignored directories), the
+//! git directory and the refs with inotify. Bursts of events are debounced and
+//! reported as the set of views they affect, along with where in the working
+//! tree the status changed. When `core.fsmonitor` runs `g fsmonitor`, changed
+//! paths also go to the hook's journal (see `fsmonitor`). Elsewhere there is no
+//! watcher and the app keeps polling (`auto_refresh`).
+
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// More places than this in one batch and the whole status is read again
+const MAX_SCOPE: usize = 64;
+
+/// What changed in a repository, by the views it affects
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RepoChanges {
+    /// Working tree files, the index or an operation in progress: status,
+    /// file tree and conflicts
+    pub status: bool,
+    /// Where the status changed when only working-tree files did: directories
+    /// ("src/") and top-level files; empty for anywhere
+    pub status_scope: Vec<String>,
+    /// HEAD, local or remote branches: branches, commits and reflog
+    pub branches: bool,
+    pub tags: bool,
+    /// The stash and the trash (`refs/g/trash/`)
+    pub stash: bool,
+    /// `.git/config` and linked worktrees: remotes, worktrees and submodules
+    pub config: bool,
+}
+
+impl RepoChanges {
+    pub const ALL: Self = Self {
+        status: true,
+        status_scope: Vec::new(),
+        branches: true,
+        tags: true,
+        stash: true,
+        config: true,
+    };
+
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// A change to `path`, relative to the working tree ("dir/" for a directory)
+    pub fn in_worktree(path: &str) -> Self {
+        // Files below the top level count for their whole directory
+        let scope = match path.trim_end_matches('/').rsplit_once('/') {
+            Some(_) if path.ends_with('/') => path.to_string(),
+            Some((dir, _)) => format!("{}/", dir),
+            None => path.to_string(),
+        };
+        Self {
+            status: true,
+            status_scope: vec![scope],
+            ..Default::default()
+        }
+    }
+
+    pub fn merge(&mut self, other: Self) {
+        self.status_scope = match (self.status, other.status) {
+            (true, true) if self.status_scope.is_empty() || other.status_scope.is_empty() => {
+                Vec::new()
+            }
+            (true, true) => {
+                let mut scope = std::mem::take(&mut self.status_scope);
+                for path in other.status_scope {
+                    if !scope.contains(&path) {
+                        scope.push(path);
+                    }
+                }
+                if scope.len() > MAX_SCOPE {
+                    scope.clear();
+                }
+                scope
+            }
+            (true, false) => std::mem::take(&mut self.status_scope),
+            (false, _) => other.status_scope,
+        };
+        self.status |= other.status;
+        self.branches |= other.branches;
+        self.tags |= other.tags;
+        self.stash |= other.stash;
+        self.config |= other.config;
+    }
+}
+
+/// Which views a change to `path`, relative to the git directory, affects
+fn classify_git_path(path: &str) -> RepoChanges {
+    let mut changes = RepoChanges::default();
+    // Lock files are renamed over the real file when the write is done
+    if path.ends_with(".lock") {
+        return changes;
+    }
+    match path {
+        "index" | "MERGE_HEAD" | "CHERRY_PICK_HEAD" | "REVERT_HEAD" | "REBASE_HEAD"
+        | "rebase-merge" | "rebase-apply" => changes.status = true,
+        // The current branch's commit decides what is staged
+        "HEAD" => {
+            changes.status = true;
+            changes.branches = true;
+        }
+        "packed-refs" => {
+            changes.branches = true;
+            changes.tags = true;
+        }
+        "config" | "worktrees" | "modules" => changes.config = true,
+        "refs/stash" => changes.stash = true,
+        _ if path.starts_with("refs/heads/") => {
+            changes.status = true;
+            changes.branches = true;
+        }
+        _ if path.starts_with("refs/remotes/") => changes.branches = true,
+        _ if path.starts_with("refs/tags/") => changes.tags = true,
+        _ if path.starts_with("refs/g/") => changes.stash = true,
+        _ => {}
+    }
+    changes
+}
+
+/// Stops the watcher thread when dropped
+pub struct Watcher {
+    stop: Arc<AtomicBool>,
+    worktree: Arc<AtomicBool>,
+}
+
+impl Watcher {
+    /// Watch a repository; `on_change` gets each debounced batch of changes
+    /// and returns false to stop. None where watching is not supported, or
+    /// when the git directory and refs could not be watched (inotify limits),
+    /// so the caller keeps polling.
+    #[cfg(target_os = "linux")]
+    pub fn start<F>(workdir: &Path, git_dir: &Path, on_change: F) -> Option<Self>
+    where
+        F: FnMut(RepoChanges) -> bool + Send + 'static,
+    {
+        let mut inotify = linux::Inotify::new()?;
+        let paths = Paths::new(workdir, git_dir);
+        if !linux::watch_git_dirs(&mut inotify, &paths) {
+            debug!("Could not watch {}; polling", git_dir.display());
+            return None;
+        }
+        let stop = Arc::new(AtomicBool::new(false));
+        // Set by the thread once the working tree is watched; until then it is polled
+        let worktree = Arc::new(AtomicBool::new(false));
+        let (thread_stop, thread_worktree) = (stop.clone(), worktree.clone());
+        std::thread::spawn(move || {
+            linux::run(inotify, paths, on_change, thread_stop, thread_worktree)
+        });
+        Some(Self { stop, worktree })
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub fn start<F>(_workdir: &Path, _git_dir: &Path, _on_change: F) -> Option<Self>
+    where
+        F: FnMut(RepoChanges) -> bool + Send + 'static,
+    {
+        None
+    }
+
+    /// False until the watcher thread has added its working tree watches, and
+    /// when the working tree has too many directories to watch (or watching
+    /// failed), so file edits have to be found by polling
+    pub fn watches_worktree(&self) -> bool {
+        self.worktree.load(Ordering::Relaxed)
+    }
+}
+
+impl Drop for Watcher {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+struct Paths {
+    workdir: PathBuf,
+    git_dir: PathBuf,
+    /// Where refs live: the main repository's git directory for a linked worktree
+    common_dir: PathBuf,
+}
+
+impl Paths {
+    fn new(workdir: &Path, git_dir: &Path) -> Self {
+        let common_dir = std::fs::read_to_string(git_dir.join("commondir"))
+            .map(|dir| git_dir.join(dir.trim()))
+            .unwrap_or_else(|_| git_dir.to_path_buf());
+        Self {
+            workdir: workdir.to_path_buf(),
+            git_dir: git_dir.to_path_buf(),
+            common_dir,
+        }
+    }
+
+    /// Changes for a path inside the git directories; None for other paths
+    fn classify_git(&self, path: &Path) -> Option<RepoChanges> {
+        let rel = path
+            .strip_prefix(&self.git_dir)
+            .or_else(|_| path.strip_prefix(&self.common_dir))
+            .ok()?;
+        let rel = rel.to_string_lossy().replace('\\', "/");
+        Some(classify_git_path(&rel))
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use super::super::fsmonitor::{self, Journal};
+    use super::{Paths, RepoChanges};
+    use std::collections::HashMap;
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+    use std::path::{Path, PathBuf};
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+    use std::time::{Duration, Instant};
+
+    /// Report once events have stopped for this long...
+    const QUIET: Duration = Duration::from_millis(150);
+    /// ...or this long after the first one, for a steady stream of writes
+    const MAX_DELAY: Duration = Duration::from_secs(1);
+    /// inotify watches one directory each; leave the rest of the user's limit
+    /// (often 8192) to other programs
+    const MAX_DIRS: usize = 4096;
+
+    const MASK: u32 = libc::IN_CREATE
+        | libc::IN_DELETE
+        | libc::IN_MODIFY
+        | libc::IN_CLOSE_WRITE
+        | libc::IN_MOVED_FROM
+        | libc::IN_MOVED_TO
+        | libc::IN_ONLYDIR;
+
+    pub(super) struct Inotify {
+        fd: libc::c_int,
+        dirs: HashMap<libc::c_int, PathBuf>,
+    }
+
+    impl Inotify {
+        pub(super) fn new() -> Option<Self> {
+            let fd = unsafe { libc::inotify_init1(libc::IN_NONBLOCK | libc::IN_CLOEXEC) };
+            (fd >= 0).then(|| Self {
+                fd,
+                dirs: HashMap::new(),
+            })
+        }
+
+        fn add(&mut self, dir: &Path) -> bool {
+            if self.dirs.len() >= MAX_DIRS {
+                return false;
+            }
+            let Ok(path) = CString::new(dir.as_os_str().as_bytes()) else {
+                return false;
+            };
+            let wd = unsafe { libc::inotify_add_watch(self.fd, path.as_ptr(), MASK) };
+            if wd < 0 {
+                return false;
+            }
+            self.dirs.insert(wd, dir.to_path_buf());
+            true
+        }
+
+        /// Watch `dir` and the directories below it, skipping those `skip`
+        /// says to; false if a watch could not be added
+        fn add_tree(&mut self, dir: &Path, skip: &dyn Fn(&Path) -> bool) -> bool {
+            if !self.add(dir) {
+                return false;
+            }
+            let Ok(entries) = std::fs::read_dir(dir) else {
+                return true;
+            };
+            for entry in entries.flatten() {
+                // Not following symlinks, which may leave the repository
+                let is_dir = entry.file_type().map(|t| t.is_dir()).unwrap_or(false);
+                if is_dir && !skip(&entry.path()) && !self.add_tree(&entry.path(), skip) {
+                    return false;
+                }
+            }
+            true
+        }
+
+        /// Events read so far, as (path, mask)
+        fn read(&mut self) -> Vec<(PathBuf, u32)> {
+            let mut events = Vec::new();
+            let mut buf = [0u8; 8192];
+            loop {
+                let n = unsafe {
+                    libc::read(self.fd, buf.as_mut_ptr() as *mut libc::c_void, buf.len())
+                };
+                if n <= 0 {
+                    return events;
+                }
+                let mut offset = 0;
+                let header = std::mem::size_of::<libc::inotify_event>();
+                while offset + header <= n as usize {
+                    let event: libc::inotify_event =
+                        unsafe { std::ptr::read_unaligned(buf[offset..].as_ptr() as *const _) };
+                    let name_bytes = &buf[offset + header..offset + header + event.len as usize];
+                    let name_len = name_bytes
+                        .iter()
+                        .position(|&b| b == 0)
+                        .unwrap_or(name_bytes.len());
+                    let name = std::ffi::OsStr::from_bytes(&name_bytes[..name_len]);
+                    offset += header + event.len as usize;
+
+                    if event.mask & libc::IN_IGNORED != 0 {
+                        // The directory is gone
+                        self.dirs.remove(&event.wd);
+                        continue;
+                    }
+                    let path = match self.dirs.get(&event.wd) {
+                        Some(dir) if name_len > 0 => dir.join(name),
+                        Some(dir) => dir.clone(),
+                        None if event.mask & libc::IN_Q_OVERFLOW != 0 => PathBuf::new(),
+                        None => continue,
+                    };
+                    events.push((path, event.mask));
+                }
+            }
+        }
+
+        fn wait(&self, timeout: Duration) -> bool {
+            let mut pfd = libc::pollfd {
+                fd: self.fd,
+                events: libc::POLLIN,
+                revents: 0,
+            };
+            unsafe { libc::poll(&mut pfd, 1, timeout.as_millis() as libc::c_int) > 0 }
+        }
+    }
+
+    impl Drop for Inotify {
+        fn drop(&mut self) {
+            unsafe {
+                libc::close(self.fd);
+            }
+        }
+    }
+
+    /// Watch the git directory, the common directory and the refs below it
+    pub(super) fn watch_git_dirs(inotify: &mut Inotify, paths: &Paths) -> bool {
+        let skip_nothing = |_: &Path| false;
+        inotify.add(&paths.git_dir)
+            && (paths.common_dir == paths.git_dir || inotify.add(&paths.common_dir))
+            && inotify.add_tree(&paths.common_dir.join("refs"), &skip_nothing)
+    }
+
+    pub(super) fn run<F>(
+        mut inotify: Inotify,
+        paths: Paths,
+        mut on_change: F,
+        stop: Arc<AtomicBool>,
+        worktree: Arc<AtomicBool>,
+    ) where
+        F: FnMut(RepoChanges) -> bool,
+    {
+        // For .gitignore; without it nothing is skipped
+        let repo = git2::Repository::open(&paths.workdir).ok();
+        let ignored = |path: &Path| -> bool {
+            let Ok(rel) = path.strip_prefix(&paths.workdir) else {
+                return true;
+            };
+            if rel.starts_with(".git") || path.starts_with(&paths.git_dir) {
+                return true;
+            }
+            match &repo {
+                // A trailing slash makes directory-only patterns ("target/") match
+                Some(repo) if path.is_dir() => repo
+                    .is_path_ignored(format!("{}/", rel.to_string_lossy()))
+                    .unwrap_or(false),
+                Some(repo) => repo.is_path_ignored(rel).unwrap_or(false),
+                None => false,
+            }
+        };
+        let skip_nothing = |_: &Path| false;
+        let refs = paths.common_dir.join("refs");
+
+        // Too many directories: keep the ones watched so far, poll for the rest
+        let worktree_ok = inotify.add_tree(&paths.workdir, &ignored);
+        if worktree_ok {
+            worktree.store(true, Ordering::Relaxed);
+        } else {
+            debug!(
+                "Watching {} only; polling the working tree",
+                paths.git_dir.display()
+            );
+        }
+        // Only a complete record of the working tree can answer the hook
+        let mut journal = match &repo {
+            Some(repo) if worktree_ok && fsmonitor::hook_configured(repo) => {
+                Journal::create(&paths.git_dir)
+            }
+            _ => None,
+        };
+
+        let mut pending = RepoChanges::default();
+        let mut first: Option<Instant> = None;
+        let mut last = Instant::now();
+        while !stop.load(Ordering::Relaxed) {
+            let timeout = if pending.is_empty() {
+                Duration::from_millis(500)
+            } else {
+                Duration::from_millis(50)
+            };
+            if inotify.wait(timeout) {
+                for (path, mask) in inotify.read() {
+                    if mask & libc::IN_Q_OVERFLOW != 0 {
+                        pending.merge(RepoChanges::ALL);
+                        if let Some(journal) = &mut journal {
+                            journal.reset();
+                        }
+                        continue;
+                    }
+                    let is_dir = mask & libc::IN_ISDIR != 0;
+                    let created_dir = is_dir && mask & (libc::IN_CREATE | libc::IN_MOVED_TO) != 0;
+                    if let Some(changes) = paths.classify_git(&path) {
+                        let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+                        if let (Some(journal), true) =
+                            (&mut journal, name.starts_with(fsmonitor::COOKIE_PREFIX))
+                        {
+                            if mask & libc::IN_CREATE != 0 {
+                                journal.cookie(name);
+                            }
+                        }
+                        pending.merge(changes);
+                        if created_dir && path.starts_with(&refs) {
+                            inotify.add_tree(&path, &skip_nothing);
+                        }
+                    } else if let Ok(rel) = path.strip_prefix(&paths.workdir) {
+                        if path.file_name() != Some(".gitignore".as_ref()) && ignored(&path) {
+                            continue;
+                        }
+                        let mut rel = rel.to_string_lossy().replace('\\', "/");
+                        if is_dir {
+                            rel.push('/');
+                        }
+                        if let Some(journal) = &mut journal {
+                            journal.record(&rel);
+                        }
+                        if path.file_name() == Some(".gitignore".as_ref()) {
+                            // May hide or show files anywhere below
+                            pending.merge(RepoChanges {
+                                status: true,
+                                ..Default::default()
+                            });
+                        } else {
+                            pending.merge(RepoChanges::in_worktree(&rel));
+                        }
+                        if created_dir && worktree_ok {
+                            inotify.add_tree(&path, &ignored);
+                        }
+                    }
+                }
+                let now = Instant::now();
+                last = now;
+                first.get_or_insert(now);
+            }
+            let due =
+                first.is_some_and(|first| last.elapsed() >= QUIET || first.elapsed() >= MAX_DELAY);
+            if due && !pending.is_empty() {
+                if !on_change(std::mem::take(&mut pending)) {
+                    return;
+                }
+                first = None;
+            } else if due {
+                first = None;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_git_path() {
+        let index = classify_git_path("index");
+        assert!(index.status && !index.branches);
+        assert!(classify_git_path("index.lock").is_empty());
+        assert!(classify_git_path("objects/ab/cdef").is_empty());
+
+        let branch = classify_git_path("refs/heads/feature/x");
+        assert!(branch.branches && branch.status && !branch.tags);
+        assert_eq!(
+            classify_git_path("refs/remotes/origin/main"),
+            RepoChanges {
+                branches: true,
+                ..Default::default()
+            }
+        );
+        assert!(classify_git_path("refs/stash").stash);
+        assert!(classify_git_path("refs/g/trash/1").stash);
+        assert!(classify_git_path("packed-refs").tags);
+        assert!(classify_git_path("config").config);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_watch_repository() {
+        use crate::fixture::Fixture;
+        use std::sync::mpsc;
+        use std::time::Duration;
+
+        let mut fixture = Fixture::new("watch");
+        fixture.write(".gitignore", "target/\n");
+        std::fs::create_dir(fixture.dir.join("target")).unwrap();
+        fixture.commit("base", &[]);
+        let dir = &fixture.dir;
+
+        let (tx, rx) = mpsc::channel();
+        let watcher =
+            Watcher::start(dir, fixture.repo.path(), move |c| tx.send(c).is_ok()).unwrap();
+        // The working tree counts as watched once the thread has added its watches
+        for _ in 0..100 {
+            if watcher.watches_worktree() {
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(20));
+        }
+        assert!(watcher.watches_worktree());
+        let next = || rx.recv_timeout(Duration::from_secs(3));
+
+        std::fs::write(dir.join("target/out.o"), "ignored").unwrap();
+        assert!(rx.recv_timeout(Duration::from_millis(500)).is_err());
+        std::fs::write(dir.join("file.txt"), "hello").unwrap();
+        assert_eq!(
+            next().unwrap(),
+            RepoChanges {
+                status: true,
+                status_scope: vec!["file.txt".to_string()],
+                ..Default::default()
+            }
+        );
+
+        fixture.branch("feature");
+        assert!(next().unwrap().branches);
+
+        drop(watcher);
+
+        // Without watches on the git directory there is no watcher to rely on
+        assert!(Watcher::start(dir, &dir.join("missing"), |_| true).is_none());
+    }
+}
@@ -2,7 +2,71 @@
 pub struct WorktreeInfo {
     pub name: String,
     pub path: String,
+    /// Short id of the commit checked out in this worktree
     pub head: String,
+    /// Checked-out branch (None when HEAD is detached)
+    pub branch: Option<String>,
     pub is_main: bool,
+    /// True for the worktree the app is currently operating on
+    pub is_current: bool,
     pub is_locked: bool,
+    pub lock_reason: Option<String>,
+    /// Working directory is gone; the worktree can be pruned
+    pub is_prunable: bool,
+    pub is_dirty: bool,
+    pub ahead: usize,
+    pub behind: usize,
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fixture::Fixture;
+    use crate::git::Repository;
+
+    #[test]
+    fn test_list_worktrees() {
+        let mut fixture = Fixture::new("worktrees");
+        fixture.commit("base", &[("a", "1")]);
+        let repo = fixture.open();
+        let linked = fixture.dir.parent().unwrap().join("linked");
+        let name = repo.worktree_add(linked.to_str().unwrap(), None).unwrap();
+        assert_eq!(name, "linked");
+
+        let worktrees = repo.worktrees().unwrap();
+        assert_eq!(worktrees.len(), 2);
+        let (main, wt) = (&worktrees[0], &worktrees[1]);
+        assert!(main.is_main && main.is_current);
+        assert_eq!(main.branch.as_deref(), Some("main"));
+        assert_eq!(wt.name, "linked");
+        assert_eq!(wt.branch.as_deref(), Some("linked"));
+        assert_eq!(wt.head, main.head);
+        assert!(!wt.is_main && !wt.is_current && !wt.is_dirty);
+
+        // Seen from the linked worktree, the main one is found through commondir
+        std::fs::write(linked.join("a"), "2").unwrap();
+        let worktrees = Repository::open(&linked).unwrap().worktrees().unwrap();
+        assert_eq!(
+            std::path::Path::new(&worktrees[0].path)
+                .canonicalize()
+                .unwrap(),
+            fixture.dir.canonicalize().unwrap()
+        );
+        assert!(!worktrees[0].is_current);
+        assert!(worktrees[1].is_current && worktrees[1].is_dirty);
+
+        // A locked worktree is not removed without force
+        repo.worktree_lock("linked", Some("on a usb stick"))
+            .unwrap();
+        let wt = &repo.worktrees().unwrap()[1];
+        assert!(wt.is_locked);
+        assert_eq!(wt.lock_reason.as_deref(), Some("on a usb stick"));
+        assert!(repo.worktree_remove("linked", false).is_err());
+
+        // Once its directory is gone it can be pruned
+        repo.worktree_unlock("linked").unwrap();
+        std::fs::remove_dir_all(&linked).unwrap();
+        assert!(repo.worktrees().unwrap()[1].is_prunable);
+        assert_eq!(repo.worktree_prune().unwrap(), ["linked"]);
+        assert_eq!(repo.worktrees().unwrap().len(), 1);
+    }
 }
@@ -0,0 +1,309 @@
+//! Headless rendering tests: an `App` drawing into a fixed-size in-memory
+//! terminal, driven by scripted events, with fixture repositories built by
+//! git2 and snapshots of the rendered text and styles.
+//!
+//! Snapshots live in `src/harness/snapshots/<name>.snap`. A missing snapshot is
+//! written on the first run; run with `UPDATE_SNAPSHOTS=1` to accept changes.
+
+use crate::app::{App, Startup};
+use crate::config::{Column, Config, LayoutConfig, PanelHeight};
+pub use crate::fixture::Fixture;
+use crate::input::{Event, KeyCode, KeyEvent, Modifiers, MouseButton, MouseEvent, MouseEventKind};
+use crate::tui::{Buffer, Style, Terminal};
+use crate::views::PanelType;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// The default configuration, without anything from the user's config file,
+/// and a layout of the local panels only: GitHub panels depend on `gh` and
+/// the network.
+pub fn test_config() -> Config {
+    let panel = |panel, height| PanelHeight { panel, height };
+    Config {
+        layout: LayoutConfig {
+            columns: vec![
+                Column {
+                    width: 0.4,
+                    panels: vec![
+                        panel(PanelType::Status, 0.5),
+                        panel(PanelType::Branches, 0.5),
+                    ],
+                },
+                Column {
+                    width: 0.6,
+                    panels: vec![panel(PanelType::Commits, 0.5), panel(PanelType::Diff, 0.5)],
+                },
+            ],
+        },
+        // Screens change only through the steps a test takes
+        watch_files: false,
+        ..Config::default()
+    }
+}
+
+pub struct Harness {
+    pub app: App,
+    fixture: Fixture,
+}
+
+impl Harness {
+    pub fn new(fixture: Fixture, width: u16, height: u16) -> Self {
+        Self::with_config(fixture, width, height, test_config())
+    }
+
+    pub fn with_config(fixture: Fixture, width: u16, height: u16, config: Config) -> Self {
+        let startup = Startup {
+            repo_dir: Some(fixture.dir.clone()),
+            ..Default::default()
+        };
+        let mut terminal = Terminal::headless(width, height);
+        terminal.init().unwrap();
+        let mut app = App::with_terminal(startup, config, terminal).unwrap();
+        app.start_isolated().unwrap();
+        let mut harness = Self { app, fixture };
+        harness.draw();
+        harness
+    }
+
+    fn draw(&mut self) {
+        self.app.process_async_results();
+        self.app.draw().unwrap();
+    }
+
+    /// Handle an event and draw the next frame
+    pub fn event(&mut self, event: Event) {
+        self.app.handle_event(event).unwrap();
+        // Loads started by the event land before the frame, as a fast repository would
+        self.app.wait_for_loads();
+        self.draw();
+    }
+
+    pub fn key(&mut self, code: KeyCode) {
+        self.event(Event::Key(KeyEvent::new(code, Modifiers::NONE)));
+    }
+
+    /// Type each character of `keys` as a key press
+    pub fn keys(&mut self, keys: &str) {
+        for c in keys.chars() {
+            self.key(KeyCode::Char(c));
+        }
+    }
+
+    pub fn ctrl(&mut self, c: char) {
+        self.event(Event::Key(KeyEvent::ctrl(c)));
+    }
+
+    /// Left click: press and release
+    pub fn click(&mut self, column: u16, row: u16) {
+        for kind in [
+            MouseEventKind::Down(MouseButton::Left),
+            MouseEventKind::Up(MouseButton::Left),
+        ] {
+            self.event(Event::Mouse(MouseEvent { kind, column, row }));
+        }
+    }
+
+    /// Keep drawing until `done` holds for the screen text, for results of
+    /// background work; panics after five seconds
+    pub fn wait_for(&mut self, done: impl Fn(&str) -> bool) {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !done(&self.screen()) {
+            assert!(Instant::now() < deadline, "timed out:\n{}", self.screen());
+            std::thread::sleep(Duration::from_millis(10));
+            self.draw();
+        }
+    }
+
+    fn frame(&self) -> &Buffer {
+        self.app.terminal.last_frame()
+    }
+
+    /// One line of the screen; wide characters count once
+    pub fn row(&self, y: u16) -> String {
+        let frame = self.frame();
+        let line: String = (frame.area.x..frame.area.x + frame.area.width)
+            .map(|x| frame.get(x, y).symbol.as_str())
+            .collect();
+        line.trim_end().to_string()
+    }
+
+    pub fn screen(&self) -> String {
+        let area = self.frame().area;
+        (area.y..area.y + area.height)
+            .map(|y| self.row(y))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Position of the first cell of `text` on the screen
+    pub fn find(&self, text: &str) -> Option<(u16, u16)> {
+        let area = self.frame().area;
+        (area.y..area.y + area.height).find_map(|y| {
+            let cells: Vec<&str> = (area.x..area.x + area.width)
+                .map(|x| self.frame().get(x, y).symbol.as_str())
+                .collect();
+            (0..cells.len())
+                .find(|&i| cells[i..].concat().starts_with(text))
+                .map(|i| (area.x + i as u16, y))
+        })
+    }
+
+    pub fn style_at(&self, x: u16, y: u16) -> Style {
+        self.frame().get(x, y).style()
+    }
+
+    /// Screen text and a map of styles: one letter per cell, with a legend.
+    /// Commit ids of the fixture are replaced by `<n>` placeholders.
+    pub fn snapshot(&self) -> String {
+        let frame = self.frame();
+        let area = frame.area;
+        let mut styles: Vec<Style> = Vec::new();
+        let mut map = Vec::new();
+        for y in area.y..area.y + area.height {
+            let mut line = String::new();
+            for x in area.x..area.x + area.width {
+                let cell = frame.get(x, y);
+                if cell.symbol.is_empty() {
+                    continue;
+                }
+                let style = cell.style();
+                let index = match styles.iter().position(|s| *s == style) {
+                    Some(i) => i,
+                    None => {
+                        styles.push(style);
+                        styles.len() - 1
+                    }
+                };
+                line.push(legend_char(index));
+            }
+            map.push(line);
+        }
+
+        let mut out = String::from("--- text\n");
+        out.push_str(&self.redact(&self.screen()));
+        out.push_str("\n--- styles\n");
+        out.push_str(&map.join("\n"));
+        out.push_str("\n--- legend\n");
+        for (i, style) in styles.iter().enumerate() {
+            out.push_str(&format!("{} {:?}\n", legend_char(i), style));
+        }
+        out
+    }
+
+    /// Replace ids of fixture commits (7 or more characters of the hash) with
+    /// `<n>` padded to the same width, keeping the layout
+    fn redact(&self, text: &str) -> String {
+        let mut text = text.to_string();
+        for (i, oid) in self.fixture.commits.iter().enumerate() {
+            let id = oid.to_string();
+            let placeholder = format!("<{}>", i + 1);
+            let mut from = 0;
+            while let Some(pos) = text[from..].find(&id[..7]).map(|p| p + from) {
+                let len = text[pos..]
+                    .bytes()
+                    .zip(id.bytes())
+                    .take_while(|(a, b)| a == b)
+                    .count();
+                let replacement = format!("{:<width$}", placeholder, width = len);
+                text.replace_range(pos..pos + len, &replacement);
+                from = pos + len;
+            }
+        }
+        text
+    }
+
+    pub fn assert_snapshot(&self, name: &str) {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("src/harness/snapshots")
+            .join(format!("{}.snap", name));
+        let actual = self.snapshot();
+        let update = std::env::var("UPDATE_SNAPSHOTS").is_ok_and(|v| !v.is_empty());
+        match std::fs::read_to_string(&path) {
+            Ok(expected) if !update => {
+                if expected != actual {
+                    panic!(
+                        "snapshot {} differs (UPDATE_SNAPSHOTS=1 to accept)\n--- expected\n{}\n--- actual\n{}",
+                        name, expected, actual
+                    );
+                }
+            }
+            _ => {
+                std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+                std::fs::write(&path, actual).unwrap();
+            }
+        }
+    }
+}
+
+/// a-z, A-Z, 0-9, then '?' for any further styles
+fn legend_char(index: usize) -> char {
+    const CHARS: &[u8] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789";
+    CHARS.get(index).map(|&c| c as char).unwrap_or('?')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture(name: &str) -> Fixture {
+        let mut fixture = Fixture::new(name);
+        fixture.commit("Initial commit", &[("README.md", "# fixture\n")]);
+        fixture.commit(
+            "Add greeting",
+            &[("src/main.rs", "fn main() {\n    println!(\"hi\");\n}\n")],
+        );
+        fixture.branch("feature");
+        fixture.write("README.md", "# fixture\n\nChanged\n");
+        fixture.write("notes.txt", "untracked\n");
+        fixture
+    }
+
+    #[test]
+    fn test_render_panels() {
+        let harness = Harness::new(fixture("panels"), 100, 30);
+        let screen = harness.screen();
+        assert!(screen.contains("README.md"), "{}", screen);
+        assert!(screen.contains("notes.txt"));
+        assert!(screen.contains("feature"));
+        assert!(screen.contains("Add greeting"));
+        harness.assert_snapshot("panels");
+    }
+
+    #[test]
+    fn test_navigate_and_resize() {
+        let mut harness = Harness::new(fixture("navigate"), 100, 30);
+
+        harness.event(Event::Resize(60, 20));
+        assert_eq!(harness.screen().lines().count(), 20);
+        harness.assert_snapshot("navigate_resized");
+
+        // Select the first commit with the mouse, then move up to the second
+        let (x, y) = harness.find("Test Initial").unwrap();
+        harness.click(x, y);
+        assert!(harness.screen().contains("Author: Test <test@example.com>"));
+        harness.keys("k");
+        assert_ne!(
+            harness.style_at(x, y).bg,
+            Some(harness.app.config.theme.selection)
+        );
+        harness.keys("j");
+        assert_eq!(
+            harness.style_at(x, y).bg,
+            Some(harness.app.config.theme.selection)
+        );
+    }
+
+    #[test]
+    fn test_command_line() {
+        let mut harness = Harness::new(fixture("command"), 80, 24);
+        harness.keys(":");
+        harness.event(Event::Paste("theme li\nght".to_string()));
+        assert!(
+            harness.row(22).contains(":theme li ght"),
+            "{}",
+            harness.row(22)
+        );
+        harness.key(KeyCode::Escape);
+        assert!(!harness.row(22).contains(":theme"));
+    }
+}
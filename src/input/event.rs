@@ -3,6 +3,19 @@ pub enum Event {
     Key(KeyEvent),
     Mouse(MouseEvent),
     Resize(u16, u16),
+    /// Text pasted with bracketed paste, delivered in one piece
+    Paste(String),
+    /// The terminal window gained or lost focus (focus reporting)
+    FocusGained,
+    FocusLost,
+    /// The terminal's answer to whether it knows synchronized output (mode 2026)
+    SynchronizedOutput(bool),
+    /// SIGTSTP from outside: suspend like Ctrl-Z
+    Suspend,
+    /// SIGCONT: set the terminal up again
+    Resume,
+    /// SIGTERM or SIGHUP: quit cleanly
+    Terminate,
     None,
 }
 
@@ -95,6 +108,10 @@ impl Modifiers {
         Self(self.0 | other.0)
     }
 
+    pub const fn difference(self, other: Self) -> Self {
+        Self(self.0 & !other.0)
+    }
+
     pub const fn is_empty(&self) -> bool {
         self.0 == 0
     }
@@ -1,5 +1,6 @@
 mod event;
 mod reader;
+pub mod signals;
 
 pub use event::{Event, KeyCode, KeyEvent, Modifiers, MouseButton, MouseEvent, MouseEventKind};
 pub use reader::EventReader;
@@ -1,28 +1,37 @@
 use super::event::{Event, KeyCode, KeyEvent, Modifiers, MouseButton, MouseEvent, MouseEventKind};
+use super::signals::{self, Signal};
 use crate::error::Result;
+use crate::tui::Terminal;
 use std::io::Read;
 use std::time::Duration;
 
+/// Bracketed paste markers (`ESC [ 200 ~` ... `ESC [ 201 ~`)
+const PASTE_START: &[u8] = b"\x1b[200~";
+const PASTE_END: &[u8] = b"\x1b[201~";
+
 pub struct EventReader {
-    buffer: [u8; 32],
-    buffer_len: usize,
+    /// Bytes read but not parsed yet; grows to hold a whole bracketed paste
+    buffer: Vec<u8>,
 }
 
 impl EventReader {
     pub fn new() -> Self {
         Self {
-            buffer: [0; 32],
-            buffer_len: 0,
+            buffer: Vec::with_capacity(64),
         }
     }
 
     pub fn read_event(&mut self, timeout: Duration) -> Result<Event> {
+        // Signals first: a resize or suspend should not wait behind typed keys
+        if let Some(event) = Self::signal_event() {
+            return Ok(event);
+        }
+
         // If we have bytes in the buffer, try to parse them first
-        if self.buffer_len > 0 {
+        if !self.buffer.is_empty() {
             let (event, consumed) = self.parse_event();
             if consumed > 0 {
-                self.buffer.copy_within(consumed..self.buffer_len, 0);
-                self.buffer_len -= consumed;
+                self.buffer.drain(..consumed);
                 if !matches!(event, Event::None) {
                     return Ok(event);
                 }
@@ -31,36 +40,59 @@ impl EventReader {
 
         // Try to read from stdin with timeout
         if !self.poll_stdin(timeout)? {
-            return Ok(Event::None);
+            return Ok(Self::signal_event().unwrap_or(Event::None));
         }
 
         // Read available bytes
-        let n = std::io::stdin().read(&mut self.buffer[self.buffer_len..])?;
+        let mut chunk = [0u8; 1024];
+        let n = std::io::stdin().read(&mut chunk)?;
         if n == 0 {
             return Ok(Event::None);
         }
-        self.buffer_len += n;
+        self.buffer.extend_from_slice(&chunk[..n]);
 
         // Parse event
         let (event, consumed) = self.parse_event();
 
         // Remove consumed bytes
-        if consumed > 0 {
-            self.buffer.copy_within(consumed..self.buffer_len, 0);
-            self.buffer_len -= consumed;
-        }
+        self.buffer.drain(..consumed);
 
         Ok(event)
     }
 
+    #[cfg(unix)]
+    fn signal_event() -> Option<Event> {
+        let event = match signals::next()? {
+            Signal::Resize => {
+                let (width, height) = Terminal::size_static().ok()?;
+                Event::Resize(width, height)
+            }
+            Signal::Suspend => Event::Suspend,
+            Signal::Resume => Event::Resume,
+            Signal::Terminate => Event::Terminate,
+        };
+        Some(event)
+    }
+
+    #[cfg(windows)]
+    fn signal_event() -> Option<Event> {
+        None
+    }
+
+    /// Wait until stdin is readable; also wakes up (returning false) for a signal
     #[cfg(unix)]
     fn poll_stdin(&self, timeout: Duration) -> Result<bool> {
         use std::os::unix::io::AsRawFd;
 
+        let signal_fd = signals::fd();
         unsafe {
             let mut fds: libc::fd_set = std::mem::zeroed();
             libc::FD_ZERO(&mut fds);
             libc::FD_SET(libc::STDIN_FILENO, &mut fds);
+            if let Some(fd) = signal_fd {
+                libc::FD_SET(fd, &mut fds);
+            }
+            let nfds = signal_fd.unwrap_or(0).max(libc::STDIN_FILENO) + 1;
 
             let mut tv = libc::timeval {
                 tv_sec: timeout.as_secs() as libc::time_t,
@@ -68,14 +100,14 @@ impl EventReader {
             };
 
             let result = libc::select(
-                libc::STDIN_FILENO + 1,
+                nfds,
                 &mut fds,
                 std::ptr::null_mut(),
                 std::ptr::null_mut(),
                 &mut tv,
             );
 
-            Ok(result > 0)
+            Ok(result > 0 && libc::FD_ISSET(libc::STDIN_FILENO, &fds))
         }
     }
 
@@ -96,15 +128,15 @@ impl EventReader {
     }
 
     fn parse_event(&self) -> (Event, usize) {
-        if self.buffer_len == 0 {
+        if self.buffer.is_empty() {
             return (Event::None, 0);
         }
 
-        let bytes = &self.buffer[..self.buffer_len];
+        let bytes = &self.buffer[..];
 
         // Check for escape sequences
         if bytes[0] == 0x1b {
-            if self.buffer_len == 1 {
+            if bytes.len() == 1 {
                 // Just escape
                 return (
                     Event::Key(KeyEvent::new(KeyCode::Escape, Modifiers::NONE)),
@@ -169,12 +201,77 @@ impl EventReader {
     }
 
     fn parse_utf8(&self) -> Option<(char, usize)> {
-        let bytes = &self.buffer[..self.buffer_len];
-        let s = std::str::from_utf8(bytes).ok()?;
+        // Only the first character needs to be complete
+        let len = self.buffer.len().min(4);
+        let s = match std::str::from_utf8(&self.buffer[..len]) {
+            Ok(s) => s,
+            Err(e) => std::str::from_utf8(&self.buffer[..e.valid_up_to()]).ok()?,
+        };
         let c = s.chars().next()?;
         Some((c, c.len_utf8()))
     }
 
+    /// `ESC [ 200 ~ text ESC [ 201 ~`; waits until the end marker has arrived
+    fn parse_paste(&self) -> (Event, usize) {
+        let body = &self.buffer[PASTE_START.len()..];
+        match body.windows(PASTE_END.len()).position(|w| w == PASTE_END) {
+            Some(end) => {
+                let text = String::from_utf8_lossy(&body[..end]).replace("\r\n", "\n");
+                (
+                    Event::Paste(text.replace('\r', "\n")),
+                    PASTE_START.len() + end + PASTE_END.len(),
+                )
+            }
+            None => (Event::None, 0),
+        }
+    }
+
+    /// Kitty keyboard protocol: `ESC [ code[:alternates] [; modifiers[:event]] [; text] u`.
+    /// `bytes` starts after `ESC [` and `end` is the index of the `u`.
+    fn parse_kitty_key(&self, bytes: &[u8], end: usize) -> (Event, usize) {
+        let consumed = end + 3;
+        let params = String::from_utf8_lossy(&bytes[..end]);
+        let mut fields = params.split(';');
+        let code: u32 = match fields
+            .next()
+            .and_then(|f| f.split(':').next())
+            .and_then(|n| n.parse().ok())
+        {
+            Some(code) => code,
+            None => return (Event::None, consumed),
+        };
+        let mut modifier_field = fields.next().unwrap_or("1").split(':');
+        let modifier = modifier_field
+            .next()
+            .and_then(|n| n.parse().ok())
+            .unwrap_or(1);
+        // Release events (3) are only sent if asked for; ignore them anyway
+        if modifier_field.next() == Some("3") {
+            return (Event::None, consumed);
+        }
+        let mut modifiers = self.parse_modifier(modifier);
+
+        let code = match code {
+            27 => KeyCode::Escape,
+            13 => KeyCode::Enter,
+            9 if modifiers.contains(Modifiers::SHIFT) => KeyCode::BackTab,
+            9 => KeyCode::Tab,
+            127 => KeyCode::Backspace,
+            c => match char::from_u32(c) {
+                // Shift+letter arrives as the lower-case key; report the
+                // character it types, as legacy input does
+                Some(ch) if modifiers.contains(Modifiers::SHIFT) && ch.is_ascii_lowercase() => {
+                    modifiers = modifiers.difference(Modifiers::SHIFT);
+                    KeyCode::Char(ch.to_ascii_uppercase())
+                }
+                Some(ch) if !ch.is_control() => KeyCode::Char(ch),
+                // Lock keys, keypad and media keys (57358+) have no use here
+                _ => return (Event::None, consumed),
+            },
+        };
+        (Event::Key(KeyEvent::new(code, modifiers)), consumed)
+    }
+
     fn parse_csi_sequence(&self, bytes: &[u8]) -> (Event, usize) {
         // CSI sequences: ESC [ ...
         // Base offset is 2 (ESC [)
@@ -196,6 +293,43 @@ impl EventReader {
             return self.parse_normal_mouse(&bytes[1..]);
         }
 
+        if self.buffer.starts_with(PASTE_START) {
+            return self.parse_paste();
+        }
+
+        // Focus reporting (mode 1004)
+        match bytes[0] {
+            b'I' => return (Event::FocusGained, 3),
+            b'O' => return (Event::FocusLost, 3),
+            _ => {}
+        }
+
+        // Replies to queries (`ESC [ ? ... u` kitty flags, `ESC [ ? ... c` device
+        // attributes): nothing to act on, but they must not turn into keys.
+        // `ESC [ ? 2026 ; Ps $ y` reports synchronized output: 1 or 2 if known
+        if bytes[0] == b'?' {
+            return match bytes.iter().position(|b| b.is_ascii_alphabetic()) {
+                Some(end) => match &bytes[1..end] {
+                    [b'2', b'0', b'2', b'6', b';', state, b'$'] if bytes[end] == b'y' => (
+                        Event::SynchronizedOutput(matches!(state, b'1' | b'2')),
+                        end + 3,
+                    ),
+                    _ => (Event::None, end + 3),
+                },
+                None => (Event::None, 0),
+            };
+        }
+
+        // Kitty keyboard protocol: ESC [ digits/;/: u
+        if let Some(end) = bytes
+            .iter()
+            .position(|b| !matches!(b, b'0'..=b'9' | b';' | b':'))
+        {
+            if bytes[end] == b'u' && end > 0 {
+                return self.parse_kitty_key(bytes, end);
+            }
+        }
+
         // Simple arrow keys and navigation
         match bytes[0] {
             b'A' => return (Event::Key(KeyEvent::new(KeyCode::Up, Modifiers::NONE)), 3),
@@ -254,13 +388,19 @@ impl EventReader {
                     let modifiers = self.parse_modifier(modifier);
                     return (Event::Key(KeyEvent::new(code, modifiers)), i + 3);
                 }
-                b'A'..=b'D' => {
-                    // Arrow keys with modifiers: ESC [ 1 ; modifier A/B/C/D
+                b'A'..=b'D' | b'H' | b'F' | b'P'..=b'S' => {
+                    // Keys with modifiers: ESC [ 1 ; modifier A/B/C/D/H/F/P/Q/R/S
                     let code = match bytes[i] {
                         b'A' => KeyCode::Up,
                         b'B' => KeyCode::Down,
                         b'C' => KeyCode::Right,
                         b'D' => KeyCode::Left,
+                        b'H' => KeyCode::Home,
+                        b'F' => KeyCode::End,
+                        b'P' => KeyCode::F(1),
+                        b'Q' => KeyCode::F(2),
+                        b'R' => KeyCode::F(3),
+                        b'S' => KeyCode::F(4),
                         _ => unreachable!(),
                     };
                     let modifiers = self.parse_modifier(modifier);
@@ -441,3 +581,66 @@ impl EventReader {
         result
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(bytes: &[u8]) -> (Event, usize) {
+        let reader = EventReader {
+            buffer: bytes.to_vec(),
+        };
+        reader.parse_event()
+    }
+
+    #[test]
+    fn test_parse_extended_input() {
+        let paste = b"\x1b[200~fix: title\r\n\r\nbody q\x1b[201~j";
+        assert_eq!(
+            parse(paste),
+            (
+                Event::Paste("fix: title\n\nbody q".to_string()),
+                paste.len() - 1
+            )
+        );
+        // Incomplete paste waits for the rest
+        assert_eq!(parse(b"\x1b[200~partial"), (Event::None, 0));
+
+        assert_eq!(parse(b"\x1b[I"), (Event::FocusGained, 3));
+        assert_eq!(parse(b"\x1b[O"), (Event::FocusLost, 3));
+
+        // Kitty keyboard: Ctrl+i is not Tab, Escape is unambiguous
+        assert_eq!(
+            parse(b"\x1b[105;5u"),
+            (
+                Event::Key(KeyEvent::new(KeyCode::Char('i'), Modifiers::CTRL)),
+                8
+            )
+        );
+        assert_eq!(
+            parse(b"\x1b[27u"),
+            (
+                Event::Key(KeyEvent::new(KeyCode::Escape, Modifiers::NONE)),
+                5
+            )
+        );
+        assert_eq!(
+            parse(b"\x1b[97;2u"),
+            (
+                Event::Key(KeyEvent::new(KeyCode::Char('A'), Modifiers::NONE)),
+                7
+            )
+        );
+        // Flags reply is swallowed
+        assert_eq!(parse(b"\x1b[?1u"), (Event::None, 5));
+        // Mode 2026 reports
+        assert_eq!(
+            parse(b"\x1b[?2026;2$y"),
+            (Event::SynchronizedOutput(true), 11)
+        );
+        assert_eq!(
+            parse(b"\x1b[?2026;0$y"),
+            (Event::SynchronizedOutput(false), 11)
+        );
+    }
+}
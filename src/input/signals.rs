@@ -0,0 +1,168 @@
+//! Unix signals as input events, through a self-pipe.
+//!
+//! The handlers only write the signal number to a pipe, which is all a signal
+//! handler can safely do. `EventReader` polls the read end next to stdin and
+//! turns each byte into an `Event`.
+
+#[cfg(unix)]
+use std::sync::atomic::{AtomicI32, Ordering};
+
+#[cfg(unix)]
+static READ_FD: AtomicI32 = AtomicI32::new(-1);
+#[cfg(unix)]
+static WRITE_FD: AtomicI32 = AtomicI32::new(-1);
+
+/// Signals delivered as events
+#[cfg(unix)]
+const HANDLED: [libc::c_int; 5] = [
+    libc::SIGWINCH,
+    libc::SIGTSTP,
+    libc::SIGCONT,
+    libc::SIGTERM,
+    libc::SIGHUP,
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Signal {
+    /// SIGWINCH
+    Resize,
+    /// SIGTSTP sent from outside (`kill -TSTP`); Ctrl-Z arrives as a key in raw mode
+    Suspend,
+    /// SIGCONT: continued after a stop, the shell may have reset the terminal
+    Resume,
+    /// SIGTERM or SIGHUP
+    Terminate,
+}
+
+#[cfg(unix)]
+extern "C" fn on_signal(signal: libc::c_int) {
+    let fd = WRITE_FD.load(Ordering::Relaxed);
+    if fd >= 0 {
+        let byte = signal as u8;
+        // Nothing to do if the pipe is full: the events already queued do
+        unsafe {
+            libc::write(fd, &byte as *const u8 as *const libc::c_void, 1);
+        }
+    }
+}
+
+#[cfg(unix)]
+fn set_handler(signal: libc::c_int) {
+    unsafe {
+        let mut action: libc::sigaction = std::mem::zeroed();
+        action.sa_sigaction = on_signal as *const () as libc::sighandler_t;
+        // Restart interrupted reads instead of failing them with EINTR
+        action.sa_flags = libc::SA_RESTART;
+        libc::sigemptyset(&mut action.sa_mask);
+        libc::sigaction(signal, &action, std::ptr::null_mut());
+    }
+}
+
+/// Create the pipe and install the handlers; later calls do nothing
+#[cfg(unix)]
+pub fn install() -> std::io::Result<()> {
+    if READ_FD.load(Ordering::Relaxed) >= 0 {
+        return Ok(());
+    }
+    let mut fds = [0; 2];
+    unsafe {
+        if libc::pipe(fds.as_mut_ptr()) != 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        for fd in fds {
+            let flags = libc::fcntl(fd, libc::F_GETFL);
+            libc::fcntl(fd, libc::F_SETFL, flags | libc::O_NONBLOCK);
+            libc::fcntl(fd, libc::F_SETFD, libc::FD_CLOEXEC);
+        }
+    }
+    READ_FD.store(fds[0], Ordering::Relaxed);
+    WRITE_FD.store(fds[1], Ordering::Relaxed);
+    for signal in HANDLED {
+        set_handler(signal);
+    }
+    Ok(())
+}
+
+#[cfg(windows)]
+pub fn install() -> std::io::Result<()> {
+    Ok(())
+}
+
+/// Read end of the pipe, once `install` has run
+#[cfg(unix)]
+pub(crate) fn fd() -> Option<libc::c_int> {
+    let fd = READ_FD.load(Ordering::Relaxed);
+    (fd >= 0).then_some(fd)
+}
+
+/// Next signal from the pipe, if any arrived
+#[cfg(unix)]
+pub(crate) fn next() -> Option<Signal> {
+    let fd = fd()?;
+    let mut byte = 0u8;
+    loop {
+        let n = unsafe { libc::read(fd, &mut byte as *mut u8 as *mut libc::c_void, 1) };
+        if n != 1 {
+            return None;
+        }
+        match byte as libc::c_int {
+            libc::SIGWINCH => return Some(Signal::Resize),
+            libc::SIGTSTP => return Some(Signal::Suspend),
+            libc::SIGCONT => return Some(Signal::Resume),
+            libc::SIGTERM | libc::SIGHUP => return Some(Signal::Terminate),
+            _ => {}
+        }
+    }
+}
+
+/// Stop the process as Ctrl-Z would in a shell; returns once it is continued.
+/// The caller releases the terminal before, and sets it up again on the
+/// `Signal::Resume` the continuing SIGCONT queues.
+#[cfg(unix)]
+pub fn suspend() {
+    unsafe {
+        libc::signal(libc::SIGTSTP, libc::SIG_DFL);
+        libc::raise(libc::SIGTSTP);
+    }
+    set_handler(libc::SIGTSTP);
+}
+
+#[cfg(windows)]
+pub fn suspend() {}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    fn send(signal: libc::c_int) {
+        let byte = signal as u8;
+        let fd = WRITE_FD.load(Ordering::Relaxed);
+        assert_eq!(
+            unsafe { libc::write(fd, &byte as *const u8 as *const libc::c_void, 1) },
+            1
+        );
+    }
+
+    #[test]
+    fn test_next_reads_signals_from_pipe() {
+        install().unwrap();
+        while next().is_some() {}
+
+        send(libc::SIGCONT);
+        send(0);
+        send(libc::SIGTSTP);
+        send(libc::SIGHUP);
+        assert_eq!(next(), Some(Signal::Resume));
+        // Bytes that are not a handled signal are skipped
+        assert_eq!(next(), Some(Signal::Suspend));
+        assert_eq!(next(), Some(Signal::Terminate));
+        assert_eq!(next(), None);
+
+        // A real signal goes through the handler
+        unsafe {
+            libc::raise(libc::SIGWINCH);
+        }
+        assert_eq!(next(), Some(Signal::Resize));
+        assert_eq!(next(), None);
+    }
+}
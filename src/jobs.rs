@@ -0,0 +1,434 @@
+//! Background jobs for `Repository` calls.
+//!
+//! Each job runs on its own thread and sends its result through the app's
+//! result channel. Loads for a view carry a generation, so a result overtaken
+//! by a newer request of the same view is dropped. Jobs that move the same
+//! refs wait for each other and run in the order they were started; calls
+//! made on the UI thread take the same queues with `try_hold`, which never
+//! waits.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::Sender;
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use std::time::Instant;
+
+/// Shared flag asking a job to stop at its next check
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+/// Views filled by load jobs; a new load supersedes the running one
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum JobView {
+    Status,
+    Commits,
+    Diff,
+    Blame,
+}
+
+/// A running job, as listed in the jobs picker
+#[derive(Debug, Clone)]
+pub struct JobInfo {
+    pub id: u64,
+    pub label: String,
+    pub started: Instant,
+    /// Waiting for an earlier job on the same refs
+    pub queued: bool,
+}
+
+struct Job {
+    id: u64,
+    label: String,
+    refs: Vec<String>,
+    started: Instant,
+    token: CancelToken,
+    queued: Arc<AtomicBool>,
+    done: Arc<AtomicBool>,
+}
+
+#[derive(Default)]
+struct Generation {
+    latest: u64,
+    loading: bool,
+    token: CancelToken,
+}
+
+/// Jobs waiting for each ref, in start order; the front one holds the ref
+#[derive(Default)]
+struct RefQueues {
+    queues: Mutex<HashMap<String, VecDeque<u64>>>,
+    turn: Condvar,
+}
+
+impl RefQueues {
+    fn enqueue(&self, id: u64, refs: &[String]) {
+        let mut queues = self.queues.lock().unwrap_or_else(|e| e.into_inner());
+        for name in refs {
+            queues.entry(name.clone()).or_default().push_back(id);
+        }
+    }
+
+    /// Queue `id` on `refs` only if no other job is on any of them
+    fn try_take(&self, id: u64, refs: &[String]) -> bool {
+        let mut queues = self.queues.lock().unwrap_or_else(|e| e.into_inner());
+        if refs.iter().any(|name| queues.contains_key(name)) {
+            return false;
+        }
+        for name in refs {
+            queues.entry(name.clone()).or_default().push_back(id);
+        }
+        true
+    }
+
+    /// Block until `id` is first in line for all of `refs`, or is cancelled
+    fn wait_turn(&self, id: u64, refs: &[String], token: &CancelToken) {
+        let mut queues = self.queues.lock().unwrap_or_else(|e| e.into_inner());
+        while !token.is_cancelled()
+            && !refs
+                .iter()
+                .all(|name| queues.get(name).and_then(|q| q.front()) == Some(&id))
+        {
+            // Cancellation does not notify, so look again now and then
+            queues = self
+                .turn
+                .wait_timeout(queues, std::time::Duration::from_millis(100))
+                .unwrap_or_else(|e| e.into_inner())
+                .0;
+        }
+    }
+
+    fn release(&self, id: u64, refs: &[String]) {
+        let mut queues = self.queues.lock().unwrap_or_else(|e| e.into_inner());
+        for name in refs {
+            if let Some(queue) = queues.get_mut(name) {
+                queue.retain(|&queued| queued != id);
+                if queue.is_empty() {
+                    queues.remove(name);
+                }
+            }
+        }
+        self.turn.notify_all();
+    }
+}
+
+/// Refs held by a call on the UI thread, given up on drop
+pub struct RefHold {
+    id: u64,
+    refs: Vec<String>,
+    queues: Arc<RefQueues>,
+}
+
+impl Drop for RefHold {
+    fn drop(&mut self) {
+        self.queues.release(self.id, &self.refs);
+    }
+}
+
+/// Marks the job done and gives up its refs even if `work` panics
+struct Finish {
+    id: u64,
+    refs: Vec<String>,
+    queues: Arc<RefQueues>,
+    done: Arc<AtomicBool>,
+}
+
+impl Drop for Finish {
+    fn drop(&mut self) {
+        self.queues.release(self.id, &self.refs);
+        self.done.store(true, Ordering::SeqCst);
+    }
+}
+
+#[derive(Default)]
+pub struct Jobs {
+    next_id: u64,
+    jobs: Vec<Job>,
+    generations: HashMap<JobView, Generation>,
+    refs: Arc<RefQueues>,
+}
+
+impl Jobs {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start a new load of `view`, cancelling the one it replaces.
+    /// Returns the generation to hand back to `finish` and the load's token.
+    pub fn begin(&mut self, view: JobView) -> (u64, CancelToken) {
+        let generation = self.generations.entry(view).or_default();
+        generation.token.cancel();
+        generation.token = CancelToken::new();
+        generation.latest += 1;
+        generation.loading = true;
+        (generation.latest, generation.token.clone())
+    }
+
+    /// Settle a load of `view`; false when a newer one was started since,
+    /// and its result should be dropped
+    pub fn finish(&mut self, view: JobView, generation: u64) -> bool {
+        match self.generations.get_mut(&view) {
+            Some(g) if g.latest == generation => {
+                g.loading = false;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Whether the latest load of `view` has not come back yet
+    pub fn is_loading(&self, view: JobView) -> bool {
+        self.generations.get(&view).is_some_and(|g| g.loading)
+    }
+
+    /// Whether any view is still waiting for a load
+    pub fn any_loading(&self) -> bool {
+        self.generations.values().any(|g| g.loading)
+    }
+
+    /// Run `work` on a new thread and send its result to `sender`.
+    ///
+    /// `refs` names the refs the job moves: jobs sharing one run one at a
+    /// time, in the order they were spawned. A job cancelled while queued
+    /// still runs `work`, which sees the cancelled token and can report it.
+    pub fn spawn<T, F>(
+        &mut self,
+        label: impl Into<String>,
+        refs: Vec<String>,
+        token: CancelToken,
+        sender: Sender<T>,
+        work: F,
+    ) -> u64
+    where
+        T: Send + 'static,
+        F: FnOnce(&CancelToken) -> T + Send + 'static,
+    {
+        self.next_id += 1;
+        let id = self.next_id;
+        let queued = Arc::new(AtomicBool::new(!refs.is_empty()));
+        let done = Arc::new(AtomicBool::new(false));
+        self.refs.enqueue(id, &refs);
+        self.jobs.retain(|job| !job.done.load(Ordering::SeqCst));
+        self.jobs.push(Job {
+            id,
+            label: label.into(),
+            refs: refs.clone(),
+            started: Instant::now(),
+            token: token.clone(),
+            queued: queued.clone(),
+            done: done.clone(),
+        });
+
+        let queues = self.refs.clone();
+        thread::spawn(move || {
+            let finish = Finish {
+                id,
+                refs,
+                queues,
+                done,
+            };
+            finish.queues.wait_turn(id, &finish.refs, &token);
+            queued.store(false, Ordering::SeqCst);
+            let result = work(&token);
+            let _ = sender.send(result);
+            drop(finish);
+        });
+        id
+    }
+
+    /// Take `refs` for a call on the calling thread if no job is running or
+    /// queued on them; otherwise the label of the job in the way
+    pub fn try_hold(&mut self, refs: &[&str]) -> std::result::Result<RefHold, String> {
+        self.next_id += 1;
+        let id = self.next_id;
+        let refs: Vec<String> = refs.iter().map(|name| name.to_string()).collect();
+        if !self.refs.try_take(id, &refs) {
+            let names: Vec<&str> = refs.iter().map(|name| name.as_str()).collect();
+            return Err(self
+                .holder(&names)
+                .unwrap_or_else(|| "another job".to_string()));
+        }
+        Ok(RefHold {
+            id,
+            refs,
+            queues: self.refs.clone(),
+        })
+    }
+
+    /// The oldest unfinished job on any of `refs`
+    pub fn holder(&self, refs: &[&str]) -> Option<String> {
+        self.jobs
+            .iter()
+            .filter(|job| !job.done.load(Ordering::SeqCst))
+            .find(|job| job.refs.iter().any(|name| refs.contains(&name.as_str())))
+            .map(|job| job.label.clone())
+    }
+
+    /// Jobs still running or queued, oldest first
+    pub fn running(&self) -> Vec<JobInfo> {
+        self.jobs
+            .iter()
+            .filter(|job| !job.done.load(Ordering::SeqCst))
+            .map(|job| JobInfo {
+                id: job.id,
+                label: job.label.clone(),
+                started: job.started,
+                queued: job.queued.load(Ordering::SeqCst),
+            })
+            .collect()
+    }
+
+    /// Whether some job thread has not finished
+    pub fn is_busy(&self) -> bool {
+        self.jobs.iter().any(|job| !job.done.load(Ordering::SeqCst))
+    }
+
+    /// Ask job `id` to stop; false when it already finished
+    pub fn cancel(&self, id: u64) -> bool {
+        match self
+            .jobs
+            .iter()
+            .find(|job| job.id == id && !job.done.load(Ordering::SeqCst))
+        {
+            Some(job) => {
+                job.token.cancel();
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    #[test]
+    fn test_newer_load_supersedes() {
+        let mut jobs = Jobs::new();
+        let (first, first_token) = jobs.begin(JobView::Diff);
+        let (second, _) = jobs.begin(JobView::Diff);
+        assert!(first_token.is_cancelled());
+        assert!(jobs.is_loading(JobView::Diff));
+        assert!(!jobs.finish(JobView::Diff, first));
+        assert!(jobs.is_loading(JobView::Diff));
+        assert!(jobs.finish(JobView::Diff, second));
+        assert!(!jobs.any_loading());
+    }
+
+    #[test]
+    fn test_jobs_on_same_ref_run_in_order() {
+        let mut jobs = Jobs::new();
+        let (sender, receiver) = mpsc::channel();
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let refs = || vec!["refs/heads/main".to_string()];
+
+        for n in 0..3 {
+            let log = log.clone();
+            jobs.spawn(
+                format!("job {}", n),
+                refs(),
+                CancelToken::new(),
+                sender.clone(),
+                move |_| {
+                    log.lock().unwrap().push(format!("start {}", n));
+                    thread::sleep(Duration::from_millis(20));
+                    log.lock().unwrap().push(format!("end {}", n));
+                    n
+                },
+            );
+        }
+        let results: Vec<i32> = (0..3).map(|_| receiver.recv().unwrap()).collect();
+        assert_eq!(results, vec![0, 1, 2]);
+        let log = log.lock().unwrap().clone();
+        assert_eq!(
+            log,
+            ["start 0", "end 0", "start 1", "end 1", "start 2", "end 2"]
+        );
+
+        // A queued job that is cancelled stops waiting
+        let (hold, release) = mpsc::channel::<()>();
+        let blocker = jobs.spawn(
+            "hold",
+            refs(),
+            CancelToken::new(),
+            sender.clone(),
+            move |_| {
+                let _ = release.recv();
+                10
+            },
+        );
+        let queued = jobs.spawn("queued", refs(), CancelToken::new(), sender, |token| {
+            if token.is_cancelled() {
+                -1
+            } else {
+                11
+            }
+        });
+        let running = jobs.running();
+        assert_eq!(running.len(), 2);
+        assert_eq!(running[0].id, blocker);
+        assert!(running[1].queued);
+        assert!(jobs.cancel(queued));
+        assert_eq!(receiver.recv().unwrap(), -1);
+        hold.send(()).unwrap();
+        assert_eq!(receiver.recv().unwrap(), 10);
+    }
+
+    #[test]
+    fn test_hold_refused_while_job_has_ref() {
+        let mut jobs = Jobs::new();
+        let (sender, receiver) = mpsc::channel();
+        let log = Arc::new(Mutex::new(Vec::new()));
+
+        let (hold_pull, release_pull) = mpsc::channel::<()>();
+        jobs.spawn(
+            "pull",
+            vec!["HEAD".to_string()],
+            CancelToken::new(),
+            sender.clone(),
+            move |_| {
+                let _ = release_pull.recv();
+            },
+        );
+        // The UI thread is turned away rather than blocked
+        assert_eq!(jobs.try_hold(&["HEAD"]).err().as_deref(), Some("pull"));
+        assert!(jobs.try_hold(&["refs/remotes/origin"]).is_ok());
+        hold_pull.send(()).unwrap();
+        receiver.recv().unwrap();
+
+        // A job started while the UI thread holds the ref waits for it
+        let hold = jobs.try_hold(&["HEAD"]).unwrap();
+        let job_log = log.clone();
+        jobs.spawn(
+            "commit",
+            vec!["HEAD".to_string()],
+            CancelToken::new(),
+            sender,
+            move |_| {
+                job_log.lock().unwrap().push("commit");
+            },
+        );
+        thread::sleep(Duration::from_millis(20));
+        log.lock().unwrap().push("undo");
+        drop(hold);
+        receiver.recv().unwrap();
+        assert_eq!(*log.lock().unwrap(), ["undo", "commit"]);
+    }
+}
@@ -5,30 +5,53 @@
 #[macro_use]
 mod logger;
 mod app;
+mod cli;
 mod config;
 mod error;
+#[cfg(test)]
+mod fixture;
 mod git;
+#[cfg(test)]
+mod harness;
 mod input;
+mod jobs;
 mod tui;
 mod views;
 mod widgets;
+mod workspace;
 
 use app::App;
 use error::{Error, Result};
 
+/// Leave the input modes, the alternate screen and show the cursor, for when
+/// `Terminal::restore` cannot run
+const RESTORE_TERMINAL: &[u8] = b"\x1b[<u\x1b[?1004l\x1b[?2004l\x1b[?1049l\x1b[?25h";
+
 fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let startup = match cli::parse(&args) {
+        Ok(invocation) => match invocation.startup() {
+            Some(startup) => startup,
+            None => std::process::exit(cli::run(&invocation)),
+        },
+        Err(message) => {
+            eprintln!("g: {}\n\n{}", message, cli::USAGE);
+            std::process::exit(cli::EXIT_USAGE);
+        }
+    };
+
     // Set up panic handler to show errors before exiting
     std::panic::set_hook(Box::new(|panic_info| {
         // Try to restore terminal state
-        let _ = std::io::Write::write_all(&mut std::io::stdout(), b"\x1b[?1049l\x1b[?25h");
+        let _ = std::io::Write::write_all(&mut std::io::stdout(), RESTORE_TERMINAL);
         eprintln!("\nPanic: {}", panic_info);
         eprintln!("\nPress Enter to exit...");
         let _ = std::io::stdin().read_line(&mut String::new());
     }));
 
-    if let Err(e) = run() {
+    if let Err(e) = run(startup) {
         // Try to restore terminal state
-        let _ = std::io::Write::write_all(&mut std::io::stdout(), b"\x1b[?1049l\x1b[?25h");
+        let _ = std::io::Write::write_all(&mut std::io::stdout(), RESTORE_TERMINAL);
 
         // Show user-friendly error messages for common git errors
         let message = match &e {
@@ -53,9 +76,9 @@ fn main() {
     }
 }
 
-fn run() -> Result<()> {
+fn run(startup: app::Startup) -> Result<()> {
     logger::init();
     info!("Application starting");
-    let mut app = App::new()?;
-    app.run()
+    let app = App::new(startup)?;
+    workspace::Workspace::new(app).run()
 }
@@ -0,0 +1,293 @@
+//! What colors the terminal can show, and mapping styles down to that.
+//!
+//! Views always use the theme's 24-bit colors; `Terminal` converts each style
+//! with `Style::for_depth` when it writes the frame.
+
+use super::style::{Color, Modifier, Style};
+use std::path::PathBuf;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorDepth {
+    /// 24-bit RGB
+    TrueColor,
+    /// xterm 256-color palette
+    Ansi256,
+    /// The 16 basic colors
+    Ansi16,
+    /// No colors (`NO_COLOR`): highlights are shown with reverse video
+    Mono,
+}
+
+impl ColorDepth {
+    /// "truecolor", "256", "16" or "none"; anything else ("auto") is None
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "truecolor" | "24bit" | "rgb" => Some(ColorDepth::TrueColor),
+            "256" => Some(ColorDepth::Ansi256),
+            "16" => Some(ColorDepth::Ansi16),
+            "none" | "mono" | "monochrome" => Some(ColorDepth::Mono),
+            _ => None,
+        }
+    }
+
+    /// From `NO_COLOR`, `COLORTERM`, `TERM` and the terminfo entry
+    pub fn detect() -> Self {
+        let var = |name| std::env::var(name).unwrap_or_default();
+        Self::from_env(&var("NO_COLOR"), &var("COLORTERM"), &var("TERM"), || {
+            terminfo_colors(&var("TERM"))
+        })
+    }
+
+    fn from_env(
+        no_color: &str,
+        colorterm: &str,
+        term: &str,
+        terminfo: impl FnOnce() -> Option<i32>,
+    ) -> Self {
+        // https://no-color.org: any non-empty value
+        if !no_color.is_empty() {
+            return ColorDepth::Mono;
+        }
+        if matches!(colorterm, "truecolor" | "24bit") || term.ends_with("-direct") {
+            return ColorDepth::TrueColor;
+        }
+        if term == "dumb" {
+            return ColorDepth::Mono;
+        }
+        match terminfo() {
+            Some(n) if n >= 1 << 24 => ColorDepth::TrueColor,
+            Some(n) if n >= 256 => ColorDepth::Ansi256,
+            Some(n) if n >= 8 => ColorDepth::Ansi16,
+            Some(_) => ColorDepth::Mono,
+            // No terminfo entry: guess from the name
+            None if term.contains("256color") => ColorDepth::Ansi256,
+            None => ColorDepth::Ansi16,
+        }
+    }
+}
+
+/// The standard xterm values of the 16 basic colors
+const ANSI16: [(Color, (u8, u8, u8)); 16] = [
+    (Color::Black, (0, 0, 0)),
+    (Color::Red, (205, 0, 0)),
+    (Color::Green, (0, 205, 0)),
+    (Color::Yellow, (205, 205, 0)),
+    (Color::Blue, (0, 0, 238)),
+    (Color::Magenta, (205, 0, 205)),
+    (Color::Cyan, (0, 205, 205)),
+    (Color::White, (229, 229, 229)),
+    (Color::DarkGray, (127, 127, 127)),
+    (Color::LightRed, (255, 0, 0)),
+    (Color::LightGreen, (0, 255, 0)),
+    (Color::LightYellow, (255, 255, 0)),
+    (Color::LightBlue, (92, 92, 255)),
+    (Color::LightMagenta, (255, 0, 255)),
+    (Color::LightCyan, (0, 255, 255)),
+    // `Color` has no bright white; `Gray` is bright black like `DarkGray`
+    (Color::White, (255, 255, 255)),
+];
+
+/// Levels of the 6x6x6 color cube (indexes 16-231)
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+fn distance(a: (u8, u8, u8), b: (u8, u8, u8)) -> u32 {
+    let d = |x: u8, y: u8| (x as i32 - y as i32).pow(2) as u32;
+    // Weighted for perceived brightness
+    2 * d(a.0, b.0) + 4 * d(a.1, b.1) + 3 * d(a.2, b.2)
+}
+
+/// RGB value of a 256-color palette index
+fn indexed_rgb(n: u8) -> (u8, u8, u8) {
+    match n {
+        0..=15 => ANSI16[n as usize].1,
+        16..=231 => {
+            let n = n - 16;
+            (
+                CUBE_LEVELS[(n / 36) as usize],
+                CUBE_LEVELS[(n / 6 % 6) as usize],
+                CUBE_LEVELS[(n % 6) as usize],
+            )
+        }
+        _ => {
+            let level = 8 + (n - 232) * 10;
+            (level, level, level)
+        }
+    }
+}
+
+/// Nearest xterm-256 index, from the color cube or the gray ramp
+fn nearest_256(rgb: (u8, u8, u8)) -> u8 {
+    let level = |c: u8| {
+        (0..6)
+            .min_by_key(|&i| (CUBE_LEVELS[i] as i32 - c as i32).abs())
+            .unwrap_or(0) as u8
+    };
+    let cube = 16 + 36 * level(rgb.0) + 6 * level(rgb.1) + level(rgb.2);
+
+    let avg = (rgb.0 as u32 + rgb.1 as u32 + rgb.2 as u32) / 3;
+    let gray = 232 + (avg.saturating_sub(3) / 10).min(23) as u8;
+
+    if distance(rgb, indexed_rgb(gray)) < distance(rgb, indexed_rgb(cube)) {
+        gray
+    } else {
+        cube
+    }
+}
+
+fn nearest_16(rgb: (u8, u8, u8)) -> Color {
+    ANSI16
+        .iter()
+        .min_by_key(|(_, value)| distance(rgb, *value))
+        .map(|(color, _)| *color)
+        .unwrap_or(Color::White)
+}
+
+impl Color {
+    /// The closest color the terminal can show; None in monochrome
+    pub fn downsample(self, depth: ColorDepth) -> Option<Color> {
+        match (depth, self) {
+            (ColorDepth::Mono, _) => None,
+            (ColorDepth::TrueColor, color) => Some(color),
+            (ColorDepth::Ansi256, Color::Rgb(r, g, b)) => {
+                Some(Color::Indexed(nearest_256((r, g, b))))
+            }
+            (ColorDepth::Ansi16, Color::Rgb(r, g, b)) => Some(nearest_16((r, g, b))),
+            (ColorDepth::Ansi16, Color::Indexed(n)) => Some(nearest_16(indexed_rgb(n))),
+            (_, color) => Some(color),
+        }
+    }
+}
+
+impl Style {
+    /// This style as the terminal can show it. Without colors, a background
+    /// (selection, highlighted line) becomes reverse video.
+    pub fn for_depth(self, depth: ColorDepth) -> Style {
+        if depth == ColorDepth::TrueColor {
+            return self;
+        }
+        let mut style = Style {
+            fg: self.fg.and_then(|c| c.downsample(depth)),
+            bg: self.bg.and_then(|c| c.downsample(depth)),
+            modifier: self.modifier,
+        };
+        if depth == ColorDepth::Mono && matches!(self.bg, Some(bg) if bg != Color::Reset) {
+            style.modifier = style.modifier.union(Modifier::REVERSED);
+        }
+        style
+    }
+}
+
+/// `max_colors` of the compiled terminfo entry for `term`
+fn terminfo_colors(term: &str) -> Option<i32> {
+    if term.is_empty() {
+        return None;
+    }
+    let first = term.chars().next()?;
+    let mut dirs: Vec<PathBuf> = Vec::new();
+    if let Ok(dir) = std::env::var("TERMINFO") {
+        dirs.push(dir.into());
+    }
+    if let Ok(home) = std::env::var("HOME") {
+        dirs.push(PathBuf::from(home).join(".terminfo"));
+    }
+    if let Ok(list) = std::env::var("TERMINFO_DIRS") {
+        dirs.extend(list.split(':').filter(|d| !d.is_empty()).map(PathBuf::from));
+    }
+    for dir in ["/etc/terminfo", "/lib/terminfo", "/usr/share/terminfo"] {
+        dirs.push(dir.into());
+    }
+    dirs.iter().find_map(|dir| {
+        // ncurses uses the first letter, macOS its hex code
+        [first.to_string(), format!("{:x}", first as u32)]
+            .iter()
+            .find_map(|sub| std::fs::read(dir.join(sub).join(term)).ok())
+            .and_then(|data| parse_terminfo_colors(&data))
+    })
+}
+
+/// Read `max_colors` (numeric capability 13) from a compiled terminfo file
+fn parse_terminfo_colors(data: &[u8]) -> Option<i32> {
+    const MAX_COLORS: usize = 13;
+    let short =
+        |i: usize| -> Option<i16> { Some(i16::from_le_bytes([*data.get(i)?, *data.get(i + 1)?])) };
+    // Legacy format has 16-bit numbers, the extended-number format 32-bit ones
+    let number_size = match short(0)? {
+        0o432 => 2,
+        0o1036 => 4,
+        _ => return None,
+    };
+    let names_size = short(2)? as usize;
+    let bools = short(4)? as usize;
+    let numbers = short(6)? as usize;
+    if numbers <= MAX_COLORS {
+        return None;
+    }
+    let mut offset = 12 + names_size + bools;
+    // Numbers start on an even byte
+    offset += offset % 2;
+    let at = offset + MAX_COLORS * number_size;
+    let value = if number_size == 2 {
+        short(at)? as i32
+    } else {
+        i32::from_le_bytes(data.get(at..at + 4)?.try_into().ok()?)
+    };
+    (value >= 0).then_some(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_and_downsample() {
+        let none = || None;
+        assert_eq!(
+            ColorDepth::from_env("1", "truecolor", "xterm", none),
+            ColorDepth::Mono
+        );
+        assert_eq!(
+            ColorDepth::from_env("", "truecolor", "xterm", none),
+            ColorDepth::TrueColor
+        );
+        assert_eq!(
+            ColorDepth::from_env("", "", "screen-256color", none),
+            ColorDepth::Ansi256
+        );
+        assert_eq!(
+            ColorDepth::from_env("", "", "xterm", || Some(8)),
+            ColorDepth::Ansi16
+        );
+
+        assert_eq!(nearest_256((255, 0, 0)), 196);
+        assert_eq!(nearest_256((128, 128, 128)), 244);
+        assert_eq!(
+            Color::Rgb(250, 10, 10).downsample(ColorDepth::Ansi16),
+            Some(Color::LightRed)
+        );
+        assert_eq!(
+            Color::Indexed(21).downsample(ColorDepth::Ansi16),
+            Some(Color::Blue)
+        );
+
+        let selected = Style::new().fg(Color::Black).bg(Color::Rgb(255, 140, 0));
+        let mono = selected.for_depth(ColorDepth::Mono);
+        assert_eq!((mono.fg, mono.bg), (None, None));
+        assert!(mono.modifier.contains(Modifier::REVERSED));
+    }
+
+    #[test]
+    fn test_parse_terminfo_colors() {
+        // Header, "x\0" name, one boolean, padding, 14 numbers with max_colors = 256
+        let mut data = Vec::new();
+        for n in [0o432, 2, 1, 14, 0, 0] {
+            data.extend_from_slice(&(n as i16).to_le_bytes());
+        }
+        data.extend_from_slice(b"x\0\x01\0");
+        for i in 0..14 {
+            let value: i16 = if i == 13 { 256 } else { -1 };
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+        assert_eq!(parse_terminfo_colors(&data), Some(256));
+        assert_eq!(parse_terminfo_colors(b"garbage"), None);
+    }
+}
@@ -1,9 +1,11 @@
 mod buffer;
+mod color_depth;
 mod render;
 mod style;
 mod terminal;
 
 pub use buffer::{str_display_width, unicode_width, Buffer, Cell};
+pub use color_depth::ColorDepth;
 pub use render::Rect;
 pub use style::{Color, Modifier, Style};
-pub use terminal::Terminal;
+pub use terminal::{InputModes, Terminal};
@@ -167,6 +167,34 @@ impl Color {
         Some(Color::Rgb(r, g, b))
     }
 
+    /// "#rrggbb" or an ANSI color name such as "red" or "lightblue"
+    pub fn parse(s: &str) -> Option<Self> {
+        if s.starts_with('#') {
+            return Self::from_hex(s);
+        }
+        let color = match s.to_ascii_lowercase().replace(['_', '-', ' '], "").as_str() {
+            "reset" | "default" => Color::Reset,
+            "black" => Color::Black,
+            "red" => Color::Red,
+            "green" => Color::Green,
+            "yellow" => Color::Yellow,
+            "blue" => Color::Blue,
+            "magenta" => Color::Magenta,
+            "cyan" => Color::Cyan,
+            "white" => Color::White,
+            "gray" | "grey" => Color::Gray,
+            "darkgray" | "darkgrey" => Color::DarkGray,
+            "lightred" => Color::LightRed,
+            "lightgreen" => Color::LightGreen,
+            "lightyellow" => Color::LightYellow,
+            "lightblue" => Color::LightBlue,
+            "lightmagenta" => Color::LightMagenta,
+            "lightcyan" => Color::LightCyan,
+            _ => return Self::from_hex(s),
+        };
+        Some(color)
+    }
+
     pub fn to_hex(self) -> Option<String> {
         match self {
             Color::Rgb(r, g, b) => Some(format!("#{:02x}{:02x}{:02x}", r, g, b)),
@@ -1,14 +1,74 @@
-use super::buffer::{Buffer, Cell};
+use super::buffer::{unicode_width, Buffer, Cell};
+use super::color_depth::ColorDepth;
 use super::render::Rect;
 use super::style::Style;
 use crate::error::{Error, Result};
 use std::io::{self, BufWriter, Stdout, Write};
 
+/// Optional input protocols asked of the terminal. Terminals that don't know
+/// one ignore the request and keep sending legacy sequences, which are still
+/// parsed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InputModes {
+    /// Pasted text arrives as one `Event::Paste` (mode 2004)
+    pub bracketed_paste: bool,
+    /// `Event::FocusGained` / `FocusLost` (mode 1004)
+    pub focus_events: bool,
+    /// Kitty progressive keyboard enhancement: unambiguous Ctrl/Alt/Shift keys
+    pub kitty_keyboard: bool,
+}
+
+impl Default for InputModes {
+    fn default() -> Self {
+        Self {
+            bracketed_paste: true,
+            focus_events: true,
+            kitty_keyboard: true,
+        }
+    }
+}
+
+/// Where frames are written
+enum Backend {
+    /// The process's terminal
+    Stdout(BufWriter<Stdout>),
+    /// A fixed-size screen that is never written anywhere: frames are only
+    /// kept in the buffers (rendering tests)
+    Headless { width: u16, height: u16 },
+}
+
+impl Write for Backend {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Backend::Stdout(out) => out.write(buf),
+            Backend::Headless { .. } => Ok(buf.len()),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Backend::Stdout(out) => out.flush(),
+            Backend::Headless { .. } => Ok(()),
+        }
+    }
+}
+
 pub struct Terminal {
-    stdout: BufWriter<Stdout>,
+    backend: Backend,
     current_buffer: Buffer,
     previous_buffer: Buffer,
     first_draw: bool,
+    /// Between `init` and `restore`; only the terminal of the active tab is
+    /// initialized (tabs swap it on switch), so the others ignore `restore`
+    active: bool,
+    /// Between `suspend` and `resume`, so only a continue after our own
+    /// stop sets the terminal up again
+    suspended: bool,
+    color_depth: ColorDepth,
+    input_modes: InputModes,
+    /// The terminal answered that it knows synchronized output (mode 2026):
+    /// each frame is wrapped so it is shown at once, never half drawn
+    synchronized_output: bool,
     #[cfg(unix)]
     original_termios: Option<libc::termios>,
     #[cfg(windows)]
@@ -19,30 +79,89 @@ impl Terminal {
     pub fn new() -> Result<Self> {
         let stdout = BufWriter::with_capacity(8192, io::stdout());
         let size = Self::size_static()?;
-        let area = Rect::new(0, 0, size.0, size.1);
+        Ok(Self::with_backend(Backend::Stdout(stdout), size))
+    }
+
+    /// An in-memory terminal of a fixed size; `last_frame` has what was drawn
+    pub fn headless(width: u16, height: u16) -> Self {
+        let mut terminal = Self::with_backend(Backend::Headless { width, height }, (width, height));
+        terminal.color_depth = ColorDepth::TrueColor;
+        terminal
+    }
 
-        Ok(Self {
-            stdout,
+    fn with_backend(backend: Backend, size: (u16, u16)) -> Self {
+        let area = Rect::new(0, 0, size.0, size.1);
+        Self {
+            backend,
             current_buffer: Buffer::empty(area),
             previous_buffer: Buffer::empty(area),
             first_draw: true,
+            active: false,
+            suspended: false,
+            color_depth: ColorDepth::detect(),
+            input_modes: InputModes::default(),
+            synchronized_output: false,
             #[cfg(unix)]
             original_termios: None,
             #[cfg(windows)]
             original_mode: None,
-        })
+        }
+    }
+
+    fn is_headless(&self) -> bool {
+        matches!(self.backend, Backend::Headless { .. })
     }
 
     pub fn init(&mut self) -> Result<()> {
+        self.active = true;
+        if self.is_headless() {
+            return Ok(());
+        }
+        self.enable_raw_mode()?;
+        self.enter_alternate_screen()?;
+        self.hide_cursor()?;
+        self.enable_mouse()?;
+        self.enable_input_modes()?;
+        self.clear()?;
+        Ok(())
+    }
+
+    /// Set the terminal up again after the process was stopped and continued:
+    /// the shell resets its modes and may have drawn over the screen
+    pub fn resume(&mut self) -> Result<()> {
+        if !std::mem::replace(&mut self.suspended, false) || !self.active || self.is_headless() {
+            return Ok(());
+        }
         self.enable_raw_mode()?;
         self.enter_alternate_screen()?;
         self.hide_cursor()?;
         self.enable_mouse()?;
+        self.enable_input_modes()?;
         self.clear()?;
+        self.first_draw = true;
         Ok(())
     }
 
+    /// Hand the terminal to the shell before the process stops. It stays
+    /// `active` and is marked `suspended`, so the SIGCONT `Event::Resume` sets
+    /// it up again with `resume`; a stray SIGCONT leaves it alone.
+    pub fn suspend(&mut self) -> Result<()> {
+        if !self.active || self.is_headless() {
+            return Ok(());
+        }
+        self.suspended = true;
+        self.reset_modes()
+    }
+
     pub fn restore(&mut self) -> Result<()> {
+        if !std::mem::replace(&mut self.active, false) || self.is_headless() {
+            return Ok(());
+        }
+        self.reset_modes()
+    }
+
+    fn reset_modes(&mut self) -> Result<()> {
+        self.disable_input_modes()?;
         self.disable_mouse()?;
         self.show_cursor()?;
         self.leave_alternate_screen()?;
@@ -56,23 +175,63 @@ impl Terminal {
         // 1002 = button-event tracking (press/release/drag)
         // 1003 = any-event tracking (all mouse events)
         // 1006 = SGR extended mode (better coordinate handling)
-        write!(self.stdout, "\x1b[?1000h\x1b[?1002h\x1b[?1006h")?;
-        self.stdout.flush()?;
+        write!(self.backend, "\x1b[?1000h\x1b[?1002h\x1b[?1006h")?;
+        self.backend.flush()?;
         Ok(())
     }
 
     fn disable_mouse(&mut self) -> Result<()> {
-        write!(self.stdout, "\x1b[?1006l\x1b[?1002l\x1b[?1000l")?;
-        self.stdout.flush()?;
+        write!(self.backend, "\x1b[?1006l\x1b[?1002l\x1b[?1000l")?;
+        self.backend.flush()?;
+        Ok(())
+    }
+
+    /// Takes effect on the next `init`
+    pub fn set_input_modes(&mut self, modes: InputModes) {
+        self.input_modes = modes;
+    }
+
+    fn enable_input_modes(&mut self) -> Result<()> {
+        if self.input_modes.bracketed_paste {
+            write!(self.backend, "\x1b[?2004h")?;
+        }
+        if self.input_modes.focus_events {
+            write!(self.backend, "\x1b[?1004h")?;
+        }
+        if self.input_modes.kitty_keyboard {
+            // Push flag 1 (disambiguate escape codes) onto the keyboard mode stack
+            write!(self.backend, "\x1b[>1u")?;
+        }
+        // Ask whether synchronized output is known (DECRQM); the reply comes
+        // back as `Event::SynchronizedOutput`
+        write!(self.backend, "\x1b[?2026$p")?;
+        self.backend.flush()?;
+        Ok(())
+    }
+
+    fn disable_input_modes(&mut self) -> Result<()> {
+        if self.input_modes.kitty_keyboard {
+            write!(self.backend, "\x1b[<u")?;
+        }
+        if self.input_modes.focus_events {
+            write!(self.backend, "\x1b[?1004l")?;
+        }
+        if self.input_modes.bracketed_paste {
+            write!(self.backend, "\x1b[?2004l")?;
+        }
+        self.backend.flush()?;
         Ok(())
     }
 
     pub fn size(&self) -> Result<(u16, u16)> {
-        Self::size_static()
+        match self.backend {
+            Backend::Headless { width, height } => Ok((width, height)),
+            Backend::Stdout(_) => Self::size_static(),
+        }
     }
 
     #[cfg(unix)]
-    fn size_static() -> Result<(u16, u16)> {
+    pub(crate) fn size_static() -> Result<(u16, u16)> {
         unsafe {
             let mut size: libc::winsize = std::mem::zeroed();
             if libc::ioctl(libc::STDOUT_FILENO, libc::TIOCGWINSZ, &mut size) == 0 {
@@ -84,7 +243,7 @@ impl Terminal {
     }
 
     #[cfg(windows)]
-    fn size_static() -> Result<(u16, u16)> {
+    pub(crate) fn size_static() -> Result<(u16, u16)> {
         use windows_sys::Win32::System::Console::*;
         unsafe {
             let handle = GetStdHandle(STD_OUTPUT_HANDLE);
@@ -109,7 +268,9 @@ impl Terminal {
                 ));
             }
 
-            self.original_termios = Some(termios);
+            // Keep the settings from before the first call: `resume` calls this
+            // again while the terminal may still be raw
+            let mut termios = *self.original_termios.get_or_insert(termios);
 
             // Disable canonical mode, echo, and signals
             termios.c_lflag &= !(libc::ICANON | libc::ECHO | libc::ISIG | libc::IEXTEN);
@@ -154,7 +315,7 @@ impl Terminal {
             GetConsoleMode(in_handle, &mut in_mode);
             GetConsoleMode(out_handle, &mut out_mode);
 
-            self.original_mode = Some((in_mode, out_mode));
+            let (in_mode, out_mode) = *self.original_mode.get_or_insert((in_mode, out_mode));
 
             // Disable line input and echo
             let new_in_mode = (in_mode & !(ENABLE_LINE_INPUT | ENABLE_ECHO_INPUT))
@@ -183,45 +344,88 @@ impl Terminal {
     }
 
     fn enter_alternate_screen(&mut self) -> Result<()> {
-        write!(self.stdout, "\x1b[?1049h")?;
-        self.stdout.flush()?;
+        write!(self.backend, "\x1b[?1049h")?;
+        self.backend.flush()?;
         Ok(())
     }
 
     fn leave_alternate_screen(&mut self) -> Result<()> {
-        write!(self.stdout, "\x1b[?1049l")?;
-        self.stdout.flush()?;
+        write!(self.backend, "\x1b[?1049l")?;
+        self.backend.flush()?;
         Ok(())
     }
 
     fn hide_cursor(&mut self) -> Result<()> {
-        write!(self.stdout, "\x1b[?25l")?;
-        self.stdout.flush()?;
+        write!(self.backend, "\x1b[?25l")?;
+        self.backend.flush()?;
         Ok(())
     }
 
     fn show_cursor(&mut self) -> Result<()> {
-        write!(self.stdout, "\x1b[?25h")?;
-        self.stdout.flush()?;
+        write!(self.backend, "\x1b[?25h")?;
+        self.backend.flush()?;
         Ok(())
     }
 
     pub fn clear(&mut self) -> Result<()> {
-        write!(self.stdout, "\x1b[2J\x1b[H")?;
-        self.stdout.flush()?;
+        write!(self.backend, "\x1b[2J\x1b[H")?;
+        self.backend.flush()?;
         Ok(())
     }
 
     pub fn resize(&mut self, width: u16, height: u16) {
+        if let Backend::Headless {
+            width: w,
+            height: h,
+        } = &mut self.backend
+        {
+            (*w, *h) = (width, height);
+        }
         let area = Rect::new(0, 0, width, height);
         self.current_buffer.resize(area);
         self.previous_buffer.resize(area);
     }
 
+    /// The frame drawn by the last `draw`
+    pub fn last_frame(&self) -> &Buffer {
+        &self.previous_buffer
+    }
+
     pub fn current_buffer_mut(&mut self) -> &mut Buffer {
         &mut self.current_buffer
     }
 
+    pub fn color_depth(&self) -> ColorDepth {
+        self.color_depth
+    }
+
+    /// Override the detected color depth (`color_depth` in the config)
+    pub fn set_color_depth(&mut self, depth: ColorDepth) {
+        self.color_depth = depth;
+        self.first_draw = true;
+    }
+
+    /// Set from the terminal's reply to the mode 2026 query
+    pub fn set_synchronized_output(&mut self, supported: bool) {
+        self.synchronized_output = supported;
+    }
+
+    fn begin_frame(&mut self) -> Result<()> {
+        if self.synchronized_output {
+            write!(self.backend, "\x1b[?2026h")?;
+        }
+        Ok(())
+    }
+
+    fn end_frame(&mut self) -> Result<()> {
+        write!(self.backend, "\x1b[0m")?;
+        if self.synchronized_output {
+            write!(self.backend, "\x1b[?2026l")?;
+        }
+        self.backend.flush()?;
+        Ok(())
+    }
+
     /// Force a full redraw on the next draw call
     pub fn force_full_redraw(&mut self) {
         self.first_draw = true;
@@ -263,15 +467,16 @@ impl Terminal {
     }
 
     fn flush_full(&mut self) -> Result<()> {
+        self.begin_frame()?;
         // Move to home position (no screen clear to avoid flicker)
-        write!(self.stdout, "\x1b[H")?;
+        write!(self.backend, "\x1b[H")?;
 
         let mut last_style = Style::default();
         let area = self.current_buffer.area;
 
         for y in area.y..area.y + area.height {
             // Move cursor to start of line
-            write!(self.stdout, "\x1b[{};{}H", y + 1, 1)?;
+            write!(self.backend, "\x1b[{};{}H", y + 1, 1)?;
 
             for x in area.x..area.x + area.width {
                 let cell = self.current_buffer.get(x, y);
@@ -284,20 +489,20 @@ impl Terminal {
                 // Apply style if changed
                 let style = cell.style();
                 if style != last_style {
-                    write!(self.stdout, "{}", style.to_ansi())?;
+                    write!(
+                        self.backend,
+                        "{}",
+                        style.for_depth(self.color_depth).to_ansi()
+                    )?;
                     last_style = style;
                 }
 
                 // Write character
-                write!(self.stdout, "{}", cell.symbol)?;
+                write!(self.backend, "{}", cell.symbol)?;
             }
         }
 
-        // Reset style at end
-        write!(self.stdout, "\x1b[0m")?;
-        self.stdout.flush()?;
-
-        Ok(())
+        self.end_frame()
     }
 
     fn flush(&mut self) -> Result<()> {
@@ -307,8 +512,14 @@ impl Terminal {
             return Ok(());
         }
 
+        // `begin_frame`, with `updates` still borrowing the buffer
+        if self.synchronized_output {
+            write!(self.backend, "\x1b[?2026h")?;
+        }
+
         let mut last_style = Style::default();
-        let mut last_pos: Option<(u16, u16)> = None;
+        // Where the cursor is after the last write
+        let mut cursor: Option<(u16, u16)> = None;
 
         for (x, y, cell) in updates {
             // Skip continuation cells (empty symbol = second half of wide char)
@@ -316,46 +527,28 @@ impl Terminal {
                 continue;
             }
 
-            // Move cursor if not adjacent
-            let need_move = match last_pos {
-                Some((lx, ly)) => !(ly == y && lx + 1 == x),
-                None => true,
-            };
-
-            if need_move {
-                write!(self.stdout, "\x1b[{};{}H", y + 1, x + 1)?;
-            }
+            write!(self.backend, "{}", cursor_move(cursor, x, y))?;
 
             // Apply style if changed
             let style = cell.style();
             if style != last_style {
-                write!(self.stdout, "{}", style.to_ansi())?;
+                write!(
+                    self.backend,
+                    "{}",
+                    style.for_depth(self.color_depth).to_ansi()
+                )?;
                 last_style = style;
             }
 
             // Write character
-            write!(self.stdout, "{}", cell.symbol)?;
-
-            // Update position tracking - wide chars advance by their width
-            let char_width = if cell
-                .symbol
-                .chars()
-                .next()
-                .map(|c| !c.is_ascii())
-                .unwrap_or(false)
-            {
-                2u16
-            } else {
-                1u16
-            };
-            last_pos = Some((x + char_width - 1, y));
-        }
+            write!(self.backend, "{}", cell.symbol)?;
 
-        // Reset style at end
-        write!(self.stdout, "\x1b[0m")?;
-        self.stdout.flush()?;
+            // Wide chars advance the cursor by their width
+            let width = cell.symbol.chars().next().map(unicode_width).unwrap_or(1);
+            cursor = Some((x + width as u16, y));
+        }
 
-        Ok(())
+        self.end_frame()
     }
 
     pub fn area(&self) -> Rect {
@@ -363,8 +556,36 @@ impl Terminal {
     }
 }
 
+/// The shortest way from the cursor to `(x, y)`: nothing when already there,
+/// a forward move across a gap in the same row, an absolute move otherwise
+fn cursor_move(cursor: Option<(u16, u16)>, x: u16, y: u16) -> String {
+    match cursor {
+        Some((cx, cy)) if cy == y && cx == x => String::new(),
+        Some((cx, cy)) if cy == y && cx < x => match x - cx {
+            1 => "\x1b[C".to_string(),
+            n => format!("\x1b[{}C", n),
+        },
+        _ => format!("\x1b[{};{}H", y + 1, x + 1),
+    }
+}
+
 impl Drop for Terminal {
     fn drop(&mut self) {
         let _ = self.restore();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cursor_move() {
+        assert_eq!(cursor_move(None, 4, 2), "\x1b[3;5H");
+        assert_eq!(cursor_move(Some((4, 2)), 4, 2), "");
+        assert_eq!(cursor_move(Some((4, 2)), 5, 2), "\x1b[C");
+        assert_eq!(cursor_move(Some((4, 2)), 10, 2), "\x1b[6C");
+        assert_eq!(cursor_move(Some((4, 2)), 2, 2), "\x1b[3;3H");
+        assert_eq!(cursor_move(Some((4, 2)), 0, 3), "\x1b[4;1H");
+    }
+}
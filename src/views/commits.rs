@@ -31,6 +31,8 @@ pub struct CommitsView {
     pub current_branch: Option<String>,
     /// User-marked commits (for multi-select)
     pub marked_commits: HashSet<String>,
+    /// Path the list is limited to, shown in the title
+    pub path_filter: Option<String>,
 }
 
 impl CommitsView {
@@ -50,6 +52,7 @@ impl CommitsView {
             highlight_commits: HashSet::new(),
             current_branch: None,
             marked_commits: HashSet::new(),
+            path_filter: None,
         }
     }
 
@@ -253,6 +256,17 @@ impl CommitsView {
         self.commits.get(self.selected)
     }
 
+    /// Select the commit with `id`; false if it is not in the list
+    pub fn select_commit(&mut self, id: &str) -> bool {
+        match self.commits.iter().position(|c| c.id == id) {
+            Some(index) => {
+                self.selected = index;
+                true
+            }
+            None => false,
+        }
+    }
+
     pub fn commit_count(&self) -> usize {
         match self.view_mode {
             CommitsViewMode::Graph => self.graph_lines.len(),
@@ -377,6 +391,11 @@ impl CommitsView {
                 self.search_results.len(),
                 mode_indicator
             )
+        } else if let Some(ref path) = self.path_filter {
+            format!(
+                " Commits ({}) [{}] -- {} ",
+                commit_count, mode_indicator, path
+            )
         } else {
             format!(" Commits ({}) [{}] ", commit_count, mode_indicator)
         };
@@ -1059,14 +1078,7 @@ impl CommitsView {
         focused: bool,
     ) {
         // Graph colors for different branches
-        let graph_colors = [
-            crate::tui::Color::Rgb(243, 139, 168), // red
-            crate::tui::Color::Rgb(166, 227, 161), // green
-            crate::tui::Color::Rgb(249, 226, 175), // yellow
-            crate::tui::Color::Rgb(137, 180, 250), // blue
-            crate::tui::Color::Rgb(203, 166, 247), // mauve
-            crate::tui::Color::Rgb(137, 220, 235), // cyan
-        ];
+        let graph_colors = &theme.graph_lanes[..];
 
         if self.graph_lines.is_empty() {
             let msg = "No commits";
@@ -1112,7 +1124,7 @@ impl CommitsView {
                             y,
                             content_width,
                             graph_str,
-                            &graph_colors,
+                            graph_colors,
                         );
                     }
                 }
@@ -1194,7 +1206,7 @@ impl CommitsView {
                             commit,
                             &refs_str,
                             theme,
-                            &graph_colors,
+                            graph_colors,
                         );
                     }
                 }
@@ -1,5 +1,5 @@
 use crate::config::Theme;
-use crate::git::{DiffInfo, FileDiff, LineType, PullRequestInfo};
+use crate::git::{DiffInfo, DiffLine, FileDiff, Hunk, LineType, Omitted, PullRequestInfo};
 use crate::tui::{str_display_width, unicode_width, Buffer, Color, Rect, Style};
 use crate::widgets::{Block, Borders, Scrollbar, Widget};
 
@@ -20,10 +20,234 @@ pub enum PreviewType {
     Action,
 }
 
+/// A file shown in the preview: its text and where each line starts, so
+/// rendering only touches the visible lines
 #[derive(Debug, Clone)]
 pub struct FileContent {
     pub path: String,
-    pub lines: Vec<String>,
+    text: String,
+    line_starts: Vec<usize>,
+    /// Widest line, in columns
+    width: usize,
+    /// Set when the file was not read
+    pub omitted: Option<Omitted>,
+}
+
+impl FileContent {
+    pub fn new(path: String, text: String) -> Self {
+        let mut line_starts = Vec::new();
+        if !text.is_empty() {
+            line_starts.push(0);
+        }
+        line_starts.extend(
+            text.match_indices('\n')
+                .map(|(i, _)| i + 1)
+                .filter(|&start| start < text.len()),
+        );
+        let mut content = Self {
+            path,
+            text,
+            line_starts,
+            width: 0,
+            omitted: None,
+        };
+        content.width = (0..content.len())
+            .map(|i| str_display_width(content.line(i)))
+            .max()
+            .unwrap_or(0);
+        content
+    }
+
+    pub fn omitted(path: String, omitted: Omitted) -> Self {
+        Self {
+            path,
+            text: String::new(),
+            line_starts: Vec::new(),
+            width: 0,
+            omitted: Some(omitted),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.line_starts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.line_starts.is_empty()
+    }
+
+    /// Line `index`, without its line ending (as `str::lines`)
+    pub fn line(&self, index: usize) -> &str {
+        let start = self.line_starts[index];
+        let end = self
+            .line_starts
+            .get(index + 1)
+            .copied()
+            .unwrap_or(self.text.len());
+        let line = &self.text[start..end];
+        let line = line.strip_suffix('\n').unwrap_or(line);
+        line.strip_suffix('\r').unwrap_or(line)
+    }
+
+    pub fn lines(&self) -> impl Iterator<Item = &str> + '_ {
+        (0..self.len()).map(|i| self.line(i))
+    }
+}
+
+/// Rows of one file's diff, counted once when the diff is set so rendering
+/// can go straight to the visible ones
+#[derive(Debug, Clone, Default)]
+struct FileRows {
+    /// Inline row of each hunk's header; its lines follow it
+    hunk_rows: Vec<usize>,
+    inline: usize,
+    /// Side-by-side rows, in blocks
+    blocks: Vec<SplitBlock>,
+    split: usize,
+    /// Widest line, in columns
+    width: usize,
+    additions: usize,
+    deletions: usize,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct SplitBlock {
+    /// First row of the block
+    row: usize,
+    hunk: usize,
+    kind: SplitKind,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum SplitKind {
+    Header,
+    /// `len` context lines from line `first`, one per row
+    Context {
+        first: usize,
+        len: usize,
+    },
+    /// Deletions from line `first`, then the additions; row n pairs the
+    /// n-th of each
+    Change {
+        first: usize,
+        deletions: usize,
+        additions: usize,
+    },
+}
+
+impl SplitKind {
+    fn rows(&self) -> usize {
+        match *self {
+            SplitKind::Header => 1,
+            SplitKind::Context { len, .. } => len,
+            SplitKind::Change {
+                deletions,
+                additions,
+                ..
+            } => deletions.max(additions),
+        }
+    }
+}
+
+/// One side of a side-by-side row: line number (0 for a header) and text
+type SplitSide = Option<(u32, String)>;
+
+impl FileRows {
+    fn new(file: &FileDiff) -> Self {
+        let mut rows = Self::default();
+        for (hunk_idx, hunk) in file.hunks.iter().enumerate() {
+            rows.hunk_rows.push(rows.inline);
+            rows.inline += 1 + hunk.lines.len();
+            rows.width = rows.width.max(str_display_width(&hunk.header));
+
+            let mut kinds = vec![SplitKind::Header];
+            for (i, line) in hunk.lines.iter().enumerate() {
+                let width = str_display_width(line.content.trim_end_matches('\n'));
+                rows.width = rows.width.max(width);
+                let last = kinds.last_mut();
+                match (line.line_type, last) {
+                    (LineType::Context, Some(SplitKind::Context { len, .. })) => *len += 1,
+                    (LineType::Context, _) => kinds.push(SplitKind::Context { first: i, len: 1 }),
+                    (
+                        LineType::Deletion,
+                        Some(SplitKind::Change {
+                            deletions,
+                            additions: 0,
+                            ..
+                        }),
+                    ) => *deletions += 1,
+                    (LineType::Addition, Some(SplitKind::Change { additions, .. })) => {
+                        *additions += 1
+                    }
+                    (line_type, _) => {
+                        let deletion = line_type == LineType::Deletion;
+                        kinds.push(SplitKind::Change {
+                            first: i,
+                            deletions: usize::from(deletion),
+                            additions: usize::from(!deletion),
+                        })
+                    }
+                }
+                match line.line_type {
+                    LineType::Addition => rows.additions += 1,
+                    LineType::Deletion => rows.deletions += 1,
+                    LineType::Context => {}
+                }
+            }
+            for kind in kinds {
+                rows.blocks.push(SplitBlock {
+                    row: rows.split,
+                    hunk: hunk_idx,
+                    kind,
+                });
+                rows.split += kind.rows();
+            }
+        }
+        rows
+    }
+
+    /// Inline row `row`: a hunk and, unless it is the hunk's header, one of its lines
+    fn inline_row<'a>(&self, file: &'a FileDiff, row: usize) -> (&'a Hunk, Option<&'a DiffLine>) {
+        let hunk_idx = self.hunk_rows.partition_point(|&start| start <= row) - 1;
+        let hunk = &file.hunks[hunk_idx];
+        match row - self.hunk_rows[hunk_idx] {
+            0 => (hunk, None),
+            n => (hunk, hunk.lines.get(n - 1)),
+        }
+    }
+
+    /// Side-by-side row `row`: the old and the new side
+    fn split_row(&self, file: &FileDiff, row: usize) -> (SplitSide, SplitSide) {
+        let block = self.blocks[self.blocks.partition_point(|b| b.row <= row) - 1];
+        let hunk = &file.hunks[block.hunk];
+        let offset = row - block.row;
+        let side = |line: &DiffLine, number: Option<u32>| {
+            let content = line.content.trim_end_matches('\n').to_string();
+            Some((number.unwrap_or(0), content))
+        };
+        match block.kind {
+            SplitKind::Header => (
+                Some((0, hunk.header.clone())),
+                Some((0, hunk.header.clone())),
+            ),
+            SplitKind::Context { first, .. } => {
+                let line = &hunk.lines[first + offset];
+                (side(line, line.old_lineno), side(line, line.new_lineno))
+            }
+            SplitKind::Change {
+                first,
+                deletions,
+                additions,
+            } => {
+                let left = (offset < deletions).then(|| &hunk.lines[first + offset]);
+                let right = (offset < additions).then(|| &hunk.lines[first + deletions + offset]);
+                (
+                    left.and_then(|line| side(line, line.old_lineno)),
+                    right.and_then(|line| side(line, line.new_lineno)),
+                )
+            }
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -270,9 +494,9 @@ impl SyntaxHighlighter {
         }
     }
 
-    fn highlight_line(&self, line: &str) -> Vec<(String, Color)> {
+    fn highlight_line(&self, line: &str, theme: &Theme) -> Vec<(String, Color)> {
         if self.syntax_type == SyntaxType::None {
-            return vec![(line.to_string(), Color::Rgb(205, 214, 244))];
+            return vec![(line.to_string(), theme.foreground)];
         }
         let mut tokens = Vec::new();
         let chars: Vec<char> = line.chars().collect();
@@ -282,7 +506,7 @@ impl SyntaxHighlighter {
         while i < chars.len() {
             // Comments
             if i + 1 < chars.len() && chars[i] == '/' && chars[i + 1] == '/' {
-                tokens.push((chars[i..].iter().collect(), Color::Rgb(108, 112, 134)));
+                tokens.push((chars[i..].iter().collect(), theme.syntax_comment));
                 break;
             }
             if chars[i] == '#'
@@ -291,7 +515,7 @@ impl SyntaxHighlighter {
                     SyntaxType::Python | SyntaxType::Shell | SyntaxType::Toml
                 )
             {
-                tokens.push((chars[i..].iter().collect(), Color::Rgb(108, 112, 134)));
+                tokens.push((chars[i..].iter().collect(), theme.syntax_comment));
                 break;
             }
             // Strings
@@ -307,7 +531,7 @@ impl SyntaxHighlighter {
                 if j < chars.len() {
                     j += 1;
                 }
-                tokens.push((chars[i..j].iter().collect(), Color::Rgb(166, 227, 161)));
+                tokens.push((chars[i..j].iter().collect(), theme.syntax_string));
                 i = j;
                 continue;
             }
@@ -322,7 +546,7 @@ impl SyntaxHighlighter {
                 {
                     j += 1;
                 }
-                tokens.push((chars[i..j].iter().collect(), Color::Rgb(250, 179, 135)));
+                tokens.push((chars[i..j].iter().collect(), theme.syntax_number));
                 i = j;
                 continue;
             }
@@ -334,28 +558,28 @@ impl SyntaxHighlighter {
                 }
                 let word: String = chars[i..j].iter().collect();
                 let color = if keywords.contains(&word.as_str()) {
-                    Color::Rgb(203, 166, 247) // keyword purple
+                    theme.syntax_keyword
                 } else if j < chars.len() && chars[j] == '(' {
-                    Color::Rgb(137, 180, 250) // function blue
+                    theme.syntax_function
                 } else if word
                     .chars()
                     .next()
                     .map(|c| c.is_uppercase())
                     .unwrap_or(false)
                 {
-                    Color::Rgb(249, 226, 175) // type yellow
+                    theme.syntax_type
                 } else {
-                    Color::Rgb(205, 214, 244) // normal
+                    theme.foreground
                 };
                 tokens.push((word, color));
                 i = j;
                 continue;
             }
-            tokens.push((chars[i].to_string(), Color::Rgb(205, 214, 244)));
+            tokens.push((chars[i].to_string(), theme.foreground));
             i += 1;
         }
         if tokens.is_empty() {
-            tokens.push((line.to_string(), Color::Rgb(205, 214, 244)));
+            tokens.push((line.to_string(), theme.foreground));
         }
         tokens
     }
@@ -385,6 +609,8 @@ pub struct DiffView {
     pub search_query: Option<String>,
     pub search_matches: Vec<(usize, usize, usize)>, // (line_idx, start_col, end_col)
     pub current_match: usize,
+    /// Row counts of each file in `diff`
+    rows: Vec<FileRows>,
 }
 
 impl DiffView {
@@ -411,6 +637,7 @@ impl DiffView {
             search_query: None,
             search_matches: Vec::new(),
             current_match: 0,
+            rows: Vec::new(),
         }
     }
 
@@ -451,12 +678,12 @@ impl DiffView {
 
     /// Set the diff for a commit preview (called after set_commit_preview)
     pub fn set_commit_diff(&mut self, diff: crate::git::DiffInfo) {
-        self.diff = diff;
+        self.set_diff(diff);
     }
 
     pub fn clear_commit_preview(&mut self) {
         self.commit_preview = None;
-        self.diff = crate::git::DiffInfo { files: Vec::new() };
+        self.set_diff(DiffInfo { files: Vec::new() });
         if self.preview_type == PreviewType::Commit {
             self.preview_type = PreviewType::Diff;
         }
@@ -580,8 +807,14 @@ impl DiffView {
         self.h_offset += 4;
     }
 
-    pub fn update(&mut self, diff: DiffInfo) {
+    /// Replace the diff and count its rows
+    fn set_diff(&mut self, diff: DiffInfo) {
+        self.rows = diff.files.iter().map(FileRows::new).collect();
         self.diff = diff;
+    }
+
+    pub fn update(&mut self, diff: DiffInfo) {
+        self.set_diff(diff);
         self.current_file = 0;
         self.scroll = 0;
         self.h_offset = 0;
@@ -592,8 +825,16 @@ impl DiffView {
     }
 
     pub fn set_file_content(&mut self, path: String, content: String) {
-        let lines: Vec<String> = content.lines().map(|s| s.to_string()).collect();
-        self.file_content = Some(FileContent { path, lines });
+        self.show_file(FileContent::new(path, content));
+    }
+
+    /// Preview a file that was not read, with the reason instead of its text
+    pub fn set_file_omitted(&mut self, path: String, omitted: Omitted) {
+        self.show_file(FileContent::omitted(path, omitted));
+    }
+
+    fn show_file(&mut self, content: FileContent) {
+        self.file_content = Some(content);
         self.preview_type = PreviewType::FileContent;
         self.scroll = 0;
         self.h_offset = 0;
@@ -602,7 +843,7 @@ impl DiffView {
     }
 
     pub fn clear(&mut self) {
-        self.diff = DiffInfo { files: Vec::new() };
+        self.set_diff(DiffInfo { files: Vec::new() });
         self.current_file = 0;
         self.scroll = 0;
         self.h_offset = 0;
@@ -616,6 +857,25 @@ impl DiffView {
         self.diff.files.get(self.current_file)
     }
 
+    fn current_rows(&self) -> Option<&FileRows> {
+        self.rows.get(self.current_file)
+    }
+
+    /// Whether the preview holds a file left out for its size, which
+    /// loading without the limit would show
+    pub fn has_large_file(&self) -> bool {
+        let large = |omitted: Option<Omitted>| matches!(omitted, Some(Omitted::TooLarge(_)));
+        match self.preview_type {
+            PreviewType::FileContent => self
+                .file_content
+                .as_ref()
+                .is_some_and(|fc| large(fc.omitted)),
+            PreviewType::Diff => self.current_file().is_some_and(|f| large(f.omitted)),
+            PreviewType::Commit => self.diff.files.iter().any(|f| large(f.omitted)),
+            _ => false,
+        }
+    }
+
     pub fn scroll_up(&mut self) {
         self.scroll = self.scroll.saturating_sub(1);
     }
@@ -767,33 +1027,22 @@ impl DiffView {
         match self.preview_type {
             PreviewType::FileContent => {
                 if let Some(ref fc) = self.file_content {
-                    let selected: Vec<&str> = fc
-                        .lines
-                        .iter()
-                        .skip(start)
-                        .take(end - start + 1)
-                        .map(|s| s.as_str())
-                        .collect();
+                    let selected: Vec<&str> =
+                        fc.lines().skip(start).take(end - start + 1).collect();
                     Some(selected.join("\n"))
                 } else {
                     None
                 }
             }
             PreviewType::Diff => {
-                // For diff, collect lines from hunks
-                if let Some(file) = self.diff.files.get(self.current_file) {
-                    let mut all_lines: Vec<String> = Vec::new();
-                    for hunk in &file.hunks {
-                        all_lines.push(hunk.header.clone());
-                        for line in &hunk.lines {
-                            all_lines.push(line.content.clone());
-                        }
-                    }
-                    let selected: Vec<&str> = all_lines
-                        .iter()
-                        .skip(start)
-                        .take(end - start + 1)
-                        .map(|s| s.as_str())
+                // For diff, the selected rows: hunk headers and lines
+                if let (Some(file), Some(rows)) = (self.current_file(), self.current_rows()) {
+                    let selected: Vec<&str> = (start..=end)
+                        .take_while(|&row| row < rows.inline)
+                        .map(|row| match rows.inline_row(file, row) {
+                            (hunk, None) => hunk.header.as_str(),
+                            (_, Some(line)) => line.content.as_str(),
+                        })
                         .collect();
                     Some(selected.join(""))
                 } else {
@@ -826,23 +1075,8 @@ impl DiffView {
     /// Get total line count for the current content
     pub fn get_total_lines(&self) -> usize {
         match self.preview_type {
-            PreviewType::FileContent => self
-                .file_content
-                .as_ref()
-                .map(|fc| fc.lines.len())
-                .unwrap_or(0),
-            PreviewType::Diff => {
-                if let Some(file) = self.diff.files.get(self.current_file) {
-                    let mut count = 0;
-                    for hunk in &file.hunks {
-                        count += 1; // header
-                        count += hunk.lines.len();
-                    }
-                    count
-                } else {
-                    0
-                }
-            }
+            PreviewType::FileContent => self.file_content.as_ref().map(|fc| fc.len()).unwrap_or(0),
+            PreviewType::Diff => self.current_rows().map(|rows| rows.inline).unwrap_or(0),
             PreviewType::PullRequest => {
                 // PR preview line count not tracked for visual mode
                 0
@@ -887,7 +1121,7 @@ impl DiffView {
             PreviewType::FileContent => self
                 .file_content
                 .as_ref()
-                .map(|fc| fc.lines.clone())
+                .map(|fc| fc.lines().map(|s| s.to_string()).collect())
                 .unwrap_or_default(),
             PreviewType::Diff => {
                 let mut lines = Vec::new();
@@ -1030,14 +1264,10 @@ impl DiffView {
                     DiffMode::SideBySide => "split",
                 };
 
-                if let Some(file) = self.current_file() {
+                if let (Some(file), Some(rows)) = (self.current_file(), self.current_rows()) {
                     format!(
                         " Preview: {} (+{} -{}) [{}]{} ",
-                        file.path,
-                        file.additions(),
-                        file.deletions(),
-                        mode_indicator,
-                        visual_indicator
+                        file.path, rows.additions, rows.deletions, mode_indicator, visual_indicator
                     )
                 } else {
                     format!(" Preview [{}]{} ", mode_indicator, visual_indicator)
@@ -1324,6 +1554,14 @@ impl DiffView {
                 ));
                 lines.push((format!("--- a/{}", file.path), theme.diff_remove));
                 lines.push((format!("+++ b/{}", file.path), theme.diff_add));
+                match file.omitted {
+                    Some(omitted @ Omitted::TooLarge(_)) => lines.push((
+                        format!("{}: Enter to load", omitted.describe()),
+                        theme.untracked,
+                    )),
+                    Some(omitted) => lines.push((omitted.describe(), theme.untracked)),
+                    None => {}
+                }
 
                 for hunk in &file.hunks {
                     // Hunk header
@@ -1542,37 +1780,37 @@ impl DiffView {
         }
     }
 
-    fn render_inline(&mut self, inner: Rect, buf: &mut Buffer, theme: &Theme) {
-        // Collect all lines from all hunks (owned data to avoid borrow issues)
-        let lines: Vec<(Option<u32>, Option<u32>, LineType, String)> = {
-            let Some(file) = self.current_file() else {
-                // No diff to show
-                let msg = "No changes to display";
-                let x = inner.x + (inner.width.saturating_sub(msg.len() as u16)) / 2;
-                let y = inner.y + inner.height / 2;
-                buf.set_string(x, y, msg, Style::new().fg(theme.untracked));
-                return;
-            };
-
-            let mut lines = Vec::new();
-            for hunk in &file.hunks {
-                // Hunk header
-                lines.push((None, None, LineType::Context, hunk.header.clone()));
+    /// In place of a file left out of the diff or preview
+    fn render_omitted(omitted: Omitted, inner: Rect, buf: &mut Buffer, theme: &Theme) {
+        let msg = match omitted {
+            Omitted::Binary => omitted.describe(),
+            Omitted::TooLarge(_) => format!("{}: Enter to load", omitted.describe()),
+        };
+        let x = inner.x + (inner.width.saturating_sub(str_display_width(&msg) as u16)) / 2;
+        let y = inner.y + inner.height / 2;
+        buf.set_string(x, y, &msg, Style::new().fg(theme.untracked));
+    }
 
-                for line in &hunk.lines {
-                    lines.push((
-                        line.old_lineno,
-                        line.new_lineno,
-                        line.line_type,
-                        line.content.clone(),
-                    ));
-                }
-            }
-            lines
+    fn render_inline(&mut self, inner: Rect, buf: &mut Buffer, theme: &Theme) {
+        let Some(file) = self.current_file() else {
+            // No diff to show
+            let msg = "No changes to display";
+            let x = inner.x + (inner.width.saturating_sub(msg.len() as u16)) / 2;
+            let y = inner.y + inner.height / 2;
+            buf.set_string(x, y, msg, Style::new().fg(theme.untracked));
+            return;
+        };
+        if let Some(omitted) = file.omitted {
+            Self::render_omitted(omitted, inner, buf, theme);
+            return;
+        }
+        let Some(rows) = self.current_rows() else {
+            return;
         };
+        let (total, width) = (rows.inline, rows.width);
 
-        // Adjust scroll (now safe because we don't hold a borrow on self)
-        let max_scroll = lines.len().saturating_sub(inner.height as usize);
+        // Adjust scroll
+        let max_scroll = total.saturating_sub(inner.height as usize);
         if self.scroll > max_scroll {
             self.scroll = max_scroll;
         }
@@ -1583,14 +1821,25 @@ impl DiffView {
 
         // Calculate max content width using display width (accounting for wide characters)
         self.view_width = (content_area_width.saturating_sub(line_num_width)) as usize;
-        self.max_content_width = lines
-            .iter()
-            .map(|(_, _, _, content)| {
-                str_display_width(content.trim_end_matches('\n')) + 1 // +1 for prefix
-            })
-            .max()
-            .unwrap_or(0)
-            + 2; // +2 for scrollbar (1) + margin (1)
+        self.max_content_width = width + 1 + 2; // +1 for prefix, +2 for scrollbar (1) + margin (1)
+
+        // Only the visible rows (owned data to avoid borrow issues)
+        let lines: Vec<(Option<u32>, Option<u32>, LineType, String)> = {
+            let (Some(file), Some(rows)) = (self.current_file(), self.current_rows()) else {
+                return;
+            };
+            (self.scroll..total.min(self.scroll + visible_height))
+                .map(|row| match rows.inline_row(file, row) {
+                    (hunk, None) => (None, None, LineType::Context, hunk.header.clone()),
+                    (_, Some(line)) => (
+                        line.old_lineno,
+                        line.new_lineno,
+                        line.line_type,
+                        line.content.clone(),
+                    ),
+                })
+                .collect()
+        };
 
         // Clamp h_offset
         if self.max_content_width <= self.view_width {
@@ -1602,12 +1851,7 @@ impl DiffView {
             }
         }
 
-        for (i, (old_line, new_line, line_type, content)) in lines
-            .iter()
-            .skip(self.scroll)
-            .take(visible_height)
-            .enumerate()
-        {
+        for (i, (old_line, new_line, line_type, content)) in lines.iter().enumerate() {
             let y = inner.y + i as u16;
 
             // Line numbers
@@ -1701,69 +1945,30 @@ impl DiffView {
         }
 
         // Render scrollbar
-        let scrollbar = Scrollbar::new(lines.len(), visible_height, self.scroll);
+        let scrollbar = Scrollbar::new(total, visible_height, self.scroll);
         let scrollbar_area = Rect::new(inner.x + inner.width - 1, inner.y, 1, inner.height);
         scrollbar.render(scrollbar_area, buf, Style::new().fg(theme.border));
     }
 
-    #[allow(clippy::type_complexity)]
     fn render_side_by_side(&mut self, inner: Rect, buf: &mut Buffer, theme: &Theme) {
-        // Build paired lines for side-by-side view
-        let paired_lines: Vec<(Option<(u32, String)>, Option<(u32, String)>)> = {
-            let Some(file) = self.current_file() else {
-                let msg = "No changes to display";
-                let x = inner.x + (inner.width.saturating_sub(msg.len() as u16)) / 2;
-                let y = inner.y + inner.height / 2;
-                buf.set_string(x, y, msg, Style::new().fg(theme.untracked));
-                return;
-            };
-
-            let mut pairs = Vec::new();
-
-            for hunk in &file.hunks {
-                // Hunk header spans both sides
-                pairs.push((
-                    Some((0, hunk.header.clone())),
-                    Some((0, hunk.header.clone())),
-                ));
-
-                // Collect deletions and additions separately, then pair them
-                let mut deletions: Vec<(u32, String)> = Vec::new();
-                let mut additions: Vec<(u32, String)> = Vec::new();
-
-                for line in &hunk.lines {
-                    match line.line_type {
-                        LineType::Context => {
-                            // Flush any pending deletions/additions
-                            Self::flush_pairs(&mut pairs, &mut deletions, &mut additions);
-
-                            let old_no = line.old_lineno.unwrap_or(0);
-                            let new_no = line.new_lineno.unwrap_or(0);
-                            let content = line.content.trim_end_matches('\n').to_string();
-                            pairs.push((Some((old_no, content.clone())), Some((new_no, content))));
-                        }
-                        LineType::Deletion => {
-                            let line_no = line.old_lineno.unwrap_or(0);
-                            let content = line.content.trim_end_matches('\n').to_string();
-                            deletions.push((line_no, content));
-                        }
-                        LineType::Addition => {
-                            let line_no = line.new_lineno.unwrap_or(0);
-                            let content = line.content.trim_end_matches('\n').to_string();
-                            additions.push((line_no, content));
-                        }
-                    }
-                }
-
-                // Flush remaining
-                Self::flush_pairs(&mut pairs, &mut deletions, &mut additions);
-            }
-
-            pairs
+        let Some(file) = self.current_file() else {
+            let msg = "No changes to display";
+            let x = inner.x + (inner.width.saturating_sub(msg.len() as u16)) / 2;
+            let y = inner.y + inner.height / 2;
+            buf.set_string(x, y, msg, Style::new().fg(theme.untracked));
+            return;
+        };
+        if let Some(omitted) = file.omitted {
+            Self::render_omitted(omitted, inner, buf, theme);
+            return;
+        }
+        let Some(rows) = self.current_rows() else {
+            return;
         };
+        let (total, width) = (rows.split, rows.width);
 
         // Adjust scroll
-        let max_scroll = paired_lines.len().saturating_sub(inner.height as usize);
+        let max_scroll = total.saturating_sub(inner.height as usize);
         if self.scroll > max_scroll {
             self.scroll = max_scroll;
         }
@@ -1775,22 +1980,7 @@ impl DiffView {
 
         // Calculate max content width for side-by-side mode (using display width)
         self.view_width = (half_width.saturating_sub(line_num_width + 1)) as usize;
-        self.max_content_width = paired_lines
-            .iter()
-            .map(|(left, right)| {
-                let left_len = left
-                    .as_ref()
-                    .map(|(_, c)| str_display_width(c))
-                    .unwrap_or(0);
-                let right_len = right
-                    .as_ref()
-                    .map(|(_, c)| str_display_width(c))
-                    .unwrap_or(0);
-                left_len.max(right_len)
-            })
-            .max()
-            .unwrap_or(0)
-            + 2; // +2 for scrollbar (1) + margin (1)
+        self.max_content_width = width + 2; // +2 for scrollbar (1) + margin (1)
 
         // Clamp h_offset
         if self.max_content_width <= self.view_width {
@@ -1808,12 +1998,17 @@ impl DiffView {
             buf.set_string(sep_x, y, "│", Style::new().fg(theme.border));
         }
 
-        for (i, (left, right)) in paired_lines
-            .iter()
-            .skip(self.scroll)
-            .take(visible_height)
-            .enumerate()
-        {
+        // Pair up only the visible rows
+        let paired_lines: Vec<(SplitSide, SplitSide)> = {
+            let (Some(file), Some(rows)) = (self.current_file(), self.current_rows()) else {
+                return;
+            };
+            (self.scroll..total.min(self.scroll + visible_height))
+                .map(|row| rows.split_row(file, row))
+                .collect()
+        };
+
+        for (i, (left, right)) in paired_lines.iter().enumerate() {
             let y = inner.y + i as u16;
             let left_content_width = half_width.saturating_sub(line_num_width + 1);
             let right_content_width = half_width.saturating_sub(line_num_width + 2);
@@ -1931,27 +2126,30 @@ impl DiffView {
         }
 
         // Render scrollbar
-        let scrollbar = Scrollbar::new(paired_lines.len(), visible_height, self.scroll);
+        let scrollbar = Scrollbar::new(total, visible_height, self.scroll);
         let scrollbar_area = Rect::new(inner.x + inner.width - 1, inner.y, 1, inner.height);
         scrollbar.render(scrollbar_area, buf, Style::new().fg(theme.border));
     }
 
-    fn render_file_content(&mut self, inner: Rect, buf: &mut Buffer, _theme: &Theme) {
+    fn render_file_content(&mut self, inner: Rect, buf: &mut Buffer, theme: &Theme) {
         let visible_height = inner.height as usize;
         let content_area_width = inner.width.saturating_sub(1); // Leave space for scrollbar
 
-        let (lines, path) = if let Some(ref fc) = self.file_content {
-            (fc.lines.clone(), fc.path.clone())
-        } else {
+        let Some(ref fc) = self.file_content else {
             let msg = "No file selected";
             let x = inner.x + (inner.width.saturating_sub(msg.len() as u16)) / 2;
             let y = inner.y + inner.height / 2;
-            buf.set_string(x, y, msg, Style::new().fg(Color::Rgb(108, 112, 134)));
+            buf.set_string(x, y, msg, Style::new().fg(theme.untracked));
             return;
         };
+        if let Some(omitted) = fc.omitted {
+            Self::render_omitted(omitted, inner, buf, theme);
+            return;
+        }
+        let (total, width, path) = (fc.len(), fc.width, fc.path.clone());
 
         // Adjust scroll
-        let max_scroll = lines.len().saturating_sub(inner.height as usize);
+        let max_scroll = total.saturating_sub(inner.height as usize);
         if self.scroll > max_scroll {
             self.scroll = max_scroll;
         }
@@ -1960,12 +2158,7 @@ impl DiffView {
 
         // Calculate max content width using display width (accounting for wide characters)
         self.view_width = (content_area_width.saturating_sub(line_num_width)) as usize;
-        self.max_content_width = lines
-            .iter()
-            .map(|line| str_display_width(line))
-            .max()
-            .unwrap_or(0)
-            + 2;
+        self.max_content_width = width + 2;
 
         // Clamp h_offset
         if self.max_content_width <= self.view_width {
@@ -1984,12 +2177,16 @@ impl DiffView {
             (usize::MAX, usize::MAX)
         };
 
-        for (i, content) in lines
-            .iter()
-            .skip(self.scroll)
-            .take(visible_height)
-            .enumerate()
-        {
+        // Only the visible lines (owned data to avoid borrow issues)
+        let lines: Vec<String> = match &self.file_content {
+            Some(fc) => (self.scroll..total.min(self.scroll + visible_height))
+                .map(|i| fc.line(i).to_string())
+                .collect(),
+            None => Vec::new(),
+        };
+        let highlighter = SyntaxHighlighter::new(&path);
+
+        for (i, content) in lines.iter().enumerate() {
             let y = inner.y + i as u16;
             let absolute_line = self.scroll + i;
             let line_no = absolute_line + 1;
@@ -1998,18 +2195,18 @@ impl DiffView {
             let is_selected =
                 self.visual_mode && absolute_line >= sel_start && absolute_line <= sel_end;
             let is_cursor_line = absolute_line == self.cursor_line;
-            let selection_bg = Color::Rgb(60, 60, 100); // Dark blue selection
-            let cursor_bg = Color::Rgb(45, 45, 55); // Subtle highlight for cursor line
+            let selection_bg = theme.visual_selection;
+            let cursor_bg = theme.cursor_line;
 
             // Line numbers
             if self.show_line_numbers {
                 let line_nums = format!("{:>4} │", line_no);
                 let num_style = if is_selected {
-                    Style::new().fg(Color::Rgb(108, 112, 134)).bg(selection_bg)
+                    Style::new().fg(theme.line_number).bg(selection_bg)
                 } else if is_cursor_line {
-                    Style::new().fg(Color::Rgb(150, 150, 170)).bg(cursor_bg)
+                    Style::new().fg(theme.foreground).bg(cursor_bg)
                 } else {
-                    Style::new().fg(Color::Rgb(108, 112, 134)).dim()
+                    Style::new().fg(theme.line_number).dim()
                 };
                 buf.set_string(inner.x, y, &line_nums, num_style);
             }
@@ -2043,8 +2240,7 @@ impl DiffView {
             let line_matches = self.get_line_search_matches(absolute_line);
 
             // Render with syntax highlighting, using display width for positioning
-            let highlighter = SyntaxHighlighter::new(&path);
-            let tokens = highlighter.highlight_line(content);
+            let tokens = highlighter.highlight_line(content, theme);
             let mut x_offset: u16 = 0;
             let mut char_idx: usize = 0;
             for (text, color) in tokens {
@@ -2100,28 +2296,66 @@ impl DiffView {
         }
 
         // Render scrollbar
-        let scrollbar = Scrollbar::new(lines.len(), visible_height, self.scroll);
+        let scrollbar = Scrollbar::new(total, visible_height, self.scroll);
         let scrollbar_area = Rect::new(inner.x + inner.width - 1, inner.y, 1, inner.height);
-        scrollbar.render(
-            scrollbar_area,
-            buf,
-            Style::new().fg(Color::Rgb(108, 112, 134)),
-        );
+        scrollbar.render(scrollbar_area, buf, Style::new().fg(theme.border));
     }
+}
 
-    #[allow(clippy::type_complexity)]
-    fn flush_pairs(
-        pairs: &mut Vec<(Option<(u32, String)>, Option<(u32, String)>)>,
-        deletions: &mut Vec<(u32, String)>,
-        additions: &mut Vec<(u32, String)>,
-    ) {
-        let max_len = deletions.len().max(additions.len());
-        for i in 0..max_len {
-            let left = deletions.get(i).cloned();
-            let right = additions.get(i).cloned();
-            pairs.push((left, right));
-        }
-        deletions.clear();
-        additions.clear();
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line(line_type: LineType, content: &str, old: Option<u32>, new: Option<u32>) -> DiffLine {
+        DiffLine {
+            line_type,
+            content: format!("{}\n", content),
+            old_lineno: old,
+            new_lineno: new,
+        }
+    }
+
+    #[test]
+    fn test_file_rows() {
+        use LineType::*;
+        let file = FileDiff {
+            path: "a.txt".to_string(),
+            hunks: vec![Hunk {
+                header: "@@ -1,4 +1,3 @@".to_string(),
+                old_start: 1,
+                old_lines: 4,
+                new_start: 1,
+                new_lines: 3,
+                lines: vec![
+                    line(Context, "keep", Some(1), Some(1)),
+                    line(Deletion, "old 1", Some(2), None),
+                    line(Deletion, "old 2", Some(3), None),
+                    line(Addition, "new", None, Some(2)),
+                    line(Context, "end", Some(4), Some(3)),
+                ],
+            }],
+            omitted: None,
+        };
+        let rows = FileRows::new(&file);
+        assert_eq!((rows.inline, rows.split), (6, 5));
+        assert_eq!((rows.additions, rows.deletions), (1, 2));
+        assert_eq!(rows.inline_row(&file, 0).1.map(|l| l.line_type), None);
+        assert_eq!(rows.inline_row(&file, 4).1.unwrap().content, "new\n");
+
+        let split: Vec<(SplitSide, SplitSide)> = (0..rows.split)
+            .map(|row| rows.split_row(&file, row))
+            .collect();
+        let side = |n: u32, s: &str| Some((n, s.to_string()));
+        assert_eq!(split[1], (side(1, "keep"), side(1, "keep")));
+        assert_eq!(split[2], (side(2, "old 1"), side(2, "new")));
+        assert_eq!(split[3], (side(3, "old 2"), None));
+        assert_eq!(split[4], (side(4, "end"), side(3, "end")));
+
+        let content = FileContent::new("a".to_string(), "one\r\ntwo\n\nfour".to_string());
+        assert_eq!(
+            content.lines().collect::<Vec<_>>(),
+            ["one", "two", "", "four"]
+        );
+        assert_eq!(content.width, 4);
     }
 }
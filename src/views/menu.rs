@@ -13,6 +13,9 @@ pub enum PanelType {
     Remotes,
     Worktrees,
     Submodules,
+    Reflog,
+    Trash,
+    Hooks,
     Blame,
     Files,
     Conflicts,
@@ -34,6 +37,9 @@ impl PanelType {
             PanelType::Remotes,
             PanelType::Worktrees,
             PanelType::Submodules,
+            PanelType::Reflog,
+            PanelType::Trash,
+            PanelType::Hooks,
             PanelType::Blame,
             PanelType::Files,
             PanelType::Conflicts,
@@ -55,6 +61,9 @@ impl PanelType {
             PanelType::Remotes => "Remotes",
             PanelType::Worktrees => "Worktrees",
             PanelType::Submodules => "Submodules",
+            PanelType::Reflog => "Reflog",
+            PanelType::Trash => "Trash",
+            PanelType::Hooks => "Hooks",
             PanelType::Blame => "Blame",
             PanelType::Files => "Files",
             PanelType::Conflicts => "Conflicts",
@@ -76,6 +85,9 @@ impl PanelType {
             PanelType::Remotes => "7",
             PanelType::Worktrees => "8",
             PanelType::Submodules => "9",
+            PanelType::Reflog => "r",
+            PanelType::Trash => "t",
+            PanelType::Hooks => "h",
             PanelType::Blame => "b",
             PanelType::Files => "f",
             PanelType::Conflicts => "x",
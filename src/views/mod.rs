@@ -5,16 +5,20 @@ mod commits;
 mod conflict;
 mod diff;
 mod filetree;
+mod hooks;
 mod issues;
 mod loading;
 mod menu;
 mod pullrequests;
+mod reflog;
 mod releases;
 mod remotes;
+mod repos;
 mod stash;
 mod status;
 mod submodules;
 mod tags;
+mod trash;
 mod worktree;
 
 pub use actions::ActionsView;
@@ -24,14 +28,18 @@ pub use commits::{CommitsView, CommitsViewMode};
 pub use conflict::ConflictView;
 pub use diff::{DiffMode, DiffView, PreviewType};
 pub use filetree::{FileTreeView, FileViewMode};
+pub use hooks::HooksView;
 pub use issues::IssuesView;
 pub use loading::{LoadingState, DEFAULT_TIMEOUT, SPINNER_FRAMES};
 pub use menu::{MenuView, PanelType};
 pub use pullrequests::PullRequestsView;
+pub use reflog::ReflogView;
 pub use releases::ReleasesView;
 pub use remotes::RemotesView;
+pub use repos::{RepoEntry, RepoSwitcherView};
 pub use stash::StashView;
 pub use status::{Section, StatusView};
 pub use submodules::SubmodulesView;
 pub use tags::TagsView;
+pub use trash::TrashView;
 pub use worktree::WorktreeView;
@@ -0,0 +1,275 @@
+use crate::config::Theme;
+use crate::git::ReflogEntry;
+use crate::tui::{Buffer, Rect, Style};
+use crate::widgets::{Block, Borders, Scrollbar, Widget};
+
+pub struct ReflogView {
+    /// Refs with a reflog (HEAD first, then local branches)
+    pub refs: Vec<String>,
+    pub current_ref: usize,
+    pub entries: Vec<ReflogEntry>,
+    pub selected: usize,
+    pub offset: usize,
+    pub h_offset: usize,
+    pub max_content_width: usize,
+    pub view_width: usize,
+    pub search_query: Option<String>,
+    pub search_results: Vec<usize>,
+}
+
+impl ReflogView {
+    pub fn new() -> Self {
+        Self {
+            refs: vec!["HEAD".to_string()],
+            current_ref: 0,
+            entries: Vec::new(),
+            selected: 0,
+            offset: 0,
+            h_offset: 0,
+            max_content_width: 0,
+            view_width: 0,
+            search_query: None,
+            search_results: Vec::new(),
+        }
+    }
+
+    pub fn can_scroll_left(&self) -> bool {
+        self.h_offset > 0
+    }
+
+    pub fn can_scroll_right(&self) -> bool {
+        if self.view_width == 0 {
+            return self.max_content_width > 0;
+        }
+        self.max_content_width > self.view_width
+            && self.h_offset < self.max_content_width.saturating_sub(self.view_width)
+    }
+
+    pub fn scroll_left(&mut self) {
+        self.h_offset = self.h_offset.saturating_sub(4);
+    }
+
+    pub fn scroll_right(&mut self) {
+        self.h_offset += 4;
+    }
+
+    pub fn update(&mut self, refs: Vec<String>, entries: Vec<ReflogEntry>) {
+        // Keep following the same ref by name; fall back to HEAD if it went away
+        let current = self.current_ref().to_string();
+        self.current_ref = refs.iter().position(|r| *r == current).unwrap_or(0);
+        self.refs = refs;
+        self.entries = entries;
+        if self.selected >= self.entries.len() && !self.entries.is_empty() {
+            self.selected = self.entries.len() - 1;
+        }
+    }
+
+    /// Ref whose reflog is shown
+    pub fn current_ref(&self) -> &str {
+        self.refs
+            .get(self.current_ref)
+            .map(|s| s.as_str())
+            .unwrap_or("HEAD")
+    }
+
+    /// Switch to the next ref; the caller reloads the entries
+    pub fn cycle_ref(&mut self) {
+        if !self.refs.is_empty() {
+            self.current_ref = (self.current_ref + 1) % self.refs.len();
+        }
+        self.selected = 0;
+        self.offset = 0;
+    }
+
+    pub fn selected_entry(&self) -> Option<&ReflogEntry> {
+        self.entries.get(self.selected)
+    }
+
+    fn entry_line(&self, entry: &ReflogEntry) -> String {
+        let short_ref = self
+            .current_ref()
+            .strip_prefix("refs/heads/")
+            .unwrap_or(self.current_ref());
+        format!(
+            "{}@{{{}}} {} {}",
+            short_ref, entry.index, entry.short_id, entry.message
+        )
+    }
+
+    pub fn move_up(&mut self) {
+        if self.selected > 0 {
+            self.selected -= 1;
+        }
+    }
+
+    pub fn move_down(&mut self) {
+        if !self.entries.is_empty() && self.selected + 1 < self.entries.len() {
+            self.selected += 1;
+        }
+    }
+
+    pub fn move_to_top(&mut self) {
+        self.selected = 0;
+    }
+
+    pub fn move_to_bottom(&mut self) {
+        if !self.entries.is_empty() {
+            self.selected = self.entries.len() - 1;
+        }
+    }
+
+    pub fn select_at_row(&mut self, row: usize) {
+        let index = self.offset + row;
+        if index < self.entries.len() {
+            self.selected = index;
+        }
+    }
+
+    pub fn search(&mut self, query: &str) {
+        self.search_query = Some(query.to_string());
+        self.search_results.clear();
+
+        let query_lower = query.to_lowercase();
+
+        for (i, entry) in self.entries.iter().enumerate() {
+            if entry.message.to_lowercase().contains(&query_lower)
+                || entry.id.starts_with(&query_lower)
+            {
+                self.search_results.push(i);
+            }
+        }
+
+        // Jump to first result
+        if let Some(&first) = self.search_results.first() {
+            self.selected = first;
+        }
+    }
+
+    pub fn clear_search(&mut self) {
+        self.search_query = None;
+        self.search_results.clear();
+    }
+
+    pub fn next_search_result(&mut self) {
+        if self.search_results.is_empty() {
+            return;
+        }
+
+        if let Some(pos) = self.search_results.iter().position(|&i| i > self.selected) {
+            self.selected = self.search_results[pos];
+        } else {
+            // Wrap around
+            self.selected = self.search_results[0];
+        }
+    }
+
+    pub fn prev_search_result(&mut self) {
+        if self.search_results.is_empty() {
+            return;
+        }
+
+        if let Some(pos) = self.search_results.iter().rposition(|&i| i < self.selected) {
+            self.selected = self.search_results[pos];
+        } else {
+            // Wrap around
+            self.selected = *self.search_results.last().unwrap();
+        }
+    }
+
+    pub fn render(&mut self, area: Rect, buf: &mut Buffer, theme: &Theme, focused: bool) {
+        let border_color = if focused {
+            theme.border_focused
+        } else {
+            theme.border_unfocused
+        };
+
+        let title = format!(" Reflog: {} ({}) ", self.current_ref(), self.entries.len());
+
+        let block = Block::new()
+            .title(&title)
+            .borders(Borders::ALL)
+            .border_style(Style::new().fg(border_color));
+
+        let inner = block.inner(area);
+        block.render(area, buf);
+
+        if inner.height < 1 {
+            return;
+        }
+
+        let height = inner.height as usize;
+
+        // Adjust offset
+        if self.selected < self.offset {
+            self.offset = self.selected;
+        } else if self.selected >= self.offset + height {
+            self.offset = self.selected - height + 1;
+        }
+
+        let content_width = inner.width.saturating_sub(1); // Leave space for scrollbar
+
+        // Calculate max content width and store view width
+        self.view_width = content_width as usize;
+        self.max_content_width = self
+            .entries
+            .iter()
+            .map(|entry| self.entry_line(entry).chars().count())
+            .max()
+            .unwrap_or(0)
+            + 2; // +2 for scrollbar (1) + margin (1)
+
+        // Clamp h_offset
+        if self.max_content_width <= self.view_width {
+            self.h_offset = 0;
+        } else {
+            let max_offset = self.max_content_width.saturating_sub(self.view_width);
+            if self.h_offset > max_offset {
+                self.h_offset = max_offset;
+            }
+        }
+
+        if self.entries.is_empty() {
+            let msg = "No reflog entries";
+            let x = inner.x + (inner.width.saturating_sub(msg.len() as u16)) / 2;
+            let y = inner.y + inner.height / 2;
+            buf.set_string(x, y, msg, Style::new().fg(theme.untracked));
+        } else {
+            for (i, entry) in self
+                .entries
+                .iter()
+                .skip(self.offset)
+                .take(height)
+                .enumerate()
+            {
+                let y = inner.y + i as u16;
+                let is_selected = self.selected == self.offset + i;
+                let is_search_match = self.search_results.contains(&(self.offset + i));
+
+                let style = if is_selected && focused {
+                    Style::new().fg(theme.selection_text).bg(theme.selection)
+                } else if is_search_match {
+                    Style::new().fg(theme.diff_hunk)
+                } else {
+                    Style::new().fg(theme.foreground)
+                };
+
+                // Fill full line width when selected and focused
+                if is_selected && focused {
+                    let blank_line = " ".repeat(content_width as usize);
+                    buf.set_string(inner.x, y, &blank_line, style);
+                }
+
+                // Format: HEAD@{0} abc1234 checkout: moving from a to b
+                let line = self.entry_line(entry);
+                // Apply horizontal scroll
+                let display_line: String = line.chars().skip(self.h_offset).collect();
+                buf.set_string_truncated(inner.x, y, &display_line, content_width, style);
+            }
+        }
+
+        // Render scrollbar
+        let scrollbar = Scrollbar::new(self.entries.len(), height, self.offset);
+        let scrollbar_area = Rect::new(inner.x + inner.width - 1, inner.y, 1, inner.height);
+        scrollbar.render(scrollbar_area, buf, Style::new().fg(theme.border));
+    }
+}
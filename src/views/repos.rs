@@ -0,0 +1,232 @@
+use crate::config::Theme;
+use crate::git::RepoSummary;
+use crate::tui::{Buffer, Rect, Style};
+use crate::widgets::{Block, Borders, Scrollbar, Widget};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RepoEntry {
+    pub path: PathBuf,
+    pub favorite: bool,
+}
+
+impl RepoEntry {
+    pub fn name(&self) -> String {
+        self.path
+            .file_name()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_else(|| self.path.to_string_lossy().to_string())
+    }
+}
+
+/// Repository switcher and dashboard: a fuzzy-filtered list of favorite,
+/// configured and recent repositories with a summary of each
+pub struct RepoSwitcherView {
+    pub entries: Vec<RepoEntry>,
+    pub summaries: HashMap<PathBuf, RepoSummary>,
+    pub query: String,
+    /// Index into `matches()`
+    pub selected: usize,
+    pub offset: usize,
+}
+
+impl RepoSwitcherView {
+    pub fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+            summaries: HashMap::new(),
+            query: String::new(),
+            selected: 0,
+            offset: 0,
+        }
+    }
+
+    pub fn open(&mut self, entries: Vec<RepoEntry>) {
+        self.entries = entries;
+        self.query.clear();
+        self.selected = 0;
+        self.offset = 0;
+    }
+
+    /// Entries matching the query, best match first
+    pub fn matches(&self) -> Vec<&RepoEntry> {
+        if self.query.is_empty() {
+            return self.entries.iter().collect();
+        }
+        let mut scored: Vec<(i64, &RepoEntry)> = self
+            .entries
+            .iter()
+            .filter_map(|e| {
+                // The name counts more than the rest of the path
+                let name = fuzzy_score(&self.query, &e.name()).map(|s| s + 100);
+                let path = fuzzy_score(&self.query, &e.path.to_string_lossy());
+                name.max(path).map(|s| (s, e))
+            })
+            .collect();
+        scored.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+        scored.into_iter().map(|(_, e)| e).collect()
+    }
+
+    pub fn selected_entry(&self) -> Option<&RepoEntry> {
+        self.matches().get(self.selected).copied()
+    }
+
+    pub fn push_char(&mut self, c: char) {
+        self.query.push(c);
+        self.selected = 0;
+    }
+
+    pub fn pop_char(&mut self) {
+        self.query.pop();
+        self.selected = 0;
+    }
+
+    pub fn move_up(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+
+    pub fn move_down(&mut self) {
+        if self.selected + 1 < self.matches().len() {
+            self.selected += 1;
+        }
+    }
+
+    pub fn set_favorite(&mut self, path: &PathBuf, favorite: bool) {
+        if let Some(entry) = self.entries.iter_mut().find(|e| &e.path == path) {
+            entry.favorite = favorite;
+        }
+    }
+
+    fn entry_line(&self, entry: &RepoEntry) -> String {
+        let star = if entry.favorite { '*' } else { ' ' };
+        let status = match self.summaries.get(&entry.path) {
+            None => "...".to_string(),
+            Some(RepoSummary { error: Some(_), .. }) => "unavailable".to_string(),
+            Some(summary) => {
+                let mut parts = vec![summary
+                    .branch
+                    .clone()
+                    .unwrap_or_else(|| "(detached)".to_string())];
+                if summary.dirty {
+                    parts.push("dirty".to_string());
+                }
+                if summary.ahead > 0 {
+                    parts.push(format!("↑{}", summary.ahead));
+                }
+                if summary.behind > 0 {
+                    parts.push(format!("↓{}", summary.behind));
+                }
+                if let Some(prs) = summary.open_prs {
+                    parts.push(format!("{} PRs", prs));
+                }
+                parts.join(" ")
+            }
+        };
+        format!(
+            "{} {:<20} {:<36} {}",
+            star,
+            entry.name(),
+            status,
+            entry.path.display()
+        )
+    }
+
+    pub fn render(&mut self, area: Rect, buf: &mut Buffer, theme: &Theme) {
+        // Popup over the panels: blank what is underneath first
+        let blank = " ".repeat(area.width as usize);
+        for y in area.y..area.y + area.height {
+            buf.set_string(area.x, y, &blank, Style::new().fg(theme.foreground));
+        }
+
+        let block = Block::new()
+            .title(" Repositories [Enter:open Tab:new tab ^f:favorite Esc:close] ")
+            .borders(Borders::ALL)
+            .border_style(Style::new().fg(theme.border_focused));
+        let inner = block.inner(area);
+        block.render(area, buf);
+        if inner.height < 2 {
+            return;
+        }
+
+        buf.set_string(
+            inner.x,
+            inner.y,
+            format!("> {}", self.query),
+            Style::new().fg(theme.branch_current).bold(),
+        );
+
+        let list_y = inner.y + 1;
+        let height = (inner.height - 1) as usize;
+        let content_width = inner.width.saturating_sub(1);
+        let lines: Vec<String> = self.matches().iter().map(|e| self.entry_line(e)).collect();
+
+        if self.selected < self.offset {
+            self.offset = self.selected;
+        } else if self.selected >= self.offset + height {
+            self.offset = self.selected - height + 1;
+        }
+
+        if lines.is_empty() {
+            let msg = if self.entries.is_empty() {
+                "No repositories yet: add [workspace] repos to the config"
+            } else {
+                "No match"
+            };
+            buf.set_string(inner.x + 2, list_y, msg, Style::new().fg(theme.untracked));
+            return;
+        }
+
+        for (i, line) in lines.iter().skip(self.offset).take(height).enumerate() {
+            let y = list_y + i as u16;
+            let style = if self.selected == self.offset + i {
+                let style = Style::new().fg(theme.selection_text).bg(theme.selection);
+                buf.set_string(inner.x, y, " ".repeat(content_width as usize), style);
+                style
+            } else {
+                Style::new().fg(theme.foreground)
+            };
+            buf.set_string_truncated(inner.x, y, line, content_width, style);
+        }
+
+        let scrollbar = Scrollbar::new(lines.len(), height, self.offset);
+        let scrollbar_area = Rect::new(inner.x + inner.width - 1, list_y, 1, height as u16);
+        scrollbar.render(scrollbar_area, buf, Style::new().fg(theme.border));
+    }
+}
+
+/// Subsequence match of `query` in `text`, ignoring case; higher is better.
+/// Consecutive and word-start matches score more, so "gc" prefers "g-core".
+pub fn fuzzy_score(query: &str, text: &str) -> Option<i64> {
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+    let mut score = 0;
+    let mut pos = 0;
+    let mut last: Option<usize> = None;
+    for q in query.to_lowercase().chars() {
+        let found = text[pos..].iter().position(|&c| c == q)? + pos;
+        score += 1;
+        if last.map(|l| l + 1 == found).unwrap_or(false) {
+            score += 5;
+        }
+        if found == 0 || matches!(text[found - 1], '/' | '-' | '_' | '.' | ' ') {
+            score += 3;
+        }
+        last = Some(found);
+        pos = found + 1;
+    }
+    // Shorter texts are closer matches
+    Some(score * 10 - text.len() as i64 / 4)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fuzzy_score() {
+        assert!(fuzzy_score("gc", "g-core").is_some());
+        assert!(fuzzy_score("xyz", "g-core").is_none());
+        assert!(fuzzy_score("core", "g-core") > fuzzy_score("core", "c-o-r-e"));
+        assert!(fuzzy_score("G", "g").is_some());
+    }
+}
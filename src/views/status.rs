@@ -70,6 +70,18 @@ impl StatusView {
         self.update_internal(entries, false);
     }
 
+    /// The entries shown, one per path, as `update` takes them
+    pub fn entries(&self) -> Vec<StatusEntry> {
+        let mut seen = std::collections::HashSet::new();
+        self.staged
+            .iter()
+            .chain(&self.unstaged)
+            .chain(&self.untracked)
+            .filter(|entry| seen.insert(entry.path.as_str()))
+            .cloned()
+            .collect()
+    }
+
     fn update_internal(&mut self, entries: Vec<StatusEntry>, reset_scroll: bool) {
         let old_section = self.section;
         let old_selected = self.list_state.selected();
@@ -120,13 +120,7 @@ impl SubmodulesView {
         self.max_content_width = self
             .submodules
             .iter()
-            .map(|sm| {
-                let status_width = 2; // "✓ " or "○ "
-                let name_width = sm.name.chars().count();
-                let path_width = sm.path.chars().count() + 3; // " (path)"
-                let head_width = sm.head.as_ref().map(|h| h.len() + 3).unwrap_or(4); // " [head]" or " [-]"
-                status_width + name_width + path_width + head_width
-            })
+            .map(|sm| submodule_line(sm).chars().count())
             .max()
             .unwrap_or(0)
             + 2; // +2 for scrollbar (1) + margin (1)
@@ -159,6 +153,8 @@ impl SubmodulesView {
 
                 let style = if is_selected && focused {
                     Style::new().fg(theme.selection_text).bg(theme.selection)
+                } else if sm.has_drift || sm.is_dirty {
+                    Style::new().fg(theme.diff_remove)
                 } else if sm.is_initialized {
                     Style::new().fg(theme.staged)
                 } else {
@@ -171,9 +167,7 @@ impl SubmodulesView {
                     buf.set_string(inner.x, y, &blank_line, style);
                 }
 
-                let status = if sm.is_initialized { "✓" } else { "○" };
-                let head = sm.head.as_deref().unwrap_or("-");
-                let line = format!("{} {} ({}) [{}]", status, sm.name, sm.path, head);
+                let line = submodule_line(sm);
                 // Apply horizontal scroll
                 let display_line: String = line.chars().skip(self.h_offset).collect();
                 buf.set_string_truncated(inner.x, y, &display_line, content_width, style);
@@ -185,3 +179,23 @@ impl SubmodulesView {
         scrollbar.render(scrollbar_area, buf, Style::new().fg(theme.border));
     }
 }
+
+/// "✓ name (path) [recorded -> checked out] *" where the arrow only appears on
+/// drift and the trailing star marks local changes
+fn submodule_line(sm: &SubmoduleInfo) -> String {
+    let status = if sm.is_initialized { "✓" } else { "○" };
+    let head = sm.head.as_deref().unwrap_or("-");
+    let mut line = if sm.has_drift {
+        let workdir = sm.workdir.as_deref().unwrap_or("-");
+        format!(
+            "{} {} ({}) [{} -> {}]",
+            status, sm.name, sm.path, head, workdir
+        )
+    } else {
+        format!("{} {} ({}) [{}]", status, sm.name, sm.path, head)
+    };
+    if sm.is_dirty {
+        line.push_str(" *");
+    }
+    line
+}
@@ -120,13 +120,7 @@ impl WorktreeView {
         self.max_content_width = self
             .worktrees
             .iter()
-            .map(|wt| {
-                let main_width = 2; // "* " or "  "
-                let lock_width = if wt.is_locked { 2 } else { 0 }; // " "
-                let name_width = wt.name.chars().count();
-                let path_width = wt.path.chars().count() + 3; // " (path)"
-                main_width + lock_width + name_width + path_width
-            })
+            .map(|wt| worktree_line(wt).chars().count())
             .max()
             .unwrap_or(0)
             + 2; // +2 for scrollbar (1) + margin (1)
@@ -159,7 +153,9 @@ impl WorktreeView {
 
                 let style = if is_selected && focused {
                     Style::new().fg(theme.selection_text).bg(theme.selection)
-                } else if wt.is_main {
+                } else if wt.is_prunable {
+                    Style::new().fg(theme.untracked)
+                } else if wt.is_current {
                     Style::new().fg(theme.branch_current)
                 } else {
                     Style::new().fg(theme.foreground)
@@ -171,9 +167,7 @@ impl WorktreeView {
                     buf.set_string(inner.x, y, &blank_line, style);
                 }
 
-                let lock_icon = if wt.is_locked { " " } else { "" };
-                let main_icon = if wt.is_main { "* " } else { "  " };
-                let line = format!("{}{}{} ({})", main_icon, lock_icon, wt.name, wt.path);
+                let line = worktree_line(wt);
                 // Apply horizontal scroll
                 let display_line: String = line.chars().skip(self.h_offset).collect();
                 buf.set_string_truncated(inner.x, y, &display_line, content_width, style);
@@ -185,3 +179,28 @@ impl WorktreeView {
         scrollbar.render(scrollbar_area, buf, Style::new().fg(theme.border));
     }
 }
+
+/// Single-line summary: current marker, name, branch, dirty, ahead/behind, lock, path
+fn worktree_line(wt: &WorktreeInfo) -> String {
+    let current_icon = if wt.is_current { "* " } else { "  " };
+    let head = match &wt.branch {
+        Some(branch) => branch.clone(),
+        None if wt.is_prunable => "missing".to_string(),
+        None => format!("detached {}", wt.head),
+    };
+    let mut line = format!("{}{} [{}]", current_icon, wt.name, head);
+    if wt.is_dirty {
+        line.push_str(" *");
+    }
+    if wt.ahead > 0 || wt.behind > 0 {
+        line.push_str(&format!(" +{} -{}", wt.ahead, wt.behind));
+    }
+    if wt.is_locked {
+        match &wt.lock_reason {
+            Some(reason) => line.push_str(&format!(" (locked: {})", reason)),
+            None => line.push_str(" (locked)"),
+        }
+    }
+    line.push_str(&format!(" ({})", wt.path));
+    line
+}
@@ -0,0 +1,98 @@
+//! Tabs: one `App` per open repository, sharing the terminal.
+
+use crate::app::{App, Startup, TabRequest};
+use crate::error::Result;
+use crate::input::signals;
+
+pub struct Workspace {
+    tabs: Vec<App>,
+    active: usize,
+}
+
+impl Workspace {
+    pub fn new(app: App) -> Self {
+        Self {
+            tabs: vec![app],
+            active: 0,
+        }
+    }
+
+    pub fn run(&mut self) -> Result<()> {
+        signals::install()?;
+        self.tabs[0].terminal.init()?;
+        let result = self.run_tabs();
+        for tab in &self.tabs {
+            tab.finish();
+        }
+        self.tabs[self.active].terminal.restore()?;
+        result
+    }
+
+    fn run_tabs(&mut self) -> Result<()> {
+        self.tabs[0].start()?;
+        loop {
+            let titles: Vec<String> = self.tabs.iter().map(|t| t.repo.name()).collect();
+            let tab = &mut self.tabs[self.active];
+            tab.tab_titles = titles;
+            tab.active_tab = self.active;
+
+            match tab.run_tab()? {
+                TabRequest::Quit => return Ok(()),
+                TabRequest::Open { path, new_tab } => {
+                    let startup = Startup {
+                        repo_dir: Some(path.clone()),
+                        ..Default::default()
+                    };
+                    let mut app = match App::new(startup) {
+                        Ok(app) => app,
+                        Err(e) => {
+                            tab.message = Some(format!("Cannot open {}: {}", path.display(), e));
+                            continue;
+                        }
+                    };
+                    app.take_screen(tab);
+                    if let Err(e) = app.start() {
+                        tab.take_screen(&mut app);
+                        tab.message = Some(format!("Cannot open {}: {}", path.display(), e));
+                        continue;
+                    }
+                    if new_tab {
+                        self.tabs.push(app);
+                        self.active = self.tabs.len() - 1;
+                    } else {
+                        tab.finish();
+                        *tab = app;
+                    }
+                }
+                TabRequest::Switch(index) if index < self.tabs.len() => self.switch(index),
+                TabRequest::Switch(_) => {}
+                TabRequest::Close => {
+                    if self.tabs.len() > 1 {
+                        let closing = self.active;
+                        self.switch(if closing == 0 { 1 } else { closing - 1 });
+                        let tab = self.tabs.remove(closing);
+                        tab.finish();
+                        if self.active > closing {
+                            self.active -= 1;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn switch(&mut self, index: usize) {
+        if index == self.active {
+            return;
+        }
+        let (to, from) = if index < self.active {
+            let (left, right) = self.tabs.split_at_mut(self.active);
+            (&mut left[index], &mut right[0])
+        } else {
+            let (left, right) = self.tabs.split_at_mut(index);
+            (&mut right[0], &mut left[self.active])
+        };
+        to.take_screen(from);
+        self.active = index;
+    }
+}